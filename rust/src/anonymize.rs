@@ -0,0 +1,318 @@
+//! Replacement of the values annotated with `+anonymize`, so that production documents can be
+//! shared for testing. Replacements are derived from the value and a secret salt: a value is
+//! always replaced the same way, which keeps identifiers matching across documents.
+
+use crate::{
+    daemon::fingerprint,
+    validator::{AS3Validator, CharClass, StringFormat},
+    AS3Data,
+};
+use serde_json::Value;
+
+pub use crate::validator::Anonymize;
+
+/// Called on the annotated values with their validator, the annotation and their path
+type Replacer<'a> =
+    dyn FnMut(&AS3Validator, Anonymize, &mut Value, &str) -> Result<(), String> + 'a;
+
+/// Replaces the annotated values of `data`, which should be valid for `validator`
+pub fn anonymize(validator: &AS3Validator, data: &mut Value, salt: &str) -> Result<(), String> {
+    walk(validator, data, "ROOT", &mut |inner, how, value, path| {
+        *value = replace(inner, value, how, salt)
+            .map_err(|e| format!("error: Could not anonymize {path} : {e}"))?;
+        Ok(())
+    })
+}
+
+/// Copy of `data` whose annotated values are `"[redacted]"`, for reports shared broadly.
+/// Unlike [`anonymize`], the copy may not be valid anymore
+pub fn redact(validator: &AS3Validator, data: &AS3Data) -> AS3Data {
+    let mut json = Value::from(data);
+    walk(validator, &mut json, "ROOT", &mut |_, _, value, _| {
+        *value = Value::String("[redacted]".to_string());
+        Ok(())
+    })
+    .unwrap();
+    AS3Data::from(&json)
+}
+
+fn walk(
+    validator: &AS3Validator,
+    data: &mut Value,
+    path: &str,
+    replacer: &mut Replacer,
+) -> Result<(), String> {
+    match (validator, &mut *data) {
+        (_, Value::Null) => Ok(()),
+        (AS3Validator::Annotated { inner, annotations }, _) => match annotations.anonymize {
+            Some(how) => replacer(inner, how, data, path),
+            None => walk(inner, data, path, replacer),
+        },
+        (AS3Validator::Nullable(inner), _) => walk(inner, data, path, replacer),
+        (AS3Validator::Ref(reference), _) => match reference.target() {
+            Some(target) => walk(&target, data, path, replacer),
+            None => Ok(()),
+        },
+        (
+            AS3Validator::Conditional {
+                base,
+                condition,
+                then,
+                otherwise,
+            },
+            _,
+        ) => {
+            // Decided on the original values, which the condition was written for
+            let branch = match condition.validate(&AS3Data::from(&*data)) {
+                Ok(()) => then,
+                Err(_) => otherwise,
+            };
+            walk(base, data, path, replacer)?;
+            match branch {
+                Some(branch) => walk(branch, data, path, replacer),
+                None => Ok(()),
+            }
+        }
+        (
+            AS3Validator::Object {
+                fields,
+                pattern_keys,
+                ..
+            },
+            Value::Object(values),
+        ) => {
+            for (key, value) in values.iter_mut() {
+                let path = format!("{path} -> {key}");
+                match (fields.get(key), pattern_keys) {
+                    (Some(field), _) => walk(field, value, &path, replacer)?,
+                    (None, Some(pattern_keys)) => {
+                        for validator in pattern_keys.matching(key) {
+                            walk(validator, value, &path, replacer)?;
+                        }
+                    }
+                    (None, None) => {}
+                }
+            }
+            Ok(())
+        }
+        (AS3Validator::List(items_type), Value::Array(items)) => {
+            for item in items {
+                walk(items_type, item, path, replacer)?;
+            }
+            Ok(())
+        }
+        (AS3Validator::Map { value_type, .. }, Value::Object(values)) => {
+            for (key, value) in values.iter_mut() {
+                walk(value_type, value, &format!("{path} -> {key}"), replacer)?;
+            }
+            Ok(())
+        }
+        (
+            AS3Validator::TaggedUnion {
+                discriminator,
+                variants,
+            },
+            Value::Object(values),
+        ) => match values
+            .get(discriminator)
+            .and_then(Value::as_str)
+            .and_then(|tag| variants.get(tag))
+        {
+            Some(variant) => walk(variant, data, path, replacer),
+            None => Ok(()),
+        },
+        _ => Ok(()),
+    }
+}
+
+/// 64 bits derived from the salted value
+fn digest(salt: &str, text: &str) -> u64 {
+    u64::from_str_radix(&fingerprint(&format!("{salt}\0{text}")), 16).unwrap()
+}
+
+/// At least `length` hex characters derived from the salted value
+fn hex_digest(salt: &str, text: &str, length: usize) -> String {
+    let mut hex = fingerprint(&format!("{salt}\0{text}"));
+    while hex.len() < length {
+        let next = fingerprint(&format!("{salt}\0{hex}"));
+        hex.push_str(&next);
+    }
+    hex
+}
+
+fn replace(
+    validator: &AS3Validator,
+    value: &Value,
+    how: Anonymize,
+    salt: &str,
+) -> Result<Value, String> {
+    let text = match value {
+        Value::String(string) => string.clone(),
+        value => value.to_string(),
+    };
+    let hash = digest(salt, &text);
+
+    match validator {
+        AS3Validator::Nullable(inner) | AS3Validator::Annotated { inner, .. } => {
+            replace(inner, value, how, salt)
+        }
+        // Only a few known values are possible
+        AS3Validator::Literal { .. } => Ok(value.clone()),
+        AS3Validator::String {
+            max_length,
+            min_length,
+            format,
+            char_classes,
+            ..
+        } => {
+            let length = text.chars().count();
+            let alphanumeric = char_classes.contains(&CharClass::Alphanumeric);
+            let fitted = |string: String| {
+                let min_length = min_length.unwrap_or(0).max(0) as usize;
+                let max_length = max_length.map_or(usize::MAX, |max| max.max(0) as usize);
+                let mut string = string;
+                if string.len() < min_length {
+                    string.push_str(&hex_digest(salt, &text, min_length - string.len()));
+                }
+                string
+                    .chars()
+                    .take(max_length.max(min_length))
+                    .collect::<String>()
+            };
+            let string = match (how, format) {
+                (Anonymize::Hash, _) => fitted(hex_digest(salt, &text, 16)),
+                (Anonymize::Mask, _) => text
+                    .chars()
+                    .enumerate()
+                    .map(|(index, c)| match length >= 8 && index >= length - 4 {
+                        true => c,
+                        false if alphanumeric => 'x',
+                        false => '*',
+                    })
+                    .collect(),
+                (Anonymize::Fake, Some(format)) => fake_formatted(format, hash, salt, &text),
+                (Anonymize::Fake, None) => {
+                    let separator = if alphanumeric { "" } else { "-" };
+                    fitted(format!(
+                        "value{separator}{}",
+                        &hex_digest(salt, &text, 8)[..8]
+                    ))
+                }
+            };
+            Ok(Value::String(string))
+        }
+        AS3Validator::Integer {
+            minimum,
+            maximum,
+            width,
+        } => {
+            let (mut lowest, mut highest) =
+                width.map_or((i64::MIN as i128, i64::MAX as i128), |w| w.range());
+            lowest = lowest.max(minimum.map_or(i128::MIN, i128::from));
+            highest = highest.min(maximum.map_or(i128::MAX, i128::from));
+            let number = match how {
+                Anonymize::Mask => 0.clamp(lowest, highest),
+                Anonymize::Hash | Anonymize::Fake => {
+                    lowest + (i128::from(hash) % (highest - lowest + 1))
+                }
+            };
+            Ok(serde_json::json!(number as i64))
+        }
+        AS3Validator::Decimal { minimum, maximum } => {
+            let (lowest, highest) = match (minimum, maximum) {
+                (Some(lowest), Some(highest)) => (*lowest, *highest),
+                (Some(lowest), None) => (*lowest, lowest + 1000.0),
+                (None, Some(highest)) => (highest - 1000.0, *highest),
+                (None, None) => (0.0, 1000.0),
+            };
+            let number = match how {
+                Anonymize::Mask => 0f64.clamp(lowest, highest),
+                Anonymize::Hash | Anonymize::Fake => {
+                    let fraction = (hash % 1_000_000) as f64 / 1_000_000.0;
+                    ((lowest + fraction * (highest - lowest)) * 100.0).round() / 100.0
+                }
+            };
+            Ok(serde_json::json!(number.clamp(lowest, highest)))
+        }
+        AS3Validator::Boolean => Ok(Value::Bool(how != Anonymize::Mask && hash % 2 == 1)),
+        AS3Validator::Date { minimum, maximum } => {
+            let lowest = minimum
+                .map_or(chrono::NaiveDate::from_ymd_opt(1970, 1, 1).unwrap(), |m| {
+                    m.resolve()
+                });
+            let highest = maximum.map_or(lowest + chrono::Days::new(20_000), |m| m.resolve());
+            let date = match how {
+                Anonymize::Mask => lowest,
+                Anonymize::Hash | Anonymize::Fake => {
+                    let days = (highest - lowest).num_days().max(0) as u64 + 1;
+                    lowest + chrono::Days::new(hash % days)
+                }
+            };
+            Ok(Value::String(date.format("%Y-%m-%d").to_string()))
+        }
+        _ => Err(
+            "`+anonymize` only applies to String, Integer, Decimal, Bool and Date values"
+                .to_string(),
+        ),
+    }
+}
+
+fn fake_formatted(format: &StringFormat, hash: u64, salt: &str, text: &str) -> String {
+    let hex = hex_digest(salt, text, 32);
+    match format {
+        StringFormat::Uuid { .. } => {
+            let mut bytes = [0u8; 16];
+            hex::decode_to_slice(&hex[..32], &mut bytes).unwrap();
+            uuid::Builder::from_random_bytes(bytes)
+                .into_uuid()
+                .to_string()
+        }
+        StringFormat::Url { schemes } => {
+            let scheme = schemes
+                .as_ref()
+                .and_then(|schemes| schemes.first())
+                .map_or("https", String::as_str);
+            format!("{scheme}://example.com/{}", &hex[..8])
+        }
+        StringFormat::Ipv4 { cidr } | StringFormat::Ip { cidr } => {
+            // TEST-NET-1, reserved for documentation
+            let address = format!("192.0.2.{}", hash % 256);
+            if *cidr {
+                format!("{address}/32")
+            } else {
+                address
+            }
+        }
+        StringFormat::Ipv6 { cidr } => {
+            let address = format!("2001:db8::{}", &hex[..4]);
+            if *cidr {
+                format!("{address}/128")
+            } else {
+                address
+            }
+        }
+        StringFormat::Hostname => format!("host-{}.example.com", &hex[..8]),
+        StringFormat::Phone { country_codes } => {
+            let code = country_codes
+                .as_ref()
+                .and_then(|codes| codes.first())
+                .map_or("1".to_string(), |code| code.to_string());
+            // Subscriber digits taken from the digest
+            let digits: String = hex
+                .chars()
+                .map(|c| char::from(b'0' + (c.to_digit(16).unwrap() % 10) as u8))
+                .take(10)
+                .collect();
+            format!("+{code}{digits}")
+        }
+        StringFormat::Semver {
+            min_version,
+            max_version,
+        } => match (min_version, max_version) {
+            // Any other version could fall out of the range
+            (Some(version), _) => version.to_string(),
+            (None, Some(_)) => "0.0.0".to_string(),
+            (None, None) => format!("{}.{}.{}", hash % 10, hash / 10 % 100, hash / 1000 % 100),
+        },
+    }
+}
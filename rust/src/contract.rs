@@ -0,0 +1,234 @@
+//! Contract tests from real traffic: the request and response bodies of a HAR capture are
+//! validated against the roots of a definition, chosen by method and path with `+endpoints`:
+//!
+//! ```yaml
+//! +endpoints:
+//!   GET /users/{id}:
+//!     response: User
+//!   POST /users:
+//!     request: NewUser
+//!     response: User
+//! NewUser:
+//!   +type: Object
+//!   name: String
+//! User:
+//!   +type: Object
+//!   id: Integer
+//!   name: String
+//! ```
+//!
+//! `{…}` segments match any segment of the path. Entries of the capture matching no endpoint
+//! are counted but not checked.
+
+use crate::{
+    error::{AS3DefinitionError, AS3ValidationError, As3JsonPath, JsonPath},
+    transcript::TranscriptValidator,
+    validator::DefinitionOptions,
+    AS3Data,
+};
+use serde_json::Value;
+
+#[derive(Debug, Clone)]
+struct Endpoint {
+    method: String,
+    /// Segments of the path, `None` for a `{…}` placeholder
+    segments: Vec<Option<String>>,
+    /// `METHOD /path` as written in the definition
+    name: String,
+    request: Option<String>,
+    response: Option<String>,
+}
+
+impl Endpoint {
+    fn matches(&self, method: &str, path: &str) -> bool {
+        let segments: Vec<&str> = path.trim_matches('/').split('/').collect();
+        self.method.eq_ignore_ascii_case(method)
+            && segments.len() == self.segments.len()
+            && self
+                .segments
+                .iter()
+                .zip(segments)
+                .all(|(expected, segment)| {
+                    expected.is_none() || expected.as_deref() == Some(segment)
+                })
+    }
+}
+
+/// Outcome of an entry of the capture matching an endpoint
+#[derive(Debug, PartialEq)]
+pub struct ExchangeResult {
+    /// Position of the entry in the capture
+    pub index: usize,
+    /// Endpoint matched, as written in the definition
+    pub endpoint: String,
+    pub result: Result<(), As3JsonPath<AS3ValidationError>>,
+}
+
+#[derive(Debug, Default, PartialEq)]
+pub struct ContractReport {
+    pub exchanges: Vec<ExchangeResult>,
+    /// Entries matching no endpoint
+    pub unmatched: usize,
+}
+
+impl ContractReport {
+    pub fn failures(&self) -> impl Iterator<Item = &ExchangeResult> {
+        self.exchanges
+            .iter()
+            .filter(|exchange| exchange.result.is_err())
+    }
+}
+
+#[derive(Debug, Clone)]
+pub struct ContractValidator {
+    roots: TranscriptValidator,
+    endpoints: Vec<Endpoint>,
+}
+
+impl ContractValidator {
+    pub fn from_with_options(
+        yaml_config: &serde_yaml::Value,
+        options: &DefinitionOptions,
+    ) -> Result<ContractValidator, As3JsonPath<AS3DefinitionError>> {
+        let roots = TranscriptValidator::from_with_options(yaml_config, options)?;
+        let error = |path: JsonPath, expected: &str| {
+            As3JsonPath(
+                path,
+                AS3DefinitionError::InvalidValue {
+                    keyword: "+endpoints".to_string(),
+                    expected: expected.to_string(),
+                },
+            )
+        };
+        let Some(serde_yaml::Value::Mapping(definitions)) = yaml_config.get("+endpoints") else {
+            return Err(error(
+                JsonPath::root("+endpoints"),
+                "a Mapping of `METHOD /path` to the roots of its request and response",
+            ));
+        };
+
+        let mut endpoints = Vec::new();
+        for (name, sides) in definitions {
+            let name = name.as_str().unwrap_or_default();
+            let path = JsonPath::root("+endpoints").with_key(name);
+            let Some((method, route)) = name.split_once(' ') else {
+                return Err(error(path, "a `METHOD /path` key"));
+            };
+            let mut endpoint = Endpoint {
+                method: method.to_string(),
+                segments: route
+                    .trim()
+                    .trim_matches('/')
+                    .split('/')
+                    .map(|segment| {
+                        (!(segment.starts_with('{') && segment.ends_with('}')))
+                            .then(|| segment.to_string())
+                    })
+                    .collect(),
+                name: name.to_string(),
+                request: None,
+                response: None,
+            };
+            let Some(sides) = sides.as_mapping() else {
+                return Err(error(path, "a Mapping with `request` and `response` roots"));
+            };
+            for (side, root) in sides {
+                let root = match root.as_str() {
+                    Some(root) if roots.root(root).is_some() => root.to_string(),
+                    _ => {
+                        return Err(error(
+                            path,
+                            "the name of a root of the definition for `request` and `response`",
+                        ))
+                    }
+                };
+                match side.as_str() {
+                    Some("request") => endpoint.request = Some(root),
+                    Some("response") => endpoint.response = Some(root),
+                    _ => return Err(error(path, "a Mapping with `request` and `response` roots")),
+                }
+            }
+            endpoints.push(endpoint);
+        }
+        Ok(ContractValidator { roots, endpoints })
+    }
+
+    /// Validates the entries of the `har` capture, the first endpoint matching an entry being
+    /// used
+    pub fn validate_har(&self, har: &Value) -> Result<ContractReport, String> {
+        let Some(entries) = har.pointer("/log/entries").and_then(Value::as_array) else {
+            return Err("error: The capture has no `log.entries`, is it a HAR file?".to_string());
+        };
+        let mut report = ContractReport::default();
+        for (index, entry) in entries.iter().enumerate() {
+            let method = entry
+                .pointer("/request/method")
+                .and_then(Value::as_str)
+                .unwrap_or_default();
+            let url = entry
+                .pointer("/request/url")
+                .and_then(Value::as_str)
+                .unwrap_or_default();
+            let path = url::Url::parse(url).map_or(String::new(), |url| url.path().to_string());
+            let Some(endpoint) = self
+                .endpoints
+                .iter()
+                .find(|endpoint| endpoint.matches(method, &path))
+            else {
+                report.unmatched += 1;
+                continue;
+            };
+            report.exchanges.push(ExchangeResult {
+                index,
+                endpoint: endpoint.name.clone(),
+                result: self.validate_entry(endpoint, entry, index),
+            });
+        }
+        Ok(report)
+    }
+
+    #[allow(clippy::result_large_err)]
+    fn validate_entry(
+        &self,
+        endpoint: &Endpoint,
+        entry: &Value,
+        index: usize,
+    ) -> Result<(), As3JsonPath<AS3ValidationError>> {
+        let sides = [
+            ("request", &endpoint.request, "/request/postData"),
+            ("response", &endpoint.response, "/response/content"),
+        ];
+        for (side, root, body) in sides {
+            let Some(validator) = root.as_deref().and_then(|root| self.roots.root(root)) else {
+                continue;
+            };
+            let mut path = JsonPath::root("ROOT").with_index(index).with_key(side);
+            let data = body_of(entry.pointer(body))
+                .map_err(|e| As3JsonPath(path.clone(), AS3ValidationError::Generic(e)))?;
+            validator.check(&AS3Data::from(&data), &mut path)?;
+        }
+        Ok(())
+    }
+}
+
+/// JSON of a HAR `postData` or `content` object, whose text may be base64 encoded
+fn body_of(body: Option<&Value>) -> Result<Value, String> {
+    let text = body
+        .and_then(|body| body.get("text"))
+        .and_then(Value::as_str)
+        .ok_or("the body is missing from the capture")?;
+    let text = match body
+        .and_then(|body| body.get("encoding"))
+        .and_then(Value::as_str)
+    {
+        Some("base64") => {
+            use base64::Engine;
+            let bytes = base64::engine::general_purpose::STANDARD
+                .decode(text)
+                .map_err(|e| format!("the body is not valid base64: {e}"))?;
+            String::from_utf8(bytes).map_err(|e| format!("the body is not UTF-8: {e}"))?
+        }
+        _ => text.to_string(),
+    };
+    serde_json::from_str(&text).map_err(|e| format!("the body is not JSON: {e}"))
+}
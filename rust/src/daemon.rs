@@ -0,0 +1,241 @@
+//! Validation jobs run on a schedule by `as3 daemon`, configured in a TOML file:
+//!
+//! ```toml
+//! results = "as3-results.jsonl"
+//! history = "as3-history.db"
+//!
+//! [[job]]
+//! name = "orders"
+//! definition = "orders.yaml"
+//! directory = "data/orders"
+//! every = "15m"
+//!
+//! [[job]]
+//! name = "feature-flags"
+//! definition = "flags.yaml"
+//! url = "https://config.example.com/flags.json"
+//! every = "1h"
+//! ```
+//!
+//! Relative paths are resolved from the folder of the configuration file. Every run appends a
+//! [`JobResult`] as a JSON line to `results` and records it in the `history` store.
+
+use crate::{
+    diagnostic,
+    error::{AS3ValidationError, As3JsonPath},
+    fetch::{Fetcher, HttpFetcher},
+    notify::FailureSummary,
+    sampling::random_seed,
+    validator::{AS3Validator, DefinitionOptions},
+    AS3Data,
+};
+use serde::{Deserialize, Serialize};
+use std::path::{Path, PathBuf};
+use std::sync::Arc;
+use std::time::Duration;
+
+#[derive(Debug, PartialEq, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct DaemonConfig {
+    /// JSON lines file receiving the result of every run
+    pub results: Option<PathBuf>,
+    /// SQLite file keeping the outcome of every document, read by `as3 history`
+    pub history: Option<PathBuf>,
+    #[serde(rename = "job", default)]
+    pub jobs: Vec<Job>,
+}
+
+#[derive(Debug, PartialEq, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct Job {
+    pub name: String,
+    pub definition: PathBuf,
+    /// Folder whose JSON and YAML files are validated, searched recursively
+    pub directory: Option<PathBuf>,
+    /// Document downloaded and validated at every run
+    pub url: Option<String>,
+    /// Interval between runs, such as `90s` or `15m`
+    #[serde(with = "interval")]
+    pub every: Duration,
+    #[serde(default)]
+    pub lenient_schema: bool,
+    /// Allow `+include` of definitions from http(s) URLs
+    #[serde(default)]
+    pub allow_remote: bool,
+    /// Offending values kept in the summary, picked at random with the `+anonymize` fields
+    /// redacted
+    #[serde(default)]
+    pub sample_values: usize,
+}
+
+#[derive(Debug, PartialEq, Serialize)]
+pub struct JobResult {
+    pub job: String,
+    /// RFC 3339 time of the run
+    pub time: String,
+    /// Fingerprint of the definition source, to tell runs of different versions apart
+    pub fingerprint: String,
+    #[serde(flatten)]
+    pub summary: FailureSummary,
+    /// Every document with its error, if any
+    #[serde(skip)]
+    pub outcomes: Vec<(String, Option<As3JsonPath<AS3ValidationError>>)>,
+}
+
+mod interval {
+    use serde::{Deserialize, Deserializer};
+    use std::time::Duration;
+
+    pub fn deserialize<'de, D: Deserializer<'de>>(deserializer: D) -> Result<Duration, D::Error> {
+        let every = String::deserialize(deserializer)?;
+        humantime::parse_duration(&every).map_err(serde::de::Error::custom)
+    }
+}
+
+impl DaemonConfig {
+    /// Reads the configuration, resolving its relative paths from the folder of `path`
+    pub fn read(path: &Path) -> Result<DaemonConfig, String> {
+        let source = std::fs::read_to_string(path)
+            .map_err(|e| format!("error: Could not read {path:?} : {e}"))?;
+        let mut config: DaemonConfig = toml::from_str(&source)
+            .map_err(|e| format!("error: Invalid daemon configuration {path:?} : {e}"))?;
+
+        let base = path.parent().unwrap_or(Path::new("."));
+        config.results = config.results.map(|results| base.join(results));
+        config.history = config.history.map(|history| base.join(history));
+        for job in &mut config.jobs {
+            match (&job.directory, &job.url) {
+                (Some(_), None) | (None, Some(_)) => {}
+                _ => {
+                    return Err(format!(
+                        "error: Job `{}` must have either a `directory` or an `url`",
+                        job.name
+                    ))
+                }
+            }
+            job.definition = base.join(&job.definition);
+            job.directory = job.directory.as_ref().map(|directory| base.join(directory));
+        }
+        Ok(config)
+    }
+}
+
+impl Job {
+    /// Validates the documents of the job once
+    pub fn run(&self) -> Result<JobResult, String> {
+        let source = std::fs::read_to_string(&self.definition)
+            .map_err(|e| format!("error: Could not read {:?} : {e}", self.definition))?;
+        let definition: serde_yaml::Value =
+            diagnostic::from_yaml("file", &self.definition, &source)?;
+        let options = DefinitionOptions {
+            lenient: self.lenient_schema,
+            fetcher: match self.allow_remote {
+                true => Some(Arc::new(HttpFetcher)),
+                false => None,
+            },
+            ..Default::default()
+        }
+        .located(&self.definition);
+        let validator = AS3Validator::from_with_options(&definition, &options)
+            .map_err(|e| format!("error: Invalid definition : {e}"))?
+            .optimize();
+
+        let documents = match (&self.directory, &self.url) {
+            (Some(directory), _) => {
+                let mut documents = Vec::new();
+                for file in documents_in(directory)? {
+                    let data: serde_json::Value = read_document(&file)?;
+                    documents.push((file.display().to_string(), AS3Data::from(&data)));
+                }
+                documents
+            }
+            (None, Some(url)) => {
+                let body = url::Url::parse(url)
+                    .map_err(|e| e.to_string())
+                    .and_then(|parsed| HttpFetcher.fetch(&parsed))
+                    .map_err(|e| format!("error: Could not download {url} : {e}"))?;
+                let data: serde_json::Value = serde_yaml::from_str(&body)
+                    .map_err(|_| format!("error: The document at {url} is not json or yaml"))?;
+                vec![(url.clone(), AS3Data::from(&data))]
+            }
+            (None, None) => Vec::new(),
+        };
+
+        let outcomes: Vec<_> = documents
+            .iter()
+            .map(|(name, document)| (name.clone(), validator.validate(document).err()))
+            .collect();
+        let mut summary = FailureSummary::new(
+            documents.len(),
+            outcomes
+                .iter()
+                .filter_map(|(name, error)| Some((name.as_str(), error.as_ref()?))),
+        );
+        summary.sample_values(
+            &validator,
+            documents
+                .iter()
+                .zip(&outcomes)
+                .filter_map(|((name, document), (_, error))| {
+                    Some((name.as_str(), document, error.as_ref()?))
+                }),
+            self.sample_values,
+            random_seed(),
+        );
+
+        Ok(JobResult {
+            job: self.name.clone(),
+            time: chrono::Local::now().to_rfc3339(),
+            fingerprint: fingerprint(&source),
+            summary,
+            outcomes,
+        })
+    }
+}
+
+/// FNV-1a hash of `source`, stable across platforms and versions
+pub fn fingerprint(source: &str) -> String {
+    let hash = source.bytes().fold(0xcbf29ce484222325_u64, |hash, byte| {
+        (hash ^ u64::from(byte)).wrapping_mul(0x100000001b3)
+    });
+    format!("{hash:016x}")
+}
+
+fn read_document<T: serde::de::DeserializeOwned>(path: &Path) -> Result<T, String> {
+    let source = std::fs::read_to_string(path)
+        .map_err(|e| format!("error: Could not read {path:?} : {e}"))?;
+    diagnostic::from_yaml("file", path, &source)
+}
+
+/// JSON and YAML files of `folder` and of its sub folders, sorted
+pub fn documents_in(folder: &Path) -> Result<Vec<PathBuf>, String> {
+    files_in(folder, &["json", "yaml", "yml"])
+}
+
+/// Files of `folder` and of its sub folders with one of the `extensions`, sorted
+pub fn files_in(folder: &Path, extensions: &[&str]) -> Result<Vec<PathBuf>, String> {
+    fn collect(folder: &Path, extensions: &[&str], files: &mut Vec<PathBuf>) -> Result<(), String> {
+        let entries = std::fs::read_dir(folder)
+            .map_err(|e| format!("error: Could not read {folder:?} : {e}"))?;
+        for entry in entries {
+            let path = entry
+                .map_err(|e| format!("error: Could not read {folder:?} : {e}"))?
+                .path();
+            if path.is_dir() {
+                collect(&path, extensions, files)?;
+            } else if path
+                .extension()
+                .and_then(|e| e.to_str())
+                .is_some_and(|extension| extensions.contains(&extension))
+            {
+                files.push(path);
+            }
+        }
+        Ok(())
+    }
+
+    let mut files = Vec::new();
+    collect(folder, extensions, &mut files)?;
+    files.sort();
+    Ok(files)
+}
@@ -0,0 +1,106 @@
+//! Keyword spellings kept so that older definitions still parse. Each one has a replacement,
+//! reported by [`deprecated_keywords`] and written into a definition by [`fix_deprecations`],
+//! so that the language can evolve without breaking existing files.
+
+use crate::error::JsonPath;
+use regex::Regex;
+use serde::Serialize;
+
+/// Deprecated keywords and the keyword replacing each of them
+pub const DEPRECATED_KEYWORDS: &[(&str, &str)] = &[
+    ("+MaxLength", "+maxLength"),
+    ("+max_length", "+maxLength"),
+    ("+MinLength", "+minLength"),
+    ("+min_length", "+minLength"),
+];
+
+/// Use of a deprecated keyword in a definition
+#[derive(Debug, Clone, PartialEq, Eq, Serialize)]
+pub struct DeprecatedKeyword {
+    /// Definition holding the keyword, such as `Root -> name`
+    pub path: JsonPath,
+    pub keyword: String,
+    pub replacement: String,
+}
+
+impl std::fmt::Display for DeprecatedKeyword {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(
+            f,
+            "`{}` is deprecated, use `{}` instead in [{}]",
+            self.keyword, self.replacement, self.path
+        )
+    }
+}
+
+/// Deprecated keywords used in `definition`, in the order of the definition. Included
+/// definitions are checked when they are read themselves
+pub fn deprecated_keywords(definition: &serde_yaml::Value) -> Vec<DeprecatedKeyword> {
+    let mut found = Vec::new();
+    if let serde_yaml::Value::Mapping(mapping) = definition {
+        for (key, value) in mapping {
+            if let Some(key) = key.as_str() {
+                collect(value, &mut JsonPath::root(key), &mut found);
+            }
+        }
+    }
+    found
+}
+
+fn collect(value: &serde_yaml::Value, path: &mut JsonPath, found: &mut Vec<DeprecatedKeyword>) {
+    match value {
+        serde_yaml::Value::Mapping(mapping) => {
+            for (key, value) in mapping {
+                let Some(key) = key.as_str() else {
+                    continue;
+                };
+                if let Some((keyword, replacement)) = replacement(key) {
+                    found.push(DeprecatedKeyword {
+                        path: path.clone(),
+                        keyword: keyword.to_string(),
+                        replacement: replacement.to_string(),
+                    });
+                }
+                path.push_key(key);
+                collect(value, path, found);
+                path.pop();
+            }
+        }
+        serde_yaml::Value::Sequence(items) => {
+            for (index, item) in items.iter().enumerate() {
+                path.push_index(index);
+                collect(item, path, found);
+                path.pop();
+            }
+        }
+        serde_yaml::Value::Tagged(tagged) => collect(&tagged.value, path, found),
+        _ => {}
+    }
+}
+
+fn replacement(keyword: &str) -> Option<(&'static str, &'static str)> {
+    DEPRECATED_KEYWORDS
+        .iter()
+        .find(|(deprecated, _)| *deprecated == keyword)
+        .copied()
+}
+
+/// `source`, the text of a JSON, YAML or TOML definition, with its deprecated keywords replaced,
+/// and the number of replacements. The rest of the text, comments included, is kept as is
+pub fn fix_deprecations(source: &str) -> (String, usize) {
+    let mut fixed = source.to_string();
+    let mut count = 0;
+    for (deprecated, replacement) in DEPRECATED_KEYWORDS {
+        // Only as a key, quoted or not, followed by `:` in JSON and YAML or `=` in TOML
+        let key = Regex::new(&format!(
+            r#"(?m)(^|[\s{{,])(["']?){}(["']?\s*[:=])"#,
+            regex::escape(deprecated)
+        ))
+        .unwrap();
+        count += key.find_iter(&fixed).count();
+        fixed = key
+            .replace_all(&fixed, format!("${{1}}${{2}}{replacement}${{3}}"))
+            .into_owned();
+    }
+    (fixed, count)
+}
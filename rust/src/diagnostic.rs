@@ -0,0 +1,442 @@
+//! Parsing of the definitions and documents read by the CLI and the daemon, whatever their
+//! format. Parse errors are shown with the lines around the error and, for common mistakes, a
+//! hint on how to fix them, as are validation errors with `--pretty`
+
+use crate::{
+    error::{AS3ValidationError, As3JsonPath},
+    messages,
+    spans::SourceMap,
+};
+use serde::de::DeserializeOwned;
+use std::path::Path;
+
+/// Syntax of a definition or of a document
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum Format {
+    Json,
+    Yaml,
+    Toml,
+    /// HCL, such as the `.tfvars` variable files of Terraform
+    Hcl,
+    /// INI, its sections and dotted keys read as nested Objects
+    Ini,
+    /// Java properties, their dotted keys read as nested Objects
+    Properties,
+}
+
+impl std::str::FromStr for Format {
+    type Err = String;
+
+    fn from_str(format: &str) -> Result<Format, String> {
+        match format.to_lowercase().as_str() {
+            "json" => Ok(Format::Json),
+            "yaml" | "yml" => Ok(Format::Yaml),
+            "toml" => Ok(Format::Toml),
+            "hcl" | "tfvars" => Ok(Format::Hcl),
+            "ini" => Ok(Format::Ini),
+            "properties" => Ok(Format::Properties),
+            _ => Err(format!(
+                "`{format}` is not one of json, yaml, toml, hcl, ini or properties"
+            )),
+        }
+    }
+}
+
+impl std::fmt::Display for Format {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            Format::Json => write!(f, "json"),
+            Format::Yaml => write!(f, "yaml"),
+            Format::Toml => write!(f, "toml"),
+            Format::Hcl => write!(f, "hcl"),
+            Format::Ini => write!(f, "ini"),
+            Format::Properties => write!(f, "properties"),
+        }
+    }
+}
+
+impl Format {
+    /// Format of `source` judged by its content, so that piped and extension-less files are
+    /// read correctly. The extension of `path` only decides between formats that fail to parse,
+    /// so that the error comes from the intended parser, HCL, INI and properties files aside
+    pub fn detect(path: &Path, source: &str) -> Format {
+        let extension = path
+            .extension()
+            .and_then(|extension| extension.to_str()?.parse().ok());
+        // Most `.tfvars` files are valid TOML too, but their comments and expressions are HCL's,
+        // and about anything reads as INI or properties
+        if let Some(format @ (Format::Hcl | Format::Ini | Format::Properties)) = extension {
+            return format;
+        }
+        let trimmed = source.trim_start();
+        if trimmed.starts_with(['{', '['])
+            && serde_json::from_str::<serde_json::Value>(source).is_ok()
+        {
+            return Format::Json;
+        }
+        // YAML reads most TOML documents as a plain string, TOML is tried first
+        if !trimmed.is_empty() && toml::from_str::<toml::Table>(source).is_ok() {
+            return Format::Toml;
+        }
+        match extension {
+            Some(format @ (Format::Json | Format::Toml | Format::Hcl)) => format,
+            _ => Format::Yaml,
+        }
+    }
+}
+
+/// Parses `source` read from `path` in `format`, or in the detected format if `None`
+pub fn parse<T: DeserializeOwned>(
+    what: &str,
+    path: &Path,
+    source: &str,
+    format: Option<Format>,
+) -> Result<T, String> {
+    match format.unwrap_or_else(|| Format::detect(path, source)) {
+        Format::Json => from_json(what, path, source),
+        Format::Yaml => from_yaml(what, path, source),
+        Format::Toml => from_toml(what, path, source),
+        Format::Hcl => from_hcl(what, path, source),
+        Format::Ini => from_ini(what, path, source),
+        Format::Properties => from_properties(what, path, source),
+    }
+}
+
+/// Lines shown before and after the line of the error
+const CONTEXT_LINES: usize = 2;
+
+/// Parses the YAML (or JSON) `source` read from `path`. `what` describes the file in the error
+/// message, such as "definition file"
+pub fn from_yaml<T: DeserializeOwned>(what: &str, path: &Path, source: &str) -> Result<T, String> {
+    serde_yaml::from_str(source).map_err(|e| {
+        let location = e
+            .location()
+            .map(|location| (location.line(), location.column()));
+        failure(what, path, "json or yaml", source, &e.to_string(), location)
+    })
+}
+
+/// Parses the JSON `source` read from `path`
+pub fn from_json<T: DeserializeOwned>(what: &str, path: &Path, source: &str) -> Result<T, String> {
+    serde_json::from_str(source).map_err(|e| {
+        let location = (e.line() > 0).then(|| (e.line(), e.column()));
+        failure(what, path, "json", source, &e.to_string(), location)
+    })
+}
+
+/// Parses the TOML `source` read from `path`. Dates and times are read as Strings
+pub fn from_toml<T: DeserializeOwned>(what: &str, path: &Path, source: &str) -> Result<T, String> {
+    let table = toml::from_str::<toml::Table>(source).map_err(|e| {
+        let location = e.span().map(|span| {
+            let before = &source[..span.start];
+            let line = before.matches('\n').count() + 1;
+            let column = before.len() - before.rfind('\n').map_or(0, |index| index + 1) + 1;
+            (line, column)
+        });
+        failure(what, path, "toml", source, e.message(), location)
+    })?;
+    T::deserialize(toml_to_json(toml::Value::Table(table)))
+        .map_err(|e| format!("error: The {what} {path:?} can't be read : {e}"))
+}
+
+/// Parses the HCL `source` read from `path`. Blocks are read as Objects keyed by their labels,
+/// and expressions other than literals as their `${…}` text
+pub fn from_hcl<T: DeserializeOwned>(what: &str, path: &Path, source: &str) -> Result<T, String> {
+    let value = hcl::from_str::<serde_json::Value>(source).map_err(|e| match e {
+        hcl::Error::Parse(e) => {
+            let location = (e.location().line(), e.location().column());
+            failure(what, path, "hcl", source, e.message(), Some(location))
+        }
+        e => failure(what, path, "hcl", source, &e.to_string(), None),
+    })?;
+    T::deserialize(value).map_err(|e| format!("error: The {what} {path:?} can't be read : {e}"))
+}
+
+/// Parses the INI `source` read from `path`. `[a.b]` sections and `a.b` keys are read as
+/// nested Objects, and values as Strings, quotes removed: `--coerce` reads numbers and booleans
+pub fn from_ini<T: DeserializeOwned>(what: &str, path: &Path, source: &str) -> Result<T, String> {
+    let mut root = serde_json::Map::new();
+    let mut section = Vec::new();
+    for (index, line) in source.lines().enumerate() {
+        let fail = |column: usize, reason: &str| {
+            failure(what, path, "ini", source, reason, Some((index + 1, column)))
+        };
+        let trimmed = line.trim();
+        if trimmed.is_empty() || trimmed.starts_with([';', '#']) {
+            continue;
+        }
+        let column = line.len() - line.trim_start().len() + 1;
+        if let Some(name) = trimmed.strip_prefix('[') {
+            let Some(name) = name.strip_suffix(']') else {
+                return Err(fail(
+                    column + trimmed.len(),
+                    "expected `]` closing the section",
+                ));
+            };
+            section = name.split('.').map(|key| key.trim().to_string()).collect();
+            insert_dotted(&mut root, &section, None).map_err(|reason| fail(column, &reason))?;
+            continue;
+        }
+        let Some((key, value)) = trimmed.split_once(['=', ':']) else {
+            return Err(fail(column, "expected `key = value`"));
+        };
+        let value = value.trim();
+        let value = [('"', '"'), ('\'', '\'')]
+            .into_iter()
+            .find_map(|(open, close)| value.strip_prefix(open)?.strip_suffix(close))
+            .unwrap_or(value);
+        let keys: Vec<String> = (section.iter().map(String::as_str))
+            .chain(key.split('.').map(str::trim))
+            .map(String::from)
+            .collect();
+        insert_dotted(&mut root, &keys, Some(value.to_string()))
+            .map_err(|reason| fail(column, &reason))?;
+    }
+    T::deserialize(serde_json::Value::Object(root))
+        .map_err(|e| format!("error: The {what} {path:?} can't be read : {e}"))
+}
+
+/// Parses the Java properties `source` read from `path`. `a.b` keys are read as nested Objects,
+/// and values as Strings: `--coerce` reads numbers and booleans
+pub fn from_properties<T: DeserializeOwned>(
+    what: &str,
+    path: &Path,
+    source: &str,
+) -> Result<T, String> {
+    let mut root = serde_json::Map::new();
+    let lines: Vec<&str> = source.lines().collect();
+    let mut index = 0;
+    while index < lines.len() {
+        let number = index + 1;
+        let mut logical = lines[index].trim_start().to_string();
+        index += 1;
+        if logical.is_empty() || logical.starts_with(['#', '!']) {
+            continue;
+        }
+        // An odd number of trailing backslashes continues the line
+        while (logical.len() - logical.trim_end_matches('\\').len()) % 2 == 1 && index < lines.len()
+        {
+            logical.pop();
+            logical.push_str(lines[index].trim_start());
+            index += 1;
+        }
+        let column = lines[number - 1].len() - lines[number - 1].trim_start().len() + 1;
+        let fail = |reason: String| {
+            let location = Some((number, column));
+            failure(what, path, "properties", source, &reason, location)
+        };
+        let (key, value) = split_property(&logical).map_err(fail)?;
+        let keys: Vec<String> = key.split('.').map(String::from).collect();
+        insert_dotted(&mut root, &keys, Some(value)).map_err(fail)?;
+    }
+    T::deserialize(serde_json::Value::Object(root))
+        .map_err(|e| format!("error: The {what} {path:?} can't be read : {e}"))
+}
+
+/// Unescaped key and value of a properties line, separated by `=`, `:` or spaces
+fn split_property(line: &str) -> Result<(String, String), String> {
+    let mut key = String::new();
+    let mut chars = line.chars();
+    while let Some(c) = chars.next() {
+        match c {
+            '\\' => key.push(unescape(&mut chars)?),
+            '=' | ':' => break,
+            c if c.is_whitespace() => {
+                let rest = chars.as_str().trim_start();
+                chars = rest.strip_prefix(['=', ':']).unwrap_or(rest).chars();
+                break;
+            }
+            c => key.push(c),
+        }
+    }
+    let mut value = String::new();
+    let mut chars = chars.as_str().trim_start().chars();
+    while let Some(c) = chars.next() {
+        match c {
+            '\\' => value.push(unescape(&mut chars)?),
+            c => value.push(c),
+        }
+    }
+    Ok((key, value))
+}
+
+/// Character escaped by the backslash read before `chars`
+fn unescape(chars: &mut std::str::Chars) -> Result<char, String> {
+    Ok(match chars.next() {
+        Some('t') => '\t',
+        Some('n') => '\n',
+        Some('r') => '\r',
+        Some('f') => '\u{c}',
+        Some('u') => {
+            let code: String = chars.by_ref().take(4).collect();
+            u32::from_str_radix(&code, 16)
+                .ok()
+                .and_then(char::from_u32)
+                .ok_or_else(|| format!("`\\u{code}` is not a valid unicode escape"))?
+        }
+        Some(c) => c,
+        None => '\\',
+    })
+}
+
+/// Sets the value at the dotted `keys` of `root`, or creates the Object of a section if `value`
+/// is `None`
+fn insert_dotted(
+    root: &mut serde_json::Map<String, serde_json::Value>,
+    keys: &[String],
+    value: Option<String>,
+) -> Result<(), String> {
+    let Some((last, sections)) = keys.split_last() else {
+        return Ok(());
+    };
+    let mut object = root;
+    for (depth, key) in sections
+        .iter()
+        .chain(value.is_none().then_some(last))
+        .enumerate()
+    {
+        let serde_json::Value::Object(child) = object
+            .entry(key.clone())
+            .or_insert_with(|| serde_json::Value::Object(Default::default()))
+        else {
+            return Err(format!(
+                "`{}` is both a value and a section",
+                keys[..=depth].join(".")
+            ));
+        };
+        object = child;
+    }
+    let Some(value) = value else {
+        return Ok(());
+    };
+    match object.entry(last.clone()) {
+        serde_json::map::Entry::Vacant(entry) => {
+            entry.insert(serde_json::Value::String(value));
+            Ok(())
+        }
+        serde_json::map::Entry::Occupied(_) => {
+            Err(format!("`{}` is defined twice", keys.join(".")))
+        }
+    }
+}
+
+fn toml_to_json(value: toml::Value) -> serde_json::Value {
+    match value {
+        toml::Value::String(string) => serde_json::Value::String(string),
+        toml::Value::Integer(number) => number.into(),
+        toml::Value::Float(number) => serde_json::Number::from_f64(number)
+            .map_or(serde_json::Value::Null, serde_json::Value::Number),
+        toml::Value::Boolean(boolean) => serde_json::Value::Bool(boolean),
+        toml::Value::Datetime(datetime) => serde_json::Value::String(datetime.to_string()),
+        toml::Value::Array(items) => items.into_iter().map(toml_to_json).collect(),
+        toml::Value::Table(table) => serde_json::Value::Object(
+            table
+                .into_iter()
+                .map(|(key, value)| (key, toml_to_json(value)))
+                .collect(),
+        ),
+    }
+}
+
+/// `location` is the 1-based line and column of the error
+fn failure(
+    what: &str,
+    path: &Path,
+    syntax: &str,
+    source: &str,
+    reason: &str,
+    location: Option<(usize, usize)>,
+) -> String {
+    let mut message = format!("error: The {what} {path:?} is not propper {syntax}: {reason}");
+    let Some((line, column)) = location else {
+        return message;
+    };
+    message.push_str(&format!("\n  --> {}:{line}:{column}\n", path.display()));
+    message.push_str(&excerpt(source, line, column));
+    if let Some(hint) = hint(source, line, reason) {
+        // Aligned with the gutter of the excerpt
+        let width = (line + CONTEXT_LINES)
+            .min(source.lines().count())
+            .to_string()
+            .len();
+        message.push_str(&format!("\n{:width$} = hint: {hint}", ""));
+    }
+    message
+}
+
+/// Validation `error` of the document `source` read from `path`, with the lines around the
+/// offending value, its path, the constraint it breaks and the value itself
+pub fn validation_failure(
+    path: &Path,
+    source: &str,
+    spans: &SourceMap,
+    error: &As3JsonPath<AS3ValidationError>,
+) -> String {
+    let mut message = format!(
+        "error[{}]: {}",
+        error.1.code(),
+        messages::render(&error.1, &messages::English)
+    );
+    let mut width = 1;
+    if let Some(position) = spans.locate_error(error) {
+        message.push_str(&format!("\n  --> {}:{position}\n", path.display()));
+        message.push_str(&excerpt(source, position.line, position.column));
+        width = (position.line + CONTEXT_LINES)
+            .min(source.lines().count())
+            .to_string()
+            .len();
+    }
+    message.push_str(&format!("\n{:width$} = path: {}", "", error.0));
+    let (expected, got) = error.1.expected_and_got();
+    if let Some(expected) = expected {
+        let expected = match expected {
+            serde_json::Value::String(expected) => expected,
+            expected => expected.to_string(),
+        };
+        message.push_str(&format!("\n{:width$} = expected: {expected}", ""));
+    }
+    if let Some(got) = got {
+        message.push_str(&format!("\n{:width$} = got: {got}", ""));
+    }
+    message
+}
+
+/// Lines around `line`, numbered, with a caret under `column`
+pub fn excerpt(source: &str, line: usize, column: usize) -> String {
+    let lines: Vec<&str> = source.lines().collect();
+    let first = line.saturating_sub(CONTEXT_LINES).max(1);
+    let last = (line + CONTEXT_LINES).min(lines.len());
+    let width = last.to_string().len();
+
+    let mut excerpt = format!("{:width$} |", "");
+    for number in first..=last {
+        excerpt.push_str(&format!("\n{number:>width$} | {}", lines[number - 1]));
+        if number == line {
+            excerpt.push_str(&format!(
+                "\n{:width$} | {}^",
+                "",
+                " ".repeat(column.saturating_sub(1))
+            ));
+        }
+    }
+    excerpt
+}
+
+/// Explanation of the mistakes behind the most puzzling parser messages
+fn hint(source: &str, line: usize, reason: &str) -> Option<&'static str> {
+    let text = source.lines().nth(line.saturating_sub(1)).unwrap_or("");
+    let indentation = &text[..text.len() - text.trim_start().len()];
+    if indentation.contains('\t') {
+        Some("tabs are not allowed in YAML indentation, use spaces")
+    } else if reason.contains("mapping values are not allowed") {
+        Some("values containing `: ` must be quoted")
+    } else if reason.contains("trailing comma") {
+        Some("JSON doesn't allow a comma after the last item")
+    } else if reason.contains("did not find expected key")
+        || reason.contains("did not find expected '-'")
+    {
+        Some("check that the keys of a same mapping, or the items of a list, are aligned")
+    } else {
+        None
+    }
+}
@@ -0,0 +1,124 @@
+//! Markdown and HTML digests of the changes recorded in the history, for nightly data-quality
+//! reviews on mailing lists or chats
+
+use crate::history::{DocumentResult, JobDigest};
+
+/// Documents listed per section, the rest is summed up
+const LISTED: usize = 20;
+
+pub fn to_markdown(digests: &[JobDigest], period: &str) -> String {
+    let mut output = format!("# AS3 digest for the last {period}\n");
+    if digests.is_empty() {
+        output.push_str("\nNo run in this period.\n");
+    }
+    for digest in digests {
+        output.push_str(&format!("\n## {}\n\n", digest.job));
+        output.push_str(&format!("Latest run: {}", digest.time));
+        if let Some(baseline_time) = &digest.baseline_time {
+            output.push_str(&format!(", compared with the run of {baseline_time}"));
+        }
+        output.push_str("\n\n");
+        output.push_str(&format!(
+            "| New failures | Regressions | Fixed | Still failing |\n|---|---|---|---|\n| {} | {} | {} | {} |\n",
+            digest.new_failures.len(),
+            digest.regressions.len(),
+            digest.fixed.len(),
+            digest.still_failing
+        ));
+        for (title, results) in [
+            ("New failures", &digest.new_failures),
+            ("Regressions", &digest.regressions),
+        ] {
+            if results.is_empty() {
+                continue;
+            }
+            output.push_str(&format!("\n### {title}\n\n"));
+            for line in failure_lines(results, "`") {
+                output.push_str(&format!("- {line}\n"));
+            }
+        }
+        if !digest.fixed.is_empty() {
+            output.push_str("\n### Fixed\n\n");
+            for line in listed(&digest.fixed, |document| format!("`{document}`")) {
+                output.push_str(&format!("- {line}\n"));
+            }
+        }
+    }
+    output
+}
+
+pub fn to_html(digests: &[JobDigest], period: &str) -> String {
+    let mut output = format!("<h1>AS3 digest for the last {}</h1>\n", escape(period));
+    if digests.is_empty() {
+        output.push_str("<p>No run in this period.</p>\n");
+    }
+    for digest in digests {
+        output.push_str(&format!(
+            "<h2>{}</h2>\n<p>Latest run: {}",
+            escape(&digest.job),
+            escape(&digest.time)
+        ));
+        if let Some(baseline_time) = &digest.baseline_time {
+            output.push_str(&format!(
+                ", compared with the run of {}",
+                escape(baseline_time)
+            ));
+        }
+        output.push_str("</p>\n");
+        output.push_str(&format!(
+            "<table>\n<tr><th>New failures</th><th>Regressions</th><th>Fixed</th><th>Still failing</th></tr>\n<tr><td>{}</td><td>{}</td><td>{}</td><td>{}</td></tr>\n</table>\n",
+            digest.new_failures.len(),
+            digest.regressions.len(),
+            digest.fixed.len(),
+            digest.still_failing
+        ));
+        for (title, results) in [
+            ("New failures", &digest.new_failures),
+            ("Regressions", &digest.regressions),
+        ] {
+            if results.is_empty() {
+                continue;
+            }
+            output.push_str(&format!("<h3>{title}</h3>\n<ul>\n"));
+            for line in failure_lines(results, "") {
+                output.push_str(&format!("<li>{}</li>\n", escape(&line)));
+            }
+            output.push_str("</ul>\n");
+        }
+        if !digest.fixed.is_empty() {
+            output.push_str("<h3>Fixed</h3>\n<ul>\n");
+            for line in listed(&digest.fixed, |document| document.to_string()) {
+                output.push_str(&format!("<li>{}</li>\n", escape(&line)));
+            }
+            output.push_str("</ul>\n");
+        }
+    }
+    output
+}
+
+/// `quote` surrounds documents and paths
+fn failure_lines(results: &[DocumentResult], quote: &str) -> Vec<String> {
+    listed(results, |result| match &result.failure {
+        Some((code, path)) => format!(
+            "{quote}{}{quote}: {code} at {quote}{path}{quote}",
+            result.document
+        ),
+        None => format!("{quote}{}{quote}", result.document),
+    })
+}
+
+/// Formats the first items, and a last line counting the others
+fn listed<T>(items: &[T], format: impl Fn(&T) -> String) -> Vec<String> {
+    let mut lines: Vec<String> = items.iter().take(LISTED).map(format).collect();
+    if items.len() > LISTED {
+        lines.push(format!("… and {} more", items.len() - LISTED));
+    }
+    lines
+}
+
+fn escape(text: &str) -> String {
+    text.replace('&', "&amp;")
+        .replace('<', "&lt;")
+        .replace('>', "&gt;")
+        .replace('"', "&quot;")
+}
@@ -1,45 +1,520 @@
-use crate::{validator::AS3Validator, AS3Data};
+use crate::{
+    messages,
+    validator::{AS3Validator, LengthUnit},
+    AS3Data,
+};
+use serde::Serialize;
+use serde_json::{json, Value};
+use std::sync::Arc;
 use thiserror::Error;
 #[derive(Error, Debug, PartialEq)]
 #[error("{1} in [{0}]. ")]
-pub struct As3JsonPath<T: std::error::Error>(pub String, pub T);
+pub struct As3JsonPath<T: std::error::Error>(pub JsonPath, #[source] pub T);
+
+/// Serialized as the fields of the error, with its `path`
+impl<T: std::error::Error + Serialize> Serialize for As3JsonPath<T> {
+    fn serialize<S: serde::Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        #[derive(Serialize)]
+        struct Located<'a, T> {
+            path: &'a JsonPath,
+            #[serde(flatten)]
+            error: &'a T,
+        }
+        Located {
+            path: &self.0,
+            error: &self.1,
+        }
+        .serialize(serializer)
+    }
+}
+
+/// Location of an error: the keys and List indices leading to the value from `ROOT`, or to the
+/// definition from `Root`. Displayed as `ROOT -> items -> [2] -> name`
+#[derive(Debug, Clone, Default, PartialEq, Eq, Hash)]
+pub struct JsonPath(pub Vec<PathSegment>);
+
+#[derive(Debug, Clone, PartialEq, Eq, Hash)]
+pub enum PathSegment {
+    Key(String),
+    /// Position of an item in a List
+    Index(usize),
+}
+
+impl JsonPath {
+    /// Path of a single key, such as `ROOT`
+    pub fn root(name: &str) -> JsonPath {
+        JsonPath(vec![PathSegment::Key(name.to_string())])
+    }
+
+    /// The path followed by `key`
+    pub fn with_key(&self, key: &str) -> JsonPath {
+        let mut path = self.clone();
+        path.push_key(key);
+        path
+    }
+
+    /// The path followed by the List `index`
+    pub fn with_index(&self, index: usize) -> JsonPath {
+        let mut path = self.clone();
+        path.push_index(index);
+        path
+    }
+
+    pub fn push_key(&mut self, key: &str) {
+        self.0.push(PathSegment::Key(key.to_string()));
+    }
+
+    pub fn push_index(&mut self, index: usize) {
+        self.0.push(PathSegment::Index(index));
+    }
+
+    pub fn pop(&mut self) -> Option<PathSegment> {
+        self.0.pop()
+    }
+
+    pub fn segments(&self) -> &[PathSegment] {
+        &self.0
+    }
+
+    /// Keys of the path, the `ROOT` and List indices aside
+    pub fn keys(&self) -> impl Iterator<Item = &str> {
+        self.0.iter().skip(1).filter_map(|segment| match segment {
+            PathSegment::Key(key) => Some(key.as_str()),
+            PathSegment::Index(_) => None,
+        })
+    }
+}
+
+impl std::fmt::Display for JsonPath {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        for (position, segment) in self.0.iter().enumerate() {
+            if position > 0 {
+                write!(f, " -> ")?;
+            }
+            match segment {
+                PathSegment::Key(key) => write!(f, "{key}")?,
+                PathSegment::Index(index) => write!(f, "[{index}]")?,
+            }
+        }
+        Ok(())
+    }
+}
+
+/// Serialized in its displayed form
+impl Serialize for JsonPath {
+    fn serialize<S: serde::Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        serializer.collect_str(self)
+    }
+}
+
+/// Error of a library or of the system behind a definition error, kept as the `source()` of
+/// the error. Causes are equal when their messages are
+#[derive(Debug, Clone)]
+pub struct Cause(Arc<dyn std::error::Error + Send + Sync>);
+
+impl Cause {
+    pub fn new(error: impl std::error::Error + Send + Sync + 'static) -> Cause {
+        Cause(Arc::new(error))
+    }
+}
+
+impl From<String> for Cause {
+    fn from(message: String) -> Cause {
+        Cause(Arc::from(Box::<dyn std::error::Error + Send + Sync>::from(
+            message,
+        )))
+    }
+}
+
+impl std::fmt::Display for Cause {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        self.0.fmt(f)
+    }
+}
+
+impl std::error::Error for Cause {
+    fn source(&self) -> Option<&(dyn std::error::Error + 'static)> {
+        self.0.source()
+    }
+}
+
+impl PartialEq for Cause {
+    fn eq(&self, other: &Cause) -> bool {
+        self.to_string() == other.to_string()
+    }
+}
 
 #[derive(Error, Debug, PartialEq)]
 pub enum AS3ValidationError {
-    #[error("Mismatched types. Expected `{:?}` got `{:?}`" , .expected , .got)]
+    #[error("Mismatched types. Expected `{:?}` got `{}`" , .expected , .got)]
     TypeError {
         expected: AS3Validator,
         got: AS3Data,
     },
-    #[error("Key {} is not" , .key )]
-    MissingKey { key: String },
+    /// `suggestion` is a key of the data spelled closely, such as `makr` for `maker`
+    #[error("Key {key} is not{}", .suggestion.as_ref().map(|s| format!("; found `{s}`, did you mean `{key}`?")).unwrap_or_default())]
+    MissingKey {
+        key: String,
+        suggestion: Option<String>,
+    },
+    /// `suggestion` is a field missing from the data spelled closely
+    #[error("Key {key} is not expected{}", .suggestion.as_ref().map(|s| format!(", did you mean `{s}`?")).unwrap_or_default())]
+    UnexpectedKey {
+        key: String,
+        suggestion: Option<String>,
+    },
+    #[error("Deprecated value{}", .message.as_ref().map(|m| format!(": {m}")).unwrap_or_default())]
+    Deprecated { message: Option<String> },
+    #[error("Unknown {} `{}`, expected one of {}" , .discriminator, .tag, .expected.join(", "))]
+    UnknownTag {
+        discriminator: String,
+        tag: String,
+        expected: Vec<String>,
+    },
+    #[error("More than {} nested references, the data is too deep" , .depth )]
+    RecursionLimit { depth: usize },
+    #[error("Key {} is required when {} is present" , .key, .required_by )]
+    MissingDependency { key: String, required_by: String },
+    #[error("Assertion `{}` failed with {} = `{}` and {} = `{}`" , .assertion, .left.0, .left.1, .right.0, .right.1)]
+    AssertionFailed {
+        assertion: String,
+        /// Field and value of each side of the comparison
+        left: (String, AS3Data),
+        right: (String, AS3Data),
+    },
     #[error("Word {} is not following the `{}` regex" , .word, .regex )]
     RegexError { word: String, regex: String },
+    #[error("Word {} is not following any of the `{}` regexes" , .word, .regexes.join("`, `") )]
+    AnyRegexError { word: String, regexes: Vec<String> },
+    #[error("Word {} is following the denied `{}` regex" , .word, .regex )]
+    DenyRegexError { word: String, regex: String },
+    /// `position` counts characters from 1
+    #[error("Word {} has `{}` at position {}, which `{}` doesn't allow" , .word, .character, .position, .class )]
+    CharacterClass {
+        word: String,
+        class: String,
+        character: char,
+        position: usize,
+    },
+    #[error("Word {} is not a valid {}: {}" , .word, .format, .reason )]
+    FormatError {
+        word: String,
+        format: String,
+        reason: String,
+    },
 
     #[error(" `{}` is under the minumum of `{}`" , .number , .minimum)]
-    MinimumDouble { number: f64, minimum: f64 },
+    MinimumDecimal { number: f64, minimum: f64 },
     #[error(" `{}` is above the maximum of `{}`" , .number , .maximum)]
-    MaximumDouble { number: f64, maximum: f64 },
+    MaximumDecimal { number: f64, maximum: f64 },
+    /// `number` is wide enough for the Integers above `i64::MAX`
     #[error(" `{}` is under the minumum of `{}`" , .number , .minimum)]
-    MinimumInteger { number: i64, minimum: i64 },
+    MinimumInteger { number: i128, minimum: i64 },
     #[error(" `{}` is above the maximum of `{}`" , .number , .maximum)]
-    MaximumInteger { number: i64, maximum: i64 },
+    MaximumInteger { number: i128, maximum: i64 },
+    #[error(" `{}` doesn't fit in a `{}`" , .number , .width)]
+    IntegerWidth { number: i128, width: String },
     #[error(" Error during validation: {0}")]
     Generic(String),
-    #[error(" {} is {} charcters long, above the max lenght allowed of {} ." , .string, .current_lenght , .max_length)]
+    #[error(" {} is {} {} long, above the max lenght allowed of {} ." , .string, .current_lenght, .unit, .max_length)]
     MaximumString {
         string: String,
         current_lenght: i64,
         max_length: i64,
+        unit: LengthUnit,
     },
 
-    #[error(" {} is {} charcters long, above the max lenght allowed of {}" , .string, .current_lenght , .min_length)]
+    #[error(" {} is {} {} long, under the min lenght allowed of {}" , .string, .current_lenght, .unit, .min_length)]
     MinimumString {
         string: String,
         current_lenght: i64,
         min_length: i64,
+        unit: LengthUnit,
     },
 
+    #[error(" `{}` is before the minimum of `{}`" , .date , .minimum)]
+    MinimumDate { date: String, minimum: String },
+    #[error(" `{}` is after the maximum of `{}`" , .date , .maximum)]
+    MaximumDate { date: String, maximum: String },
+    #[error(" `{}` is before the minimum of `{}`" , .datetime , .minimum)]
+    MinimumDateTime { datetime: String, minimum: String },
+    #[error(" `{}` is after the maximum of `{}`" , .datetime , .maximum)]
+    MaximumDateTime { datetime: String, maximum: String },
+    #[error(" `{}` is earlier than the minimum of `{}`" , .time , .minimum)]
+    MinimumTime { time: String, minimum: String },
+    #[error(" `{}` is later than the maximum of `{}`" , .time , .maximum)]
+    MaximumTime { time: String, maximum: String },
+    #[error(" `{}` is shorter than the minimum of `{}`" , .duration , .minimum)]
+    MinimumDuration { duration: String, minimum: String },
+    #[error(" `{}` is longer than the maximum of `{}`" , .duration , .maximum)]
+    MaximumDuration { duration: String, maximum: String },
+    #[error(" {} entries, under the minimum of `{}`" , .count , .min_entries)]
+    MinimumEntries { count: i64, min_entries: i64 },
+    #[error(" {} entries, above the maximum of `{}`" , .count , .max_entries)]
+    MaximumEntries { count: i64, max_entries: i64 },
+    #[error(" `{}` bytes once decoded, under the minimum of `{}`" , .length , .min_bytes)]
+    MinimumBytes { length: i64, min_bytes: i64 },
+    #[error(" `{}` bytes once decoded, above the maximum of `{}`" , .length , .max_bytes)]
+    MaximumBytes { length: i64, max_bytes: i64 },
+
     #[error("field not set as not nullable but is a null")]
     NotNullableNull,
+    /// Error of a definition with a `+message`
+    #[error("{}", .message)]
+    Custom {
+        message: String,
+        #[source]
+        error: Box<AS3ValidationError>,
+    },
+}
+
+impl AS3ValidationError {
+    /// Structured error, behind a `+message` if any
+    pub fn underlying(&self) -> &AS3ValidationError {
+        match self {
+            AS3ValidationError::Custom { error, .. } => error.underlying(),
+            error => error,
+        }
+    }
+
+    /// Name of the variant, to group errors of the same kind
+    pub fn kind(&self) -> &'static str {
+        match self {
+            AS3ValidationError::TypeError { .. } => "TypeError",
+            AS3ValidationError::MissingKey { .. } => "MissingKey",
+            AS3ValidationError::UnexpectedKey { .. } => "UnexpectedKey",
+            AS3ValidationError::Deprecated { .. } => "Deprecated",
+            AS3ValidationError::UnknownTag { .. } => "UnknownTag",
+            AS3ValidationError::RecursionLimit { .. } => "RecursionLimit",
+            AS3ValidationError::MissingDependency { .. } => "MissingDependency",
+            AS3ValidationError::AssertionFailed { .. } => "AssertionFailed",
+            AS3ValidationError::RegexError { .. } => "RegexError",
+            AS3ValidationError::AnyRegexError { .. } => "AnyRegexError",
+            AS3ValidationError::DenyRegexError { .. } => "DenyRegexError",
+            AS3ValidationError::CharacterClass { .. } => "CharacterClass",
+            AS3ValidationError::FormatError { .. } => "FormatError",
+            AS3ValidationError::MinimumDecimal { .. } => "MinimumDecimal",
+            AS3ValidationError::MaximumDecimal { .. } => "MaximumDecimal",
+            AS3ValidationError::MinimumInteger { .. } => "MinimumInteger",
+            AS3ValidationError::MaximumInteger { .. } => "MaximumInteger",
+            AS3ValidationError::IntegerWidth { .. } => "IntegerWidth",
+            AS3ValidationError::Generic(..) => "Generic",
+            AS3ValidationError::MaximumString { .. } => "MaximumString",
+            AS3ValidationError::MinimumString { .. } => "MinimumString",
+            AS3ValidationError::MinimumDate { .. } => "MinimumDate",
+            AS3ValidationError::MaximumDate { .. } => "MaximumDate",
+            AS3ValidationError::MinimumDateTime { .. } => "MinimumDateTime",
+            AS3ValidationError::MaximumDateTime { .. } => "MaximumDateTime",
+            AS3ValidationError::MinimumTime { .. } => "MinimumTime",
+            AS3ValidationError::MaximumTime { .. } => "MaximumTime",
+            AS3ValidationError::MinimumDuration { .. } => "MinimumDuration",
+            AS3ValidationError::MaximumDuration { .. } => "MaximumDuration",
+            AS3ValidationError::MinimumEntries { .. } => "MinimumEntries",
+            AS3ValidationError::MaximumEntries { .. } => "MaximumEntries",
+            AS3ValidationError::MinimumBytes { .. } => "MinimumBytes",
+            AS3ValidationError::MaximumBytes { .. } => "MaximumBytes",
+            AS3ValidationError::NotNullableNull => "NotNullableNull",
+            // Grouped with the errors of the same constraint
+            AS3ValidationError::Custom { error, .. } => error.kind(),
+        }
+    }
+
+    /// Stable code of the error, for tools branching on the kind of error. Unlike
+    /// [`AS3ValidationError::kind`], codes are kept when variants are renamed
+    pub fn code(&self) -> &'static str {
+        match self {
+            AS3ValidationError::TypeError { .. } => "E_TYPE_MISMATCH",
+            AS3ValidationError::MissingKey { .. } => "E_MISSING_KEY",
+            AS3ValidationError::UnexpectedKey { .. } => "E_UNEXPECTED_KEY",
+            AS3ValidationError::Deprecated { .. } => "E_DEPRECATED",
+            AS3ValidationError::UnknownTag { .. } => "E_UNKNOWN_TAG",
+            AS3ValidationError::RecursionLimit { .. } => "E_RECURSION_LIMIT",
+            AS3ValidationError::MissingDependency { .. } => "E_MISSING_DEPENDENCY",
+            AS3ValidationError::AssertionFailed { .. } => "E_ASSERTION",
+            AS3ValidationError::RegexError { .. } => "E_REGEX",
+            AS3ValidationError::AnyRegexError { .. } => "E_ANY_REGEX",
+            AS3ValidationError::DenyRegexError { .. } => "E_DENY_REGEX",
+            AS3ValidationError::CharacterClass { .. } => "E_CHARACTER_CLASS",
+            AS3ValidationError::FormatError { .. } => "E_FORMAT",
+            AS3ValidationError::MinimumDecimal { .. } => "E_MIN_DECIMAL",
+            AS3ValidationError::MaximumDecimal { .. } => "E_MAX_DECIMAL",
+            AS3ValidationError::MinimumInteger { .. } => "E_MIN_INT",
+            AS3ValidationError::MaximumInteger { .. } => "E_MAX_INT",
+            AS3ValidationError::IntegerWidth { .. } => "E_INT_WIDTH",
+            AS3ValidationError::Generic(..) => "E_GENERIC",
+            AS3ValidationError::MaximumString { .. } => "E_MAX_LENGTH",
+            AS3ValidationError::MinimumString { .. } => "E_MIN_LENGTH",
+            AS3ValidationError::MinimumDate { .. } => "E_MIN_DATE",
+            AS3ValidationError::MaximumDate { .. } => "E_MAX_DATE",
+            AS3ValidationError::MinimumDateTime { .. } => "E_MIN_DATETIME",
+            AS3ValidationError::MaximumDateTime { .. } => "E_MAX_DATETIME",
+            AS3ValidationError::MinimumTime { .. } => "E_MIN_TIME",
+            AS3ValidationError::MaximumTime { .. } => "E_MAX_TIME",
+            AS3ValidationError::MinimumDuration { .. } => "E_MIN_DURATION",
+            AS3ValidationError::MaximumDuration { .. } => "E_MAX_DURATION",
+            AS3ValidationError::MinimumEntries { .. } => "E_MIN_ENTRIES",
+            AS3ValidationError::MaximumEntries { .. } => "E_MAX_ENTRIES",
+            AS3ValidationError::MinimumBytes { .. } => "E_MIN_BYTES",
+            AS3ValidationError::MaximumBytes { .. } => "E_MAX_BYTES",
+            AS3ValidationError::NotNullableNull => "E_NULL",
+            AS3ValidationError::Custom { error, .. } => error.code(),
+        }
+    }
+
+    /// Constraint the value broke and the offending value, when the error has them
+    pub fn expected_and_got(&self) -> (Option<Value>, Option<Value>) {
+        use AS3ValidationError::*;
+        match self {
+            TypeError { expected, got } => (Some(json!(type_name(expected))), Some(got.into())),
+            MissingKey { key, .. } => (Some(json!(key)), None),
+            UnexpectedKey { key, .. } => (None, Some(json!(key))),
+            Deprecated { .. } | Generic(..) => (None, None),
+            UnknownTag { tag, expected, .. } => (Some(json!(expected)), Some(json!(tag))),
+            RecursionLimit { depth } => (Some(json!(depth)), None),
+            MissingDependency { key, .. } => (Some(json!(key)), None),
+            AssertionFailed {
+                assertion,
+                left,
+                right,
+            } => (
+                Some(json!(assertion)),
+                Some(json!({ &left.0: Value::from(&left.1), &right.0: Value::from(&right.1) })),
+            ),
+            RegexError { word, regex } => (Some(json!(regex)), Some(json!(word))),
+            AnyRegexError { word, regexes } => (Some(json!(regexes)), Some(json!(word))),
+            DenyRegexError { word, .. } => (None, Some(json!(word))),
+            CharacterClass {
+                class, character, ..
+            } => (Some(json!(class)), Some(json!(character))),
+            FormatError { word, format, .. } => (Some(json!(format)), Some(json!(word))),
+            MinimumDecimal { number, minimum } => (Some(json!(minimum)), Some(json!(number))),
+            MaximumDecimal { number, maximum } => (Some(json!(maximum)), Some(json!(number))),
+            MinimumInteger { number, minimum } => (Some(json!(minimum)), Some(integer(*number))),
+            MaximumInteger { number, maximum } => (Some(json!(maximum)), Some(integer(*number))),
+            IntegerWidth { number, width } => (Some(json!(width)), Some(integer(*number))),
+            MaximumString {
+                current_lenght,
+                max_length,
+                ..
+            } => (Some(json!(max_length)), Some(json!(current_lenght))),
+            MinimumString {
+                current_lenght,
+                min_length,
+                ..
+            } => (Some(json!(min_length)), Some(json!(current_lenght))),
+            MinimumDate { date, minimum } => (Some(json!(minimum)), Some(json!(date))),
+            MaximumDate { date, maximum } => (Some(json!(maximum)), Some(json!(date))),
+            MinimumDateTime { datetime, minimum } => (Some(json!(minimum)), Some(json!(datetime))),
+            MaximumDateTime { datetime, maximum } => (Some(json!(maximum)), Some(json!(datetime))),
+            MinimumTime { time, minimum } => (Some(json!(minimum)), Some(json!(time))),
+            MaximumTime { time, maximum } => (Some(json!(maximum)), Some(json!(time))),
+            MinimumDuration { duration, minimum } => (Some(json!(minimum)), Some(json!(duration))),
+            MaximumDuration { duration, maximum } => (Some(json!(maximum)), Some(json!(duration))),
+            MinimumEntries { count, min_entries } => (Some(json!(min_entries)), Some(json!(count))),
+            MaximumEntries { count, max_entries } => (Some(json!(max_entries)), Some(json!(count))),
+            MinimumBytes { length, min_bytes } => (Some(json!(min_bytes)), Some(json!(length))),
+            MaximumBytes { length, max_bytes } => (Some(json!(max_bytes)), Some(json!(length))),
+            NotNullableNull => (None, Some(Value::Null)),
+            Custom { error, .. } => error.expected_and_got(),
+        }
+    }
+}
+
+/// Serialized as its `code` and English `message`, with the `expected` constraint and the value `got`
+/// when the error has them
+impl Serialize for AS3ValidationError {
+    fn serialize<S: serde::Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        use serde::ser::SerializeMap;
+        let (expected, got) = self.expected_and_got();
+        let mut map = serializer.serialize_map(None)?;
+        map.serialize_entry("code", self.code())?;
+        map.serialize_entry("message", &messages::render(self, &messages::English))?;
+        if let Some(expected) = expected {
+            map.serialize_entry("expected", &expected)?;
+        }
+        if let Some(got) = got {
+            map.serialize_entry("got", &got)?;
+        }
+        map.end()
+    }
+}
+
+/// `number` as a JSON number, or as a String past the range of `u64`
+fn integer(number: i128) -> Value {
+    serde_json::Number::from_i128(number).map_or(json!(number.to_string()), Value::Number)
+}
+
+/// Short name of the type checked by `validator`, such as `List` or `Integer`
+pub(crate) fn type_name(validator: &AS3Validator) -> String {
+    match validator {
+        AS3Validator::Annotated { inner, .. } => type_name(inner),
+        AS3Validator::Conditional { base, .. } => type_name(base),
+        AS3Validator::Nullable(inner) => format!("{}?", type_name(inner)),
+        AS3Validator::Ref(reference) => reference.name().to_string(),
+        AS3Validator::Literal { .. } => validator
+            .enumeration()
+            .unwrap_or_default()
+            .iter()
+            .map(|value| format!("`{value}`"))
+            .collect::<Vec<_>>()
+            .join(" | "),
+        AS3Validator::Object { .. } => "Object".to_string(),
+        AS3Validator::String { .. } => "String".to_string(),
+        AS3Validator::Integer { .. } => "Integer".to_string(),
+        AS3Validator::Decimal { .. } => "Decimal".to_string(),
+        AS3Validator::List(..) => "List".to_string(),
+        AS3Validator::Map { .. } => "Map".to_string(),
+        AS3Validator::Boolean => "Boolean".to_string(),
+        AS3Validator::Null => "Null".to_string(),
+        AS3Validator::Any => "Any".to_string(),
+        AS3Validator::Date { .. } => "Date".to_string(),
+        AS3Validator::DateTime { .. } => "DateTime".to_string(),
+        AS3Validator::Time { .. } => "Time".to_string(),
+        AS3Validator::Duration { .. } => "Duration".to_string(),
+        AS3Validator::Bytes { .. } => "Bytes".to_string(),
+        AS3Validator::TaggedUnion { .. } => "TaggedUnion".to_string(),
+    }
+}
+
+#[derive(Error, Debug, PartialEq)]
+pub enum AS3DefinitionError {
+    #[error("Definition must start with a Yaml Mapping")]
+    NotAMapping,
+    #[error("Missing root word `{0}` from definition")]
+    MissingRoot(String),
+    #[error("Type definition missing")]
+    MissingType,
+    #[error("`{0}` is an unsupported type")]
+    UnsupportedType(String),
+    #[error("Unknown keyword `{}`{}" , .keyword, .suggestion.as_ref().map(|s| format!(", did you mean `{s}`?")).unwrap_or_default())]
+    UnknownKeyword {
+        keyword: String,
+        suggestion: Option<String>,
+    },
+    #[error("The required `{0}` property is missing")]
+    MissingKeyword(String),
+    #[error("`{}` must be {}" , .keyword, .expected)]
+    InvalidValue { keyword: String, expected: String },
+    #[error("Invalid regex in `{}` : {}" , .keyword, .reason)]
+    InvalidRegex {
+        keyword: String,
+        #[source]
+        reason: Cause,
+    },
+    #[error("Conflicting settings: {0}")]
+    Conflict(String),
+    #[error("`{0}` is not among the `+defs`")]
+    UnknownDefinition(String),
+    #[error("Circular reference between definitions: {0}")]
+    CircularReference(String),
+    #[error("Could not include `{}` : {}", .file, .reason)]
+    Include {
+        file: String,
+        #[source]
+        reason: Cause,
+    },
+    #[error("Circular include between files: {0}")]
+    CircularInclude(String),
+    #[error("{0}")]
+    Generic(String),
 }
@@ -0,0 +1,32 @@
+//! Retrieval of the definitions included from an `http(s)` URL with `+include`. Remote
+//! includes are refused unless a [`Fetcher`] is set in the
+//! [`DefinitionOptions`](crate::validator::DefinitionOptions), letting embedders decide on
+//! caching and on which hosts can be reached.
+
+/// Source of remote definitions
+pub trait Fetcher: std::fmt::Debug + Send + Sync {
+    /// Text of the definition at `url`
+    fn fetch(&self, url: &url::Url) -> Result<String, String>;
+}
+
+/// Downloads every definition, without caching. WASI has no network, it always fails there
+#[cfg(feature = "tools")]
+#[derive(Debug, Default, Clone, Copy)]
+pub struct HttpFetcher;
+
+#[cfg(feature = "tools")]
+impl Fetcher for HttpFetcher {
+    #[cfg(not(target_os = "wasi"))]
+    fn fetch(&self, url: &url::Url) -> Result<String, String> {
+        ureq::get(url.as_str())
+            .call()
+            .map_err(|e| e.to_string())?
+            .into_string()
+            .map_err(|e| e.to_string())
+    }
+
+    #[cfg(target_os = "wasi")]
+    fn fetch(&self, _url: &url::Url) -> Result<String, String> {
+        Err("the network is not available on WASI".to_string())
+    }
+}
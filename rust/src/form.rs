@@ -0,0 +1,228 @@
+//! Edit forms generated from a definition: the JSON Schema of the data, with the UI schema of
+//! react-jsonschema-form or JSONForms. Fields are ordered as in the definition, labelled with
+//! their `+title`, and get a widget chosen from their type
+
+use crate::{json_schema::to_editor_schema, validator::AS3Validator, validator::StringFormat};
+use serde_json::{json, Map, Value};
+use std::borrow::Cow;
+
+/// Form library the UI schema is written for
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum FormStyle {
+    /// react-jsonschema-form, given `schema` and `uiSchema`
+    Rjsf,
+    /// JSONForms, given `schema` and `uischema`
+    JsonForms,
+}
+
+impl std::str::FromStr for FormStyle {
+    type Err = String;
+
+    fn from_str(style: &str) -> Result<FormStyle, String> {
+        match style.to_lowercase().as_str() {
+            "rjsf" => Ok(FormStyle::Rjsf),
+            "jsonforms" => Ok(FormStyle::JsonForms),
+            _ => Err(format!("`{style}` is not one of rjsf or jsonforms")),
+        }
+    }
+}
+
+/// Strings longer than this are edited in a multi-line text area
+const TEXTAREA_LENGTH: i64 = 256;
+
+/// Schema and UI schema of the form editing the values accepted by `validator`
+pub fn to_form(validator: &AS3Validator, style: FormStyle) -> Value {
+    let schema = to_editor_schema(validator);
+    let mut builder = Builder::default();
+    match style {
+        FormStyle::Rjsf => json!({ "schema": schema, "uiSchema": builder.rjsf(validator) }),
+        FormStyle::JsonForms => {
+            json!({ "schema": schema, "uischema": builder.jsonforms(validator, "#", None) })
+        }
+    }
+}
+
+/// Validator of a value, past the wrappers that don't change the widget. Validators reached
+/// through a `+defs` are copied, as the definition is only kept while it is used
+struct Resolved<'a> {
+    validator: Cow<'a, AS3Validator>,
+    title: Option<String>,
+    /// `+defs` followed to reach the validator
+    references: Vec<String>,
+}
+
+#[derive(Default)]
+struct Builder {
+    /// `+defs` being expanded, as definitions can refer to themselves
+    expanding: Vec<String>,
+}
+
+impl Builder {
+    fn resolve<'a>(&self, validator: &'a AS3Validator) -> Resolved<'a> {
+        let mut title = None;
+        let mut references = Vec::new();
+        let mut validator = Cow::Borrowed(validator);
+        loop {
+            validator = match validator {
+                Cow::Borrowed(borrowed) => match self.step(borrowed, &mut title, &mut references) {
+                    Some(next) => next,
+                    None => break,
+                },
+                Cow::Owned(owned) => {
+                    let next = self.step(&owned, &mut title, &mut references);
+                    match next.map(Cow::into_owned) {
+                        Some(next) => Cow::Owned(next),
+                        None => {
+                            validator = Cow::Owned(owned);
+                            break;
+                        }
+                    }
+                }
+            };
+        }
+        Resolved {
+            validator,
+            title,
+            references,
+        }
+    }
+
+    /// Validator wrapped by `validator`, `None` when it is the one giving the widget
+    fn step<'a>(
+        &self,
+        validator: &'a AS3Validator,
+        title: &mut Option<String>,
+        references: &mut Vec<String>,
+    ) -> Option<Cow<'a, AS3Validator>> {
+        match validator {
+            AS3Validator::Annotated { inner, annotations } => {
+                if title.is_none() {
+                    title.clone_from(&annotations.title);
+                }
+                Some(Cow::Borrowed(inner))
+            }
+            AS3Validator::Nullable(inner) => Some(Cow::Borrowed(inner)),
+            AS3Validator::Conditional { base, .. } => Some(Cow::Borrowed(base)),
+            AS3Validator::Ref(reference) => {
+                let name = reference.name().to_string();
+                if self.expanding.contains(&name) || references.contains(&name) {
+                    return None;
+                }
+                let target = reference.target()?;
+                references.push(name);
+                Some(Cow::Owned(AS3Validator::clone(&target)))
+            }
+            _ => None,
+        }
+    }
+
+    /// react-jsonschema-form UI schema, with the keys of the nested values
+    fn rjsf(&mut self, validator: &AS3Validator) -> Value {
+        let resolved = self.resolve(validator);
+        let depth = self.expanding.len();
+        self.expanding.extend(resolved.references);
+        let mut ui = Map::new();
+        match &*resolved.validator {
+            AS3Validator::Object { fields, order, .. } => {
+                ui.insert("ui:order".to_string(), json!(order));
+                for key in order {
+                    let field = self.rjsf(&fields[key]);
+                    if field.as_object().is_some_and(|field| !field.is_empty()) {
+                        ui.insert(key.clone(), field);
+                    }
+                }
+            }
+            AS3Validator::List(items_type) => {
+                let items = self.rjsf(items_type);
+                if items.as_object().is_some_and(|items| !items.is_empty()) {
+                    ui.insert("items".to_string(), items);
+                }
+            }
+            AS3Validator::Map { value_type, .. } => {
+                let values = self.rjsf(value_type);
+                if values.as_object().is_some_and(|values| !values.is_empty()) {
+                    ui.insert("additionalProperties".to_string(), values);
+                }
+            }
+            validator => {
+                if let Some(widget) = widget(validator) {
+                    ui.insert("ui:widget".to_string(), json!(widget));
+                }
+            }
+        }
+        if let Some(title) = &resolved.title {
+            ui.insert("ui:title".to_string(), json!(title));
+        }
+        self.expanding.truncate(depth);
+        Value::Object(ui)
+    }
+
+    /// JSONForms layout of the value at `scope`, a Group for Objects and a Control otherwise
+    fn jsonforms(&mut self, validator: &AS3Validator, scope: &str, label: Option<&str>) -> Value {
+        let resolved = self.resolve(validator);
+        let depth = self.expanding.len();
+        self.expanding.extend(resolved.references);
+        let label = resolved.title.as_deref().or(label);
+        let element = match &*resolved.validator {
+            AS3Validator::Object { fields, order, .. } => {
+                let elements: Vec<Value> = order
+                    .iter()
+                    .map(|key| {
+                        self.jsonforms(
+                            &fields[key],
+                            &format!("{scope}/properties/{}", pointer_escape(key)),
+                            Some(key),
+                        )
+                    })
+                    .collect();
+                let mut group = json!({ "type": "VerticalLayout", "elements": elements });
+                if let Some(label) = label {
+                    group["type"] = json!("Group");
+                    group["label"] = json!(label);
+                }
+                group
+            }
+            validator => {
+                let mut control = json!({ "type": "Control", "scope": scope });
+                // Default labels come from the key
+                if let Some(title) = &resolved.title {
+                    control["label"] = json!(title);
+                }
+                if widget(validator) == Some("textarea") {
+                    control["options"] = json!({ "multi": true });
+                }
+                control
+            }
+        };
+        self.expanding.truncate(depth);
+        element
+    }
+}
+
+/// react-jsonschema-form widget of the value, when the default one doesn't fit
+fn widget(validator: &AS3Validator) -> Option<&'static str> {
+    if validator.enumeration().is_some() {
+        return Some("select");
+    }
+    match validator {
+        AS3Validator::Boolean => Some("checkbox"),
+        AS3Validator::Integer { .. } => Some("updown"),
+        AS3Validator::Date { .. } => Some("date"),
+        AS3Validator::DateTime { .. } => Some("datetime"),
+        AS3Validator::Time { .. } => Some("time"),
+        AS3Validator::String {
+            format: Some(StringFormat::Url { .. }),
+            ..
+        } => Some("uri"),
+        AS3Validator::String {
+            max_length: Some(max_length),
+            ..
+        } if *max_length > TEXTAREA_LENGTH => Some("textarea"),
+        _ => None,
+    }
+}
+
+/// `key` escaped for a JSON pointer
+fn pointer_escape(key: &str) -> String {
+    key.replace('~', "~0").replace('/', "~1")
+}
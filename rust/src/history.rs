@@ -0,0 +1,222 @@
+//! SQLite store of the daemon runs, keeping the outcome of every document so that data
+//! quality can be followed over time with `as3 history`
+
+use crate::{daemon::JobResult, error::As3JsonPath};
+use rusqlite::{params, Connection};
+use std::path::Path;
+
+const SCHEMA: &str = "
+CREATE TABLE IF NOT EXISTS runs (
+    id INTEGER PRIMARY KEY,
+    job TEXT NOT NULL,
+    fingerprint TEXT NOT NULL,
+    time TEXT NOT NULL
+);
+CREATE TABLE IF NOT EXISTS results (
+    run_id INTEGER NOT NULL REFERENCES runs(id),
+    document TEXT NOT NULL,
+    outcome TEXT NOT NULL,
+    error_code TEXT,
+    path TEXT
+);
+CREATE INDEX IF NOT EXISTS results_run ON results(run_id);
+";
+
+pub struct History {
+    connection: Connection,
+}
+
+/// Totals of a recorded run
+#[derive(Debug, PartialEq)]
+pub struct RunSummary {
+    pub job: String,
+    pub time: String,
+    pub fingerprint: String,
+    pub total: usize,
+    pub failing: usize,
+}
+
+/// Outcome of a document in a recorded run
+#[derive(Debug, PartialEq, Clone)]
+pub struct DocumentResult {
+    pub document: String,
+    /// Error code and path, when it failed
+    pub failure: Option<(String, String)>,
+}
+
+/// Changes of a job between the start of the digest period and its latest run
+#[derive(Debug, Default, PartialEq)]
+pub struct JobDigest {
+    pub job: String,
+    /// Time of the latest run
+    pub time: String,
+    /// Time of the run it is compared with, `None` when the job started in the period
+    pub baseline_time: Option<String>,
+    /// Failing documents that weren't validated before
+    pub new_failures: Vec<DocumentResult>,
+    /// Documents that passed before and fail now
+    pub regressions: Vec<DocumentResult>,
+    /// Documents that failed before and pass now
+    pub fixed: Vec<String>,
+    pub still_failing: usize,
+}
+
+impl std::fmt::Display for RunSummary {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(
+            f,
+            "{} [{}] schema {}: {} of {} documents failed",
+            self.time, self.job, self.fingerprint, self.failing, self.total
+        )
+    }
+}
+
+impl History {
+    /// Opens the store, creating it if needed. `:memory:` gives a store that isn't persisted
+    pub fn open(path: &Path) -> Result<History, String> {
+        let error =
+            |e: rusqlite::Error| format!("error: Could not open the history {path:?} : {e}");
+        let connection = Connection::open(path).map_err(error)?;
+        connection.execute_batch(SCHEMA).map_err(error)?;
+        Ok(History { connection })
+    }
+
+    pub fn record(&mut self, result: &JobResult) -> Result<(), String> {
+        let error = |e: rusqlite::Error| format!("error: Could not record the run : {e}");
+        let transaction = self.connection.transaction().map_err(error)?;
+        transaction
+            .execute(
+                "INSERT INTO runs (job, fingerprint, time) VALUES (?1, ?2, ?3)",
+                params![result.job, result.fingerprint, result.time],
+            )
+            .map_err(error)?;
+        let run_id = transaction.last_insert_rowid();
+        for (document, outcome) in &result.outcomes {
+            let (outcome, error_code, path) = match outcome {
+                None => ("passed", None, None),
+                Some(As3JsonPath(path, e)) => ("failed", Some(e.kind()), Some(path.to_string())),
+            };
+            transaction
+                .execute(
+                    "INSERT INTO results (run_id, document, outcome, error_code, path)
+                     VALUES (?1, ?2, ?3, ?4, ?5)",
+                    params![run_id, document, outcome, error_code, path],
+                )
+                .map_err(error)?;
+        }
+        transaction.commit().map_err(error)
+    }
+
+    /// Latest `limit` runs, most recent first, optionally of a single job
+    pub fn runs(&self, job: Option<&str>, limit: usize) -> Result<Vec<RunSummary>, String> {
+        let error = |e: rusqlite::Error| format!("error: Could not read the history : {e}");
+        let mut statement = self
+            .connection
+            .prepare(
+                "SELECT runs.job, runs.time, runs.fingerprint, COUNT(results.run_id),
+                        COUNT(CASE results.outcome WHEN 'failed' THEN 1 END)
+                 FROM runs LEFT JOIN results ON results.run_id = runs.id
+                 WHERE ?1 IS NULL OR runs.job = ?1
+                 GROUP BY runs.id
+                 ORDER BY runs.id DESC
+                 LIMIT ?2",
+            )
+            .map_err(error)?;
+        let runs = statement
+            .query_map(params![job, limit as i64], |row| {
+                Ok(RunSummary {
+                    job: row.get(0)?,
+                    time: row.get(1)?,
+                    fingerprint: row.get(2)?,
+                    total: row.get::<_, i64>(3)? as usize,
+                    failing: row.get::<_, i64>(4)? as usize,
+                })
+            })
+            .map_err(error)?;
+        runs.collect::<Result<_, _>>().map_err(error)
+    }
+
+    /// Compares, for every job, its latest run with the latest run before `since`
+    pub fn digest(
+        &self,
+        since: chrono::DateTime<chrono::FixedOffset>,
+    ) -> Result<Vec<JobDigest>, String> {
+        let error = |e: rusqlite::Error| format!("error: Could not read the history : {e}");
+        let mut statement = self
+            .connection
+            .prepare("SELECT id, job, time FROM runs ORDER BY id")
+            .map_err(error)?;
+        let runs: Vec<(i64, String, String)> = statement
+            .query_map([], |row| Ok((row.get(0)?, row.get(1)?, row.get(2)?)))
+            .map_err(error)?
+            .collect::<Result<_, _>>()
+            .map_err(error)?;
+
+        let mut jobs: Vec<&str> = runs.iter().map(|(_, job, _)| job.as_str()).collect();
+        jobs.sort();
+        jobs.dedup();
+        let mut digests = Vec::new();
+        for job in jobs {
+            let (before, after): (Vec<_>, Vec<_>) = runs
+                .iter()
+                .filter(|(_, run_job, _)| run_job == job)
+                .partition(|(_, _, time)| {
+                    chrono::DateTime::parse_from_rfc3339(time).is_ok_and(|time| time < since)
+                });
+            let Some((current, _, time)) = after.last() else {
+                continue;
+            };
+            let baseline = before.last();
+            let current_results = self.results(*current)?;
+            let baseline_results = match baseline {
+                Some((baseline, _, _)) => self.results(*baseline)?,
+                None => Vec::new(),
+            };
+
+            let mut digest = JobDigest {
+                job: job.to_string(),
+                time: time.clone(),
+                baseline_time: baseline.map(|(_, _, time)| time.clone()),
+                ..Default::default()
+            };
+            for result in &current_results {
+                let previous = baseline_results
+                    .iter()
+                    .find(|previous| previous.document == result.document);
+                match (
+                    previous.map(|previous| previous.failure.is_some()),
+                    &result.failure,
+                ) {
+                    (None, Some(_)) => digest.new_failures.push(result.clone()),
+                    (Some(false), Some(_)) => digest.regressions.push(result.clone()),
+                    (Some(true), Some(_)) => digest.still_failing += 1,
+                    (Some(true), None) => digest.fixed.push(result.document.clone()),
+                    _ => {}
+                }
+            }
+            digests.push(digest);
+        }
+        Ok(digests)
+    }
+
+    fn results(&self, run_id: i64) -> Result<Vec<DocumentResult>, String> {
+        let error = |e: rusqlite::Error| format!("error: Could not read the history : {e}");
+        let mut statement = self
+            .connection
+            .prepare(
+                "SELECT document, error_code, path FROM results WHERE run_id = ?1 ORDER BY document",
+            )
+            .map_err(error)?;
+        let results = statement
+            .query_map(params![run_id], |row| {
+                let error_code: Option<String> = row.get(1)?;
+                let path: Option<String> = row.get(2)?;
+                Ok(DocumentResult {
+                    document: row.get(0)?,
+                    failure: error_code.zip(path),
+                })
+            })
+            .map_err(error)?;
+        results.collect::<Result<_, _>>().map_err(error)
+    }
+}
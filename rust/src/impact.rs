@@ -0,0 +1,73 @@
+//! Dry-run comparison of two versions of a definition against a corpus of documents, to
+//! measure the effect of a schema change before deploying it
+
+use crate::{
+    error::{AS3ValidationError, As3JsonPath},
+    validator::AS3Validator,
+    AS3Data,
+};
+
+#[derive(Debug, Default, PartialEq)]
+pub struct ImpactReport {
+    /// Documents accepted by the old definition and rejected by the new one
+    pub newly_failing: Vec<(String, As3JsonPath<AS3ValidationError>)>,
+    /// Documents rejected by the old definition and accepted by the new one
+    pub newly_passing: Vec<(String, As3JsonPath<AS3ValidationError>)>,
+    /// Documents rejected by both, for a different reason
+    pub changed: Vec<(
+        String,
+        As3JsonPath<AS3ValidationError>,
+        As3JsonPath<AS3ValidationError>,
+    )>,
+    pub unchanged_passing: usize,
+    pub unchanged_failing: usize,
+}
+
+impl ImpactReport {
+    pub fn is_breaking(&self) -> bool {
+        !self.newly_failing.is_empty() || !self.changed.is_empty()
+    }
+}
+
+/// Validates every `(name, document)` of the corpus with both definitions
+pub fn impact<'a>(
+    old: &AS3Validator,
+    new: &AS3Validator,
+    corpus: impl IntoIterator<Item = (String, &'a AS3Data)>,
+) -> ImpactReport {
+    let mut report = ImpactReport::default();
+    for (name, document) in corpus {
+        match (old.validate(document), new.validate(document)) {
+            (Ok(()), Ok(())) => report.unchanged_passing += 1,
+            (Ok(()), Err(e)) => report.newly_failing.push((name, e)),
+            (Err(e), Ok(())) => report.newly_passing.push((name, e)),
+            (Err(old_error), Err(new_error)) if old_error == new_error => {
+                report.unchanged_failing += 1
+            }
+            (Err(old_error), Err(new_error)) => report.changed.push((name, old_error, new_error)),
+        }
+    }
+    report
+}
+
+impl std::fmt::Display for ImpactReport {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        writeln!(f, "Newly failing: {}", self.newly_failing.len())?;
+        for (name, error) in &self.newly_failing {
+            writeln!(f, "  ❌ {name}: {error}")?;
+        }
+        writeln!(f, "Newly passing: {}", self.newly_passing.len())?;
+        for (name, error) in &self.newly_passing {
+            writeln!(f, "  ✅ {name} (was: {error})")?;
+        }
+        writeln!(f, "Failing differently: {}", self.changed.len())?;
+        for (name, old_error, new_error) in &self.changed {
+            writeln!(f, "  ⚠️ {name}: {old_error}-> {new_error}")?;
+        }
+        write!(
+            f,
+            "Unchanged: {} passing, {} failing",
+            self.unchanged_passing, self.unchanged_failing
+        )
+    }
+}
@@ -0,0 +1,174 @@
+//! Definitions embedded in a YAML data file through `# as3:` comments, so that small config
+//! files can validate themselves without a separate definition file:
+//!
+//! ```yaml
+//! name: billing      # as3: String(regex="^[a-z]+$")
+//! replicas: 3        # as3: Integer min=1 max=10
+//! database:
+//!   host: db.local   # as3: String(format="hostname")
+//! workers:
+//!   - queue: emails  # as3: String
+//! ```
+//!
+//! Annotated fields are required, parents of annotated fields become `Object` (or `List` of
+//! `Object` when their items start with `- `) and fields without annotation are not checked.
+
+const MARKER: &str = "# as3:";
+
+#[derive(Debug, Default)]
+struct Entry {
+    key: String,
+    annotation: Option<String>,
+    /// Annotation of scalar list items (`- value  # as3: Type`)
+    item_annotation: Option<String>,
+    is_list: bool,
+    children: Vec<Entry>,
+}
+
+/// Builds a definition from the `# as3:` comments of `source`, `None` if there are none
+pub fn definition_from_comments(source: &str) -> Option<serde_yaml::Value> {
+    // Stack of the entries still open, with the column of their key
+    let mut stack: Vec<(usize, Entry)> = vec![(0, Entry::default())];
+
+    for line in source.lines() {
+        let (content, annotation) = match line.find(MARKER) {
+            Some(index) => (
+                &line[..index],
+                Some(to_inline_syntax(line[index + MARKER.len()..].trim())),
+            ),
+            None => (strip_comment(line), None),
+        };
+        if content.trim().is_empty() {
+            continue;
+        }
+
+        let mut column = content.len() - content.trim_start().len();
+        let mut content = content.trim();
+        if let Some(item) = content.strip_prefix('-') {
+            close_entries(&mut stack, column + 1);
+            stack.last_mut().unwrap().1.is_list = true;
+            column += 1 + item.len() - item.trim_start().len();
+            content = item.trim();
+            if !content.contains(':') {
+                if annotation.is_some() {
+                    stack.last_mut().unwrap().1.item_annotation = annotation;
+                }
+                continue;
+            }
+        }
+
+        let Some((key, _)) = content.split_once(':') else {
+            continue;
+        };
+        close_entries(&mut stack, column);
+        let key = key
+            .trim()
+            .trim_matches(|c| c == '"' || c == '\'')
+            .to_string();
+        stack.push((
+            column + 1,
+            Entry {
+                key,
+                annotation,
+                ..Default::default()
+            },
+        ));
+    }
+    close_entries(&mut stack, 0);
+
+    let (_, root) = stack.pop()?;
+    let definition = entry_definition(&root)?;
+    let mut document = serde_yaml::Mapping::new();
+    document.insert("Root".into(), definition);
+    Some(serde_yaml::Value::Mapping(document))
+}
+
+/// Attaches to their parents the entries whose key starts at or after `column`
+fn close_entries(stack: &mut Vec<(usize, Entry)>, column: usize) {
+    while stack.len() > 1 && stack.last().unwrap().0 > column {
+        let (_, entry) = stack.pop().unwrap();
+        stack.last_mut().unwrap().1.children.push(entry);
+    }
+}
+
+fn entry_definition(entry: &Entry) -> Option<serde_yaml::Value> {
+    let fields: Vec<(&String, serde_yaml::Value)> = entry
+        .children
+        .iter()
+        .filter_map(|child| Some((&child.key, entry_definition(child)?)))
+        .collect();
+
+    let item = if !fields.is_empty() {
+        let mut object = serde_yaml::Mapping::new();
+        object.insert("+type".into(), "Object".into());
+        for (key, definition) in fields {
+            object.insert(key.as_str().into(), definition);
+        }
+        serde_yaml::Value::Mapping(object)
+    } else if let Some(annotation) = &entry.item_annotation {
+        annotation.as_str().into()
+    } else {
+        return entry.annotation.as_deref().map(Into::into);
+    };
+
+    if !entry.is_list {
+        return Some(item);
+    }
+    let mut list = serde_yaml::Mapping::new();
+    list.insert("+type".into(), "List".into());
+    list.insert("+ValueType".into(), item);
+    Some(serde_yaml::Value::Mapping(list))
+}
+
+/// Accepts `Integer min=0 max=10` on top of the inline syntax `Integer(min=0, max=10)`
+fn to_inline_syntax(annotation: &str) -> String {
+    if annotation.contains(['(', '<']) {
+        return annotation.to_string();
+    }
+    let Some((validator_type, arguments)) = annotation.split_once(char::is_whitespace) else {
+        return annotation.to_string();
+    };
+
+    let mut split = Vec::new();
+    let mut current = String::new();
+    let mut quoted = false;
+    for c in arguments.trim().chars() {
+        match c {
+            '"' => {
+                quoted = !quoted;
+                current.push(c);
+            }
+            c if c.is_whitespace() && !quoted => {
+                if !current.is_empty() {
+                    split.push(std::mem::take(&mut current));
+                }
+            }
+            c => current.push(c),
+        }
+    }
+    if !current.is_empty() {
+        split.push(current);
+    }
+
+    let (validator_type, nullable) = match validator_type.strip_suffix('?') {
+        Some(validator_type) => (validator_type, "?"),
+        None => (validator_type, ""),
+    };
+    format!("{validator_type}({}){nullable}", split.join(", "))
+}
+
+/// Removes a trailing `#` comment that isn't inside a quoted value
+fn strip_comment(line: &str) -> &str {
+    let mut quote = None;
+    for (index, c) in line.char_indices() {
+        match (c, quote) {
+            ('"' | '\'', None) => quote = Some(c),
+            (c, Some(open)) if c == open => quote = None,
+            ('#', None) if index == 0 || line[..index].ends_with(char::is_whitespace) => {
+                return &line[..index]
+            }
+            _ => {}
+        }
+    }
+    line
+}
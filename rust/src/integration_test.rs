@@ -1,19 +1,44 @@
 use super::*;
 use serde_json::json;
+use std::collections::{BTreeMap, HashSet};
+use std::num::NonZeroUsize;
 
+#[cfg(feature = "tools")]
+use crate::report::ValidationReport;
 use crate::{
-    error::{AS3ValidationError, As3JsonPath},
-    validator::AS3Validator,
-    AS3Data,
+    error::{AS3DefinitionError, AS3ValidationError, As3JsonPath, JsonPath, PathSegment},
+    validator::{
+        AS3Validator, DefinitionOptions, ExtraKeys, LengthUnit, PathGlob, RegexPattern,
+        ValidationOptions,
+    },
+    AS3Data, Truncation,
 };
 
+/// Path written in its displayed form, `[2]` segments being List indices
+fn json_path(displayed: &str) -> JsonPath {
+    JsonPath(
+        displayed
+            .split(" -> ")
+            .map(|segment| {
+                match segment
+                    .strip_prefix('[')
+                    .and_then(|index| index.strip_suffix(']')?.parse().ok())
+                {
+                    Some(index) => PathSegment::Index(index),
+                    None => PathSegment::Key(segment.to_string()),
+                }
+            })
+            .collect(),
+    )
+}
+
 fn verify(
     data: &serde_json::Value,
     validator_config: &serde_yaml::Value,
     expected: Result<(), As3JsonPath<AS3ValidationError>>,
 ) {
     let data = AS3Data::from(data);
-    let validator = AS3Validator::from(&validator_config).unwrap();
+    let validator = AS3Validator::from(validator_config).unwrap();
     assert_eq!(validator.validate(&data), expected);
 }
 
@@ -32,7 +57,7 @@ fn should_run() {
     });
 
     let validator: serde_yaml::Value = serde_yaml::from_str(
-        &r#"
+        r#"
         Root:
             +type: Object
             age: 
@@ -41,7 +66,7 @@ fn should_run() {
                 +type: Integer
             name:
                 +type: String
-                +Regex: "^[A-Z][a-z]"
+                +regex: "^[A-Z][a-z]"
             vehicles:
                 +type: Object
                 list: 
@@ -72,7 +97,7 @@ fn with_decimal_error() {
     });
 
     let validator: serde_yaml::Value = serde_yaml::from_str(
-        &r#"
+        r#"
         Root:
             +type: Object
             vehicles:
@@ -95,11 +120,12 @@ fn with_decimal_error() {
         &data,
         &validator,
         Err(As3JsonPath(
-            "ROOT -> vehicles -> list -> year".to_string(),
+            json_path("ROOT -> vehicles -> list -> [0] -> year"),
             AS3ValidationError::TypeError {
                 expected: AS3Validator::Integer {
                     minimum: None,
                     maximum: None,
+                    width: None,
                 },
                 got: AS3Data::Decimal(20.18),
             },
@@ -118,7 +144,7 @@ fn with_string_error() {
     });
 
     let validator: serde_yaml::Value = serde_yaml::from_str(
-        &r#"
+        r#"
         Root:
             +type: Object
             vehicles:
@@ -141,11 +167,12 @@ fn with_string_error() {
         &data,
         &validator,
         Err(As3JsonPath(
-            "ROOT -> vehicles -> list -> year".to_string(),
+            json_path("ROOT -> vehicles -> list -> [1] -> year"),
             AS3ValidationError::TypeError {
                 expected: AS3Validator::Integer {
                     minimum: None,
                     maximum: None,
+                    width: None,
                 },
                 got: AS3Data::String("2018".to_string()),
             },
@@ -165,7 +192,7 @@ fn with_regex_error() {
     });
 
     let validator: serde_yaml::Value = serde_yaml::from_str(
-        &r#"
+        r#"
         Root:
             +type: Object
             vehicles:
@@ -188,7 +215,7 @@ fn with_regex_error() {
         &data,
         &validator,
         Err(As3JsonPath(
-            "ROOT -> vehicles -> list -> maker".to_string(),
+            json_path("ROOT -> vehicles -> list -> [1] -> maker"),
             AS3ValidationError::RegexError {
                 word: "ford".to_string(),
                 regex: "^[A-Z][a-z]".to_string(),
@@ -204,27 +231,36 @@ fn with_minimum_error() {
       "children": 5,
     });
 
-    let validator = AS3Validator::Object(HashMap::from([
-        (
-            "age".to_owned(),
-            AS3Validator::Integer {
-                minimum: Some(20),
-                maximum: None,
-            },
-        ),
-        (
-            "children".to_owned(),
-            AS3Validator::Integer {
-                minimum: Some(2),
-                maximum: None,
-            },
-        ),
-    ]));
+    let validator = AS3Validator::Object {
+        fields: HashMap::from([
+            (
+                "age".to_owned(),
+                AS3Validator::Integer {
+                    minimum: Some(20),
+                    maximum: None,
+                    width: None,
+                },
+            ),
+            (
+                "children".to_owned(),
+                AS3Validator::Integer {
+                    minimum: Some(2),
+                    maximum: None,
+                    width: None,
+                },
+            ),
+        ]),
+        extra_keys: ExtraKeys::Allow,
+        pattern_keys: None,
+        assertions: Vec::new(),
+        requires: BTreeMap::new(),
+        order: ["age", "children"].map(String::from).to_vec(),
+    };
 
     assert_eq!(
         validator.validate(&AS3Data::from(&json)),
         Err(As3JsonPath(
-            "ROOT -> age".to_string(),
+            json_path("ROOT -> age"),
             AS3ValidationError::MinimumInteger {
                 number: 18,
                 minimum: 20
@@ -240,7 +276,7 @@ fn with_minimum_error() {
     assert_eq!(
         validator.validate(&AS3Data::from(&json)),
         Err(As3JsonPath(
-            "ROOT -> children".to_string(),
+            json_path("ROOT -> children"),
             AS3ValidationError::MinimumInteger {
                 number: 0,
                 minimum: 2
@@ -271,7 +307,7 @@ fn with_missing_field_error_validator_derive() {
     });
 
     let validator: serde_yaml::Value = serde_yaml::from_str(
-        &r#"
+        r#"
         Root:
             +type: Object
             vehicles:
@@ -298,9 +334,10 @@ fn with_missing_field_error_validator_derive() {
         &data,
         &validator,
         Err(As3JsonPath(
-            "ROOT -> vehicles".to_string(),
+            json_path("ROOT -> vehicles"),
             AS3ValidationError::MissingKey {
                 key: "maker".to_string(),
+                suggestion: None,
             },
         )),
     );
@@ -329,7 +366,7 @@ fn with_list() {
       });
 
     let validator: serde_yaml::Value = serde_yaml::from_str(
-        &r#"
+        r#"
             Root:
                 +type: Object
                 students:
@@ -350,9 +387,10 @@ fn with_list() {
         &data,
         &validator,
         Err(As3JsonPath(
-            "ROOT -> students".to_string(),
+            json_path("ROOT -> students -> [1]"),
             AS3ValidationError::MissingKey {
                 key: "year".to_string(),
+                suggestion: None,
             },
         )),
     );
@@ -372,12 +410,17 @@ fn with_list() {
         &data2,
         &validator,
         Err(As3JsonPath(
-            "ROOT -> students -> grade".to_string(),
+            json_path("ROOT -> students -> [0] -> grade"),
             AS3ValidationError::TypeError {
                 expected: AS3Validator::String {
                     regex: None,
                     max_length: None,
                     min_length: None,
+                    format: None,
+                    any_regex: None,
+                    deny_regex: None,
+                    char_classes: Vec::new(),
+                    length_unit: LengthUnit::default(),
                 },
                 got: AS3Data::Integer(20),
             },
@@ -402,7 +445,7 @@ fn with_map() {
       });
 
     let validator: serde_yaml::Value = serde_yaml::from_str(
-        &r#"
+        r#"
         Root:
             +type: Object
             People:
@@ -434,7 +477,7 @@ fn with_bool_and_map() {
       });
 
     let validator: serde_yaml::Value = serde_yaml::from_str(
-        &r#"
+        r#"
         Root:
             +type: Map
             +KeyType:
@@ -463,7 +506,7 @@ fn with_date_and_map() {
       });
 
     let validator: serde_yaml::Value = serde_yaml::from_str(
-        &r#"
+        r#"
         Root:
             +type: Map
             +KeyType:
@@ -490,7 +533,7 @@ fn with_date_and_map() {
       });
 
     let validator_config: serde_yaml::Value = serde_yaml::from_str(
-        &r#"
+        r#"
             Root:
                 +type: Map
                 +KeyType:
@@ -509,7 +552,7 @@ fn with_date_and_map() {
         &data,
         &validator_config,
         Err(As3JsonPath(
-            "ROOT -> 2020/10/15".to_string(),
+            json_path("ROOT -> 2020/10/15"),
             AS3ValidationError::Generic(
                 "The Key `2020/10/15` can't be converted to a Date".to_string(),
             ),
@@ -530,7 +573,7 @@ fn with_abbreviation_types() {
     );
 
     let validator: serde_yaml::Value = serde_yaml::from_str(
-        &r#"
+        r#"
         Root:
             +type: Object
             name: String
@@ -576,3 +619,4934 @@ fn with_abbreviation_types() {
     //     )),
     // );
 }
+
+#[test]
+fn with_url_format() {
+    let validator: serde_yaml::Value = serde_yaml::from_str(
+        r#"
+        Root:
+            +type: Object
+            homepage:
+                +type: String
+                +format: url
+                +schemes: [https]
+                    "#,
+    )
+    .unwrap();
+
+    verify(
+        &json!({ "homepage": "https://appcove.com/about" }),
+        &validator,
+        Ok(()),
+    );
+
+    verify(
+        &json!({ "homepage": "http://appcove.com" }),
+        &validator,
+        Err(As3JsonPath(
+            json_path("ROOT -> homepage"),
+            AS3ValidationError::FormatError {
+                word: "http://appcove.com".to_string(),
+                format: "url".to_string(),
+                reason: "scheme `http` is not one of the allowed schemes (https)".to_string(),
+            },
+        )),
+    );
+
+    verify(
+        &json!({ "homepage": "appcove.com" }),
+        &validator,
+        Err(As3JsonPath(
+            json_path("ROOT -> homepage"),
+            AS3ValidationError::FormatError {
+                word: "appcove.com".to_string(),
+                format: "url".to_string(),
+                reason: "relative URL without a base".to_string(),
+            },
+        )),
+    );
+}
+
+#[test]
+fn with_optimized_validator() {
+    let validator = AS3Validator::Object {
+        fields: HashMap::from([
+            (
+                "status".to_owned(),
+                AS3Validator::String {
+                    regex: Some(RegexPattern::new("^active$".to_owned()).unwrap()),
+                    max_length: None,
+                    min_length: None,
+                    format: None,
+                    any_regex: None,
+                    deny_regex: None,
+                    char_classes: Vec::new(),
+                    length_unit: LengthUnit::default(),
+                },
+            ),
+            (
+                "note".to_owned(),
+                AS3Validator::Nullable(Box::new(AS3Validator::Nullable(Box::new(
+                    AS3Validator::Boolean,
+                )))),
+            ),
+        ]),
+        extra_keys: ExtraKeys::Allow,
+        pattern_keys: None,
+        assertions: Vec::new(),
+        requires: BTreeMap::new(),
+        order: ["status", "note"].map(String::from).to_vec(),
+    }
+    .optimize();
+
+    assert_eq!(
+        validator,
+        AS3Validator::Object {
+            fields: HashMap::from([
+                (
+                    "status".to_owned(),
+                    AS3Validator::Literal {
+                        values: HashSet::from(["active".to_owned()]),
+                        regex: "^active$".to_owned(),
+                    },
+                ),
+                (
+                    "note".to_owned(),
+                    AS3Validator::Nullable(Box::new(AS3Validator::Boolean)),
+                ),
+            ]),
+            extra_keys: ExtraKeys::Allow,
+            pattern_keys: None,
+            assertions: Vec::new(),
+            requires: BTreeMap::new(),
+            order: ["status", "note"].map(String::from).to_vec(),
+        }
+    );
+
+    assert_eq!(
+        validator.validate(&AS3Data::from(
+            &json!({ "status": "inactive", "note": null })
+        )),
+        Err(As3JsonPath(
+            json_path("ROOT -> status"),
+            AS3ValidationError::RegexError {
+                word: "inactive".to_string(),
+                regex: "^active$".to_string(),
+            }
+        ))
+    );
+
+    // Each listed value is looked up, other regexes are kept
+    let statuses: serde_yaml::Value = serde_yaml::from_str(
+        r#"
+        Root:
+            +type: Object
+            status: { +type: String, +regex: "^(draft|published)$" }
+            code: { +type: String, +regex: "^a|b$" }
+                    "#,
+    )
+    .unwrap();
+    let validator = AS3Validator::from(&statuses).unwrap().optimize();
+    let AS3Validator::Object { fields, .. } = &validator else {
+        panic!("not an Object: {validator:?}");
+    };
+    assert_eq!(
+        fields["status"],
+        AS3Validator::Literal {
+            values: HashSet::from(["draft".to_owned(), "published".to_owned()]),
+            regex: "^(draft|published)$".to_owned(),
+        }
+    );
+    assert!(matches!(fields["code"], AS3Validator::String { .. }));
+    assert_eq!(
+        validator.validate(&AS3Data::from(
+            &json!({ "status": "published", "code": "ab" })
+        )),
+        Ok(())
+    );
+    assert_eq!(
+        validator.validate(&AS3Data::from(
+            &json!({ "status": "archived", "code": "a" })
+        )),
+        Err(As3JsonPath(
+            json_path("ROOT -> status"),
+            AS3ValidationError::RegexError {
+                word: "archived".to_string(),
+                regex: "^(draft|published)$".to_string(),
+            }
+        ))
+    );
+
+    // Serialized validators are read back as they were
+    let serialized = serde_json::to_string(&validator).unwrap();
+    assert_eq!(
+        serde_json::from_str::<AS3Validator>(&serialized).unwrap(),
+        validator
+    );
+    // A `+regex` that doesn't compile is refused when read back, not on the first validation
+    let broken = serialized.replace("^a|b$", "^(a|b$");
+    assert_ne!(broken, serialized);
+    let error = serde_json::from_str::<AS3Validator>(&broken).unwrap_err();
+    assert!(error.to_string().contains("regex parse error"), "{error}");
+}
+
+#[test]
+fn with_uuid_format() {
+    let validator: serde_yaml::Value = serde_yaml::from_str(
+        r#"
+        Root:
+            +type: Object
+            id:
+                +type: String
+                +format: uuid
+                +version: 4
+                    "#,
+    )
+    .unwrap();
+
+    verify(
+        &json!({ "id": "6f1c1a4e-3b2d-4c5e-9a8f-7e6d5c4b3a21" }),
+        &validator,
+        Ok(()),
+    );
+
+    verify(
+        &json!({ "id": "6f1c1a4e-3b2d-1c5e-9a8f-7e6d5c4b3a21" }),
+        &validator,
+        Err(As3JsonPath(
+            json_path("ROOT -> id"),
+            AS3ValidationError::FormatError {
+                word: "6f1c1a4e-3b2d-1c5e-9a8f-7e6d5c4b3a21".to_string(),
+                format: "uuid".to_string(),
+                reason: "UUID is version 1 instead of version 4".to_string(),
+            },
+        )),
+    );
+
+    assert!(matches!(
+        AS3Validator::from(&validator)
+            .unwrap()
+            .validate(&AS3Data::from(&json!({ "id": "not-a-uuid" }))),
+        Err(As3JsonPath(_, AS3ValidationError::FormatError { .. }))
+    ));
+}
+
+#[test]
+fn with_ip_format() {
+    let validator: serde_yaml::Value = serde_yaml::from_str(
+        r#"
+        Root:
+            +type: Object
+            gateway:
+                +type: String
+                +format: ipv4
+            dns:
+                +type: String
+                +format: ip
+            subnet:
+                +type: String
+                +format: ipv6
+                +cidr: true
+                    "#,
+    )
+    .unwrap();
+
+    verify(
+        &json!({ "gateway": "192.168.1.1", "dns": "2001:4860:4860::8888", "subnet": "fd00::/8" }),
+        &validator,
+        Ok(()),
+    );
+
+    verify(
+        &json!({ "gateway": "192.168.1.1", "dns": "1.1.1.1", "subnet": "fd00::/129" }),
+        &validator,
+        Err(As3JsonPath(
+            json_path("ROOT -> subnet"),
+            AS3ValidationError::FormatError {
+                word: "fd00::/129".to_string(),
+                format: "ipv6".to_string(),
+                reason: "`129` is not a valid prefix length (0 to 128)".to_string(),
+            },
+        )),
+    );
+
+    verify(
+        &json!({ "gateway": "::1", "dns": "1.1.1.1", "subnet": "fd00::/8" }),
+        &validator,
+        Err(As3JsonPath(
+            json_path("ROOT -> gateway"),
+            AS3ValidationError::FormatError {
+                word: "::1".to_string(),
+                format: "ipv4".to_string(),
+                reason: "expected an IPv4 address, got an IPv6 one".to_string(),
+            },
+        )),
+    );
+}
+
+#[test]
+fn with_regex_flags() {
+    let validator: serde_yaml::Value = serde_yaml::from_str(
+        r#"
+        Root:
+            +type: List
+            +ValueType:
+                +type: String
+                +regex: "[a-z]+-[0-9]+"
+                +regexFlags: [i]
+                +fullMatch: true
+                    "#,
+    )
+    .unwrap();
+
+    verify(&json!(["abc-12", "ABC-12"]), &validator, Ok(()));
+
+    verify(
+        &json!(["id abc-12"]),
+        &validator,
+        Err(As3JsonPath(
+            json_path("ROOT -> [0]"),
+            AS3ValidationError::RegexError {
+                word: "id abc-12".to_string(),
+                regex: "(?i)^(?:[a-z]+-[0-9]+)$".to_string(),
+            },
+        )),
+    );
+
+    let validator: serde_yaml::Value = serde_yaml::from_str(
+        r#"
+        Root:
+            +type: String
+            +regex: "^b.c$"
+            +regexFlags: [m, s]
+                    "#,
+    )
+    .unwrap();
+    verify(&json!("a\nb\nc"), &validator, Ok(()));
+
+    for definition in [
+        "Root: { +type: String, +fullMatch: true }",
+        "Root: { +type: String, +regex: a, +regexFlags: [u] }",
+        "Root: { +type: String, +regex: a, +fullMatch: yes please }",
+    ] {
+        assert!(AS3Validator::from(&serde_yaml::from_str(definition).unwrap()).is_err());
+    }
+}
+
+#[test]
+fn with_character_classes() {
+    let validator: serde_yaml::Value = serde_yaml::from_str(
+        r#"
+        Root:
+            +type: Object
+            code:
+                +type: String
+                +ascii: true
+                +alphanumeric: true
+            login:
+                +type: String
+                +noWhitespace: true
+                +ascii: false
+                    "#,
+    )
+    .unwrap();
+
+    verify(
+        &json!({ "code": "AB12", "login": "jöhn.doe" }),
+        &validator,
+        Ok(()),
+    );
+
+    verify(
+        &json!({ "code": "AB-12", "login": "john" }),
+        &validator,
+        Err(As3JsonPath(
+            json_path("ROOT -> code"),
+            AS3ValidationError::CharacterClass {
+                word: "AB-12".to_string(),
+                class: "+alphanumeric".to_string(),
+                character: '-',
+                position: 3,
+            },
+        )),
+    );
+
+    verify(
+        &json!({ "code": "ÀB12", "login": "john" }),
+        &validator,
+        Err(As3JsonPath(
+            json_path("ROOT -> code"),
+            AS3ValidationError::CharacterClass {
+                word: "ÀB12".to_string(),
+                class: "+ascii".to_string(),
+                character: 'À',
+                position: 1,
+            },
+        )),
+    );
+
+    let error = AS3Validator::from(&validator)
+        .unwrap()
+        .validate(&AS3Data::from(
+            &json!({ "code": "A1", "login": "john doe" }),
+        ))
+        .unwrap_err();
+    assert_eq!(
+        error.1.to_string(),
+        "Word john doe has ` ` at position 5, which `+noWhitespace` doesn't allow"
+    );
+
+    let invalid = serde_yaml::from_str("Root: { +type: String, +ascii: yes please }").unwrap();
+    assert!(AS3Validator::from(&invalid).is_err());
+}
+
+#[test]
+fn with_length_units() {
+    let build = |unit: &str| {
+        let definition = format!("Root: {{ +type: String, +maxLength: 5{unit} }}");
+        AS3Validator::from(&serde_yaml::from_str(&definition).unwrap()).unwrap()
+    };
+    let accented = AS3Data::String("héllo".to_string());
+    // "e" followed by a combining acute accent
+    let combining = AS3Data::String("he\u{301}llo".to_string());
+
+    assert_eq!(build("").validate(&accented), Ok(()));
+    assert_eq!(build(", +lengthUnit: chars").validate(&accented), Ok(()));
+    assert_eq!(
+        build(", +lengthUnit: bytes").validate(&accented),
+        Err(As3JsonPath(
+            json_path("ROOT"),
+            AS3ValidationError::MaximumString {
+                string: "héllo".to_string(),
+                current_lenght: 6,
+                max_length: 5,
+                unit: LengthUnit::Bytes,
+            },
+        ))
+    );
+    assert!(build("").validate(&combining).is_err());
+    assert_eq!(
+        build(", +lengthUnit: graphemes").validate(&combining),
+        Ok(())
+    );
+
+    let validator = AS3Validator::from(
+        &serde_yaml::from_str("Root: { +type: String, +minLength: 2, +lengthUnit: graphemes }")
+            .unwrap(),
+    )
+    .unwrap();
+    let error = validator
+        .validate(&AS3Data::String("🇫🇷".to_string()))
+        .unwrap_err();
+    assert_eq!(
+        error.1.to_string(),
+        " 🇫🇷 is 1 graphemes long, under the min lenght allowed of 2"
+    );
+
+    let invalid = serde_yaml::from_str("Root: { +type: String, +lengthUnit: words }").unwrap();
+    assert!(AS3Validator::from(&invalid).is_err());
+}
+
+#[test]
+fn with_regex_lists() {
+    let validator: serde_yaml::Value = serde_yaml::from_str(
+        r#"
+        Root:
+            +type: Object
+            username:
+                +type: String
+                +anyRegex: ["^[a-z]+$", "^[a-z]+_[0-9]+$"]
+                +denyRegex: ["^admin", "^root$"]
+                    "#,
+    )
+    .unwrap();
+
+    verify(&json!({ "username": "dilec_42" }), &validator, Ok(()));
+
+    verify(
+        &json!({ "username": "Dilec" }),
+        &validator,
+        Err(As3JsonPath(
+            json_path("ROOT -> username"),
+            AS3ValidationError::AnyRegexError {
+                word: "Dilec".to_string(),
+                regexes: vec!["^[a-z]+$".to_string(), "^[a-z]+_[0-9]+$".to_string()],
+            },
+        )),
+    );
+
+    verify(
+        &json!({ "username": "administrator" }),
+        &validator,
+        Err(As3JsonPath(
+            json_path("ROOT -> username"),
+            AS3ValidationError::DenyRegexError {
+                word: "administrator".to_string(),
+                regex: "^admin".to_string(),
+            },
+        )),
+    );
+
+    let invalid: serde_yaml::Value = serde_yaml::from_str(
+        r#"
+        Root:
+            +type: String
+            +denyRegex: ["(unclosed"]
+                    "#,
+    )
+    .unwrap();
+    assert!(AS3Validator::from(&invalid).is_err());
+
+    // Lists read back from a serialized validator are compiled as well
+    let built = AS3Validator::from(&validator).unwrap();
+    let serialized = serde_json::to_string(&built).unwrap();
+    assert_eq!(
+        serde_json::from_str::<AS3Validator>(&serialized).unwrap(),
+        built
+    );
+    let broken = serialized.replace("^root$", "(root");
+    assert_ne!(broken, serialized);
+    assert!(serde_json::from_str::<AS3Validator>(&broken).is_err());
+}
+
+#[test]
+fn with_hostname_format() {
+    let validator: serde_yaml::Value = serde_yaml::from_str(
+        r#"
+        Root:
+            +type: List
+            +ValueType:
+                +type: String
+                +format: hostname
+                    "#,
+    )
+    .unwrap();
+
+    verify(
+        &json!(["appcove.com", "db-01.internal.", "localhost"]),
+        &validator,
+        Ok(()),
+    );
+
+    verify(
+        &json!(["appcove.com", "-db.internal"]),
+        &validator,
+        Err(As3JsonPath(
+            json_path("ROOT -> [1]"),
+            AS3ValidationError::FormatError {
+                word: "-db.internal".to_string(),
+                format: "hostname".to_string(),
+                reason: "label `-db` starts or ends with a dash".to_string(),
+            },
+        )),
+    );
+
+    verify(
+        &json!(["app_cove.com"]),
+        &validator,
+        Err(As3JsonPath(
+            json_path("ROOT -> [0]"),
+            AS3ValidationError::FormatError {
+                word: "app_cove.com".to_string(),
+                format: "hostname".to_string(),
+                reason: "label `app_cove` contains the invalid character `_`".to_string(),
+            },
+        )),
+    );
+}
+
+#[test]
+fn with_phone_format() {
+    let validator: serde_yaml::Value = serde_yaml::from_str(
+        r#"
+        Root:
+            +type: List
+            +ValueType:
+                +type: String
+                +format: phone
+                +countryCodes: [1, 44]
+                    "#,
+    )
+    .unwrap();
+
+    verify(
+        &json!(["+14155550100", "+442071838750"]),
+        &validator,
+        Ok(()),
+    );
+
+    verify(
+        &json!(["+33142685300"]),
+        &validator,
+        Err(As3JsonPath(
+            json_path("ROOT -> [0]"),
+            AS3ValidationError::FormatError {
+                word: "+33142685300".to_string(),
+                format: "phone".to_string(),
+                reason: "country calling code is not one of the allowed codes (1,44)".to_string(),
+            },
+        )),
+    );
+
+    verify(
+        &json!(["+1 415 555 0100"]),
+        &validator,
+        Err(As3JsonPath(
+            json_path("ROOT -> [0]"),
+            AS3ValidationError::FormatError {
+                word: "+1 415 555 0100".to_string(),
+                format: "phone".to_string(),
+                reason: "contains the invalid character ` `".to_string(),
+            },
+        )),
+    );
+
+    let validator = AS3Validator::from(
+        &serde_yaml::from_str("Root: { +type: String, +format: phone }").unwrap(),
+    )
+    .unwrap();
+    for invalid in ["4155550100", "+0155550100", "+1234", "+1234567890123456"] {
+        assert!(validator
+            .validate(&AS3Data::String(invalid.to_string()))
+            .is_err());
+    }
+    assert!(AS3Validator::from(
+        &serde_yaml::from_str("Root: { +type: String, +format: phone, +countryCodes: [1000] }")
+            .unwrap()
+    )
+    .is_err());
+    assert_eq!(
+        AS3Validator::from(
+            &serde_yaml::from_str("Root: { +type: String, +countryCodes: [1] }").unwrap()
+        ),
+        Err(As3JsonPath(
+            json_path("Root"),
+            AS3DefinitionError::Conflict(
+                "`+countryCodes` can only be used together with `+format`".to_string()
+            ),
+        ))
+    );
+}
+
+#[test]
+fn with_semver_format() {
+    let validator: serde_yaml::Value = serde_yaml::from_str(
+        r#"
+        Root:
+            +type: List
+            +ValueType:
+                +type: String
+                +format: semver
+                +minVersion: 1.2.0
+                +maxVersion: 2.0.0
+                    "#,
+    )
+    .unwrap();
+
+    verify(
+        &json!(["1.2.0", "1.10.3-beta.1+build.5", "2.0.0+build"]),
+        &validator,
+        Ok(()),
+    );
+
+    verify(
+        &json!(["1.2.0-rc.1"]),
+        &validator,
+        Err(As3JsonPath(
+            json_path("ROOT -> [0]"),
+            AS3ValidationError::FormatError {
+                word: "1.2.0-rc.1".to_string(),
+                format: "semver".to_string(),
+                reason: "version is below the minimum of `1.2.0`".to_string(),
+            },
+        )),
+    );
+
+    verify(
+        &json!(["2.1.0"]),
+        &validator,
+        Err(As3JsonPath(
+            json_path("ROOT -> [0]"),
+            AS3ValidationError::FormatError {
+                word: "2.1.0".to_string(),
+                format: "semver".to_string(),
+                reason: "version is above the maximum of `2.0.0`".to_string(),
+            },
+        )),
+    );
+
+    assert!(matches!(
+        AS3Validator::from(&validator)
+            .unwrap()
+            .validate(&AS3Data::from(&json!(["v1.3"]))),
+        Err(As3JsonPath(_, AS3ValidationError::FormatError { .. }))
+    ));
+
+    for definition in [
+        "Root: { +type: String, +format: semver, +minVersion: '1.0' }",
+        "Root: { +type: String, +format: semver, +minVersion: 2.0.0, +maxVersion: 1.0.0 }",
+        "Root: { +type: String, +format: url, +maxVersion: 1.0.0 }",
+    ] {
+        assert!(AS3Validator::from(&serde_yaml::from_str(definition).unwrap()).is_err());
+    }
+}
+
+#[test]
+fn with_strict_definition_errors() {
+    let build = |definition: &str| {
+        AS3Validator::from(&serde_yaml::from_str::<serde_yaml::Value>(definition).unwrap())
+    };
+
+    assert_eq!(
+        build(
+            r#"
+            Root:
+                +type: Object
+                age:
+                    +type: Integer
+                    +min: "18"
+            "#
+        ),
+        Err(As3JsonPath(
+            json_path("Root -> age"),
+            AS3DefinitionError::InvalidValue {
+                keyword: "+min".to_string(),
+                expected: "an Integer".to_string(),
+            }
+        ))
+    );
+
+    assert_eq!(
+        build(
+            r#"
+            Root:
+                +type: Object
+                name:
+                    +type: String
+                    +minLength: 10
+                    +maxLength: 3
+            "#
+        ),
+        Err(As3JsonPath(
+            json_path("Root -> name"),
+            AS3DefinitionError::Conflict(
+                "the minimum length (10) is above the maximum length (3)".to_string()
+            )
+        ))
+    );
+
+    assert_eq!(
+        build(
+            r#"
+            Root:
+                +type: Decimal
+                +min: 2.5
+                +max: 1
+            "#
+        ),
+        Err(As3JsonPath(
+            json_path("Root"),
+            AS3DefinitionError::Conflict("`+min` (2.5) is above `+max` (1)".to_string())
+        ))
+    );
+
+    assert_eq!(
+        build(
+            r#"
+            Root:
+                +type: Map
+                +KeyType: String
+                +ValueType:
+                    +type: List
+            "#
+        ),
+        Err(As3JsonPath(
+            json_path("Root -> +ValueType"),
+            AS3DefinitionError::MissingKeyword("+ValueType".to_string())
+        ))
+    );
+}
+
+#[test]
+fn with_bytes() {
+    let validator: serde_yaml::Value = serde_yaml::from_str(
+        r#"
+        Root:
+            +type: Object
+            payload:
+                +type: Bytes
+                +maxBytes: 8
+            checksum:
+                +type: Bytes
+                +encoding: hex
+                +minBytes: 4
+                    "#,
+    )
+    .unwrap();
+
+    verify(
+        &json!({ "payload": "aGVsbG8=", "checksum": "deadbeef" }),
+        &validator,
+        Ok(()),
+    );
+
+    verify(
+        &json!({ "payload": "aGVsbG8gd29ybGQ=", "checksum": "deadbeef" }),
+        &validator,
+        Err(As3JsonPath(
+            json_path("ROOT -> payload"),
+            AS3ValidationError::MaximumBytes {
+                length: 11,
+                max_bytes: 8,
+            },
+        )),
+    );
+
+    verify(
+        &json!({ "payload": "aGVsbG8=", "checksum": "beef" }),
+        &validator,
+        Err(As3JsonPath(
+            json_path("ROOT -> checksum"),
+            AS3ValidationError::MinimumBytes {
+                length: 2,
+                min_bytes: 4,
+            },
+        )),
+    );
+
+    assert!(matches!(
+        AS3Validator::from(&validator)
+            .unwrap()
+            .validate(&AS3Data::from(
+                &json!({ "payload": "not base64!", "checksum": "deadbeef" })
+            )),
+        Err(As3JsonPath(_, AS3ValidationError::FormatError { .. }))
+    ));
+}
+
+#[test]
+fn with_unknown_keyword() {
+    let validator: serde_yaml::Value = serde_yaml::from_str(
+        r#"
+        Root:
+            +type: Object
+            name:
+                +type: String
+                +Regex: "^[A-Z][a-z]"
+                    "#,
+    )
+    .unwrap();
+
+    assert_eq!(
+        AS3Validator::from(&validator),
+        Err(As3JsonPath(
+            json_path("Root -> name"),
+            AS3DefinitionError::UnknownKeyword {
+                keyword: "+Regex".to_string(),
+                suggestion: Some("+regex".to_string()),
+            }
+        ))
+    );
+
+    let lenient = AS3Validator::from_with_options(
+        &validator,
+        &DefinitionOptions {
+            lenient: true,
+            ..Default::default()
+        },
+    )
+    .unwrap();
+    assert_eq!(
+        lenient.validate(&AS3Data::from(&json!({ "name": "dilec" }))),
+        Ok(())
+    );
+}
+
+#[test]
+fn with_collection_shorthand() {
+    let validator: serde_yaml::Value = serde_yaml::from_str(
+        r#"
+        Root:
+            +type: Object
+            tags: List<String>
+            scores: Map<String, Integer>
+            matrix: List<List<Decimal>>
+                    "#,
+    )
+    .unwrap();
+
+    assert_eq!(
+        AS3Validator::from(&validator).unwrap(),
+        AS3Validator::Object {
+            fields: HashMap::from([
+                (
+                    "tags".to_owned(),
+                    AS3Validator::List(Box::new(AS3Validator::String {
+                        regex: None,
+                        max_length: None,
+                        min_length: None,
+                        format: None,
+                        any_regex: None,
+                        deny_regex: None,
+                        char_classes: Vec::new(),
+                        length_unit: LengthUnit::default(),
+                    })),
+                ),
+                (
+                    "scores".to_owned(),
+                    AS3Validator::Map {
+                        key_type: Box::new(AS3Validator::String {
+                            regex: None,
+                            max_length: None,
+                            min_length: None,
+                            format: None,
+                            any_regex: None,
+                            deny_regex: None,
+                            char_classes: Vec::new(),
+                            length_unit: LengthUnit::default(),
+                        }),
+                        value_type: Box::new(AS3Validator::Integer {
+                            minimum: None,
+                            maximum: None,
+                            width: None,
+                        }),
+                        min_entries: None,
+                        max_entries: None,
+                    },
+                ),
+                (
+                    "matrix".to_owned(),
+                    AS3Validator::List(Box::new(AS3Validator::List(Box::new(
+                        AS3Validator::Decimal {
+                            minimum: None,
+                            maximum: None,
+                        }
+                    )))),
+                ),
+            ]),
+            extra_keys: ExtraKeys::Allow,
+            pattern_keys: None,
+            assertions: Vec::new(),
+            requires: BTreeMap::new(),
+            order: ["tags", "scores", "matrix"].map(String::from).to_vec(),
+        }
+    );
+
+    verify(
+        &json!({ "tags": ["a", "b"], "scores": { "math": 7 }, "matrix": [[1.5, 2.5]] }),
+        &validator,
+        Ok(()),
+    );
+
+    let invalid: serde_yaml::Value = serde_yaml::from_str("Root: Map<String>").unwrap();
+    assert!(AS3Validator::from(&invalid).is_err());
+}
+
+#[test]
+fn with_datetime() {
+    let validator: serde_yaml::Value = serde_yaml::from_str(
+        r#"
+        Root:
+            +type: Object
+            created_at: DateTime
+            expires_at:
+                +type: DateTime
+                +min: "2020-01-01T00:00:00Z"
+                +max: "2030-12-31T23:59:59Z"
+                    "#,
+    )
+    .unwrap();
+
+    verify(
+        &json!({ "created_at": "2022-04-01T10:30:00+02:00", "expires_at": "2025-06-01T00:00:00-05:00" }),
+        &validator,
+        Ok(()),
+    );
+
+    verify(
+        &json!({ "created_at": "2022-04-01T10:30:00+02:00", "expires_at": "2019-12-31T23:00:00-02:00" }),
+        &validator,
+        Ok(()),
+    );
+
+    verify(
+        &json!({ "created_at": "2022-04-01T10:30:00+02:00", "expires_at": "2019-12-31T23:00:00Z" }),
+        &validator,
+        Err(As3JsonPath(
+            json_path("ROOT -> expires_at"),
+            AS3ValidationError::MinimumDateTime {
+                datetime: "2019-12-31T23:00:00Z".to_string(),
+                minimum: "2020-01-01T00:00:00+00:00".to_string(),
+            },
+        )),
+    );
+
+    assert!(matches!(
+        AS3Validator::from(&validator)
+            .unwrap()
+            .validate(&AS3Data::from(
+                &json!({ "created_at": "2022-04-01 10:30", "expires_at": "2025-06-01T00:00:00Z" })
+            )),
+        Err(As3JsonPath(_, AS3ValidationError::FormatError { .. }))
+    ));
+}
+
+#[test]
+fn with_inline_constraints() {
+    let validator: serde_yaml::Value = serde_yaml::from_str(
+        r#"
+        Root:
+            +type: Object
+            age: Integer(min=0, max=150)
+            name: String(regex="^[A-Z]", maxLength=10)
+            nickname: String(regex="^\"(.*)\"$")?
+            tags: List<String(max_length=3)>
+                    "#,
+    )
+    .unwrap();
+
+    verify(
+        &json!({ "age": 21, "name": "Dilec", "nickname": null, "tags": ["a", "bc"] }),
+        &validator,
+        Ok(()),
+    );
+
+    verify(
+        &json!({ "age": 21, "name": "Dilec", "nickname": "\"Dil\"", "tags": [] }),
+        &validator,
+        Ok(()),
+    );
+
+    verify(
+        &json!({ "age": 151, "name": "Dilec", "nickname": null, "tags": [] }),
+        &validator,
+        Err(As3JsonPath(
+            json_path("ROOT -> age"),
+            AS3ValidationError::MaximumInteger {
+                number: 151,
+                maximum: 150,
+            },
+        )),
+    );
+
+    verify(
+        &json!({ "age": 21, "name": "dilec", "nickname": null, "tags": [] }),
+        &validator,
+        Err(As3JsonPath(
+            json_path("ROOT -> name"),
+            AS3ValidationError::RegexError {
+                word: "dilec".to_string(),
+                regex: "^[A-Z]".to_string(),
+            },
+        )),
+    );
+
+    let typo: serde_yaml::Value = serde_yaml::from_str("Root: Integer(minimum=0)").unwrap();
+    assert_eq!(
+        AS3Validator::from(&typo),
+        Err(As3JsonPath(
+            json_path("Root"),
+            AS3DefinitionError::UnknownKeyword {
+                keyword: "+minimum".to_string(),
+                suggestion: None,
+            }
+        ))
+    );
+}
+
+#[test]
+fn with_time() {
+    let validator: serde_yaml::Value = serde_yaml::from_str(
+        r#"
+        Root:
+            +type: Map
+            +KeyType: String
+            +ValueType:
+                +type: Time
+                +min: "08:00"
+                +max: "20:30"
+                    "#,
+    )
+    .unwrap();
+
+    verify(
+        &json!({ "monday": "08:00", "tuesday": "20:30:00", "friday": "12:15:30" }),
+        &validator,
+        Ok(()),
+    );
+
+    verify(
+        &json!({ "sunday": "21:00" }),
+        &validator,
+        Err(As3JsonPath(
+            json_path("ROOT -> sunday"),
+            AS3ValidationError::MaximumTime {
+                time: "21:00".to_string(),
+                maximum: "20:30:00".to_string(),
+            },
+        )),
+    );
+
+    verify(
+        &json!({ "sunday": "8:00" }),
+        &validator,
+        Err(As3JsonPath(
+            json_path("ROOT -> sunday"),
+            AS3ValidationError::FormatError {
+                word: "8:00".to_string(),
+                format: "time".to_string(),
+                reason: "expected HH:MM or HH:MM:SS".to_string(),
+            },
+        )),
+    );
+}
+
+#[test]
+fn with_definition_from_comments() {
+    let source = r#"
+name: billing        # as3: String(regex="^[a-z]+$")
+replicas: 3          # as3: Integer min=1 max=10
+comment: "not # as3 checked"
+database:
+  host: db.local     # as3: String format="hostname"
+  port: 5432
+workers:
+  - queue: emails    # as3: String
+    threads: 2       # as3: Integer min=1
+  - queue: reports
+    threads: 1
+"#;
+
+    let definition = crate::inline_schema::definition_from_comments(source).unwrap();
+    let expected: serde_yaml::Value = serde_yaml::from_str(
+        r#"
+        Root:
+            +type: Object
+            name: String(regex="^[a-z]+$")
+            replicas: Integer(min=1, max=10)
+            database:
+                +type: Object
+                host: String(format="hostname")
+            workers:
+                +type: List
+                +ValueType:
+                    +type: Object
+                    queue: String
+                    threads: Integer(min=1)
+                    "#,
+    )
+    .unwrap();
+    assert_eq!(definition, expected);
+
+    let data: serde_json::Value = serde_yaml::from_str(source).unwrap();
+    verify(&data, &definition, Ok(()));
+
+    let data: serde_json::Value =
+        serde_yaml::from_str(&source.replace("replicas: 3 ", "replicas: 30")).unwrap();
+    verify(
+        &data,
+        &definition,
+        Err(As3JsonPath(
+            json_path("ROOT -> replicas"),
+            AS3ValidationError::MaximumInteger {
+                number: 30,
+                maximum: 10,
+            },
+        )),
+    );
+
+    assert_eq!(
+        crate::inline_schema::definition_from_comments("name: billing # plain comment"),
+        None
+    );
+}
+
+#[test]
+fn with_duration() {
+    let validator: serde_yaml::Value = serde_yaml::from_str(
+        r#"
+        Root:
+            +type: Object
+            timeout:
+                +type: Duration
+                +min: 1s
+                +max: PT1H
+            retry_interval:
+                +type: Duration
+                +format: iso8601
+                    "#,
+    )
+    .unwrap();
+
+    verify(
+        &json!({ "timeout": "30min", "retry_interval": "PT1M30.5S" }),
+        &validator,
+        Ok(()),
+    );
+
+    verify(
+        &json!({ "timeout": "P1DT2H", "retry_interval": "PT10S" }),
+        &validator,
+        Err(As3JsonPath(
+            json_path("ROOT -> timeout"),
+            AS3ValidationError::MaximumDuration {
+                duration: "P1DT2H".to_string(),
+                maximum: "1h".to_string(),
+            },
+        )),
+    );
+
+    verify(
+        &json!({ "timeout": "90s", "retry_interval": "90s" }),
+        &validator,
+        Err(As3JsonPath(
+            json_path("ROOT -> retry_interval"),
+            AS3ValidationError::FormatError {
+                word: "90s".to_string(),
+                format: "duration".to_string(),
+                reason: "ISO 8601 durations start with `P`".to_string(),
+            },
+        )),
+    );
+
+    verify(
+        &json!({ "timeout": "90s", "retry_interval": "P1M" }),
+        &validator,
+        Err(As3JsonPath(
+            json_path("ROOT -> retry_interval"),
+            AS3ValidationError::FormatError {
+                word: "P1M".to_string(),
+                format: "duration".to_string(),
+                reason: "years and months are not supported as they have no fixed length"
+                    .to_string(),
+            },
+        )),
+    );
+
+    // Past the range of a Duration, rejected rather than panicking
+    verify(
+        &json!({ "timeout": "90s", "retry_interval": "P99999999999999999999W" }),
+        &validator,
+        Err(As3JsonPath(
+            json_path("ROOT -> retry_interval"),
+            AS3ValidationError::FormatError {
+                word: "P99999999999999999999W".to_string(),
+                format: "duration".to_string(),
+                reason: "duration is too long to be represented".to_string(),
+            },
+        )),
+    );
+
+    // Units are written once each, from the largest to the smallest
+    for (duration, unit) in [("PT1H1H", 'H'), ("PT5S1H", 'H'), ("P1D1W", 'W')] {
+        verify(
+            &json!({ "timeout": "90s", "retry_interval": duration }),
+            &validator,
+            Err(As3JsonPath(
+                json_path("ROOT -> retry_interval"),
+                AS3ValidationError::FormatError {
+                    word: duration.to_string(),
+                    format: "duration".to_string(),
+                    reason: format!(
+                        "`{unit}` is repeated or out of order, units go as W, D, T, H, M, S"
+                    ),
+                },
+            )),
+        );
+    }
+    verify(
+        &json!({ "timeout": "90s", "retry_interval": "P1W1DT1H1M1.5S" }),
+        &validator,
+        Ok(()),
+    );
+
+    let validator = AS3Validator::from(&validator).unwrap();
+    let data = json!({ "timeout": "P99999999999999999999W", "retry_interval": "PT1S" });
+    assert!(validator.validate(&AS3Data::from(&data)).is_err());
+}
+
+#[test]
+fn with_calendar_dates() {
+    let validator: serde_yaml::Value = serde_yaml::from_str(
+        r#"
+        Root:
+            +type: List
+            +ValueType: Date
+                    "#,
+    )
+    .unwrap();
+
+    verify(
+        &json!(["2024-02-29", "2023-12-31", "2000-02-29"]),
+        &validator,
+        Ok(()),
+    );
+
+    for date in [
+        "2023-02-29",
+        "2023-02-30",
+        "2023-04-31",
+        "1900-02-29",
+        "2023-2-03",
+    ] {
+        verify(
+            &json!([date]),
+            &validator,
+            Err(As3JsonPath(
+                json_path("ROOT -> [0]"),
+                AS3ValidationError::Generic(format!(
+                    " `{date}` can't be converted to a valid date. [Supported YYYY-MM-DD] "
+                )),
+            )),
+        );
+    }
+}
+
+#[cfg(feature = "tools")]
+#[test]
+fn with_editor_schema() {
+    let validator: serde_yaml::Value = serde_yaml::from_str(
+        r#"
+        Root:
+            +type: Object
+            name:
+                +type: String
+                +regex: "^[A-Z]"
+            age: Integer(min=0, max=150)
+            active: Bool?
+            tags: List<String>
+                    "#,
+    )
+    .unwrap();
+
+    assert_eq!(
+        crate::json_schema::to_editor_schema(&AS3Validator::from(&validator).unwrap()),
+        json!({
+            "$schema": "http://json-schema.org/draft-07/schema#",
+            "type": "object",
+            "description": "Object",
+            "required": ["active", "age", "name", "tags"],
+            "properties": {
+                "active": {
+                    "type": ["boolean", "null"],
+                    "enum": [true, false, null],
+                    "description": "Bool",
+                },
+                "age": {
+                    "type": "integer",
+                    "minimum": 0,
+                    "maximum": 150,
+                    "description": "Integer",
+                },
+                "name": {
+                    "type": "string",
+                    "pattern": "^[A-Z]",
+                    "description": "String",
+                },
+                "tags": {
+                    "type": "array",
+                    "items": { "type": "string", "description": "String" },
+                    "description": "List",
+                },
+            },
+        })
+    );
+}
+
+#[cfg(feature = "tools")]
+#[test]
+fn with_impact_analysis() {
+    let old = AS3Validator::from(&serde_yaml::from_str("Root: Integer(min=0)").unwrap()).unwrap();
+    let new = AS3Validator::from(&serde_yaml::from_str("Root: Integer(min=10)").unwrap()).unwrap();
+    let corpus = [
+        ("a.json", AS3Data::Integer(20)),
+        ("b.json", AS3Data::Integer(5)),
+        ("c.json", AS3Data::Integer(-1)),
+        ("d.json", AS3Data::Null),
+    ];
+
+    let report = crate::impact::impact(
+        &old,
+        &new,
+        corpus
+            .iter()
+            .map(|(name, document)| (name.to_string(), document)),
+    );
+
+    assert_eq!(report.unchanged_passing, 1);
+    assert_eq!(report.unchanged_failing, 1);
+    assert_eq!(
+        report.newly_failing,
+        vec![(
+            "b.json".to_string(),
+            As3JsonPath(
+                json_path("ROOT"),
+                AS3ValidationError::MinimumInteger {
+                    number: 5,
+                    minimum: 10
+                }
+            )
+        )]
+    );
+    assert_eq!(report.changed.len(), 1);
+    assert_eq!(report.changed[0].0, "c.json");
+    assert!(report.newly_passing.is_empty());
+    assert!(report.is_breaking());
+}
+
+#[test]
+fn with_date_bounds() {
+    let validator: serde_yaml::Value = serde_yaml::from_str(
+        r#"
+        Root:
+            +type: Object
+            birthday:
+                +type: Date
+                +min: 1900-01-01
+                +max: today
+            expiry:
+                +type: Date
+                +min: today
+                    "#,
+    )
+    .unwrap();
+
+    verify(
+        &json!({ "birthday": "1985-06-15", "expiry": "9999-12-31" }),
+        &validator,
+        Ok(()),
+    );
+
+    verify(
+        &json!({ "birthday": "1899-12-31", "expiry": "9999-12-31" }),
+        &validator,
+        Err(As3JsonPath(
+            json_path("ROOT -> birthday"),
+            AS3ValidationError::MinimumDate {
+                date: "1899-12-31".to_string(),
+                minimum: "1900-01-01".to_string(),
+            },
+        )),
+    );
+
+    let today = chrono::Local::now().date_naive();
+    verify(
+        &json!({ "birthday": "1985-06-15", "expiry": "2000-01-01" }),
+        &validator,
+        Err(As3JsonPath(
+            json_path("ROOT -> expiry"),
+            AS3ValidationError::MinimumDate {
+                date: "2000-01-01".to_string(),
+                minimum: format!("today ({today})"),
+            },
+        )),
+    );
+
+    let conflict: serde_yaml::Value =
+        serde_yaml::from_str("Root: Date(min=\"2030-01-01\", max=\"2000-01-01\")").unwrap();
+    assert!(matches!(
+        AS3Validator::from(&conflict),
+        Err(As3JsonPath(_, AS3DefinitionError::Conflict(_)))
+    ));
+}
+
+#[cfg(feature = "tools")]
+#[test]
+fn with_report_template() {
+    let validator =
+        AS3Validator::from(&serde_yaml::from_str("Root: Integer(max=10)").unwrap()).unwrap();
+    let template = "{{#if valid}}✅ {{input}}{{else}}❌ {{ input }}: {{message}} [{{path}}]{{/if}}";
+
+    let report = ValidationReport::new(
+        "ok.json".to_string(),
+        None,
+        &validator.validate(&AS3Data::Integer(3)),
+    );
+    assert_eq!(report.render(template), Ok("✅ ok.json".to_string()));
+
+    let report = ValidationReport::new(
+        "ko.json".to_string(),
+        Some("schema.yaml".to_string()),
+        &validator.validate(&AS3Data::Integer(11)),
+    );
+    assert_eq!(
+        report.render(template),
+        Ok("❌ ko.json: `11` is above the maximum of `10` [ROOT]".to_string())
+    );
+    assert_eq!(
+        report.render("{{status}} {{definition}} {{code}}"),
+        Ok("failed schema.yaml E_MAX_INT".to_string())
+    );
+    assert!(report.render("{{severity}}").is_err());
+    assert!(report.render("{{#if valid}}unclosed").is_err());
+}
+
+#[test]
+fn with_sized_integers() {
+    let validator: serde_yaml::Value = serde_yaml::from_str(
+        r#"
+        Root:
+            +type: Object
+            port: UInt16
+            offset:
+                +type: Integer
+                +bits: 8
+            count:
+                +type: UInt32
+                +max: 1000
+                    "#,
+    )
+    .unwrap();
+
+    verify(
+        &json!({ "port": 65535, "offset": -128, "count": 0 }),
+        &validator,
+        Ok(()),
+    );
+
+    verify(
+        &json!({ "port": 65536, "offset": 0, "count": 0 }),
+        &validator,
+        Err(As3JsonPath(
+            json_path("ROOT -> port"),
+            AS3ValidationError::IntegerWidth {
+                number: 65536,
+                width: "u16".to_string(),
+            },
+        )),
+    );
+
+    verify(
+        &json!({ "port": 80, "offset": 128, "count": 0 }),
+        &validator,
+        Err(As3JsonPath(
+            json_path("ROOT -> offset"),
+            AS3ValidationError::IntegerWidth {
+                number: 128,
+                width: "i8".to_string(),
+            },
+        )),
+    );
+
+    verify(
+        &json!({ "port": 80, "offset": 0, "count": -1 }),
+        &validator,
+        Err(As3JsonPath(
+            json_path("ROOT -> count"),
+            AS3ValidationError::IntegerWidth {
+                number: -1,
+                width: "u32".to_string(),
+            },
+        )),
+    );
+
+    for definition in [
+        "Root: { +type: Integer, +bits: 12 }",
+        "Root: { +type: Integer, +unsigned: true }",
+        "Root: { +type: Int8, +bits: 16 }",
+    ] {
+        assert!(AS3Validator::from(&serde_yaml::from_str(definition).unwrap()).is_err());
+    }
+}
+
+#[test]
+fn with_unsigned_64_bits_integers() {
+    let data = json!({ "id": 18446744073709551615u64 });
+    assert_eq!(
+        AS3Data::from(&data),
+        AS3Data::Object(HashMap::from([(
+            "id".to_string(),
+            Box::new(AS3Data::UInteger(u64::MAX))
+        )]))
+    );
+
+    for (definition, expected) in [
+        ("Integer", Ok(())),
+        ("UInt64", Ok(())),
+        (
+            "Int64",
+            Err(As3JsonPath(
+                json_path("ROOT -> id"),
+                AS3ValidationError::IntegerWidth {
+                    number: u64::MAX as i128,
+                    width: "i64".to_string(),
+                },
+            )),
+        ),
+        (
+            "Integer(max=10)",
+            Err(As3JsonPath(
+                json_path("ROOT -> id"),
+                AS3ValidationError::MaximumInteger {
+                    number: i128::from(u64::MAX),
+                    maximum: 10,
+                },
+            )),
+        ),
+    ] {
+        let validator =
+            serde_yaml::from_str(&format!("Root:\n  +type: Object\n  id: {definition}")).unwrap();
+        verify(&data, &validator, expected);
+    }
+}
+
+#[cfg(feature = "tools")]
+#[test]
+fn with_failure_summary() {
+    let validator =
+        AS3Validator::from(&serde_yaml::from_str("Root: Integer(min=0, max=10)").unwrap()).unwrap();
+    let failures: Vec<_> = [
+        ("a.json", 11),
+        ("b.json", -1),
+        ("c.json", 12),
+        ("d.json", 5),
+    ]
+    .into_iter()
+    .filter_map(|(name, number)| {
+        Some((
+            name.to_string(),
+            validator.validate(&AS3Data::Integer(number)).err()?,
+        ))
+    })
+    .collect();
+
+    let summary = crate::notify::FailureSummary::new(
+        4,
+        failures.iter().map(|(name, error)| (name.as_str(), error)),
+    );
+    assert_eq!(summary.failing, 3);
+    assert_eq!(
+        summary.top_errors,
+        vec![
+            ("MaximumInteger".to_string(), 2),
+            ("MinimumInteger".to_string(), 1)
+        ]
+    );
+    assert_eq!(summary.samples[1], "b.json: ROOT");
+
+    let payload = summary.payload();
+    assert_eq!(payload["total"], json!(4));
+    assert!(payload["text"]
+        .as_str()
+        .unwrap()
+        .starts_with("❌ AS3: 3 of 4 documents failed validation"));
+}
+
+#[cfg(feature = "tools")]
+#[test]
+fn with_sampled_values() {
+    let validator: serde_yaml::Value = serde_yaml::from_str(
+        r#"
+Root:
+  +type: Object
+  email: { +type: String, +anonymize: mask }
+  age: Integer(min=0)
+"#,
+    )
+    .unwrap();
+    let validator = AS3Validator::from(&validator).unwrap();
+    let documents: Vec<_> = (0..50)
+        .map(|index| {
+            let data = json!({ "email": format!("user{index}@example.org"), "age": -index - 1 });
+            (format!("{index}.json"), AS3Data::from(&data))
+        })
+        .collect();
+    let failures: Vec<_> = documents
+        .iter()
+        .map(|(name, data)| (name.as_str(), data, validator.validate(data).unwrap_err()))
+        .collect();
+
+    let mut summary = crate::notify::FailureSummary::new(
+        documents.len(),
+        failures.iter().map(|(name, _, error)| (*name, error)),
+    );
+    let sampled = failures
+        .iter()
+        .map(|(name, data, error)| (*name, *data, error));
+    summary.sample_values(&validator, sampled.clone(), 3, 7);
+    assert_eq!(summary.values.len(), 3);
+    assert!(summary
+        .values
+        .iter()
+        .all(|sample| sample.path == "ROOT -> age" && sample.value.starts_with('-')));
+    let documents: Vec<_> = summary.values.iter().map(|s| s.document.clone()).collect();
+    summary.sample_values(&validator, sampled.clone(), 3, 7);
+    assert_eq!(
+        summary
+            .values
+            .iter()
+            .map(|s| s.document.clone())
+            .collect::<Vec<_>>(),
+        documents
+    );
+    summary.sample_values(&validator, sampled.clone(), 3, 8);
+    assert_ne!(
+        summary
+            .values
+            .iter()
+            .map(|s| s.document.clone())
+            .collect::<Vec<_>>(),
+        documents
+    );
+
+    let redacted = crate::anonymize::redact(&validator, failures[0].1);
+    assert_eq!(
+        redacted,
+        AS3Data::from(&json!({ "email": "[redacted]", "age": -1 }))
+    );
+}
+
+#[test]
+fn with_null() {
+    let validator: serde_yaml::Value = serde_yaml::from_str(
+        r#"
+        Root:
+            +type: Object
+            deleted_at: Null
+            payload:
+                +type: Null
+                    "#,
+    )
+    .unwrap();
+
+    verify(
+        &json!({ "deleted_at": null, "payload": null }),
+        &validator,
+        Ok(()),
+    );
+
+    verify(
+        &json!({ "deleted_at": null, "payload": "data" }),
+        &validator,
+        Err(As3JsonPath(
+            json_path("ROOT -> payload"),
+            AS3ValidationError::TypeError {
+                expected: AS3Validator::Null,
+                got: AS3Data::String("data".to_string()),
+            },
+        )),
+    );
+
+    let nullable_null = serde_yaml::from_str("Root: Null?").unwrap();
+    assert_eq!(
+        AS3Validator::from(&nullable_null).unwrap().optimize(),
+        AS3Validator::Null
+    );
+
+    // Named as written in definitions
+    let nullable = serde_yaml::from_str("Root: Integer?").unwrap();
+    assert_eq!(
+        AS3Validator::from(&nullable).unwrap().type_name(),
+        "Integer?"
+    );
+}
+
+#[cfg(feature = "tools")]
+#[test]
+fn with_daemon_job() {
+    let folder = std::env::temp_dir().join(format!("as3-daemon-{}", std::process::id()));
+    std::fs::create_dir_all(folder.join("data/nested")).unwrap();
+    std::fs::write(folder.join("schema.yaml"), "Root: Integer(max=10)").unwrap();
+    std::fs::write(folder.join("data/a.json"), "3").unwrap();
+    std::fs::write(folder.join("data/nested/b.yaml"), "11").unwrap();
+    std::fs::write(folder.join("data/notes.txt"), "ignored").unwrap();
+    std::fs::write(
+        folder.join("as3.toml"),
+        r#"
+        [[job]]
+        name = "numbers"
+        definition = "schema.yaml"
+        directory = "data"
+        every = "15m"
+        "#,
+    )
+    .unwrap();
+
+    let config = crate::daemon::DaemonConfig::read(&folder.join("as3.toml")).unwrap();
+    assert_eq!(
+        config.jobs[0].every,
+        std::time::Duration::from_secs(15 * 60)
+    );
+    let result = config.jobs[0].run().unwrap();
+    assert_eq!(result.job, "numbers");
+    assert_eq!(result.summary.total, 2);
+    assert_eq!(result.summary.failing, 1);
+    assert_eq!(
+        result.summary.top_errors,
+        vec![("MaximumInteger".to_string(), 1)]
+    );
+
+    std::fs::write(
+        folder.join("invalid.toml"),
+        "[[job]]\nname = \"x\"\ndefinition = \"schema.yaml\"\nevery = \"1h\"\n",
+    )
+    .unwrap();
+    assert!(crate::daemon::DaemonConfig::read(&folder.join("invalid.toml")).is_err());
+    std::fs::remove_dir_all(folder).unwrap();
+}
+
+#[test]
+fn with_any() {
+    let validator: serde_yaml::Value = serde_yaml::from_str(
+        r#"
+        Root:
+            +type: Object
+            id: Integer
+            metadata: Any
+            tags:
+                +type: List
+                +ValueType:
+                    +type: Any
+                    "#,
+    )
+    .unwrap();
+
+    for metadata in [
+        json!(null),
+        json!("text"),
+        json!({ "nested": [1, 2.5, true] }),
+    ] {
+        verify(
+            &json!({ "id": 1, "metadata": metadata, "tags": [1, "two", null, []] }),
+            &validator,
+            Ok(()),
+        );
+    }
+
+    verify(
+        &json!({ "id": 1, "tags": [] }),
+        &validator,
+        Err(As3JsonPath(
+            json_path("ROOT"),
+            AS3ValidationError::MissingKey {
+                key: "metadata".to_string(),
+                suggestion: None,
+            },
+        )),
+    );
+}
+
+#[cfg(all(feature = "tools", not(target_os = "wasi")))]
+#[test]
+fn with_history() {
+    let validator =
+        AS3Validator::from(&serde_yaml::from_str("Root: Integer(max=10)").unwrap()).unwrap();
+    let run = |job: &str, numbers: &[i64]| {
+        let outcomes: Vec<_> = numbers
+            .iter()
+            .map(|number| {
+                (
+                    format!("{number}.json"),
+                    validator.validate(&AS3Data::Integer(*number)).err(),
+                )
+            })
+            .collect();
+        crate::daemon::JobResult {
+            job: job.to_string(),
+            time: "2024-01-01T00:00:00+00:00".to_string(),
+            fingerprint: crate::daemon::fingerprint("Root: Integer(max=10)"),
+            summary: crate::notify::FailureSummary::new(numbers.len(), []),
+            outcomes,
+        }
+    };
+
+    let mut history = crate::history::History::open(std::path::Path::new(":memory:")).unwrap();
+    history.record(&run("numbers", &[1, 20, 30])).unwrap();
+    history.record(&run("numbers", &[1, 2])).unwrap();
+    history.record(&run("other", &[])).unwrap();
+
+    let runs = history.runs(Some("numbers"), 10).unwrap();
+    assert_eq!(runs.len(), 2);
+    assert_eq!((runs[0].total, runs[0].failing), (2, 0));
+    assert_eq!((runs[1].total, runs[1].failing), (3, 2));
+    assert_eq!(runs[1].fingerprint, "2238de5f8004d96c");
+    assert_eq!(history.runs(None, 1).unwrap()[0].job, "other");
+}
+
+#[test]
+fn with_map_entries() {
+    let validator: serde_yaml::Value = serde_yaml::from_str(
+        r#"
+        Root:
+            +type: Map
+            +KeyType: String
+            +ValueType: Integer
+            +MinEntries: 1
+            +MaxEntries: 2
+                    "#,
+    )
+    .unwrap();
+
+    verify(&json!({ "a": 1, "b": 2 }), &validator, Ok(()));
+
+    verify(
+        &json!({}),
+        &validator,
+        Err(As3JsonPath(
+            json_path("ROOT"),
+            AS3ValidationError::MinimumEntries {
+                count: 0,
+                min_entries: 1,
+            },
+        )),
+    );
+
+    verify(
+        &json!({ "a": 1, "b": 2, "c": 3 }),
+        &validator,
+        Err(As3JsonPath(
+            json_path("ROOT"),
+            AS3ValidationError::MaximumEntries {
+                count: 3,
+                max_entries: 2,
+            },
+        )),
+    );
+
+    let conflict = serde_yaml::from_str(
+        "Root: { +type: Map, +KeyType: String, +ValueType: Integer, +MinEntries: 3, +MaxEntries: 2 }",
+    )
+    .unwrap();
+    assert!(matches!(
+        AS3Validator::from(&conflict),
+        Err(As3JsonPath(_, AS3DefinitionError::Conflict(_)))
+    ));
+}
+
+#[cfg(feature = "tools")]
+#[test]
+fn with_corpus_stats() {
+    let validator = AS3Validator::from(
+        &serde_yaml::from_str(
+            r#"
+            Root:
+                +type: Object
+                age: Integer(max=120)?
+                tags: List<String>
+            "#,
+        )
+        .unwrap(),
+    )
+    .unwrap();
+
+    let mut stats = crate::stats::CorpusStats::default();
+    for document in [
+        json!({ "age": 30, "tags": ["a", "b"] }),
+        json!({ "age": null, "tags": [] }),
+        json!({ "age": 130, "tags": [1] }),
+        json!({ "tags": [] }),
+    ] {
+        stats.add(&validator, &AS3Data::from(&document));
+    }
+
+    assert_eq!(stats.documents, 4);
+    let age = &stats.fields["ROOT -> age"];
+    assert_eq!(
+        (age.values, age.nulls, age.missing, age.violations),
+        (3, 1, 1, 1)
+    );
+    assert_eq!((age.minimum, age.maximum), (Some(30.0), Some(130.0)));
+    assert_eq!(stats.fields["ROOT -> tags -> []"].values, 3);
+    assert_eq!(stats.fields["ROOT -> tags -> []"].violations, 1);
+
+    let prometheus = stats.to_prometheus();
+    assert!(prometheus.contains("as3_documents 4\n"));
+    assert!(prometheus.contains("as3_field_null_rate{field=\"ROOT -> age\"} 0.3333333333333333\n"));
+    assert!(!prometheus.contains("as3_field_minimum{field=\"ROOT -> tags\"}"));
+
+    let json = stats.to_json("2024-01-01T00:00:00Z");
+    assert_eq!(json["fields"]["ROOT -> age"]["violations"], json!(1));
+}
+
+#[cfg(all(feature = "tools", not(target_os = "wasi")))]
+#[test]
+fn with_digest() {
+    let validator =
+        AS3Validator::from(&serde_yaml::from_str("Root: Integer(max=10)").unwrap()).unwrap();
+    let run = |time: &str, documents: &[(&str, i64)]| crate::daemon::JobResult {
+        job: "numbers".to_string(),
+        time: time.to_string(),
+        fingerprint: String::new(),
+        summary: crate::notify::FailureSummary::new(documents.len(), []),
+        outcomes: documents
+            .iter()
+            .map(|(document, number)| {
+                (
+                    document.to_string(),
+                    validator.validate(&AS3Data::Integer(*number)).err(),
+                )
+            })
+            .collect(),
+    };
+
+    let mut history = crate::history::History::open(std::path::Path::new(":memory:")).unwrap();
+    history
+        .record(&run(
+            "2024-01-01T00:00:00+00:00",
+            &[("a", 1), ("b", 1), ("c", 20), ("d", 20)],
+        ))
+        .unwrap();
+    history
+        .record(&run(
+            "2024-01-02T12:00:00+02:00",
+            &[("a", 1), ("b", 20), ("c", 1), ("d", 20), ("e", 30)],
+        ))
+        .unwrap();
+
+    let since = chrono::DateTime::parse_from_rfc3339("2024-01-02T00:00:00Z").unwrap();
+    let digests = history.digest(since).unwrap();
+    assert_eq!(digests.len(), 1);
+    let digest = &digests[0];
+    assert_eq!(
+        digest.baseline_time.as_deref(),
+        Some("2024-01-01T00:00:00+00:00")
+    );
+    let failure = |document: &str| crate::history::DocumentResult {
+        document: document.to_string(),
+        failure: Some(("MaximumInteger".to_string(), "ROOT".to_string())),
+    };
+    assert_eq!(digest.new_failures, vec![failure("e")]);
+    assert_eq!(digest.regressions, vec![failure("b")]);
+    assert_eq!(digest.fixed, vec!["c".to_string()]);
+    assert_eq!(digest.still_failing, 1);
+
+    let markdown = crate::digest::to_markdown(&digests, "1day");
+    assert!(markdown.contains("| 1 | 1 | 1 | 1 |"));
+    assert!(markdown.contains("- `b`: MaximumInteger at `ROOT`"));
+    assert!(crate::digest::to_html(&digests, "1day").contains("<li>e: MaximumInteger at ROOT</li>"));
+    assert!(history
+        .digest(chrono::DateTime::parse_from_rfc3339("2025-01-01T00:00:00Z").unwrap())
+        .unwrap()
+        .is_empty());
+}
+
+#[test]
+fn with_extra_keys() {
+    let validator: serde_yaml::Value = serde_yaml::from_str(
+        r#"
+        Root:
+            +type: Object
+            +extraKeys: forbid
+            name: String
+            labels:
+                +type: Object
+                +extraKeys: warn
+                team: String
+                    "#,
+    )
+    .unwrap();
+
+    verify(
+        &json!({ "name": "api", "labels": { "team": "core" } }),
+        &validator,
+        Ok(()),
+    );
+
+    verify(
+        &json!({ "name": "api", "nmae": "typo", "labels": { "team": "core" } }),
+        &validator,
+        Err(As3JsonPath(
+            json_path("ROOT"),
+            AS3ValidationError::UnexpectedKey {
+                key: "nmae".to_string(),
+                suggestion: None,
+            },
+        )),
+    );
+
+    let data = AS3Data::from(&json!({ "name": "api", "labels": { "team": "core", "tier": 1 } }));
+    let validator = AS3Validator::from(&validator).unwrap();
+    assert_eq!(validator.validate(&data), Ok(()));
+    assert_eq!(
+        validator.unexpected_keys(&data),
+        vec![As3JsonPath(
+            json_path("ROOT -> labels"),
+            AS3ValidationError::UnexpectedKey {
+                key: "tier".to_string(),
+                suggestion: None
+            },
+        )]
+    );
+
+    let invalid = serde_yaml::from_str("Root: { +type: Object, +extraKeys: deny }").unwrap();
+    assert!(AS3Validator::from(&invalid).is_err());
+}
+
+#[test]
+fn with_pattern_keys() {
+    let validator: serde_yaml::Value = serde_yaml::from_str(
+        r#"
+        Root:
+            +type: Object
+            +extraKeys: forbid
+            +patternKeys:
+                "^x-": String
+                "^count-": Integer(min=0)
+            name: String
+                    "#,
+    )
+    .unwrap();
+
+    verify(
+        &json!({ "name": "api", "x-owner": "core", "count-calls": 3 }),
+        &validator,
+        Ok(()),
+    );
+
+    verify(
+        &json!({ "name": "api", "x-owner": 1 }),
+        &validator,
+        Err(As3JsonPath(
+            json_path("ROOT -> x-owner"),
+            AS3ValidationError::TypeError {
+                expected: AS3Validator::String {
+                    regex: None,
+                    max_length: None,
+                    min_length: None,
+                    format: None,
+                    any_regex: None,
+                    deny_regex: None,
+                    char_classes: Vec::new(),
+                    length_unit: LengthUnit::default(),
+                },
+                got: AS3Data::Integer(1),
+            },
+        )),
+    );
+
+    verify(
+        &json!({ "name": "api", "owner": "core" }),
+        &validator,
+        Err(As3JsonPath(
+            json_path("ROOT"),
+            AS3ValidationError::UnexpectedKey {
+                key: "owner".to_string(),
+                suggestion: None,
+            },
+        )),
+    );
+
+    let invalid =
+        serde_yaml::from_str("Root: { +type: Object, +patternKeys: { \"(\": String } }").unwrap();
+    assert!(matches!(
+        AS3Validator::from(&invalid),
+        Err(As3JsonPath(_, AS3DefinitionError::InvalidRegex { .. }))
+    ));
+}
+
+#[test]
+fn with_canonical_validators() {
+    let definition = |source: &str| {
+        AS3Validator::from(&serde_yaml::from_str::<serde_yaml::Value>(source).unwrap()).unwrap()
+    };
+
+    let first = definition(
+        r#"
+        Root:
+            +type: Object
+            id: UInt8
+            tags:
+                +type: List
+                +ValueType:
+                    +type: String
+                    +anyRegex: ["^b", "^a"]
+            owner: String??
+                    "#,
+    );
+    let second = definition(
+        r#"
+        Root:
+            +type: Object
+            owner: String?
+            tags:
+                +type: List
+                +ValueType:
+                    +type: String
+                    +anyRegex: ["^a", "^b", "^a"]
+            id: Integer(bits=8, unsigned=true, min=0, max=255)
+                    "#,
+    );
+    let third = definition(
+        r#"
+        Root:
+            +type: Object
+            id: UInt16
+            tags: List<String>
+            owner: String?
+                    "#,
+    );
+
+    assert_ne!(first, second);
+    assert_eq!(first.canonical(), second.canonical());
+    assert_ne!(first.canonical(), third.canonical());
+
+    let registry =
+        std::collections::HashSet::from([first.canonical(), second.canonical(), third.canonical()]);
+    assert_eq!(registry.len(), 2);
+}
+
+#[test]
+fn with_conditional() {
+    let validator: serde_yaml::Value = serde_yaml::from_str(
+        r#"
+        Root:
+            +type: Object
+            kind: String
+            +if:
+                +type: Object
+                kind:
+                    +type: String
+                    +regex: "^car$"
+            +then:
+                +type: Object
+                doors: Integer
+            +else:
+                +type: Object
+                wheels: Integer
+                    "#,
+    )
+    .unwrap();
+
+    verify(&json!({ "kind": "car", "doors": 4 }), &validator, Ok(()));
+    verify(&json!({ "kind": "bike", "wheels": 2 }), &validator, Ok(()));
+
+    verify(
+        &json!({ "kind": "car", "wheels": 4 }),
+        &validator,
+        Err(As3JsonPath(
+            json_path("ROOT"),
+            AS3ValidationError::MissingKey {
+                key: "doors".to_string(),
+                suggestion: None,
+            },
+        )),
+    );
+
+    verify(
+        &json!({ "kind": "bike", "doors": 2 }),
+        &validator,
+        Err(As3JsonPath(
+            json_path("ROOT"),
+            AS3ValidationError::MissingKey {
+                key: "wheels".to_string(),
+                suggestion: None,
+            },
+        )),
+    );
+
+    // Serialized validators are read back as they were
+    let built = AS3Validator::from(&validator).unwrap();
+    let serialized = serde_json::to_value(&built).unwrap();
+    assert!(serialized.get("Conditional").is_some());
+    assert_eq!(
+        serde_json::from_value::<AS3Validator>(serialized).unwrap(),
+        built
+    );
+
+    let missing_if: serde_yaml::Value =
+        serde_yaml::from_str("Root: { +type: Object, +then: { +type: Object, a: String } }")
+            .unwrap();
+    assert_eq!(
+        AS3Validator::from(&missing_if),
+        Err(As3JsonPath(
+            json_path("Root"),
+            AS3DefinitionError::MissingKeyword("+if".to_string()),
+        ))
+    );
+}
+
+#[test]
+fn with_ordered_data() {
+    let mut data: Vec<AS3Data> = [
+        json!({ "b": 1, "a": 2 }),
+        json!("text"),
+        json!(1.5),
+        json!([1, 2]),
+        json!(null),
+        json!(u64::MAX),
+        json!(1),
+        json!(true),
+        json!(1.0),
+        json!({ "a": 2, "b": 1 }),
+        json!([1]),
+        json!(-3),
+    ]
+    .iter()
+    .map(AS3Data::from)
+    .collect();
+    data.sort();
+    data.dedup();
+
+    assert_eq!(
+        data,
+        vec![
+            AS3Data::Null,
+            AS3Data::Boolean(true),
+            AS3Data::Integer(-3),
+            AS3Data::Integer(1),
+            AS3Data::Decimal(1.0),
+            AS3Data::Decimal(1.5),
+            AS3Data::UInteger(u64::MAX),
+            AS3Data::String("text".to_string()),
+            AS3Data::List(vec![AS3Data::Integer(1)]),
+            AS3Data::List(vec![AS3Data::Integer(1), AS3Data::Integer(2)]),
+            AS3Data::from(&json!({ "a": 2, "b": 1 })),
+        ]
+    );
+
+    assert_eq!(AS3Data::Decimal(f64::NAN), AS3Data::Decimal(f64::NAN));
+    assert!(AS3Data::Decimal(-0.0) < AS3Data::Decimal(0.0));
+
+    let keys = std::collections::HashSet::from([
+        AS3Data::from(&json!({ "a": [1, 2], "b": null })),
+        AS3Data::from(&json!({ "b": null, "a": [1, 2] })),
+    ]);
+    assert_eq!(keys.len(), 1);
+}
+
+#[test]
+fn with_assertions() {
+    let validator: serde_yaml::Value = serde_yaml::from_str(
+        r#"
+        Root:
+            +type: Object
+            +assert:
+                - end_date >= start_date
+                - max > min
+            start_date: Date
+            end_date: Date?
+            min: Integer
+            max: Decimal
+                    "#,
+    )
+    .unwrap();
+
+    verify(
+        &json!({ "start_date": "2024-01-01", "end_date": "2024-03-01", "min": 1, "max": 1.5 }),
+        &validator,
+        Ok(()),
+    );
+    verify(
+        &json!({ "start_date": "2024-01-01", "end_date": null, "min": 1, "max": 2.0 }),
+        &validator,
+        Ok(()),
+    );
+
+    verify(
+        &json!({ "start_date": "2024-01-01", "end_date": "2023-12-31", "min": 1, "max": 2.0 }),
+        &validator,
+        Err(As3JsonPath(
+            json_path("ROOT"),
+            AS3ValidationError::AssertionFailed {
+                assertion: "end_date >= start_date".to_string(),
+                left: (
+                    "end_date".to_string(),
+                    AS3Data::String("2023-12-31".to_string()),
+                ),
+                right: (
+                    "start_date".to_string(),
+                    AS3Data::String("2024-01-01".to_string()),
+                ),
+            },
+        )),
+    );
+
+    verify(
+        &json!({ "start_date": "2024-01-01", "end_date": "2024-01-01", "min": 2, "max": 2.0 }),
+        &validator,
+        Err(As3JsonPath(
+            json_path("ROOT"),
+            AS3ValidationError::AssertionFailed {
+                assertion: "max > min".to_string(),
+                left: ("max".to_string(), AS3Data::Decimal(2.0)),
+                right: ("min".to_string(), AS3Data::Integer(2)),
+            },
+        )),
+    );
+
+    let unknown_field: serde_yaml::Value =
+        serde_yaml::from_str("Root: { +type: Object, +assert: a < b, a: Integer }").unwrap();
+    assert!(matches!(
+        AS3Validator::from(&unknown_field),
+        Err(As3JsonPath(_, AS3DefinitionError::Generic(_)))
+    ));
+
+    let invalid: serde_yaml::Value =
+        serde_yaml::from_str("Root: { +type: Object, +assert: a b, a: Integer, b: Integer }")
+            .unwrap();
+    assert!(matches!(
+        AS3Validator::from(&invalid),
+        Err(As3JsonPath(_, AS3DefinitionError::InvalidValue { .. }))
+    ));
+}
+
+#[test]
+fn with_displayed_data() {
+    let data = AS3Data::from(&json!({
+        "name": "a\"b",
+        "ratio": 1.0,
+        "tags": [],
+        "items": [1, null, true],
+    }));
+    assert_eq!(
+        data.to_string(),
+        r#"{"items": [1, null, true], "name": "a\"b", "ratio": 1.0, "tags": []}"#
+    );
+    assert_eq!(
+        data.to_pretty_string(2),
+        r#"{
+  "items": [
+    1,
+    null,
+    true
+  ],
+  "name": "a\"b",
+  "ratio": 1.0,
+  "tags": []
+}"#
+    );
+    assert_eq!(format!("{data:#}"), data.to_pretty_string(2));
+
+    let truncation = Truncation {
+        max_chars: 3,
+        max_items: 2,
+    };
+    assert_eq!(
+        AS3Data::from(&json!(["abcdef", "ab", 3, 4])).to_pretty_string_with(0, &truncation),
+        "[\n\"abc…\",\n\"ab\",\n… 2 more\n]"
+    );
+    assert_eq!(
+        AS3Validator::from(&serde_yaml::from_str("Root: Integer").unwrap())
+            .unwrap()
+            .validate(&AS3Data::from(&json!("12")))
+            .unwrap_err()
+            .1
+            .to_string(),
+        "Mismatched types. Expected `Integer { minimum: None, maximum: None, width: None }` got `\"12\"`"
+    );
+}
+
+#[test]
+fn with_dependent_keys() {
+    let validator: serde_yaml::Value = serde_yaml::from_str(
+        r#"
+        Root:
+            +type: Object
+            +requires:
+                credit_card: [billing_address, card_holder]
+                coupon: campaign
+            order_id: String
+                    "#,
+    )
+    .unwrap();
+
+    verify(&json!({ "order_id": "A1" }), &validator, Ok(()));
+    verify(
+        &json!({
+            "order_id": "A1",
+            "credit_card": "4111",
+            "billing_address": "1 Main St",
+            "card_holder": "J. Smith",
+        }),
+        &validator,
+        Ok(()),
+    );
+
+    verify(
+        &json!({ "order_id": "A1", "credit_card": "4111", "billing_address": "1 Main St" }),
+        &validator,
+        Err(As3JsonPath(
+            json_path("ROOT"),
+            AS3ValidationError::MissingDependency {
+                key: "card_holder".to_string(),
+                required_by: "credit_card".to_string(),
+            },
+        )),
+    );
+
+    verify(
+        &json!({ "coupon": "SPRING" }),
+        &validator,
+        Err(As3JsonPath(
+            json_path("ROOT"),
+            AS3ValidationError::MissingDependency {
+                key: "campaign".to_string(),
+                required_by: "coupon".to_string(),
+            },
+        )),
+    );
+}
+
+#[test]
+fn with_path_globs() {
+    let validator = AS3Validator::from(
+        &serde_yaml::from_str(
+            r#"
+        Root:
+            +type: Object
+            +extraKeys: forbid
+            vehicles:
+                +type: Object
+                list:
+                    +type: List
+                    +ValueType:
+                        +type: Object
+                        year: Integer
+                count: Integer
+            metadata:
+                +type: Object
+                owner: String
+                    "#,
+        )
+        .unwrap(),
+    )
+    .unwrap();
+    let options = |include: &[&str], exclude: &[&str]| ValidationOptions {
+        include: include.iter().map(|glob| glob.parse().unwrap()).collect(),
+        exclude: exclude.iter().map(|glob| glob.parse().unwrap()).collect(),
+        ..Default::default()
+    };
+
+    let data = AS3Data::from(&json!({
+        "vehicles": { "list": [{ "year": 2020 }], "count": "one" },
+        "metadata": { "owner": 3 },
+        "extra": true,
+    }));
+    assert!(validator.validate(&data).is_err());
+
+    assert_eq!(
+        validator.validate_with_options(&data, &options(&["vehicles.list[*].year"], &[])),
+        Ok(())
+    );
+    assert_eq!(
+        validator.validate_with_options(&data, &options(&["vehicles"], &[])),
+        Err(As3JsonPath(
+            json_path("ROOT -> vehicles -> count"),
+            AS3ValidationError::TypeError {
+                expected: AS3Validator::Integer {
+                    minimum: None,
+                    maximum: None,
+                    width: None,
+                },
+                got: AS3Data::String("one".to_string()),
+            },
+        ))
+    );
+    assert_eq!(
+        validator.validate_with_options(
+            &data,
+            &options(&[], &["metadata.**", "vehicles.*", "extra"])
+        ),
+        Ok(())
+    );
+    assert_eq!(
+        validator.validate_with_options(&data, &options(&["*.owner"], &[])),
+        Err(As3JsonPath(
+            json_path("ROOT -> metadata -> owner"),
+            AS3ValidationError::TypeError {
+                expected: AS3Validator::String {
+                    regex: None,
+                    max_length: None,
+                    min_length: None,
+                    format: None,
+                    any_regex: None,
+                    deny_regex: None,
+                    char_classes: Vec::new(),
+                    length_unit: LengthUnit::default(),
+                },
+                got: AS3Data::Integer(3),
+            },
+        ))
+    );
+
+    assert!("vehicles..year".parse::<PathGlob>().is_err());
+    // Items of a List are selected together
+    assert!("vehicles.list[0].year".parse::<PathGlob>().is_err());
+    assert!("vehicles.list[*]year".parse::<PathGlob>().is_err());
+    assert!("vehicles.list[*".parse::<PathGlob>().is_err());
+    assert!("vehicles.list\\".parse::<PathGlob>().is_err());
+
+    // Dots of keys are escaped
+    let hosts =
+        AS3Validator::from(&serde_yaml::from_str("Root: Map<String, Integer>").unwrap()).unwrap();
+    let data = AS3Data::from(&json!({ "example.com": "one", "*": "two" }));
+    assert_eq!(
+        hosts
+            .validate_with_options(&data, &options(&[r"example\.com"], &[]))
+            .map_err(|error| error.0),
+        Err(JsonPath::root("ROOT").with_key("example.com"))
+    );
+    assert_eq!(
+        hosts.validate_with_options(&data, &options(&["example.com"], &[])),
+        Ok(())
+    );
+    assert_eq!(
+        hosts
+            .validate_with_options(&data, &options(&[], &[r"example\.com"]))
+            .map_err(|error| error.0),
+        Err(JsonPath::root("ROOT").with_key("*"))
+    );
+    assert_eq!(
+        hosts.validate_with_options(&data, &options(&[], &[r"example\.com", r"\*"])),
+        Ok(())
+    );
+}
+
+#[test]
+fn with_named_definitions() {
+    let validator: serde_yaml::Value = serde_yaml::from_str(
+        r##"
+        +defs:
+            Address:
+                +type: Object
+                street: String
+                city: String
+            Contact:
+                +type: Object
+                name: String
+                address:
+                    +ref: "#/defs/Address"
+        Root:
+            +type: Object
+            billing:
+                +ref: "#/defs/Address"
+            contacts:
+                +type: List
+                +ValueType:
+                    +ref: "#/defs/Contact"
+                    "##,
+    )
+    .unwrap();
+
+    let address = json!({ "street": "1 Main St", "city": "Springfield" });
+    verify(
+        &json!({
+            "billing": address,
+            "contacts": [{ "name": "Ann", "address": address }],
+        }),
+        &validator,
+        Ok(()),
+    );
+    verify(
+        &json!({
+            "billing": address,
+            "contacts": [{ "name": "Ann", "address": { "street": "2 Main St" } }],
+        }),
+        &validator,
+        Err(As3JsonPath(
+            json_path("ROOT -> contacts -> [0] -> address"),
+            AS3ValidationError::MissingKey {
+                key: "city".to_string(),
+                suggestion: None,
+            },
+        )),
+    );
+
+    let definition = |source: &str| {
+        AS3Validator::from(&serde_yaml::from_str::<serde_yaml::Value>(source).unwrap())
+    };
+    assert_eq!(
+        definition("Root: { +type: Object, a: { +ref: '#/defs/Missing' } }\n+defs: {}"),
+        Err(As3JsonPath(
+            json_path("Root -> a"),
+            AS3DefinitionError::UnknownDefinition("Missing".to_string()),
+        ))
+    );
+}
+
+#[test]
+#[allow(clippy::result_large_err)]
+fn with_recursive_definitions() {
+    let validator: serde_yaml::Value = serde_yaml::from_str(
+        r##"
+        +defs:
+            Node:
+                +type: Object
+                name: String
+                children:
+                    +type: List
+                    +ValueType:
+                        +ref: "#/defs/Node"
+        Root:
+            +ref: "#/defs/Node"
+                    "##,
+    )
+    .unwrap();
+
+    verify(
+        &json!({
+            "name": "root",
+            "children": [
+                { "name": "a", "children": [] },
+                { "name": "b", "children": [{ "name": "c", "children": [] }] },
+            ],
+        }),
+        &validator,
+        Ok(()),
+    );
+    verify(
+        &json!({
+            "name": "root",
+            "children": [{ "name": "b", "children": [{ "name": "c" }] }],
+        }),
+        &validator,
+        Err(As3JsonPath(
+            json_path("ROOT -> children -> [0] -> children -> [0]"),
+            AS3ValidationError::MissingKey {
+                key: "children".to_string(),
+                suggestion: None,
+            },
+        )),
+    );
+
+    #[cfg(feature = "tools")]
+    {
+        let schema = crate::json_schema::to_editor_schema(&AS3Validator::from(&validator).unwrap());
+        assert_eq!(schema["$ref"], json!("#/definitions/Node"));
+        assert_eq!(
+            schema["definitions"]["Node"]["properties"]["children"]["items"],
+            json!({ "$ref": "#/definitions/Node" })
+        );
+    }
+
+    let mut deep = json!({ "name": "leaf", "children": [] });
+    for _ in 0..200 {
+        deep = json!({ "name": "node", "children": [deep] });
+    }
+    let deep = AS3Data::from(&deep);
+    // Unoptimized builds need larger stacks than the default to reach the limit
+    let validator = AS3Validator::from(&validator).unwrap();
+    #[cfg(all(feature = "parallel", not(target_os = "wasi")))]
+    let result = rayon::ThreadPoolBuilder::new()
+        .stack_size(256 * 1024 * 1024)
+        .build()
+        .unwrap()
+        .install(|| validator.validate(&deep));
+    #[cfg(not(all(feature = "parallel", not(target_os = "wasi"))))]
+    let result = std::thread::scope(|scope| {
+        std::thread::Builder::new()
+            .stack_size(256 * 1024 * 1024)
+            .spawn_scoped(scope, || validator.validate(&deep))
+            .unwrap()
+            .join()
+            .unwrap()
+    });
+    assert_eq!(
+        result.unwrap_err().1,
+        AS3ValidationError::RecursionLimit { depth: 128 }
+    );
+
+    // The definitions go with the validator, even though they refer to themselves
+    let AS3Validator::Ref(root) = &validator else {
+        panic!("`Root` is not a reference: {validator:?}");
+    };
+    let node = root.target().unwrap();
+    let AS3Validator::Object { fields, .. } = &*node else {
+        panic!("`Node` is not an Object: {:?}", *node);
+    };
+    let AS3Validator::List(items) = &fields["children"] else {
+        panic!("`children` is not a List: {:?}", fields["children"]);
+    };
+    let AS3Validator::Ref(child) = &**items else {
+        panic!("`children` items are not a reference: {items:?}");
+    };
+    let child = child.clone();
+    assert!(child.target().is_some());
+    drop(node);
+    drop(validator);
+    assert!(child.target().is_none());
+
+    let looping: serde_yaml::Value = serde_yaml::from_str(
+        "Root: { +ref: '#/defs/A' }\n+defs: { A: { +ref: '#/defs/B' }, B: { +ref: '#/defs/A' } }",
+    )
+    .unwrap();
+    assert_eq!(
+        AS3Validator::from(&looping),
+        Err(As3JsonPath(
+            json_path("+defs -> A"),
+            AS3DefinitionError::CircularReference("A -> B -> A".to_string()),
+        ))
+    );
+}
+
+#[cfg(feature = "tools")]
+#[test]
+fn with_anonymization() {
+    let validator: serde_yaml::Value = serde_yaml::from_str(
+        r#"
+Root:
+  +type: Object
+  id: { +type: String, +format: uuid, +anonymize: fake }
+  email: { +type: String, +maxLength: 12, +anonymize: hash }
+  card: { +type: String, +anonymize: mask }
+  age: { +type: Integer, +min: 18, +max: 99, +anonymize: hash }
+  nickname: { +type: String??, +anonymize: fake }
+  country: String
+"#,
+    )
+    .unwrap();
+    let validator = AS3Validator::from(&validator).unwrap();
+    let original = json!({
+        "id": "67e55044-10b1-426f-9247-bb680e5fe0c8",
+        "email": "jane@example.org",
+        "card": "4111111111111111",
+        "age": 42,
+        "nickname": null,
+        "country": "FR",
+    });
+
+    let mut data = original.clone();
+    crate::anonymize::anonymize(&validator, &mut data, "secret").unwrap();
+    assert_eq!(validator.validate(&AS3Data::from(&data)), Ok(()));
+    assert_ne!(data["id"], original["id"]);
+    assert_eq!(data["email"].as_str().unwrap().len(), 12);
+    assert_eq!(data["card"], json!("************1111"));
+    assert_eq!(data["nickname"], json!(null));
+    assert_eq!(data["country"], json!("FR"));
+
+    let mut again = original.clone();
+    crate::anonymize::anonymize(&validator, &mut again, "secret").unwrap();
+    assert_eq!(again, data);
+    crate::anonymize::anonymize(&validator, &mut again, "other").unwrap();
+    assert_ne!(again["email"], data["email"]);
+
+    let invalid: serde_yaml::Value =
+        serde_yaml::from_str("Root: { +type: String, +anonymize: scramble }").unwrap();
+    assert!(AS3Validator::from(&invalid).is_err());
+}
+
+#[cfg(not(feature = "std"))]
+#[test]
+fn without_files() {
+    let definition = serde_yaml::from_str("Root: { +include: ./address.yaml }").unwrap();
+    let error = AS3Validator::from(&definition).unwrap_err();
+    assert_eq!(error.0, json_path("Root"));
+    assert_eq!(
+        error.1.to_string(),
+        AS3DefinitionError::Include {
+            file: "./address.yaml".to_string(),
+            reason: "files can't be read without the `std` feature"
+                .to_string()
+                .into(),
+        }
+        .to_string()
+    );
+}
+
+#[cfg(feature = "std")]
+#[test]
+fn with_included_files() {
+    let folder = std::env::temp_dir().join(format!("as3-include-{}", std::process::id()));
+    std::fs::create_dir_all(folder.join("common")).unwrap();
+    std::fs::write(
+        folder.join("common/address.yaml"),
+        "Root: { +type: Object, street: String, zip: { +include: ./zip.yaml } }",
+    )
+    .unwrap();
+    std::fs::write(
+        folder.join("common/zip.yaml"),
+        "Root: String(regex='^[0-9]{5}$')",
+    )
+    .unwrap();
+    std::fs::write(
+        folder.join("order.yaml"),
+        "Root: { +type: Object, shipping: { +include: ./common/address.yaml } }",
+    )
+    .unwrap();
+
+    let validator =
+        AS3Validator::from_file(&folder.join("order.yaml"), &DefinitionOptions::default()).unwrap();
+    assert_eq!(
+        validator.validate(&AS3Data::from(
+            &json!({ "shipping": { "street": "Main St", "zip": "12345" } })
+        )),
+        Ok(())
+    );
+    assert_eq!(
+        validator
+            .validate(&AS3Data::from(
+                &json!({ "shipping": { "street": "Main St", "zip": "1234" } })
+            ))
+            .unwrap_err()
+            .0,
+        json_path("ROOT -> shipping -> zip")
+    );
+
+    std::fs::write(
+        folder.join("common/zip.yaml"),
+        "Root: { +include: ../order.yaml }",
+    )
+    .unwrap();
+    let error = AS3Validator::from_file(&folder.join("order.yaml"), &DefinitionOptions::default())
+        .unwrap_err();
+    assert!(matches!(error.1, AS3DefinitionError::CircularInclude(_)));
+    assert_eq!(
+        error.0,
+        json_path("Root -> shipping -> ./common/address.yaml -> Root -> zip -> ./zip.yaml -> Root")
+    );
+
+    let missing: serde_yaml::Value =
+        serde_yaml::from_str("Root: { +include: ./missing.yaml }").unwrap();
+    assert!(matches!(
+        AS3Validator::from(&missing).unwrap_err().1,
+        AS3DefinitionError::Include { .. }
+    ));
+    std::fs::remove_dir_all(folder).unwrap();
+}
+
+#[test]
+fn with_remote_includes() {
+    #[derive(Debug)]
+    struct Schemas(HashMap<&'static str, &'static str>);
+
+    impl crate::fetch::Fetcher for Schemas {
+        fn fetch(&self, url: &url::Url) -> Result<String, String> {
+            self.0
+                .get(url.as_str())
+                .map(|source| source.to_string())
+                .ok_or_else(|| format!("{url} not found"))
+        }
+    }
+
+    let fetcher = Schemas(HashMap::from([
+        (
+            "https://schemas.example.com/address.yaml",
+            "Root: { +type: Object, street: String, zip: { +include: ./zip.yaml } }",
+        ),
+        ("https://schemas.example.com/zip.yaml", "Root: String"),
+        (
+            "https://schemas.example.com/loop.yaml",
+            "Root: { +include: loop.yaml }",
+        ),
+    ]));
+    let options = DefinitionOptions {
+        fetcher: Some(std::sync::Arc::new(fetcher)),
+        ..Default::default()
+    };
+    let definition: serde_yaml::Value = serde_yaml::from_str(
+        "Root: { +type: Object, shipping: { +include: 'https://schemas.example.com/address.yaml' } }",
+    )
+    .unwrap();
+
+    let validator = AS3Validator::from_with_options(&definition, &options).unwrap();
+    assert_eq!(
+        validator.validate(&AS3Data::from(
+            &json!({ "shipping": { "street": "Main St", "zip": "12345" } })
+        )),
+        Ok(())
+    );
+    assert!(matches!(
+        AS3Validator::from(&definition).unwrap_err().1,
+        AS3DefinitionError::Include { .. }
+    ));
+
+    let looping: serde_yaml::Value =
+        serde_yaml::from_str("Root: { +include: 'https://schemas.example.com/loop.yaml' }")
+            .unwrap();
+    assert_eq!(
+        AS3Validator::from_with_options(&looping, &options)
+            .unwrap_err()
+            .1,
+        AS3DefinitionError::CircularInclude(
+            "https://schemas.example.com/loop.yaml -> https://schemas.example.com/loop.yaml"
+                .to_string()
+        )
+    );
+}
+
+#[test]
+fn with_extended_objects() {
+    let validator: serde_yaml::Value = serde_yaml::from_str(
+        r##"
++defs:
+  Record:
+    +type: Object
+    +extraKeys: forbid
+    id: Integer
+    created_at: Date
+  Person:
+    +extends: "#/defs/Record"
+    name: String
+Root:
+  +extends: "#/defs/Person"
+  id: String
+  email: String
+"##,
+    )
+    .unwrap();
+    verify(
+        &json!({ "id": "p-1", "created_at": "2024-01-01", "name": "Ada", "email": "ada@example.org" }),
+        &validator,
+        Ok(()),
+    );
+    assert_eq!(
+        AS3Validator::from(&validator)
+            .unwrap()
+            .validate(&AS3Data::from(&json!({
+                "id": 1, "created_at": "2024-01-01", "name": "Ada", "email": "ada@example.org"
+            })))
+            .unwrap_err()
+            .0,
+        json_path("ROOT -> id")
+    );
+    verify(
+        &json!({ "id": "p-1", "created_at": "2024-01-01", "name": "Ada" }),
+        &validator,
+        Err(As3JsonPath(
+            json_path("ROOT"),
+            AS3ValidationError::MissingKey {
+                key: "email".to_string(),
+                suggestion: None,
+            },
+        )),
+    );
+
+    let looping: serde_yaml::Value = serde_yaml::from_str(
+        r##"
++defs:
+  A: { +extends: "#/defs/B" }
+  B: { +extends: "#/defs/A" }
+Root: { +extends: "#/defs/A" }
+"##,
+    )
+    .unwrap();
+    assert_eq!(
+        AS3Validator::from(&looping).unwrap_err().1,
+        AS3DefinitionError::CircularReference("B -> A -> B".to_string())
+    );
+    let not_object: serde_yaml::Value =
+        serde_yaml::from_str("+defs: { Id: Integer }\nRoot: { +extends: '#/defs/Id' }").unwrap();
+    assert!(matches!(
+        AS3Validator::from(&not_object).unwrap_err().1,
+        AS3DefinitionError::Conflict(_)
+    ));
+}
+
+#[test]
+fn with_deprecated_fields() {
+    let validator: serde_yaml::Value = serde_yaml::from_str(
+        r#"
+Root:
+  +type: Object
+  email: String
+  mail: { +type: String??, +deprecated: use email instead }
+  fax: { +type: String??, +deprecated: true }
+  legacy: { +type: String??, +deprecated: false }
+"#,
+    )
+    .unwrap();
+    let validator = AS3Validator::from(&validator).unwrap();
+
+    let data = AS3Data::from(&json!({
+        "email": "ada@example.org",
+        "mail": "ada@example.org",
+        "fax": "0123",
+        "legacy": "x",
+    }));
+    assert_eq!(validator.validate(&data), Ok(()));
+    let warnings = validator.warnings(&data);
+    assert_eq!(
+        warnings,
+        vec![
+            As3JsonPath(
+                json_path("ROOT -> fax"),
+                AS3ValidationError::Deprecated { message: None },
+            ),
+            As3JsonPath(
+                json_path("ROOT -> mail"),
+                AS3ValidationError::Deprecated {
+                    message: Some("use email instead".to_string()),
+                },
+            ),
+        ]
+    );
+    assert_eq!(
+        warnings[1].to_string(),
+        "Deprecated value: use email instead in [ROOT -> mail]. "
+    );
+    assert!(validator.unexpected_keys(&data).is_empty());
+
+    // Only the values selected are looked at
+    let without_fax = ValidationOptions {
+        exclude: vec!["fax".parse().unwrap()],
+        ..Default::default()
+    };
+    assert_eq!(
+        validator.warnings_with_options(&data, &without_fax),
+        warnings[1..]
+    );
+    let only_fax = ValidationOptions {
+        include: vec!["fax".parse().unwrap()],
+        ..Default::default()
+    };
+    assert_eq!(
+        validator.warnings_with_options(&data, &only_fax),
+        warnings[..1]
+    );
+
+    let data = AS3Data::from(&json!({ "email": "ada@example.org", "mail": null }));
+    assert!(validator.warnings(&data).is_empty());
+
+    // Values are coerced as they are for the validation
+    #[cfg(feature = "coerce")]
+    {
+        let validator = AS3Validator::from(
+            &serde_yaml::from_str(
+                "Root: { +type: Object, port: { +type: Integer, +severity: warning } }",
+            )
+            .unwrap(),
+        )
+        .unwrap();
+        let data = AS3Data::from(&json!({ "port": "80" }));
+        assert_eq!(validator.warnings(&data).len(), 1);
+        let coerce = ValidationOptions {
+            coerce: true,
+            ..Default::default()
+        };
+        assert!(validator.warnings_with_options(&data, &coerce).is_empty());
+        assert!(validator
+            .validate_with_warnings(&data, &coerce)
+            .warnings
+            .is_empty());
+    }
+
+    let invalid = serde_yaml::from_str("Root: { +type: String, +deprecated: 1 }").unwrap();
+    assert!(AS3Validator::from(&invalid).is_err());
+}
+
+#[test]
+fn with_key_suggestions() {
+    let validator: serde_yaml::Value = serde_yaml::from_str(
+        r#"
+Root:
+  +type: Object
+  maker: String
+  model: String
+  vehicle:
+    +type: Object
+    +extraKeys: forbid
+    year: Integer
+    color: String??
+"#,
+    )
+    .unwrap();
+    let validator = AS3Validator::from(&validator).unwrap();
+
+    let errors = validator
+        .validate_all(&AS3Data::from(&json!({
+            "makr": "Ford",
+            "model": "T",
+            "vehicle": { "year": 1908, "colour": "black" },
+        })))
+        .unwrap_err();
+    assert_eq!(
+        errors,
+        [
+            As3JsonPath(
+                json_path("ROOT"),
+                AS3ValidationError::MissingKey {
+                    key: "maker".to_string(),
+                    suggestion: Some("makr".to_string()),
+                },
+            ),
+            As3JsonPath(
+                json_path("ROOT -> vehicle"),
+                AS3ValidationError::UnexpectedKey {
+                    key: "colour".to_string(),
+                    suggestion: Some("color".to_string()),
+                },
+            ),
+            As3JsonPath(
+                json_path("ROOT -> vehicle"),
+                AS3ValidationError::MissingKey {
+                    key: "color".to_string(),
+                    suggestion: Some("colour".to_string()),
+                },
+            ),
+        ]
+    );
+    assert_eq!(
+        errors[0].1.to_string(),
+        "Key maker is not; found `makr`, did you mean `maker`?"
+    );
+    assert_eq!(
+        errors[1].1.to_string(),
+        "Key colour is not expected, did you mean `color`?"
+    );
+
+    // Keys too far apart aren't suggested
+    let error = validator
+        .validate(&AS3Data::from(&json!({
+            "manufacturer": "Ford",
+            "model": "T",
+            "vehicle": { "year": 1908, "color": null },
+        })))
+        .unwrap_err();
+    assert_eq!(
+        error.1,
+        AS3ValidationError::MissingKey {
+            key: "maker".to_string(),
+            suggestion: None,
+        }
+    );
+}
+
+#[test]
+fn with_translated_messages() {
+    use crate::messages::{render, English, TemplateCatalog};
+    let validator: serde_yaml::Value = serde_yaml::from_str(
+        r#"
+Root:
+  +type: Object
+  +extraKeys: forbid
+  count: Integer(max=10)
+  name: { +type: String, +maxLength: 3 }
+  sku: { +type: String, +regex: "^[A-Z]+$", +message: SKU must be uppercase }
+"#,
+    )
+    .unwrap();
+    let errors = AS3Validator::from(&validator)
+        .unwrap()
+        .validate_all(&AS3Data::from(
+            &json!({ "count": 11, "name": "Ada Lovelace", "sku": "abc", "extra": 1 }),
+        ))
+        .unwrap_err();
+    let french = TemplateCatalog::from_yaml(
+        r#"
+E_MAX_INT: "`{got}` dépasse le maximum de `{expected}`"
+E_UNEXPECTED_KEY: La clé {key} n'est pas attendue
+"#,
+    )
+    .unwrap();
+
+    assert_eq!(
+        errors
+            .iter()
+            .map(|As3JsonPath(_, error)| render(error, &french))
+            .collect::<Vec<_>>(),
+        [
+            "La clé extra n'est pas attendue",
+            "`11` dépasse le maximum de `10`",
+            // Left in English
+            "12 characters long, above the maximum length of 3",
+            "SKU must be uppercase",
+        ]
+    );
+    assert_eq!(
+        render(&errors[1].1, &English),
+        "`11` is above the maximum of `10`"
+    );
+    assert!(TemplateCatalog::from_yaml("- not a mapping").is_err());
+}
+
+#[test]
+fn with_warning_severity() {
+    let validator: serde_yaml::Value = serde_yaml::from_str(
+        r#"
+Root:
+  +type: Object
+  name: { +type: String, +maxLength: 10, +severity: warning, +message: Names should be short }
+  retries: { +type: Integer, +max: 5, +severity: warning }
+  port: Integer(max=65535)
+"#,
+    )
+    .unwrap();
+    let validator = AS3Validator::from(&validator).unwrap();
+
+    let data = AS3Data::from(&json!({ "name": "A rather long name", "retries": 8, "port": 80 }));
+    assert_eq!(validator.validate(&data), Ok(()));
+    let outcome = validator.validate_with_warnings(&data, &ValidationOptions::default());
+    assert!(outcome.is_valid());
+    assert_eq!(
+        outcome
+            .warnings
+            .iter()
+            .map(|warning| (warning.0.to_string(), warning.1.code()))
+            .collect::<Vec<_>>(),
+        [
+            ("ROOT -> name".to_string(), "E_MAX_LENGTH"),
+            ("ROOT -> retries".to_string(), "E_MAX_INT"),
+        ]
+    );
+    assert_eq!(outcome.warnings[0].1.to_string(), "Names should be short");
+
+    let data = AS3Data::from(&json!({ "name": "Ada", "retries": 8, "port": 70000 }));
+    let outcome = validator.validate_with_warnings(&data, &ValidationOptions::default());
+    assert!(!outcome.is_valid());
+    assert_eq!(outcome.errors.len(), 1);
+    assert_eq!(outcome.errors[0].0.to_string(), "ROOT -> port");
+    assert_eq!(outcome.warnings.len(), 1);
+
+    let invalid = serde_yaml::from_str("Root: { +type: String, +severity: info }").unwrap();
+    assert!(AS3Validator::from(&invalid).is_err());
+}
+
+#[test]
+fn with_custom_messages() {
+    let validator: serde_yaml::Value = serde_yaml::from_str(
+        r#"
+Root:
+  +type: Object
+  +message: The product is incomplete
+  sku: { +type: String, +regex: "^[A-Z]{3}-[0-9]{4}$", +message: SKU must look like ABC-1234 }
+  price: Decimal(min=0)
+"#,
+    )
+    .unwrap();
+    verify(
+        &json!({ "sku": "ABC-1234", "price": 1.5 }),
+        &validator,
+        Ok(()),
+    );
+
+    let validator = AS3Validator::from(&validator).unwrap();
+    let error = validator
+        .validate(&AS3Data::from(&json!({ "sku": "abc", "price": 1.5 })))
+        .unwrap_err();
+    assert_eq!(
+        error,
+        As3JsonPath(
+            json_path("ROOT -> sku"),
+            AS3ValidationError::Custom {
+                message: "SKU must look like ABC-1234".to_string(),
+                error: Box::new(AS3ValidationError::RegexError {
+                    word: "abc".to_string(),
+                    regex: "^[A-Z]{3}-[0-9]{4}$".to_string(),
+                }),
+            },
+        )
+    );
+    assert_eq!(
+        error.to_string(),
+        "SKU must look like ABC-1234 in [ROOT -> sku]. "
+    );
+    assert_eq!(error.1.kind(), "RegexError");
+    assert_eq!(error.1.code(), "E_REGEX");
+
+    let error = validator
+        .validate(&AS3Data::from(&json!({ "sku": "ABC-1234" })))
+        .unwrap_err();
+    assert_eq!(error.1.to_string(), "The product is incomplete");
+    assert_eq!(
+        error.1.underlying(),
+        &AS3ValidationError::MissingKey {
+            key: "price".to_string(),
+            suggestion: None
+        }
+    );
+}
+
+#[test]
+fn with_error_codes() {
+    use AS3ValidationError::*;
+
+    let text = String::new;
+    let errors = [
+        (
+            TypeError {
+                expected: AS3Validator::Null,
+                got: AS3Data::Null,
+            },
+            "E_TYPE_MISMATCH",
+        ),
+        (
+            MissingKey {
+                key: text(),
+                suggestion: None,
+            },
+            "E_MISSING_KEY",
+        ),
+        (
+            UnexpectedKey {
+                key: text(),
+                suggestion: None,
+            },
+            "E_UNEXPECTED_KEY",
+        ),
+        (Deprecated { message: None }, "E_DEPRECATED"),
+        (
+            UnknownTag {
+                discriminator: text(),
+                tag: text(),
+                expected: Vec::new(),
+            },
+            "E_UNKNOWN_TAG",
+        ),
+        (RecursionLimit { depth: 1 }, "E_RECURSION_LIMIT"),
+        (
+            MissingDependency {
+                key: text(),
+                required_by: text(),
+            },
+            "E_MISSING_DEPENDENCY",
+        ),
+        (
+            AssertionFailed {
+                assertion: text(),
+                left: (text(), AS3Data::Null),
+                right: (text(), AS3Data::Null),
+            },
+            "E_ASSERTION",
+        ),
+        (
+            RegexError {
+                word: text(),
+                regex: text(),
+            },
+            "E_REGEX",
+        ),
+        (
+            AnyRegexError {
+                word: text(),
+                regexes: Vec::new(),
+            },
+            "E_ANY_REGEX",
+        ),
+        (
+            DenyRegexError {
+                word: text(),
+                regex: text(),
+            },
+            "E_DENY_REGEX",
+        ),
+        (
+            CharacterClass {
+                word: text(),
+                class: text(),
+                character: 'a',
+                position: 1,
+            },
+            "E_CHARACTER_CLASS",
+        ),
+        (
+            FormatError {
+                word: text(),
+                format: text(),
+                reason: text(),
+            },
+            "E_FORMAT",
+        ),
+        (
+            MinimumDecimal {
+                number: 0.0,
+                minimum: 1.0,
+            },
+            "E_MIN_DECIMAL",
+        ),
+        (
+            MaximumDecimal {
+                number: 1.0,
+                maximum: 0.0,
+            },
+            "E_MAX_DECIMAL",
+        ),
+        (
+            MinimumInteger {
+                number: 0,
+                minimum: 1,
+            },
+            "E_MIN_INT",
+        ),
+        (
+            MaximumInteger {
+                number: 1,
+                maximum: 0,
+            },
+            "E_MAX_INT",
+        ),
+        (
+            IntegerWidth {
+                number: 256,
+                width: text(),
+            },
+            "E_INT_WIDTH",
+        ),
+        (Generic(text()), "E_GENERIC"),
+        (
+            MaximumString {
+                string: text(),
+                current_lenght: 1,
+                max_length: 0,
+                unit: LengthUnit::default(),
+            },
+            "E_MAX_LENGTH",
+        ),
+        (
+            MinimumString {
+                string: text(),
+                current_lenght: 0,
+                min_length: 1,
+                unit: LengthUnit::default(),
+            },
+            "E_MIN_LENGTH",
+        ),
+        (
+            MinimumDate {
+                date: text(),
+                minimum: text(),
+            },
+            "E_MIN_DATE",
+        ),
+        (
+            MaximumDate {
+                date: text(),
+                maximum: text(),
+            },
+            "E_MAX_DATE",
+        ),
+        (
+            MinimumDateTime {
+                datetime: text(),
+                minimum: text(),
+            },
+            "E_MIN_DATETIME",
+        ),
+        (
+            MaximumDateTime {
+                datetime: text(),
+                maximum: text(),
+            },
+            "E_MAX_DATETIME",
+        ),
+        (
+            MinimumTime {
+                time: text(),
+                minimum: text(),
+            },
+            "E_MIN_TIME",
+        ),
+        (
+            MaximumTime {
+                time: text(),
+                maximum: text(),
+            },
+            "E_MAX_TIME",
+        ),
+        (
+            MinimumDuration {
+                duration: text(),
+                minimum: text(),
+            },
+            "E_MIN_DURATION",
+        ),
+        (
+            MaximumDuration {
+                duration: text(),
+                maximum: text(),
+            },
+            "E_MAX_DURATION",
+        ),
+        (
+            MinimumEntries {
+                count: 0,
+                min_entries: 1,
+            },
+            "E_MIN_ENTRIES",
+        ),
+        (
+            MaximumEntries {
+                count: 1,
+                max_entries: 0,
+            },
+            "E_MAX_ENTRIES",
+        ),
+        (
+            MinimumBytes {
+                length: 0,
+                min_bytes: 1,
+            },
+            "E_MIN_BYTES",
+        ),
+        (
+            MaximumBytes {
+                length: 1,
+                max_bytes: 0,
+            },
+            "E_MAX_BYTES",
+        ),
+        (NotNullableNull, "E_NULL"),
+    ];
+    for (error, code) in &errors {
+        assert_eq!(error.code(), *code, "{}", error.kind());
+    }
+    // Each kind of error has its own code
+    let codes: std::collections::HashSet<_> = errors.iter().map(|(_, code)| code).collect();
+    assert_eq!(codes.len(), errors.len());
+
+    // Errors with a `+message` keep the code of the constraint they replace
+    let custom = Custom {
+        message: "bad SKU".to_string(),
+        error: Box::new(RegexError {
+            word: text(),
+            regex: text(),
+        }),
+    };
+    assert_eq!(custom.code(), "E_REGEX");
+}
+
+#[test]
+fn with_descriptions_and_examples() {
+    let validator: serde_yaml::Value = serde_yaml::from_str(
+        r#"
+Root:
+  +type: Object
+  +description: A customer
+  age:
+    +type: Integer??
+    +description: customer age
+    +examples: [18, 42]
+  name: String
+"#,
+    )
+    .unwrap();
+    let validator = AS3Validator::from(&validator).unwrap();
+    assert_eq!(validator.description(), Some("A customer"));
+    let AS3Validator::Annotated { inner, .. } = &validator else {
+        panic!("the description should annotate the Root");
+    };
+    let AS3Validator::Object { fields, .. } = inner.as_ref() else {
+        panic!("the Root should be an Object");
+    };
+    assert_eq!(fields["age"].description(), Some("customer age"));
+    assert_eq!(fields["age"].examples(), &[json!(18), json!(42)]);
+    assert_eq!(fields["name"].description(), None);
+    assert!(fields["name"].examples().is_empty());
+    assert_eq!(
+        validator.validate(&AS3Data::from(&json!({ "age": null, "name": "Ada" }))),
+        Ok(())
+    );
+
+    #[cfg(feature = "tools")]
+    {
+        let schema = crate::json_schema::to_editor_schema(&validator);
+        assert_eq!(schema["description"], json!("A customer"));
+        assert_eq!(schema["properties"]["age"]["examples"], json!([18, 42]));
+    }
+
+    let invalid = serde_yaml::from_str("Root: { +type: String, +examples: abc }").unwrap();
+    assert!(AS3Validator::from(&invalid).is_err());
+}
+
+#[cfg(feature = "coerce")]
+#[test]
+fn with_coercion() {
+    let validator: serde_yaml::Value = serde_yaml::from_str(
+        r#"
+Root:
+  +type: Object
+  port: Integer(min=1)
+  debug: Bool
+  ratio: Decimal
+  tags:
+    +type: List
+    +ValueType: Integer
+"#,
+    )
+    .unwrap();
+    let validator = AS3Validator::from(&validator).unwrap();
+    let data = AS3Data::from(&json!({ "port": "8080", "debug": 1, "ratio": 2, "tags": ["1", 2] }));
+    assert!(validator.validate(&data).is_err());
+
+    let coerce = ValidationOptions {
+        coerce: true,
+        ..Default::default()
+    };
+    assert_eq!(validator.validate_with_options(&data, &coerce), Ok(()));
+    assert_eq!(
+        validator.validate_coerced(&data),
+        Ok(AS3Data::from(
+            &json!({ "port": 8080, "debug": true, "ratio": 2.0, "tags": [1, 2] })
+        ))
+    );
+    let data = AS3Data::from(&json!({ "port": "0", "debug": "true", "ratio": "0.5", "tags": [] }));
+    assert_eq!(
+        validator
+            .validate_with_options(&data, &coerce)
+            .unwrap_err()
+            .0,
+        json_path("ROOT -> port")
+    );
+    let data = AS3Data::from(&json!({ "port": "80", "debug": 2, "ratio": 1, "tags": [] }));
+    assert_eq!(
+        validator.validate_coerced(&data).unwrap_err().0,
+        json_path("ROOT -> debug")
+    );
+}
+
+#[test]
+fn with_error_sources() {
+    use std::error::Error;
+
+    let invalid = serde_yaml::from_str("Root: { +type: String, +regex: \"(\" }").unwrap();
+    let error = AS3Validator::from(&invalid).unwrap_err();
+    let AS3DefinitionError::InvalidRegex { reason, .. } = &error.1 else {
+        panic!("the regex should be invalid");
+    };
+    assert_eq!(
+        error.source().unwrap().source().unwrap().to_string(),
+        reason.to_string()
+    );
+
+    #[cfg(feature = "std")]
+    {
+        let error = AS3Validator::from_file(
+            std::path::Path::new("missing.yaml"),
+            &DefinitionOptions::default(),
+        )
+        .unwrap_err();
+        let cause = error.source().unwrap().source().unwrap();
+        assert!(cause.downcast_ref::<crate::error::Cause>().is_some());
+    }
+
+    let validator = serde_yaml::from_str(
+        "Root: { +type: Object, sku: { +type: String, +regex: '^[A-Z]+$', +message: bad SKU } }",
+    )
+    .unwrap();
+    let validator = AS3Validator::from(&validator).unwrap();
+    let error = validator
+        .validate(&AS3Data::from(&json!({ "sku": "abc" })))
+        .unwrap_err();
+    let chain: Vec<String> =
+        std::iter::successors(Some(&error as &(dyn Error + 'static)), |e| (*e).source())
+            .map(|e| e.to_string())
+            .collect();
+    assert_eq!(chain.len(), 3);
+    assert_eq!(chain[1], "bad SKU");
+    assert!(chain[2].contains("^[A-Z]+$"));
+}
+
+#[cfg(feature = "tools")]
+#[test]
+fn with_parse_error_excerpts() {
+    use crate::diagnostic;
+    let path = std::path::Path::new("definition.yaml");
+
+    let source = "Root:\n  +type: Object\n\tname: String\n  age: Integer\n";
+    let error =
+        diagnostic::from_yaml::<serde_yaml::Value>("definition file", path, source).unwrap_err();
+    assert!(error.contains("--> definition.yaml:3:1"), "{error}");
+    assert!(error.contains("3 | \tname: String\n  | ^"), "{error}");
+    assert!(error.contains("hint: tabs are not allowed in YAML indentation"));
+
+    let source = "Root:\n  +type: Object\n  name: a: b\n";
+    let error =
+        diagnostic::from_yaml::<serde_yaml::Value>("definition file", path, source).unwrap_err();
+    assert!(
+        error.contains("hint: values containing `: ` must be quoted"),
+        "{error}"
+    );
+    assert!(error.contains("1 | Root:"));
+
+    let error =
+        diagnostic::from_json::<serde_json::Value>("Data file", path, "{\n  \"a\": 1,\n}\n")
+            .unwrap_err();
+    assert!(error.contains("definition.yaml:3:1"), "{error}");
+    assert!(error.contains("hint: JSON doesn't allow a comma after the last item"));
+
+    assert_eq!(
+        diagnostic::excerpt("a\nb", 1, 3),
+        "  |\n1 | a\n  |   ^\n2 | b"
+    );
+}
+
+#[cfg(feature = "tools")]
+#[test]
+fn with_format_detection() {
+    use crate::diagnostic::{self, Format};
+    let path = std::path::Path::new("piped");
+
+    assert_eq!(Format::detect(path, " {\"a\": 1}"), Format::Json);
+    assert_eq!(Format::detect(path, "[1, 2]"), Format::Json);
+    assert_eq!(Format::detect(path, "[server]\nport = 80\n"), Format::Toml);
+    assert_eq!(Format::detect(path, "port: 80\n"), Format::Yaml);
+    assert_eq!(Format::detect(path, "{a: 1}"), Format::Yaml);
+    assert_eq!(
+        Format::detect(std::path::Path::new("data.json"), "{\"a\": 1,}"),
+        Format::Json
+    );
+
+    let data: serde_json::Value = diagnostic::parse(
+        "Data file",
+        path,
+        "name = \"Ada\"\nborn = 1815-12-10\n",
+        None,
+    )
+    .unwrap();
+    assert_eq!(data, json!({ "name": "Ada", "born": "1815-12-10" }));
+    let definition: serde_yaml::Value = diagnostic::parse(
+        "definition file",
+        path,
+        "[Root]\n\"+type\" = \"Object\"\nborn = \"Date\"\n",
+        None,
+    )
+    .unwrap();
+    let validator = AS3Validator::from(&definition).unwrap();
+    assert_eq!(validator.validate(&AS3Data::from(&data)), Ok(()));
+
+    let error =
+        diagnostic::parse::<serde_json::Value>("Data file", path, "a: 1", Some(Format::Toml))
+            .unwrap_err();
+    assert!(error.contains("is not propper toml"), "{error}");
+    assert!(error.contains("--> piped:1:"), "{error}");
+}
+
+#[test]
+fn with_transcripts() {
+    use crate::transcript::TranscriptValidator;
+
+    let definition: serde_yaml::Value = serde_yaml::from_str(
+        r#"
++defs:
+  Id: String(regex='^[0-9]+$')
+Request:
+  +type: Object
+  method: String
+  id: { +ref: '#/defs/Id' }
+Response:
+  +type: Object
+  status: Integer(min=100, max=599)
+"#,
+    )
+    .unwrap();
+    let validator =
+        TranscriptValidator::from_with_options(&definition, &DefinitionOptions::default()).unwrap();
+    assert_eq!(
+        validator.roots().collect::<Vec<_>>(),
+        vec!["Request", "Response"]
+    );
+
+    let transcript = json!([
+        {
+            "at": "2024-05-01T10:00:00Z",
+            "request": { "method": "GET", "id": "12" },
+            "response": { "status": 200 }
+        },
+        {
+            "request": { "method": "DELETE", "id": "12" },
+            "response": { "status": 1000 }
+        }
+    ]);
+    let error = validator.validate(&AS3Data::from(&transcript)).unwrap_err();
+    assert_eq!(error.0, json_path("ROOT -> [1] -> response -> status"));
+
+    let exchange =
+        json!({ "Request": { "method": "GET", "id": "7" }, "Response": { "status": 204 } });
+    assert_eq!(validator.validate(&AS3Data::from(&exchange)), Ok(()));
+    assert_eq!(
+        validator.validate(&AS3Data::from(
+            &json!({ "request": { "method": "GET", "id": "7" } })
+        )),
+        Err(As3JsonPath(
+            json_path("ROOT"),
+            AS3ValidationError::MissingKey {
+                key: "response".to_string(),
+                suggestion: None
+            }
+        ))
+    );
+
+    let error = AS3Validator::from_root_with_options(
+        &serde_yaml::from_str("Request: { +type: Object, id: { +type: Nope } }").unwrap(),
+        "Request",
+        &DefinitionOptions::default(),
+    )
+    .unwrap_err();
+    assert_eq!(error.0, json_path("Request -> id"));
+}
+
+#[cfg(feature = "tools")]
+#[test]
+fn with_har_contracts() {
+    use crate::contract::ContractValidator;
+
+    let definition: serde_yaml::Value = serde_yaml::from_str(
+        r#"
++endpoints:
+  GET /users/{id}:
+    response: User
+  POST /users:
+    request: NewUser
+    response: User
+NewUser:
+  +type: Object
+  name: String
+User:
+  +type: Object
+  id: Integer
+  name: String
+"#,
+    )
+    .unwrap();
+    let validator =
+        ContractValidator::from_with_options(&definition, &DefinitionOptions::default()).unwrap();
+
+    let entry = |method: &str, url: &str, request: Option<&str>, response: &str| {
+        json!({
+            "request": {
+                "method": method,
+                "url": url,
+                "postData": request.map(|text| json!({ "mimeType": "application/json", "text": text })),
+            },
+            "response": { "status": 200, "content": { "mimeType": "application/json", "text": response } }
+        })
+    };
+    let har = json!({ "log": { "entries": [
+        entry("GET", "https://api.test/users/7?full=1", None, r#"{"id": 7, "name": "Ada"}"#),
+        entry("POST", "https://api.test/users", Some(r#"{"name": "Bob"}"#), r#"{"id": "8", "name": "Bob"}"#),
+        entry("GET", "https://api.test/health", None, "ok"),
+        entry("POST", "https://api.test/users/", Some("{}"), r#"{"id": 9, "name": "Eve"}"#),
+    ] } });
+
+    let report = validator.validate_har(&har).unwrap();
+    assert_eq!(report.unmatched, 1);
+    assert_eq!(report.exchanges.len(), 3);
+    assert_eq!(report.exchanges[0].result, Ok(()));
+    assert_eq!(report.exchanges[1].endpoint, "POST /users");
+    assert_eq!(
+        report.exchanges[1].result.as_ref().unwrap_err().0,
+        json_path("ROOT -> [1] -> response -> id")
+    );
+    assert_eq!(
+        report.exchanges[2].result,
+        Err(As3JsonPath(
+            json_path("ROOT -> [3] -> request"),
+            AS3ValidationError::MissingKey {
+                key: "name".to_string(),
+                suggestion: None
+            }
+        ))
+    );
+    assert_eq!(report.failures().count(), 2);
+
+    let invalid = serde_yaml::from_str(
+        "{ +endpoints: { GET /users: { response: Nope } }, User: { +type: Object } }",
+    )
+    .unwrap();
+    assert!(ContractValidator::from_with_options(&invalid, &DefinitionOptions::default()).is_err());
+}
+
+#[test]
+fn with_tagged_unions() {
+    let validator: serde_yaml::Value = serde_yaml::from_str(
+        r#"
+        Root:
+            +type: TaggedUnion
+            +discriminator: kind
+            +variants:
+                circle:
+                    +type: Object
+                    radius: Decimal
+                rect:
+                    +type: Object
+                    width: Decimal
+                    height: Decimal
+                    "#,
+    )
+    .unwrap();
+
+    verify(
+        &json!({ "kind": "circle", "radius": 2.5 }),
+        &validator,
+        Ok(()),
+    );
+    verify(
+        &json!({ "kind": "rect", "width": 1.0, "height": 2.0 }),
+        &validator,
+        Ok(()),
+    );
+    verify(
+        &json!({ "kind": "rect", "width": 1.0 }),
+        &validator,
+        Err(As3JsonPath(
+            json_path("ROOT"),
+            AS3ValidationError::MissingKey {
+                key: "height".to_string(),
+                suggestion: None,
+            },
+        )),
+    );
+    verify(
+        &json!({ "radius": 2.5 }),
+        &validator,
+        Err(As3JsonPath(
+            json_path("ROOT"),
+            AS3ValidationError::MissingKey {
+                key: "kind".to_string(),
+                suggestion: None,
+            },
+        )),
+    );
+    let error = AS3Validator::from(&validator)
+        .unwrap()
+        .validate(&AS3Data::from(&json!({ "kind": "square", "side": 1.0 })))
+        .unwrap_err();
+    assert_eq!(error.0, json_path("ROOT -> kind"));
+    assert_eq!(
+        error.1.to_string(),
+        "Unknown kind `square`, expected one of circle, rect"
+    );
+
+    let missing = serde_yaml::from_str("Root: { +type: TaggedUnion, +variants: {} }").unwrap();
+    assert!(AS3Validator::from(&missing).is_err());
+}
+
+#[cfg(feature = "tools")]
+#[test]
+fn with_pact_rules() {
+    let validator: serde_yaml::Value = serde_yaml::from_str(
+        r#"
+        Root:
+            +type: Object
+            id: Integer
+            sku: { +type: String, +regex: "^[A-Z]{3}$" }
+            price: Decimal?
+            tags: { +type: List, +ValueType: String }
+            first name: Any
+                    "#,
+    )
+    .unwrap();
+    let rule = |matchers: serde_json::Value| json!({ "matchers": matchers, "combine": "AND" });
+
+    assert_eq!(
+        crate::pact::to_pact_rules(&AS3Validator::from(&validator).unwrap().optimize()),
+        json!({
+            "body": {
+                "$": rule(json!([{ "match": "type" }])),
+                "$.id": rule(json!([{ "match": "integer" }])),
+                "$.sku": rule(json!([{ "match": "regex", "regex": "^[A-Z]{3}$" }])),
+                "$.price": {
+                    "matchers": [{ "match": "null" }, { "match": "number" }],
+                    "combine": "OR",
+                },
+                "$.tags": rule(json!([{ "match": "type" }])),
+                "$.tags[*]": rule(json!([{ "match": "type" }])),
+            }
+        })
+    );
+}
+
+#[test]
+fn with_all_errors() {
+    let validator: serde_yaml::Value = serde_yaml::from_str(
+        r#"
+        Root:
+            +type: Object
+            +extraKeys: forbid
+            age: { +type: Integer, +min: 0 }
+            name: String
+            tags: { +type: List, +ValueType: { +type: String, +maxLength: 3 } }
+                    "#,
+    )
+    .unwrap();
+    let validator = AS3Validator::from(&validator).unwrap();
+    let data = AS3Data::from(&json!({ "age": -1, "tags": ["ok", "long", "nope"], "x": 1 }));
+
+    let errors = validator.validate_all(&data).unwrap_err();
+    let paths: Vec<String> = errors.iter().map(|e| e.0.to_string()).collect();
+    assert_eq!(
+        paths,
+        [
+            "ROOT",
+            "ROOT -> age",
+            "ROOT",
+            "ROOT -> tags -> [1]",
+            "ROOT -> tags -> [2]"
+        ]
+    );
+    assert_eq!(
+        errors[0].1,
+        AS3ValidationError::UnexpectedKey {
+            key: "x".to_string(),
+            suggestion: None
+        }
+    );
+    assert_eq!(
+        errors[2].1,
+        AS3ValidationError::MissingKey {
+            key: "name".to_string(),
+            suggestion: None
+        }
+    );
+    assert!(errors.contains(&validator.validate(&data).unwrap_err()));
+
+    let valid = AS3Data::from(&json!({ "age": 1, "name": "Dilec", "tags": [] }));
+    assert_eq!(validator.validate_all(&valid), Ok(()));
+}
+
+#[cfg(feature = "coerce")]
+#[test]
+fn with_record_streams() {
+    let validator: serde_yaml::Value = serde_yaml::from_str(
+        r#"
+        Root:
+            +type: Object
+            id: Integer
+            amount: Decimal(min=0)
+                    "#,
+    )
+    .unwrap();
+    let validator = AS3Validator::from(&validator).unwrap();
+    let records = [
+        json!({ "id": 1, "amount": 9.5 }),
+        json!({ "id": "2", "amount": 3 }),
+        json!({ "id": 3, "amount": -1 }),
+    ];
+
+    let (mut valid, mut quarantined) = (Vec::new(), Vec::new());
+    let options = ValidationOptions {
+        coerce: true,
+        ..Default::default()
+    };
+    validator.validate_stream(
+        records.iter().map(AS3Data::from),
+        &options,
+        |record| match record.is_valid() {
+            true => valid.push(serde_json::Value::from(&record.data)),
+            false => quarantined.push(record.result.unwrap_err().0.to_string()),
+        },
+    );
+    assert_eq!(
+        valid,
+        [
+            json!({ "id": 1, "amount": 9.5 }),
+            json!({ "id": 2, "amount": 3.0 })
+        ]
+    );
+    assert_eq!(quarantined, ["ROOT -> [2] -> amount"]);
+
+    let validator =
+        AS3Validator::from(&serde_yaml::from_str("Root: Map<String, Integer(min=0)>").unwrap())
+            .unwrap();
+    let mut results = Vec::new();
+    validator.validate_each(
+        &AS3Data::from(&json!({ "b": -1, "a": 1 })),
+        &ValidationOptions::default(),
+        |record| results.push((record.path.to_string(), record.is_valid())),
+    );
+    assert_eq!(
+        results,
+        [
+            ("ROOT -> a".to_string(), true),
+            ("ROOT -> b".to_string(), false)
+        ]
+    );
+}
+
+#[cfg(feature = "tools")]
+#[test]
+fn with_sampling() {
+    use crate::sampling::{sample, Sample};
+    assert_eq!("1%".parse(), Ok(Sample::Fraction(0.01)));
+    assert_eq!("1000".parse(), Ok(Sample::Count(1000)));
+    assert!("150%".parse::<Sample>().is_err());
+    assert!("some".parse::<Sample>().is_err());
+
+    let picked: Vec<usize> = sample(0..10_000, Sample::Count(5), 42).collect();
+    assert_eq!(picked.len(), 5);
+    assert!(picked.windows(2).all(|pair| pair[0] < pair[1]));
+    assert_eq!(
+        picked,
+        sample(0..10_000, Sample::Count(5), 42).collect::<Vec<_>>()
+    );
+    assert_ne!(
+        picked,
+        sample(0..10_000, Sample::Count(5), 7).collect::<Vec<_>>()
+    );
+    assert_eq!(sample(0..3, Sample::Count(5), 42).count(), 3);
+
+    let share = sample(0..10_000, Sample::Fraction(0.1), 42).count();
+    assert!((800..1200).contains(&share), "{share}");
+    assert_eq!(sample(0..100, Sample::Fraction(0.0), 42).count(), 0);
+    assert_eq!(sample(0..100, Sample::Fraction(1.0), 42).count(), 100);
+}
+
+#[test]
+fn with_error_limit() {
+    let validator =
+        AS3Validator::from(&serde_yaml::from_str("Root: List<Integer(max=10)>").unwrap()).unwrap();
+    let data = AS3Data::from(&json!((0..1000).map(|n| n * 2).collect::<Vec<_>>()));
+    let options = ValidationOptions {
+        max_errors: NonZeroUsize::new(3),
+        ..Default::default()
+    };
+
+    let errors = validator
+        .validate_all_with_options(&data, &options)
+        .unwrap_err();
+    assert_eq!(
+        errors.iter().map(|e| e.0.to_string()).collect::<Vec<_>>(),
+        ["ROOT -> [6]", "ROOT -> [7]", "ROOT -> [8]"]
+    );
+    let outcome = validator.validate_with_warnings(&data, &options);
+    assert_eq!(outcome.errors, errors);
+    assert!(outcome.truncated);
+
+    // Exactly as many errors as the limit
+    let data = AS3Data::from(&json!([1, 20, 30, 40]));
+    let outcome = validator.validate_with_warnings(&data, &options);
+    assert_eq!(outcome.errors.len(), 3);
+    assert!(!outcome.truncated);
+}
+
+#[derive(Debug, Default)]
+struct Visited(std::sync::Mutex<Vec<String>>);
+
+impl crate::validator::Tracer for Visited {
+    fn visit(&self, path: &JsonPath) {
+        self.0.lock().unwrap().push(path.to_string());
+    }
+}
+
+#[test]
+fn with_tracer() {
+    let validator = AS3Validator::from(
+        &serde_yaml::from_str(
+            r#"
+            Root:
+              +type: Object
+              tags: List<String>
+              metadata: Any
+            "#,
+        )
+        .unwrap(),
+    )
+    .unwrap();
+    let data = AS3Data::from(&json!({ "tags": ["a", "b"], "metadata": {} }));
+    let visited = std::sync::Arc::new(Visited::default());
+    let options = ValidationOptions {
+        exclude: vec!["metadata".parse().unwrap()],
+        tracer: Some(visited.clone()),
+        ..Default::default()
+    };
+
+    assert_eq!(validator.validate_with_options(&data, &options), Ok(()));
+    let mut paths = visited.0.lock().unwrap().clone();
+    // Fields are validated in parallel
+    paths.sort();
+    paths.dedup();
+    assert_eq!(
+        paths,
+        [
+            "ROOT",
+            "ROOT -> tags",
+            "ROOT -> tags -> [0]",
+            "ROOT -> tags -> [1]"
+        ]
+    );
+}
+
+#[cfg(feature = "tools")]
+#[test]
+fn with_form_generation() {
+    use crate::form::{to_form, FormStyle};
+
+    let validator: serde_yaml::Value = serde_yaml::from_str(
+        r#"
+        Root:
+            +type: Object
+            name: { +type: String, +title: Full name }
+            status: { +type: String, +regex: "^(draft|published)$" }
+            active: Bool
+            address:
+                +type: Object
+                street: String
+                city: String
+                    "#,
+    )
+    .unwrap();
+    let validator = AS3Validator::from(&validator).unwrap().optimize();
+
+    let form = to_form(&validator, FormStyle::Rjsf);
+    assert_eq!(
+        form["uiSchema"],
+        json!({
+            "ui:order": ["name", "status", "active", "address"],
+            "name": { "ui:title": "Full name" },
+            "status": { "ui:widget": "select" },
+            "active": { "ui:widget": "checkbox" },
+            "address": { "ui:order": ["street", "city"] },
+        })
+    );
+    assert_eq!(
+        form["schema"]["properties"]["status"]["enum"],
+        json!(["draft", "published"])
+    );
+
+    let form = to_form(&validator, FormStyle::JsonForms);
+    assert_eq!(
+        form["uischema"]["elements"][0],
+        json!({ "type": "Control", "scope": "#/properties/name", "label": "Full name" })
+    );
+    assert_eq!(
+        form["uischema"]["elements"][3]["elements"][1],
+        json!({ "type": "Control", "scope": "#/properties/address/properties/city" })
+    );
+}
+
+#[test]
+fn with_structured_paths() {
+    let validator: serde_yaml::Value = serde_yaml::from_str(
+        r#"
+        Root:
+            +type: Object
+            orders:
+                +type: List
+                +ValueType: { +type: Object, sku: String }
+                    "#,
+    )
+    .unwrap();
+    let error = AS3Validator::from(&validator)
+        .unwrap()
+        .validate(&AS3Data::from(
+            &json!({ "orders": [{ "sku": "A-1" }, { "sku": 2 }] }),
+        ))
+        .unwrap_err();
+
+    assert_eq!(
+        error.0.segments(),
+        [
+            PathSegment::Key("ROOT".to_string()),
+            PathSegment::Key("orders".to_string()),
+            PathSegment::Index(1),
+            PathSegment::Key("sku".to_string()),
+        ]
+    );
+    assert_eq!(error.0.keys().collect::<Vec<_>>(), ["orders", "sku"]);
+    assert_eq!(error.0.to_string(), "ROOT -> orders -> [1] -> sku");
+}
+
+#[test]
+fn with_keys_looking_like_paths() {
+    let validator: serde_yaml::Value = serde_yaml::from_str(
+        r#"
+        Root:
+            +type: Object
+            "[0]": Integer
+            scores: Map<String, Integer>
+                    "#,
+    )
+    .unwrap();
+    let errors = AS3Validator::from(&validator)
+        .unwrap()
+        .validate_all(&AS3Data::from(
+            &json!({ "[0]": "zero", "scores": { "a -> b": "high" } }),
+        ))
+        .unwrap_err();
+
+    let root = JsonPath::root("ROOT");
+    assert_eq!(
+        errors.iter().map(|error| &error.0).collect::<Vec<_>>(),
+        [
+            &root.with_key("[0]"),
+            &root.with_key("scores").with_key("a -> b")
+        ]
+    );
+    assert_eq!(
+        errors[0].0.segments(),
+        [
+            PathSegment::Key("ROOT".to_string()),
+            PathSegment::Key("[0]".to_string()),
+        ]
+    );
+    assert_eq!(errors[1].0.segments().len(), 3);
+}
+
+#[test]
+fn with_serialized_errors() {
+    let validator: serde_yaml::Value = serde_yaml::from_str(
+        r#"
+        Root:
+            +type: Object
+            count: Integer(max=10)
+            tags: List<String>
+            sku: { +type: String, +regex: "^[A-Z]+$", +message: SKU must be uppercase }
+                    "#,
+    )
+    .unwrap();
+    let errors = AS3Validator::from(&validator)
+        .unwrap()
+        .validate_all(&AS3Data::from(
+            &json!({ "count": 11, "tags": ["a", 2], "sku": "abc" }),
+        ))
+        .unwrap_err();
+
+    assert_eq!(
+        serde_json::to_value(&errors).unwrap(),
+        json!([
+            {
+                "path": "ROOT -> count",
+                "code": "E_MAX_INT",
+                "message": "`11` is above the maximum of `10`",
+                "expected": 10,
+                "got": 11,
+            },
+            {
+                "path": "ROOT -> sku",
+                "code": "E_REGEX",
+                "message": "SKU must be uppercase",
+                "expected": "^[A-Z]+$",
+                "got": "abc",
+            },
+            {
+                "path": "ROOT -> tags -> [1]",
+                "code": "E_TYPE_MISMATCH",
+                "message": "Expected String, got `2`",
+                "expected": "String",
+                "got": 2,
+            },
+        ])
+    );
+}
+
+#[cfg(feature = "tools")]
+#[test]
+fn with_terraform_variables() {
+    use crate::diagnostic::{self, Format};
+    let path = std::path::Path::new("prod.tfvars");
+    let source = "region = \"eu-west-1\"\ntags = {\n  env = \"prod\"\n}\nzones = [\"a\", \"b\"]\n";
+
+    assert_eq!(Format::detect(path, source), Format::Hcl);
+    let data: serde_json::Value = diagnostic::parse("Data file", path, source, None).unwrap();
+    assert_eq!(
+        data,
+        json!({ "region": "eu-west-1", "tags": { "env": "prod" }, "zones": ["a", "b"] })
+    );
+
+    let validator: serde_yaml::Value = serde_yaml::from_str(
+        r#"
+        Root:
+            +type: Object
+            region: { +type: String, +regex: "^[a-z]+-[a-z]+-[0-9]$" }
+            tags: Map<String, String>
+            zones: List<String>
+                    "#,
+    )
+    .unwrap();
+    verify(&data, &validator, Ok(()));
+
+    let error =
+        diagnostic::parse::<serde_json::Value>("Data file", path, "region = \n", None).unwrap_err();
+    assert!(error.contains("is not propper hcl"), "{error}");
+    assert!(error.contains("--> prod.tfvars:1:10"), "{error}");
+}
+
+#[cfg(feature = "tools")]
+#[test]
+fn with_ini_and_properties() {
+    use crate::diagnostic::{self, Format};
+    let ini = std::path::Path::new("app.ini");
+    let source =
+        "; Service\nname = \"billing\"\n\n[database]\nport = 5432\n\n[database.pool]\nsize: 4\n";
+    assert_eq!(Format::detect(ini, source), Format::Ini);
+    let data: serde_json::Value = diagnostic::parse("Data file", ini, source, None).unwrap();
+    assert_eq!(
+        data,
+        json!({ "name": "billing", "database": { "port": "5432", "pool": { "size": "4" } } })
+    );
+
+    let properties = std::path::Path::new("app.properties");
+    let source = "# Service\nname=billing\ndatabase.port : 5432\ndatabase.pool.size 4\ngreeting = Hello,\\\n    world \\u00e9\n";
+    let data: serde_json::Value = diagnostic::parse("Data file", properties, source, None).unwrap();
+    assert_eq!(
+        data,
+        json!({
+            "name": "billing",
+            "database": { "port": "5432", "pool": { "size": "4" } },
+            "greeting": "Hello,world é",
+        })
+    );
+
+    let validator = AS3Validator::from(
+        &serde_yaml::from_str(
+            r#"
+            Root:
+                +type: Object
+                name: String
+                database:
+                    +type: Object
+                    port: Integer
+                    pool: { +type: Object, size: Integer }
+                greeting: String
+            "#,
+        )
+        .unwrap(),
+    )
+    .unwrap();
+    let coerce = ValidationOptions {
+        coerce: true,
+        ..Default::default()
+    };
+    assert_eq!(
+        validator.validate_with_options(&AS3Data::from(&data), &coerce),
+        Ok(())
+    );
+
+    let error =
+        diagnostic::parse::<serde_json::Value>("Data file", ini, "a = 1\n[a]\n", None).unwrap_err();
+    assert!(
+        error.contains("`a` is both a value and a section"),
+        "{error}"
+    );
+    assert!(error.contains("--> app.ini:2:1"), "{error}");
+    let error = diagnostic::parse::<serde_json::Value>("Data file", properties, "a=1\na=2\n", None)
+        .unwrap_err();
+    assert!(error.contains("`a` is defined twice"), "{error}");
+}
+
+#[cfg(feature = "tools")]
+#[test]
+fn with_source_positions() {
+    use crate::{
+        diagnostic::Format,
+        spans::{Position, SourceMap},
+    };
+    let json = SourceMap::new(
+        "{\n  \"name\": \"x\",\n  \"tags\": [\"a\", 3]\n}\n",
+        Format::Json,
+    );
+    assert_eq!(
+        json.locate(&json_path("ROOT -> tags -> [1]")),
+        Some(Position {
+            line: 3,
+            column: 17
+        })
+    );
+    // A missing key is located at the Object that should hold it
+    assert_eq!(
+        json.locate(&json_path("ROOT -> age")),
+        Some(Position { line: 1, column: 1 })
+    );
+
+    let yaml = SourceMap::new("name: x\ntags:\n  - a\n  - 3\n", Format::Yaml);
+    assert_eq!(
+        yaml.locate(&json_path("ROOT -> tags -> [1]")),
+        Some(Position { line: 4, column: 5 })
+    );
+    let unexpected = As3JsonPath(
+        json_path("ROOT"),
+        AS3ValidationError::UnexpectedKey {
+            key: "tags".to_string(),
+            suggestion: None,
+        },
+    );
+    assert_eq!(
+        yaml.locate_error(&unexpected),
+        Some(Position { line: 3, column: 3 })
+    );
+
+    let toml = SourceMap::new("name = \"x\"\n\n[owner]\nage = \"7\"\n", Format::Toml);
+    assert_eq!(
+        toml.locate(&json_path("ROOT -> owner -> age")),
+        Some(Position { line: 4, column: 7 })
+    );
+    assert_eq!(SourceMap::new("a=1", Format::Ini), SourceMap::default());
+}
+
+#[cfg(feature = "tools")]
+#[test]
+fn with_sarif_output() {
+    use crate::{diagnostic::Format, sarif::to_sarif, spans::SourceMap};
+    let validator = AS3Validator::from(
+        &serde_yaml::from_str(
+            r#"
+            Root:
+                +type: Object
+                count: { +type: Integer, +max: 10 }
+                retries: { +type: Integer, +max: 5, +severity: warning }
+            "#,
+        )
+        .unwrap(),
+    )
+    .unwrap();
+    let source = "{\n  \"count\": 11,\n  \"retries\": 6\n}\n";
+    let data = AS3Data::from(&serde_json::from_str::<serde_json::Value>(source).unwrap());
+    let outcome = validator.validate_with_warnings(&data, &ValidationOptions::default());
+    let log = to_sarif("data.json", &outcome, &SourceMap::new(source, Format::Json));
+    let run = &log["runs"][0];
+    assert_eq!(log["version"], "2.1.0");
+    assert_eq!(
+        run["tool"]["driver"]["rules"],
+        json!([{ "id": "E_MAX_INT" }])
+    );
+    assert_eq!(
+        run["results"][0],
+        json!({
+            "ruleId": "E_MAX_INT",
+            "ruleIndex": 0,
+            "level": "error",
+            "message": { "text": "`11` is above the maximum of `10`" },
+            "locations": [{
+                "physicalLocation": {
+                    "artifactLocation": { "uri": "data.json" },
+                    "region": { "startLine": 2, "startColumn": 12 },
+                },
+                "logicalLocations": [{ "fullyQualifiedName": "ROOT -> count" }],
+            }],
+        })
+    );
+    assert_eq!(run["results"][1]["level"], "warning");
+    assert_eq!(
+        run["results"][1]["locations"][0]["physicalLocation"]["region"],
+        json!({ "startLine": 3, "startColumn": 14 })
+    );
+}
+
+#[cfg(feature = "tools")]
+#[test]
+fn with_pretty_errors() {
+    use crate::{
+        diagnostic::{self, Format},
+        spans::SourceMap,
+    };
+    let source = "name: x\nage: \"7\"\n";
+    let error = As3JsonPath(
+        json_path("ROOT -> age"),
+        AS3ValidationError::TypeError {
+            expected: AS3Validator::Integer {
+                minimum: None,
+                maximum: None,
+                width: None,
+            },
+            got: AS3Data::String("7".to_string()),
+        },
+    );
+    let failure = diagnostic::validation_failure(
+        std::path::Path::new("data.yaml"),
+        source,
+        &SourceMap::new(source, Format::Yaml),
+        &error,
+    );
+    assert_eq!(
+        failure,
+        "error[E_TYPE_MISMATCH]: Expected Integer, got `7`
+  --> data.yaml:2:6
+  |
+1 | name: x
+2 | age: \"7\"
+  |      ^
+  = path: ROOT -> age
+  = expected: Integer
+  = got: \"7\""
+    );
+}
+
+#[test]
+fn with_decimal_bounds() {
+    let validator: serde_yaml::Value =
+        serde_yaml::from_str("Root: { +type: Decimal, +min: 0.5, +max: 2.5 }").unwrap();
+    verify(&json!(1.5), &validator, Ok(()));
+    verify(
+        &json!(0.25),
+        &validator,
+        Err(As3JsonPath(
+            json_path("ROOT"),
+            AS3ValidationError::MinimumDecimal {
+                number: 0.25,
+                minimum: 0.5,
+            },
+        )),
+    );
+    let above = Err(As3JsonPath(
+        json_path("ROOT"),
+        AS3ValidationError::MaximumDecimal {
+            number: 3.5,
+            maximum: 2.5,
+        },
+    ));
+    assert_eq!(above.as_ref().unwrap_err().1.code(), "E_MAX_DECIMAL");
+    verify(&json!(3.5), &validator, above);
+}
+
+#[test]
+fn with_metrics() {
+    use crate::metrics::Metrics;
+    let validator = AS3Validator::from(
+        &serde_yaml::from_str(
+            r#"
+            Root:
+                +type: Object
+                name: { +type: String, +regex: "^[a-z]+$" }
+                tags: { +type: List, +ValueType: Integer }
+            "#,
+        )
+        .unwrap(),
+    )
+    .unwrap();
+    // Other tests validate at the same time, the counters only grow
+    let before = Metrics::snapshot();
+    let data = AS3Data::from(&json!({ "name": "abc", "tags": [1, 2] }));
+    validator.validate(&data).unwrap();
+    let after = Metrics::snapshot();
+    match cfg!(feature = "metrics") {
+        true => {
+            assert!(after.nodes_visited >= before.nodes_visited + 5);
+            assert!(after.regex_evaluations > before.regex_evaluations);
+        }
+        false => assert_eq!(after, Metrics::default()),
+    }
+}
+
+/// The output of `render_error` is a public guarantee: a change to these snapshots needs a
+/// new `RENDERING_VERSION`
+#[test]
+fn with_rendering_snapshots() {
+    use crate::messages::{render_error, Format, RENDERING_VERSION};
+    assert_eq!(RENDERING_VERSION, 1);
+    let errors = [
+        As3JsonPath(
+            json_path("ROOT -> age"),
+            AS3ValidationError::TypeError {
+                expected: AS3Validator::Integer {
+                    minimum: None,
+                    maximum: None,
+                    width: None,
+                },
+                got: AS3Data::String("7".to_string()),
+            },
+        ),
+        As3JsonPath(
+            json_path("ROOT"),
+            AS3ValidationError::MissingKey {
+                key: "maker".to_string(),
+                suggestion: Some("makr".to_string()),
+            },
+        ),
+        As3JsonPath(
+            json_path("ROOT -> price"),
+            AS3ValidationError::MaximumDecimal {
+                number: 12.5,
+                maximum: 10.0,
+            },
+        ),
+    ];
+    let rendered: Vec<[String; 3]> = errors
+        .iter()
+        .map(|error| {
+            [Format::Short, Format::Long, Format::Json].map(|format| render_error(error, format))
+        })
+        .collect();
+    assert_eq!(
+        rendered,
+        [
+            [
+                "ROOT -> age: Expected Integer, got `7`",
+                "error[E_TYPE_MISMATCH]: Expected Integer, got `7`\n  path: ROOT -> age\n  expected: Integer\n  got: \"7\"",
+                r#"{"path":"ROOT -> age","code":"E_TYPE_MISMATCH","message":"Expected Integer, got `7`","expected":"Integer","got":"7"}"#,
+            ],
+            [
+                "ROOT: Key maker is missing",
+                "error[E_MISSING_KEY]: Key maker is missing\n  path: ROOT\n  expected: maker",
+                r#"{"path":"ROOT","code":"E_MISSING_KEY","message":"Key maker is missing","expected":"maker"}"#,
+            ],
+            [
+                "ROOT -> price: `12.5` is above the maximum of `10.0`",
+                "error[E_MAX_DECIMAL]: `12.5` is above the maximum of `10.0`\n  path: ROOT -> price\n  expected: 10.0\n  got: 12.5",
+                r#"{"path":"ROOT -> price","code":"E_MAX_DECIMAL","message":"`12.5` is above the maximum of `10.0`","expected":10.0,"got":12.5}"#,
+            ],
+        ]
+        .map(|formats| formats.map(String::from))
+    );
+}
+
+#[cfg(feature = "tools")]
+#[test]
+fn with_error_formatters() {
+    use crate::report::{
+        CompactFormatter, ErrorFormatter, JsonFormatter, TextFormatter, YamlFormatter,
+    };
+    let validator =
+        AS3Validator::from(&serde_yaml::from_str("Root: Integer(max=10)").unwrap()).unwrap();
+    let passed = ValidationReport::new(
+        "ok.json".to_string(),
+        None,
+        &validator.validate(&AS3Data::Integer(3)),
+    );
+    let failed = ValidationReport::new(
+        "ko.json".to_string(),
+        Some("schema.yaml".to_string()),
+        &validator.validate(&AS3Data::Integer(11)),
+    );
+
+    assert_eq!(TextFormatter.format(&passed), "✅ ok.json is valid");
+    assert_eq!(
+        TextFormatter.format(&failed),
+        "❌ ko.json: `11` is above the maximum of `10` at ROOT"
+    );
+    assert_eq!(CompactFormatter.format(&passed), "passed ok.json");
+    assert_eq!(
+        CompactFormatter.format(&failed),
+        "failed ko.json E_MAX_INT ROOT"
+    );
+    let json: serde_json::Value = serde_json::from_str(&JsonFormatter.format(&failed)).unwrap();
+    assert_eq!(json["code"], "E_MAX_INT");
+    assert_eq!(json["valid"], false);
+    let yaml: serde_yaml::Value = serde_yaml::from_str(&YamlFormatter.format(&failed)).unwrap();
+    assert_eq!(yaml["path"], "ROOT");
+    assert_eq!(yaml["definition"], "schema.yaml");
+}
+
+#[test]
+fn with_deprecated_keywords() {
+    use crate::deprecation::{deprecated_keywords, fix_deprecations, DeprecatedKeyword};
+    let source = r##"
++defs:
+  Code: { +type: String, "+min_length": 2 }
+Root:
+  +type: Object
+  # Display name
+  name:
+    +type: String
+    +MaxLength: 10
+  code: { +ref: "#/defs/Code" }
+"##;
+    let definition: serde_yaml::Value = serde_yaml::from_str(source).unwrap();
+    // Old spellings still parse
+    let validator = AS3Validator::from(&definition).unwrap();
+    assert!(validator
+        .validate(&AS3Data::from(
+            &json!({ "name": "a very long name", "code": "AB" })
+        ))
+        .is_err());
+    assert_eq!(
+        deprecated_keywords(&definition),
+        vec![
+            DeprecatedKeyword {
+                path: json_path("+defs -> Code"),
+                keyword: "+min_length".to_string(),
+                replacement: "+minLength".to_string(),
+            },
+            DeprecatedKeyword {
+                path: json_path("Root -> name"),
+                keyword: "+MaxLength".to_string(),
+                replacement: "+maxLength".to_string(),
+            },
+        ]
+    );
+
+    let (fixed, count) = fix_deprecations(source);
+    assert_eq!(count, 2);
+    assert_eq!(
+        fixed,
+        source
+            .replace("\"+min_length\"", "\"+minLength\"")
+            .replace("+MaxLength", "+maxLength")
+    );
+    let fixed: serde_yaml::Value = serde_yaml::from_str(&fixed).unwrap();
+    assert!(deprecated_keywords(&fixed).is_empty());
+    assert_eq!(AS3Validator::from(&fixed).unwrap(), validator);
+}
+
+#[test]
+fn with_lint() {
+    use crate::{lint::lint, validator::Severity};
+    let definition = serde_yaml::from_str(
+        r#"
+        Root:
+          +type: Object
+          name:
+            +type: String
+            +regex: "[a-"
+            +MaxLength: 5
+          age:
+            +type: Integer
+            +min: 10
+            +max: 2
+          tags:
+            +type: List
+          extra: Any?
+        "#,
+    )
+    .unwrap();
+
+    let lints = lint(&definition, &DefinitionOptions::default());
+    assert_eq!(
+        lints
+            .iter()
+            .map(|lint| (lint.path.to_string(), lint.severity))
+            .collect::<Vec<_>>(),
+        [
+            ("Root -> name -> +regex".to_string(), Severity::Error),
+            ("Root -> age".to_string(), Severity::Error),
+            ("Root -> tags".to_string(), Severity::Error),
+            ("Root -> name -> +MaxLength".to_string(), Severity::Warning),
+            ("Root -> extra".to_string(), Severity::Warning),
+        ]
+    );
+    assert_eq!(
+        lints[2].to_string(),
+        "The required `+ValueType` property is missing in [Root -> tags]"
+    );
+    assert_eq!(
+        lints[4].message,
+        "`Any?` is the same as `Any`, which already accepts null"
+    );
+
+    let definition = serde_yaml::from_str("Root: List<Integer>").unwrap();
+    assert!(lint(&definition, &DefinitionOptions::default()).is_empty());
+}
+
+#[test]
+fn with_serialized_outcome() {
+    use crate::validator::ValidationOutcome;
+    let validator = AS3Validator::from(
+        &serde_yaml::from_str(
+            r#"
+            Root:
+                +type: Object
+                count: Integer(max=10)
+                retries: { +type: Integer, +max: 5, +severity: warning }
+            "#,
+        )
+        .unwrap(),
+    )
+    .unwrap();
+    let options = ValidationOptions::default();
+    let valid = AS3Data::from(&json!({ "count": 1, "retries": 0 }));
+    assert_eq!(
+        serde_json::to_value(validator.validate_with_warnings(&valid, &options)).unwrap(),
+        json!({ "valid": true })
+    );
+    let invalid = AS3Data::from(&json!({ "count": 11, "retries": 6 }));
+    let outcome = validator.validate_with_warnings(&invalid, &options);
+    assert_eq!(
+        serde_json::to_value(&outcome).unwrap(),
+        json!({
+            "valid": false,
+            "errors": [outcome.errors[0]],
+            "warnings": [outcome.warnings[0]],
+        })
+    );
+    let truncated = ValidationOutcome {
+        truncated: true,
+        ..ValidationOutcome::default()
+    };
+    assert_eq!(
+        serde_json::to_value(truncated).unwrap(),
+        json!({ "valid": true, "truncated": true })
+    );
+}
+
+#[cfg(feature = "tools")]
+#[test]
+fn with_files_by_extension() {
+    use crate::daemon::{documents_in, files_in};
+    let folder = std::env::temp_dir().join(format!("as3-files-{}", std::process::id()));
+    std::fs::create_dir_all(folder.join("nested/deeper")).unwrap();
+    for file in [
+        "a.json",
+        "nested/b.yaml",
+        "nested/deeper/c.toml",
+        "notes.txt",
+    ] {
+        std::fs::write(folder.join(file), "1").unwrap();
+    }
+    assert_eq!(
+        files_in(&folder, &["json", "toml"]).unwrap(),
+        vec![folder.join("a.json"), folder.join("nested/deeper/c.toml")]
+    );
+    assert_eq!(
+        documents_in(&folder).unwrap(),
+        vec![folder.join("a.json"), folder.join("nested/b.yaml")]
+    );
+    std::fs::remove_dir_all(folder).unwrap();
+}
@@ -0,0 +1,340 @@
+//! JSON Schema emission tuned for editors (YAML language server, VS Code), so data files get
+//! autocomplete, hover documentation and inline errors from an AS3 definition
+
+use crate::validator::{
+    AS3Validator, BytesEncoding, CharClass, DurationSyntax, ExtraKeys, LengthUnit, StringFormat,
+};
+use serde_json::{json, Map, Value};
+use std::collections::BTreeMap;
+
+/// Converts a validator to a draft-07 JSON Schema document
+pub fn to_editor_schema(validator: &AS3Validator) -> Value {
+    let mut definitions = BTreeMap::new();
+    let mut schema = schema_of(validator, &mut definitions);
+    if let Value::Object(schema) = &mut schema {
+        schema.insert(
+            "$schema".to_string(),
+            json!("http://json-schema.org/draft-07/schema#"),
+        );
+        if !definitions.is_empty() {
+            schema.insert("definitions".to_string(), json!(definitions));
+        }
+    }
+    schema
+}
+
+/// `definitions` receives the schemas of the `+defs` that are referenced
+fn schema_of(validator: &AS3Validator, definitions: &mut BTreeMap<String, Value>) -> Value {
+    match validator {
+        AS3Validator::Object {
+            fields,
+            extra_keys,
+            pattern_keys,
+            requires,
+            ..
+        } => {
+            let mut keys: Vec<&String> = fields.keys().collect();
+            keys.sort();
+            let properties: Map<String, Value> = keys
+                .iter()
+                .map(|key| (key.to_string(), schema_of(&fields[*key], definitions)))
+                .collect();
+            let mut schema = json!({
+                "type": "object",
+                "properties": properties,
+                "required": keys,
+                "description": "Object",
+            });
+            if let Some(pattern_keys) = pattern_keys {
+                let pattern_properties: Map<String, Value> = pattern_keys
+                    .iter()
+                    .map(|(pattern, validator)| {
+                        (pattern.clone(), schema_of(validator, definitions))
+                    })
+                    .collect();
+                schema["patternProperties"] = Value::Object(pattern_properties);
+            }
+            if !requires.is_empty() {
+                schema["dependentRequired"] = json!(requires);
+            }
+            if *extra_keys == ExtraKeys::Forbid {
+                schema["additionalProperties"] = json!(false);
+            }
+            schema
+        }
+        AS3Validator::String {
+            regex,
+            max_length,
+            min_length,
+            format,
+            any_regex,
+            deny_regex,
+            char_classes,
+            length_unit,
+        } => {
+            let mut schema = Map::new();
+            schema.insert("type".to_string(), json!("string"));
+            let mut description = vec!["String".to_string()];
+            for class in char_classes {
+                description.push(
+                    match class {
+                        CharClass::Ascii => "of ASCII characters",
+                        CharClass::Alphanumeric => "of letters and digits",
+                        CharClass::NoWhitespace => "without whitespace",
+                    }
+                    .to_string(),
+                );
+            }
+            if let Some(regex) = regex {
+                schema.insert("pattern".to_string(), json!(regex));
+            }
+            if let Some(values) = validator.enumeration() {
+                schema.insert("enum".to_string(), json!(values));
+            }
+            // JSON Schema lengths count characters
+            if *length_unit == LengthUnit::Chars {
+                if let Some(min_length) = min_length {
+                    schema.insert("minLength".to_string(), json!(min_length));
+                }
+                if let Some(max_length) = max_length {
+                    schema.insert("maxLength".to_string(), json!(max_length));
+                }
+            } else {
+                match (min_length, max_length) {
+                    (Some(min), Some(max)) => {
+                        description.push(format!("of {min} to {max} {length_unit}"))
+                    }
+                    (Some(min), None) => {
+                        description.push(format!("of at least {min} {length_unit}"))
+                    }
+                    (None, Some(max)) => {
+                        description.push(format!("of at most {max} {length_unit}"))
+                    }
+                    (None, None) => {}
+                }
+            }
+            if let Some(format) = format {
+                let json_format = match format {
+                    StringFormat::Url { .. } => Some("uri"),
+                    StringFormat::Uuid { .. } => Some("uuid"),
+                    StringFormat::Ipv4 { cidr: false } => Some("ipv4"),
+                    StringFormat::Ipv6 { cidr: false } => Some("ipv6"),
+                    StringFormat::Hostname => Some("hostname"),
+                    _ => None,
+                };
+                if let Some(json_format) = json_format {
+                    schema.insert("format".to_string(), json!(json_format));
+                }
+                description.push(format!("in the `{format}` format"));
+            }
+            let mut all_of = Vec::new();
+            if let Some(any_regex) = any_regex {
+                all_of.push(json!({
+                    "anyOf": any_regex
+                        .patterns()
+                        .iter()
+                        .map(|pattern| json!({ "pattern": pattern }))
+                        .collect::<Vec<Value>>()
+                }));
+            }
+            if let Some(deny_regex) = deny_regex {
+                all_of.extend(
+                    deny_regex
+                        .patterns()
+                        .iter()
+                        .map(|pattern| json!({ "not": { "pattern": pattern } })),
+                );
+            }
+            if !all_of.is_empty() {
+                schema.insert("allOf".to_string(), Value::Array(all_of));
+            }
+            schema.insert("description".to_string(), json!(description.join(" ")));
+            Value::Object(schema)
+        }
+        AS3Validator::Integer {
+            minimum,
+            maximum,
+            width,
+        } => {
+            let mut schema = json!({ "type": "integer", "description": "Integer" });
+            let (mut minimum, mut maximum) = (minimum.map(i128::from), maximum.map(i128::from));
+            if let Some(width) = width {
+                let (lowest, highest) = width.range();
+                minimum = Some(minimum.map_or(lowest, |minimum| minimum.max(lowest)));
+                maximum = Some(maximum.map_or(highest, |maximum| maximum.min(highest)));
+                schema["description"] = json!(format!("Integer ({width})"));
+            }
+            if let Some(minimum) = minimum {
+                schema["minimum"] = json!(minimum);
+            }
+            if let Some(maximum) = maximum {
+                schema["maximum"] = json!(maximum);
+            }
+            schema
+        }
+        AS3Validator::Decimal { minimum, maximum } => {
+            let mut schema = json!({ "type": "number", "description": "Decimal" });
+            if let Some(minimum) = minimum {
+                schema["minimum"] = json!(minimum);
+            }
+            if let Some(maximum) = maximum {
+                schema["maximum"] = json!(maximum);
+            }
+            schema
+        }
+        AS3Validator::List(items_type) => json!({
+            "type": "array",
+            "items": schema_of(items_type, definitions),
+            "description": "List",
+        }),
+        AS3Validator::Map {
+            key_type,
+            value_type,
+            min_entries,
+            max_entries,
+        } => {
+            let mut schema = json!({
+                "type": "object",
+                "additionalProperties": schema_of(value_type, definitions),
+                "description": "Map",
+            });
+            let key_pattern = match key_type.as_ref() {
+                AS3Validator::Integer { .. } => Some(r"^-?\d+$"),
+                AS3Validator::Boolean => Some("^(?i:true|false|1|0)$"),
+                AS3Validator::Date { .. } => Some(r"^\d{4}-\d{2}-\d{2}$"),
+                _ => None,
+            };
+            if let Some(key_pattern) = key_pattern {
+                schema["propertyNames"] = json!({ "pattern": key_pattern });
+            }
+            if let Some(min_entries) = min_entries {
+                schema["minProperties"] = json!(min_entries);
+            }
+            if let Some(max_entries) = max_entries {
+                schema["maxProperties"] = json!(max_entries);
+            }
+            schema
+        }
+        AS3Validator::Boolean => json!({
+            "type": "boolean",
+            "enum": [true, false],
+            "description": "Bool",
+        }),
+        AS3Validator::Date { .. } => json!({
+            "type": "string",
+            "format": "date",
+            "description": "Date (YYYY-MM-DD)",
+        }),
+        AS3Validator::Null => json!({ "type": "null", "description": "Null" }),
+        AS3Validator::Any => json!({ "description": "Any" }),
+        AS3Validator::Nullable(inner) => {
+            let mut schema = schema_of(inner, definitions);
+            match schema.get("type").cloned() {
+                Some(Value::String(json_type)) => {
+                    schema["type"] = json!([json_type, "null"]);
+                    if let Some(Value::Array(values)) = schema.get_mut("enum") {
+                        values.push(Value::Null);
+                    }
+                    schema
+                }
+                _ => json!({ "anyOf": [schema, { "type": "null" }] }),
+            }
+        }
+        AS3Validator::Conditional {
+            base,
+            condition,
+            then,
+            otherwise,
+        } => {
+            let mut schema = schema_of(base, definitions);
+            schema["if"] = schema_of(condition, definitions);
+            if let Some(then) = then {
+                schema["then"] = schema_of(then, definitions);
+            }
+            if let Some(otherwise) = otherwise {
+                schema["else"] = schema_of(otherwise, definitions);
+            }
+            schema
+        }
+        AS3Validator::TaggedUnion {
+            discriminator,
+            variants,
+        } => json!({
+            "type": "object",
+            "required": [discriminator],
+            "oneOf": variants
+                .values()
+                .map(|variant| schema_of(variant, definitions))
+                .collect::<Vec<_>>(),
+            "description": format!("TaggedUnion on `{discriminator}`"),
+        }),
+        // Documentation keywords, the accepted values being those of the inner validator
+        AS3Validator::Annotated { inner, annotations } => {
+            let mut schema = schema_of(inner, definitions);
+            if let Some(title) = &annotations.title {
+                schema["title"] = json!(title);
+            }
+            if let Some(description) = &annotations.description {
+                schema["description"] = json!(description);
+            }
+            if !annotations.examples.is_empty() {
+                schema["examples"] = json!(annotations.examples);
+            }
+            if annotations.deprecated.is_some() {
+                schema["deprecated"] = json!(true);
+            }
+            schema
+        }
+        AS3Validator::Ref(reference) => {
+            let name = reference.name().to_string();
+            if !definitions.contains_key(&name) {
+                // Inserted first, as the definition can refer to itself
+                definitions.insert(name.clone(), Value::Null);
+                let schema = match reference.target() {
+                    Some(target) => schema_of(&target, definitions),
+                    None => json!({}),
+                };
+                definitions.insert(name.clone(), schema);
+            }
+            json!({ "$ref": format!("#/definitions/{name}") })
+        }
+        AS3Validator::Literal { .. } => {
+            let values = validator.enumeration().unwrap_or_default();
+            let mut schema = json!({
+                "type": "string",
+                "enum": values,
+                "description": "String",
+            });
+            if let [value] = values.as_slice() {
+                schema["default"] = json!(value);
+            }
+            schema
+        }
+        AS3Validator::DateTime { .. } => json!({
+            "type": "string",
+            "format": "date-time",
+            "description": "DateTime (RFC 3339)",
+        }),
+        AS3Validator::Time { .. } => json!({
+            "type": "string",
+            "pattern": r"^\d{2}:\d{2}(:\d{2})?$",
+            "description": "Time (HH:MM[:SS])",
+        }),
+        AS3Validator::Duration { syntax, .. } => json!({
+            "type": "string",
+            "description": match syntax {
+                DurationSyntax::Iso8601 => "Duration (ISO 8601, e.g. PT30M)",
+                DurationSyntax::Humantime => "Duration (e.g. 90s, 2h 30min)",
+                DurationSyntax::Any => "Duration (ISO 8601 such as PT30M, or 90s, 2h 30min)",
+            },
+        }),
+        AS3Validator::Bytes { encoding, .. } => json!({
+            "type": "string",
+            "contentEncoding": match encoding {
+                BytesEncoding::Base64 => "base64",
+                BytesEncoding::Hex => "base16",
+            },
+            "description": format!("Bytes ({encoding})"),
+        }),
+    }
+}
@@ -1,18 +1,69 @@
 use serde::{Deserialize, Serialize};
-use std::collections::HashMap;
+use std::{cmp::Ordering, collections::HashMap};
 
+pub mod deprecation;
 pub mod error;
+pub mod fetch;
+pub mod inline_schema;
+pub mod lint;
+pub mod messages;
+pub mod metrics;
+pub mod transcript;
 pub mod validator;
+
+// Tooling around the validation, left out of minimal builds
+#[cfg(feature = "tools")]
+pub mod anonymize;
+#[cfg(feature = "tools")]
+pub mod contract;
+#[cfg(feature = "tools")]
+pub mod daemon;
+#[cfg(feature = "tools")]
+pub mod diagnostic;
+#[cfg(all(feature = "tools", not(target_os = "wasi")))]
+pub mod digest;
+#[cfg(feature = "tools")]
+pub mod form;
+#[cfg(all(feature = "tools", not(target_os = "wasi")))]
+pub mod history;
+#[cfg(feature = "tools")]
+pub mod impact;
+#[cfg(feature = "tools")]
+pub mod json_schema;
+#[cfg(feature = "tools")]
+pub mod notify;
+#[cfg(feature = "tools")]
+pub mod pact;
+#[cfg(feature = "tools")]
+pub mod report;
+#[cfg(feature = "tools")]
+pub mod sampling;
+#[cfg(feature = "tools")]
+pub mod sarif;
+#[cfg(feature = "tools")]
+pub mod spans;
+#[cfg(feature = "tools")]
+pub mod stats;
 use error::*;
 
 use validator::AS3Validator;
 
-#[derive(Debug, PartialEq, Clone, Serialize, Deserialize)]
+/// Data is totally ordered so that it can be sorted, deduplicated and used as a key:
+///
+/// - Values of different kinds are ordered `Null`, `Boolean`, numbers, `String`, `List`,
+///   `Object`.
+/// - Numbers compare by value whatever their variant. Equal values of different variants are
+///   ordered `Integer`, `UInteger`, `Decimal`, so that `1` and `1.0` stay distinct.
+/// - Decimals use [`f64::total_cmp`]: `-0.0` is below `0.0` and `NaN` equals itself.
+/// - Objects compare their entries sorted by key.
+#[derive(Debug, Clone, Serialize, Deserialize)]
 pub enum AS3Data {
     Object(HashMap<String, Box<AS3Data>>),
     String(String),
     Boolean(bool),
     Integer(i64),
+    /// Integer above `i64::MAX`
+    UInteger(u64),
     Decimal(f64),
     List(Vec<AS3Data>),
     Null,
@@ -34,6 +85,8 @@ impl From<&serde_json::Value> for AS3Data {
             serde_json::Value::Number(inner) => {
                 if let Some(number) = inner.as_i64() {
                     AS3Data::Integer(number)
+                } else if let Some(number) = inner.as_u64() {
+                    AS3Data::UInteger(number)
                 } else {
                     AS3Data::Decimal(inner.as_f64().unwrap())
                 }
@@ -44,6 +97,237 @@ impl From<&serde_json::Value> for AS3Data {
     }
 }
 
+impl From<&AS3Data> for serde_json::Value {
+    fn from(data: &AS3Data) -> serde_json::Value {
+        match data {
+            AS3Data::Object(values) => serde_json::Value::Object(
+                values
+                    .iter()
+                    .map(|(key, value)| (key.clone(), value.as_ref().into()))
+                    .collect(),
+            ),
+            AS3Data::List(items) => {
+                serde_json::Value::Array(items.iter().map(|item| item.into()).collect())
+            }
+            AS3Data::String(string) => serde_json::Value::String(string.clone()),
+            AS3Data::Boolean(boolean) => serde_json::Value::Bool(*boolean),
+            AS3Data::Integer(number) => (*number).into(),
+            AS3Data::UInteger(number) => (*number).into(),
+            // JSON has no NaN nor infinities
+            AS3Data::Decimal(number) => serde_json::Number::from_f64(*number)
+                .map_or(serde_json::Value::Null, serde_json::Value::Number),
+            AS3Data::Null => serde_json::Value::Null,
+        }
+    }
+}
+
+/// Limits applied when displaying data, so that error messages stay readable
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct Truncation {
+    /// Characters shown of a String
+    pub max_chars: usize,
+    /// Items shown of a List, and entries of an Object
+    pub max_items: usize,
+}
+
+impl Default for Truncation {
+    fn default() -> Self {
+        Truncation {
+            max_chars: 80,
+            max_items: 10,
+        }
+    }
+}
+
+impl AS3Data {
+    /// JSON-like rendering over several lines, nested values being indented by `indent` spaces
+    pub fn to_pretty_string(&self, indent: usize) -> String {
+        self.to_pretty_string_with(indent, &Truncation::default())
+    }
+
+    pub fn to_pretty_string_with(&self, indent: usize, truncation: &Truncation) -> String {
+        let mut output = String::new();
+        self.write(&mut output, Some(indent), 0, truncation);
+        output
+    }
+
+    /// Writes the data on one line when `indent` is `None`. Object keys are sorted
+    fn write(
+        &self,
+        output: &mut String,
+        indent: Option<usize>,
+        level: usize,
+        truncation: &Truncation,
+    ) {
+        let items: Vec<(Option<&String>, &AS3Data)> = match self {
+            AS3Data::Null => return output.push_str("null"),
+            AS3Data::Boolean(boolean) => return output.push_str(&boolean.to_string()),
+            AS3Data::Integer(number) => return output.push_str(&number.to_string()),
+            AS3Data::UInteger(number) => return output.push_str(&number.to_string()),
+            AS3Data::Decimal(number) => return output.push_str(&format!("{number:?}")),
+            AS3Data::String(string) => {
+                let mut shown: String = string.chars().take(truncation.max_chars).collect();
+                if shown.len() < string.len() {
+                    shown.push('…');
+                }
+                return output.push_str(&serde_json::to_string(&shown).unwrap());
+            }
+            AS3Data::List(items) => items.iter().map(|item| (None, item)).collect(),
+            AS3Data::Object(values) => AS3Data::sorted_entries(values)
+                .into_iter()
+                .map(|(key, value)| (Some(key), value))
+                .collect(),
+        };
+        let (open, close) = match self {
+            AS3Data::List(_) => ('[', ']'),
+            _ => ('{', '}'),
+        };
+
+        output.push(open);
+        if items.is_empty() {
+            output.push(close);
+            return;
+        }
+        let newline = |output: &mut String, level: usize| {
+            if let Some(indent) = indent {
+                output.push('\n');
+                output.push_str(&" ".repeat(indent * level));
+            }
+        };
+        for (index, (key, value)) in items.iter().take(truncation.max_items).enumerate() {
+            if index > 0 {
+                output.push(',');
+                if indent.is_none() {
+                    output.push(' ');
+                }
+            }
+            newline(output, level + 1);
+            if let Some(key) = key {
+                output.push_str(&serde_json::to_string(key).unwrap());
+                output.push_str(": ");
+            }
+            value.write(output, indent, level + 1, truncation);
+        }
+        if items.len() > truncation.max_items {
+            output.push(',');
+            if indent.is_none() {
+                output.push(' ');
+            }
+            newline(output, level + 1);
+            output.push_str(&format!("… {} more", items.len() - truncation.max_items));
+        }
+        newline(output, level);
+        output.push(close);
+    }
+
+    /// Rank of the kind of value, numbers sharing the same rank
+    fn kind_rank(&self) -> u8 {
+        match self {
+            AS3Data::Null => 0,
+            AS3Data::Boolean(_) => 1,
+            AS3Data::Integer(_) | AS3Data::UInteger(_) | AS3Data::Decimal(_) => 2,
+            AS3Data::String(_) => 3,
+            AS3Data::List(_) => 4,
+            AS3Data::Object(_) => 5,
+        }
+    }
+
+    fn number_rank(&self) -> u8 {
+        match self {
+            AS3Data::Integer(_) => 0,
+            AS3Data::UInteger(_) => 1,
+            _ => 2,
+        }
+    }
+
+    fn sorted_entries(values: &HashMap<String, Box<AS3Data>>) -> Vec<(&String, &AS3Data)> {
+        let mut entries: Vec<_> = values
+            .iter()
+            .map(|(key, value)| (key, value.as_ref()))
+            .collect();
+        entries.sort_by_key(|(key, _)| *key);
+        entries
+    }
+}
+
+impl AS3Data {
+    /// Compares two numbers by value whatever their variant, `None` if either isn't a number
+    pub fn cmp_numbers(&self, other: &AS3Data) -> Option<Ordering> {
+        Some(match (self, other) {
+            (AS3Data::Integer(a), AS3Data::Integer(b)) => a.cmp(b),
+            (AS3Data::UInteger(a), AS3Data::UInteger(b)) => a.cmp(b),
+            (AS3Data::Decimal(a), AS3Data::Decimal(b)) => a.total_cmp(b),
+            (AS3Data::Integer(a), AS3Data::UInteger(b)) => i128::from(*a).cmp(&i128::from(*b)),
+            (AS3Data::UInteger(a), AS3Data::Integer(b)) => i128::from(*a).cmp(&i128::from(*b)),
+            (AS3Data::Integer(a), AS3Data::Decimal(b)) => (*a as f64).total_cmp(b),
+            (AS3Data::UInteger(a), AS3Data::Decimal(b)) => (*a as f64).total_cmp(b),
+            (AS3Data::Decimal(a), AS3Data::Integer(b)) => a.total_cmp(&(*b as f64)),
+            (AS3Data::Decimal(a), AS3Data::UInteger(b)) => a.total_cmp(&(*b as f64)),
+            _ => return None,
+        })
+    }
+}
+
+/// Compact JSON-like rendering, truncated with the default [`Truncation`]. The alternate flag
+/// (`{:#}`) renders it over several lines
+impl std::fmt::Display for AS3Data {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        let mut output = String::new();
+        let indent = if f.alternate() { Some(2) } else { None };
+        self.write(&mut output, indent, 0, &Truncation::default());
+        write!(f, "{output}")
+    }
+}
+
+impl Ord for AS3Data {
+    fn cmp(&self, other: &Self) -> Ordering {
+        match (self, other) {
+            (AS3Data::Boolean(a), AS3Data::Boolean(b)) => a.cmp(b),
+            (AS3Data::String(a), AS3Data::String(b)) => a.cmp(b),
+            (AS3Data::List(a), AS3Data::List(b)) => a.cmp(b),
+            (AS3Data::Object(a), AS3Data::Object(b)) => {
+                AS3Data::sorted_entries(a).cmp(&AS3Data::sorted_entries(b))
+            }
+            (a, b) => match a.cmp_numbers(b) {
+                // Equal numbers of different variants are ordered by variant
+                Some(ordering) => ordering.then_with(|| a.number_rank().cmp(&b.number_rank())),
+                None => a.kind_rank().cmp(&b.kind_rank()),
+            },
+        }
+    }
+}
+
+impl PartialOrd for AS3Data {
+    fn partial_cmp(&self, other: &Self) -> Option<Ordering> {
+        Some(self.cmp(other))
+    }
+}
+
+impl PartialEq for AS3Data {
+    fn eq(&self, other: &Self) -> bool {
+        self.cmp(other) == Ordering::Equal
+    }
+}
+
+impl Eq for AS3Data {}
+
+impl std::hash::Hash for AS3Data {
+    fn hash<H: std::hash::Hasher>(&self, state: &mut H) {
+        std::mem::discriminant(self).hash(state);
+        match self {
+            AS3Data::Object(values) => AS3Data::sorted_entries(values).hash(state),
+            AS3Data::String(string) => string.hash(state),
+            AS3Data::Boolean(boolean) => boolean.hash(state),
+            AS3Data::Integer(number) => number.hash(state),
+            AS3Data::UInteger(number) => number.hash(state),
+            AS3Data::Decimal(number) => number.to_bits().hash(state),
+            AS3Data::List(items) => items.hash(state),
+            AS3Data::Null => {}
+        }
+    }
+}
+
+#[allow(clippy::result_large_err)]
 pub fn verify(
     data: String,
     validator_config: String,
@@ -0,0 +1,219 @@
+//! Checks of a definition on its own, before any data is validated with it. Every error that
+//! [`AS3Validator::from_with_options`] would stop at is reported, not only the first one, along
+//! with warnings about what the definition accepts without saying so, such as a `?` on a type
+//! that already accepts `null`.
+
+use crate::{
+    deprecation::deprecated_keywords,
+    error::{AS3DefinitionError, JsonPath, PathSegment},
+    validator::{AS3Validator, DefinitionOptions, Severity},
+};
+use serde::Serialize;
+use std::collections::HashSet;
+
+/// Definition errors reported at most, each being found by reading the definition again
+const MAX_ERRORS: usize = 100;
+
+/// Problem found in a definition
+#[derive(Debug, Clone, PartialEq, Serialize)]
+pub struct Lint {
+    /// Definition, or keyword, the problem is in, such as `Root -> name -> +regex`
+    pub path: JsonPath,
+    /// `Error` when the definition can't be used as is
+    pub severity: Severity,
+    pub message: String,
+}
+
+impl std::fmt::Display for Lint {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "{} in [{}]", self.message, self.path)
+    }
+}
+
+/// Problems of `definition`, errors first. After each error, the definition is read again with
+/// the faulty type replaced by `Any`, so that the errors of the other types are found as well
+pub fn lint(definition: &serde_yaml::Value, options: &DefinitionOptions) -> Vec<Lint> {
+    let mut lints = Vec::new();
+    let original = definition;
+    let mut definition = definition.clone();
+    let mut validator = None;
+    while lints.len() < MAX_ERRORS {
+        let error = match AS3Validator::from_with_options(&definition, options) {
+            Ok(built) => {
+                validator = Some(built);
+                break;
+            }
+            Err(error) => error,
+        };
+        let keyword = match &error.1 {
+            AS3DefinitionError::UnknownKeyword { keyword, .. }
+            | AS3DefinitionError::InvalidValue { keyword, .. }
+            | AS3DefinitionError::InvalidRegex { keyword, .. } => Some(keyword.as_str()),
+            _ => None,
+        };
+        lints.push(Lint {
+            path: located(&definition, &error.0, keyword),
+            severity: Severity::Error,
+            message: error.1.to_string(),
+        });
+        if !replace_with_any(&mut definition, &error.0) {
+            break;
+        }
+    }
+
+    for deprecated in deprecated_keywords(original) {
+        lints.push(Lint {
+            path: located(original, &deprecated.path, Some(&deprecated.keyword)),
+            severity: Severity::Warning,
+            message: format!(
+                "`{}` is deprecated, use `{}` instead",
+                deprecated.keyword, deprecated.replacement
+            ),
+        });
+    }
+    if let Some(validator) = validator {
+        let mut walk = Walk {
+            lints: &mut lints,
+            expanded: HashSet::new(),
+        };
+        walk.validator(&validator, JsonPath::root("Root"));
+    }
+    lints
+}
+
+/// `path`, followed by `keyword` when the definition at `path` has it
+fn located(definition: &serde_yaml::Value, path: &JsonPath, keyword: Option<&str>) -> JsonPath {
+    let mut path = path.clone();
+    if let Some(keyword) = keyword {
+        if node(definition, &path).is_some_and(|node| node.get(keyword).is_some()) {
+            path.0.push(PathSegment::Key(keyword.to_string()));
+        }
+    }
+    path
+}
+
+fn node<'d>(definition: &'d serde_yaml::Value, path: &JsonPath) -> Option<&'d serde_yaml::Value> {
+    path.0
+        .iter()
+        .try_fold(definition, |node, segment| match segment {
+            PathSegment::Key(key) => node.get(key.as_str()),
+            PathSegment::Index(index) => node.get(index),
+        })
+}
+
+/// Replaces the type at `path`, or the closest type containing it, by `Any`. `false` when
+/// there is nothing left to replace
+fn replace_with_any(definition: &mut serde_yaml::Value, path: &JsonPath) -> bool {
+    let found = (1..=path.0.len())
+        .rev()
+        .map(|length| JsonPath(path.0[..length].to_vec()))
+        .find(|path| node(definition, path).is_some());
+    let Some(found) = found else {
+        // Only the whole definition, which can't be replaced
+        return false;
+    };
+    let mut node = definition;
+    for segment in &found.0 {
+        node = match segment {
+            PathSegment::Key(key) => node.get_mut(key.as_str()).unwrap(),
+            PathSegment::Index(index) => node.get_mut(index).unwrap(),
+        };
+    }
+    let any = serde_yaml::Value::String("Any".to_string());
+    if *node == any {
+        return false;
+    }
+    *node = any;
+    true
+}
+
+/// Walk of a validator reporting its warnings
+struct Walk<'l> {
+    lints: &'l mut Vec<Lint>,
+    /// `+defs` already walked, as definitions can refer to themselves
+    expanded: HashSet<String>,
+}
+
+impl Walk<'_> {
+    fn validator(&mut self, validator: &AS3Validator, path: JsonPath) {
+        match validator {
+            AS3Validator::Nullable(inner) => {
+                if accepts_null(inner) {
+                    self.lints.push(Lint {
+                        path: path.clone(),
+                        severity: Severity::Warning,
+                        message: format!(
+                            "`{}?` is the same as `{}`, which already accepts null",
+                            inner.type_name(),
+                            inner.type_name()
+                        ),
+                    });
+                }
+                self.validator(inner, path);
+            }
+            AS3Validator::Annotated { inner, .. } => self.validator(inner, path),
+            AS3Validator::Conditional {
+                base,
+                condition,
+                then,
+                otherwise,
+            } => {
+                self.validator(base, path.clone());
+                self.validator(condition, path.with_key("+if"));
+                if let Some(then) = then {
+                    self.validator(then, path.with_key("+then"));
+                }
+                if let Some(otherwise) = otherwise {
+                    self.validator(otherwise, path.with_key("+else"));
+                }
+            }
+            AS3Validator::Ref(reference) => {
+                if let Some(target) = reference.target() {
+                    if self.expanded.insert(reference.name().to_string()) {
+                        self.validator(&target, JsonPath::root("+defs").with_key(reference.name()));
+                    }
+                }
+            }
+            AS3Validator::Object {
+                fields,
+                pattern_keys,
+                order,
+                ..
+            } => {
+                for key in order {
+                    self.validator(&fields[key], path.with_key(key));
+                }
+                for (pattern, validator) in pattern_keys.iter().flat_map(|keys| keys.iter()) {
+                    self.validator(validator, path.with_key("+patternKeys").with_key(pattern));
+                }
+            }
+            AS3Validator::List(items) => self.validator(items, path.with_key("+ValueType")),
+            AS3Validator::Map {
+                key_type,
+                value_type,
+                ..
+            } => {
+                self.validator(key_type, path.with_key("+KeyType"));
+                self.validator(value_type, path.with_key("+ValueType"));
+            }
+            AS3Validator::TaggedUnion { variants, .. } => {
+                for (tag, variant) in variants {
+                    self.validator(variant, path.with_key("+variants").with_key(tag));
+                }
+            }
+            _ => {}
+        }
+    }
+}
+
+/// Whether `validator` accepts `null` without being made nullable
+fn accepts_null(validator: &AS3Validator) -> bool {
+    match validator {
+        AS3Validator::Null | AS3Validator::Any | AS3Validator::Nullable(_) => true,
+        AS3Validator::Annotated { inner, .. } => accepts_null(inner),
+        AS3Validator::Ref(reference) => reference
+            .target()
+            .is_some_and(|target| accepts_null(&target)),
+        _ => false,
+    }
+}
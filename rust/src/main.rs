@@ -1,77 +1,1787 @@
-use clap::{Arg, Parser};
-use std::{fs, path::PathBuf};
+use clap::{Parser, Subcommand};
+use std::{
+    collections::HashSet,
+    num::NonZeroUsize,
+    path::{Path, PathBuf},
+    sync::{Arc, Mutex},
+    time::Instant,
+};
 
-use as3::{validator::AS3Validator, AS3Data};
+use as3::{
+    anonymize::anonymize,
+    contract::ContractValidator,
+    daemon::{documents_in, files_in},
+    deprecation::{deprecated_keywords, fix_deprecations},
+    diagnostic::{self, Format},
+    error::{AS3ValidationError, As3JsonPath, JsonPath},
+    fetch::HttpFetcher,
+    form::{to_form, FormStyle},
+    impact::impact,
+    inline_schema::definition_from_comments,
+    json_schema::to_editor_schema,
+    lint::lint,
+    notify::{self, FailureSummary},
+    pact::to_pact_rules,
+    report::{CompactFormatter, ErrorFormatter, JsonFormatter, ValidationReport, YamlFormatter},
+    sampling::{random_seed, Sample},
+    sarif::to_sarif,
+    spans::SourceMap,
+    stats::CorpusStats,
+    transcript::TranscriptValidator,
+    validator::{
+        AS3Validator, DefinitionOptions, PathGlob, Severity, Tracer, ValidationOptions,
+        ValidationOutcome,
+    },
+    AS3Data,
+};
+#[cfg(not(target_os = "wasi"))]
+use as3::{daemon::DaemonConfig, digest, history::History};
 
 #[derive(Parser, Debug)]
 #[clap(author, version, about, long_about = None,propagate_version = true)]
+#[clap(
+    after_help = "Exit codes: 0 when valid, 1 when the check fails, 2 for an invalid definition, 3 when a file can't be read, parsed or written, 4 for wrong arguments"
+)]
+#[command(args_conflicts_with_subcommands = true, subcommand_negates_reqs = true)]
 struct Args {
-    #[clap(long, help = "File with definition")]
+    #[command(subcommand)]
+    command: Option<Command>,
+    #[clap(
+        long,
+        help = "File with definition. When missing, the definition is read from the `# as3:` comments of the input"
+    )]
     // #[arg(value_parser = clap::value_parser!(PathBuf))]
     #[arg(value_parser = check_file_path)]
-    definition: PathBuf,
+    definition: Option<PathBuf>,
     #[clap(long, help = "File with the data to verify")]
+    #[arg(value_parser = check_file_path, required_unless_present = "input_dir")]
+    input: Option<PathBuf>,
+    #[clap(
+        long,
+        conflicts_with_all = ["input", "report_template", "error_format", "pretty", "output_format"],
+        requires = "definition",
+        help = "Folder with the data files to verify, searched recursively"
+    )]
+    #[arg(value_parser = check_dir_path)]
+    input_dir: Option<PathBuf>,
+    #[clap(
+        long,
+        value_delimiter = ',',
+        default_value = "json,yaml,yml",
+        help = "Extensions of the files verified in --input-dir"
+    )]
+    ext: Vec<String>,
+    #[cfg(not(target_os = "wasi"))]
+    #[clap(
+        long,
+        conflicts_with_all = ["report_template", "error_format", "output_format", "quiet"],
+        help = "Validate again whenever the input or the definition changes"
+    )]
+    watch: bool,
+    #[clap(
+        long,
+        help = "Format of the definition (json, yaml, toml, hcl, ini or properties), detected from its content by default"
+    )]
+    definition_format: Option<Format>,
+    #[clap(
+        long,
+        help = "Format of the input (json, yaml, toml, hcl, ini or properties), detected from its content by default"
+    )]
+    input_format: Option<Format>,
+    #[clap(
+        long,
+        help = "Ignore unknown `+` keywords in the definition instead of failing"
+    )]
+    lenient_schema: bool,
+    #[clap(
+        long,
+        global = true,
+        help = "Allow `+include` of definitions from http(s) URLs"
+    )]
+    allow_remote: bool,
+    #[clap(
+        long,
+        help = "Template used to print the outcome, with fields such as {{status}}, {{path}} and {{message}}"
+    )]
     #[arg(value_parser = check_file_path)]
-    input: PathBuf,
+    report_template: Option<PathBuf>,
+    #[clap(
+        long,
+        help = "Only validate this path and what it contains, such as `vehicles.list[*].year`, a `\\` escaping a dot of a key. Can be repeated"
+    )]
+    only: Vec<PathGlob>,
+    #[clap(
+        long,
+        help = "Skip this path and what it contains, such as `metadata.**`. Can be repeated"
+    )]
+    skip: Vec<PathGlob>,
+    #[clap(
+        long,
+        help = "Accept stringified numbers and booleans, and 1 or 0 as booleans"
+    )]
+    coerce: bool,
+    #[clap(long, help = "Report every error of the data instead of the first one")]
+    all_errors: bool,
+    #[clap(long, help = "Report the errors of the data up to this number")]
+    max_errors: Option<NonZeroUsize>,
+    #[clap(
+        long,
+        value_enum,
+        default_value_t = ErrorFormat::Text,
+        conflicts_with = "report_template",
+        help = "Format of the errors printed"
+    )]
+    error_format: ErrorFormat,
+    #[clap(
+        long,
+        conflicts_with = "error_format",
+        help = "Show each error with the lines of the input around the offending value"
+    )]
+    pretty: bool,
+    #[clap(
+        long,
+        value_enum,
+        default_value_t = OutputFormat::Human,
+        conflicts_with_all = ["report_template", "error_format", "pretty"],
+        help = "Format of the outcome: human, or a json or yaml document for scripts"
+    )]
+    output_format: OutputFormat,
+    #[clap(
+        short,
+        long,
+        requires = "output_format",
+        help = "File to write the json or yaml outcome to, stdout when missing"
+    )]
+    output: Option<PathBuf>,
+    #[clap(
+        short,
+        long,
+        conflicts_with_all = ["verbose", "report_template", "error_format", "pretty"],
+        help = "Print nothing, the exit code telling whether the data is valid"
+    )]
+    quiet: bool,
+    #[clap(
+        short,
+        long,
+        action = clap::ArgAction::Count,
+        help = "Print a summary of the definition and the timings, and with -vv every path validated"
+    )]
+    verbose: u8,
+}
+
+/// How much the validation prints, from `-q` to `-vv`
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord)]
+enum Verbosity {
+    Quiet,
+    Normal,
+    /// Summary of the definition and timings
+    Verbose,
+    /// Paths of the values validated as well
+    Trace,
+}
+
+#[derive(Subcommand, Debug)]
+enum Command {
+    /// Report the deprecated keywords of a definition, or replace them
+    Fmt {
+        #[clap(long, help = "File with definition")]
+        #[arg(value_parser = check_file_path)]
+        definition: PathBuf,
+        #[clap(
+            long,
+            help = "Rewrite the definition with the replacements of its deprecated keywords"
+        )]
+        fix_deprecations: bool,
+    },
+    /// Check a definition without any data, reporting all its errors with their line
+    Lint {
+        #[clap(long, help = "File with definition")]
+        #[arg(value_parser = check_file_path)]
+        definition: PathBuf,
+        #[clap(
+            long,
+            help = "Format of the definition (json, yaml, toml, hcl, ini or properties), detected from its content by default"
+        )]
+        definition_format: Option<Format>,
+    },
+    /// Generate a JSON Schema for editor autocomplete (YAML language server, VS Code)
+    EditorSchema {
+        #[clap(long, help = "File with definition")]
+        #[arg(value_parser = check_file_path)]
+        definition: PathBuf,
+        #[clap(
+            short,
+            long,
+            help = "File to write the JSON Schema to, stdout when missing"
+        )]
+        output: Option<PathBuf>,
+        #[clap(
+            long,
+            help = "Ignore unknown `+` keywords in the definition instead of failing"
+        )]
+        lenient_schema: bool,
+    },
+    /// Generate the Pact matching rules of the values accepted by a root of a definition
+    PactRules {
+        #[clap(long, help = "File with definition")]
+        #[arg(value_parser = check_file_path)]
+        definition: PathBuf,
+        #[clap(long, default_value = "Root", help = "Root to generate the rules of")]
+        root: String,
+        #[clap(
+            short,
+            long,
+            help = "File to write the matching rules to, stdout when missing"
+        )]
+        output: Option<PathBuf>,
+        #[clap(
+            long,
+            help = "Ignore unknown `+` keywords in the definition instead of failing"
+        )]
+        lenient_schema: bool,
+    },
+    /// Compare how two versions of a definition judge a corpus of JSON or YAML documents
+    Impact {
+        #[clap(long, help = "Current definition")]
+        #[arg(value_parser = check_file_path)]
+        old: PathBuf,
+        #[clap(long, help = "Candidate definition")]
+        #[arg(value_parser = check_file_path)]
+        new: PathBuf,
+        #[clap(long, help = "Folder with the documents, searched recursively")]
+        corpus: PathBuf,
+        #[clap(
+            long,
+            help = "Ignore unknown `+` keywords in the definitions instead of failing"
+        )]
+        lenient_schema: bool,
+        #[clap(
+            long,
+            help = "Webhook (Slack-compatible or generic) receiving a summary of the documents rejected by the new definition"
+        )]
+        notify_webhook: Option<String>,
+        #[clap(
+            long,
+            default_value_t = 0,
+            help = "Offending values added to the webhook summary, picked at random with the `+anonymize` fields redacted"
+        )]
+        sample_values: usize,
+    },
+    #[cfg(not(target_os = "wasi"))]
+    /// Run the validation jobs of a TOML configuration on their schedule
+    Daemon {
+        #[clap(long, help = "TOML file with the jobs")]
+        #[arg(value_parser = check_file_path)]
+        config: PathBuf,
+        #[clap(long, help = "Run every job a single time and exit")]
+        once: bool,
+    },
+    /// Export per-field data-quality metrics of a corpus for dashboards
+    Stats {
+        #[clap(long, help = "File with definition")]
+        #[arg(value_parser = check_file_path)]
+        definition: PathBuf,
+        #[clap(long, help = "Folder with the documents, searched recursively")]
+        corpus: PathBuf,
+        #[clap(long, value_enum, default_value_t = StatsFormat::Prometheus)]
+        format: StatsFormat,
+        #[clap(
+            short,
+            long,
+            help = "File to write the metrics to, stdout when missing"
+        )]
+        output: Option<PathBuf>,
+        #[clap(
+            long,
+            help = "Only check a random share of the documents, such as `1%`, or a number of them, such as `1000`"
+        )]
+        sample: Option<Sample>,
+        #[clap(
+            long,
+            help = "Seed picking the sample, to pick the same documents again"
+        )]
+        seed: Option<u64>,
+        #[clap(
+            long,
+            help = "Ignore unknown `+` keywords in the definition instead of failing"
+        )]
+        lenient_schema: bool,
+    },
+    /// Replace the fields annotated with `+anonymize`, keeping the document valid
+    Anonymize {
+        #[clap(long, help = "File with definition")]
+        #[arg(value_parser = check_file_path)]
+        definition: PathBuf,
+        #[clap(long, help = "JSON or YAML document")]
+        #[arg(value_parser = check_file_path)]
+        input: PathBuf,
+        #[clap(
+            short,
+            long,
+            help = "File to write the anonymized JSON to, stdout when missing"
+        )]
+        output: Option<PathBuf>,
+        #[clap(
+            long,
+            help = "Secret mixed into the replacements, keep it to anonymize other documents consistently"
+        )]
+        salt: String,
+        #[clap(
+            long,
+            help = "Ignore unknown `+` keywords in the definition instead of failing"
+        )]
+        lenient_schema: bool,
+    },
+    /// Validate the records of a JSON lines file, setting the invalid ones aside
+    Records {
+        #[clap(long, help = "File with definition of a record")]
+        #[arg(value_parser = check_file_path)]
+        definition: PathBuf,
+        #[clap(long, help = "JSON lines file, with a record per line")]
+        #[arg(value_parser = check_file_path)]
+        input: PathBuf,
+        #[clap(
+            short,
+            long,
+            help = "JSON lines file to write the valid records to, stdout when missing"
+        )]
+        output: Option<PathBuf>,
+        #[clap(
+            long,
+            help = "JSON lines file to write the invalid records to, with their error"
+        )]
+        quarantine: Option<PathBuf>,
+        #[clap(
+            long,
+            help = "Accept stringified numbers and booleans, and 1 or 0 as booleans"
+        )]
+        coerce: bool,
+        #[clap(
+            long,
+            help = "Only check a random share of the records, such as `1%`, or a number of them, such as `1000`"
+        )]
+        sample: Option<Sample>,
+        #[clap(long, help = "Seed picking the sample, to pick the same records again")]
+        seed: Option<u64>,
+        #[clap(
+            long,
+            help = "Ignore unknown `+` keywords in the definition instead of failing"
+        )]
+        lenient_schema: bool,
+    },
+    /// Validate recorded exchanges against the roots of a definition, such as `Request` and `Response`
+    Transcript {
+        #[clap(long, help = "File with a definition declaring several roots")]
+        #[arg(value_parser = check_file_path)]
+        definition: PathBuf,
+        #[clap(long, help = "List of exchanges, with a key per root")]
+        #[arg(value_parser = check_file_path)]
+        input: PathBuf,
+        #[clap(
+            long,
+            help = "Ignore unknown `+` keywords in the definition instead of failing"
+        )]
+        lenient_schema: bool,
+    },
+    /// Validate the bodies of a HAR capture against the `+endpoints` of a definition
+    Contract {
+        #[clap(long, help = "File with a definition declaring `+endpoints`")]
+        #[arg(value_parser = check_file_path)]
+        definition: PathBuf,
+        #[clap(long, help = "HAR capture of the traffic to check")]
+        #[arg(value_parser = check_file_path)]
+        har: PathBuf,
+        #[clap(
+            long,
+            help = "Ignore unknown `+` keywords in the definition instead of failing"
+        )]
+        lenient_schema: bool,
+    },
+    /// Artifacts generated from a definition
+    Generate {
+        #[command(subcommand)]
+        generate: GenerateCommand,
+    },
+    #[cfg(not(target_os = "wasi"))]
+    /// Reports built from the history recorded by the daemon
+    Report {
+        #[command(subcommand)]
+        report: ReportCommand,
+    },
+    #[cfg(not(target_os = "wasi"))]
+    /// Show the latest runs recorded by the daemon
+    History {
+        #[clap(
+            long,
+            help = "SQLite file set as `history` in the daemon configuration"
+        )]
+        #[arg(value_parser = check_file_path)]
+        db: PathBuf,
+        #[clap(long, help = "Only show the runs of this job")]
+        job: Option<String>,
+        #[clap(long, default_value_t = 20, help = "Number of runs to show")]
+        limit: usize,
+    },
+}
+
+#[derive(Subcommand, Debug)]
+enum GenerateCommand {
+    /// Schema and UI schema of an edit form (react-jsonschema-form or JSONForms)
+    Form {
+        #[clap(long, help = "File with definition")]
+        #[arg(value_parser = check_file_path)]
+        definition: PathBuf,
+        #[clap(long, default_value = "rjsf", help = "Form library: rjsf or jsonforms")]
+        style: FormStyle,
+        #[clap(short, long, help = "File to write the form to, stdout when missing")]
+        output: Option<PathBuf>,
+        #[clap(
+            long,
+            help = "Ignore unknown `+` keywords in the definition instead of failing"
+        )]
+        lenient_schema: bool,
+    },
+}
+
+#[cfg(not(target_os = "wasi"))]
+#[derive(Subcommand, Debug)]
+enum ReportCommand {
+    /// New failures, regressions and fixed documents of every job over a period
+    Digest {
+        #[clap(
+            long,
+            help = "SQLite file set as `history` in the daemon configuration"
+        )]
+        #[arg(value_parser = check_file_path)]
+        db: PathBuf,
+        #[clap(
+            long,
+            default_value = "24h",
+            help = "Length of the period, such as 24h or 7days"
+        )]
+        #[arg(value_parser = humantime::parse_duration)]
+        since: std::time::Duration,
+        #[clap(long, value_enum, default_value_t = DigestFormat::Markdown)]
+        format: DigestFormat,
+        #[clap(short, long, help = "File to write the digest to, stdout when missing")]
+        output: Option<PathBuf>,
+    },
+}
+
+#[cfg(not(target_os = "wasi"))]
+#[derive(clap::ValueEnum, Clone, Copy, Debug)]
+enum DigestFormat {
+    Markdown,
+    Html,
+}
+
+#[derive(clap::ValueEnum, Clone, Copy, Debug, PartialEq)]
+enum OutputFormat {
+    /// ✅ and ❌ lines
+    Human,
+    /// `{"valid": …, "errors": […]}`
+    Json,
+    /// Same document as json, in YAML
+    Yaml,
+}
+
+#[derive(clap::ValueEnum, Clone, Copy, Debug, PartialEq)]
+enum ErrorFormat {
+    /// One line per error
+    Text,
+    /// SARIF log, for code scanning UIs
+    Sarif,
+    /// Report of the first error, as JSON
+    Json,
+    /// Report of the first error, as YAML
+    Yaml,
+    /// Report of the first error, on one line of fields
+    Compact,
+}
+
+#[derive(clap::ValueEnum, Clone, Copy, Debug)]
+enum StatsFormat {
+    /// Prometheus textfile
+    Prometheus,
+    /// JSON sample of a time-series
+    Json,
+}
+
+fn check_dir_path(path: &str) -> Result<PathBuf, String> {
+    let path = std::path::Path::new(&path).to_path_buf();
+    match path.is_dir() {
+        true => Ok(path),
+        false => Err(format!("The specified path {path:?} is not a folder")),
+    }
 }
 
 fn check_file_path(path: &str) -> Result<PathBuf, String> {
     let path = std::path::Path::new(&path).to_path_buf();
-    match (path.exists(), path.is_file()) {
-        (true, true) => Ok(path),
-        (_, false) => Err(format!(
+    // Pipes such as `/dev/stdin` are accepted along with regular files
+    match (path.exists(), path.is_dir()) {
+        (true, false) => Ok(path),
+        (true, true) => Err(format!(
             "The specified path {path:?} is a folder and not a file"
         )),
         _ => Err(format!("The specified path {path:?} doesn't exists")),
     }
 }
 
-fn main() -> Result<(), String> {
-    let args = Args::parse();
+/// Exit code of a failed command, 0 being left for success. Scripts branch on them, so they
+/// don't change between releases
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum Exit {
+    /// The check failed, such as data not matching the definition
+    Invalid = 1,
+    /// The definition can't be parsed or isn't valid AS3
+    Definition = 2,
+    /// A file can't be read, parsed or written
+    Input = 3,
+    /// The arguments are wrong
+    Usage = 4,
+}
+
+/// Error of a command, printed before exiting with its code
+#[derive(Debug)]
+struct Failure {
+    exit: Exit,
+    message: String,
+}
+
+impl Failure {
+    fn invalid(message: String) -> Failure {
+        Failure {
+            exit: Exit::Invalid,
+            message,
+        }
+    }
+
+    fn definition(message: String) -> Failure {
+        Failure {
+            exit: Exit::Definition,
+            message,
+        }
+    }
+}
+
+/// Errors of the library and of the files, whose messages say which file failed
+impl From<String> for Failure {
+    fn from(message: String) -> Failure {
+        Failure {
+            exit: Exit::Input,
+            message,
+        }
+    }
+}
+
+fn main() {
+    let args = Args::try_parse().unwrap_or_else(|e| {
+        let _ = e.print();
+        std::process::exit(usage_exit(&e))
+    });
+    let quiet = args.quiet;
+    if let Err(failure) = run(args) {
+        // Printed as is rather than debug formatted, errors may span several lines. Empty
+        // when the outcome was already printed
+        if !quiet && !failure.message.is_empty() {
+            eprintln!("{}", failure.message);
+        }
+        std::process::exit(failure.exit as i32);
+    }
+}
+
+/// Exit code of arguments clap rejects, or of `--help` and `--version`. clap exits with 2 on
+/// wrong arguments, the code of an invalid definition here
+fn usage_exit(error: &clap::Error) -> i32 {
+    match error.use_stderr() {
+        true => Exit::Usage as i32,
+        false => 0,
+    }
+}
+
+fn run(args: Args) -> Result<(), Failure> {
+    match args.command {
+        Some(Command::Fmt {
+            definition,
+            fix_deprecations,
+        }) => fmt(&definition, fix_deprecations),
+        Some(Command::Lint {
+            definition,
+            definition_format,
+        }) => lint_definition(
+            &definition,
+            definition_format,
+            &definition_options(false, args.allow_remote),
+        ),
+        Some(Command::EditorSchema {
+            definition,
+            output,
+            lenient_schema,
+        }) => editor_schema(
+            &definition,
+            output.as_deref(),
+            &definition_options(lenient_schema, args.allow_remote),
+        ),
+        Some(Command::PactRules {
+            definition,
+            root,
+            output,
+            lenient_schema,
+        }) => pact_rules(
+            &definition,
+            &root,
+            output.as_deref(),
+            &definition_options(lenient_schema, args.allow_remote),
+        ),
+        Some(Command::Impact {
+            old,
+            new,
+            corpus,
+            lenient_schema,
+            notify_webhook,
+            sample_values,
+        }) => impact_analysis(
+            &old,
+            &new,
+            &corpus,
+            &definition_options(lenient_schema, args.allow_remote),
+            notify_webhook.as_deref(),
+            sample_values,
+        ),
+        #[cfg(not(target_os = "wasi"))]
+        Some(Command::Daemon { config, once }) => daemon(&config, once),
+        Some(Command::Stats {
+            definition,
+            corpus,
+            format,
+            output,
+            sample,
+            seed,
+            lenient_schema,
+        }) => stats(
+            &definition,
+            &corpus,
+            format,
+            output.as_deref(),
+            sampling(sample, seed),
+            &definition_options(lenient_schema, args.allow_remote),
+        ),
+        Some(Command::Anonymize {
+            definition,
+            input,
+            output,
+            salt,
+            lenient_schema,
+        }) => anonymize_document(
+            &definition,
+            &input,
+            output.as_deref(),
+            &salt,
+            &definition_options(lenient_schema, args.allow_remote),
+        ),
+        Some(Command::Records {
+            definition,
+            input,
+            output,
+            quarantine,
+            coerce,
+            sample,
+            seed,
+            lenient_schema,
+        }) => validate_records(
+            &definition,
+            &input,
+            output.as_deref(),
+            quarantine.as_deref(),
+            coerce,
+            sampling(sample, seed),
+            &definition_options(lenient_schema, args.allow_remote),
+        ),
+        Some(Command::Transcript {
+            definition,
+            input,
+            lenient_schema,
+        }) => validate_transcript(
+            &definition,
+            &input,
+            &definition_options(lenient_schema, args.allow_remote),
+        ),
+        Some(Command::Contract {
+            definition,
+            har,
+            lenient_schema,
+        }) => check_contract(
+            &definition,
+            &har,
+            &definition_options(lenient_schema, args.allow_remote),
+        ),
+        Some(Command::Generate {
+            generate:
+                GenerateCommand::Form {
+                    definition,
+                    style,
+                    output,
+                    lenient_schema,
+                },
+        }) => generate_form(
+            &definition,
+            style,
+            output.as_deref(),
+            &definition_options(lenient_schema, args.allow_remote),
+        ),
+        #[cfg(not(target_os = "wasi"))]
+        Some(Command::Report {
+            report:
+                ReportCommand::Digest {
+                    db,
+                    since,
+                    format,
+                    output,
+                },
+        }) => report_digest(&db, since, format, output.as_deref()),
+        #[cfg(not(target_os = "wasi"))]
+        Some(Command::History { db, job, limit }) => history(&db, job.as_deref(), limit),
+        None => {
+            let definition_options = definition_options(args.lenient_schema, args.allow_remote);
+            let options = ValidationOptions {
+                include: args.only,
+                exclude: args.skip,
+                coerce: args.coerce,
+                max_errors: args.max_errors,
+                tracer: None,
+            };
+            let verbosity = match (args.quiet, args.verbose) {
+                (true, _) => Verbosity::Quiet,
+                (false, 0) => Verbosity::Normal,
+                (false, 1) => Verbosity::Verbose,
+                (false, _) => Verbosity::Trace,
+            };
+            let all_errors = args.all_errors || args.max_errors.is_some();
+            let check = || match args.input_dir.as_deref() {
+                Some(folder) => validate_dir(
+                    // Required with `--input-dir`
+                    args.definition.as_deref().unwrap(),
+                    folder,
+                    &args.ext,
+                    args.definition_format,
+                    args.input_format,
+                    &definition_options,
+                    &options,
+                    all_errors,
+                    verbosity,
+                ),
+                None => validate(
+                    args.definition.as_deref(),
+                    // Required unless a subcommand is used
+                    args.input.as_deref().unwrap(),
+                    args.definition_format,
+                    args.input_format,
+                    &definition_options,
+                    args.report_template.as_deref(),
+                    args.error_format,
+                    args.pretty,
+                    args.output_format,
+                    args.output.as_deref(),
+                    &options,
+                    all_errors,
+                    verbosity,
+                ),
+            };
+            #[cfg(not(target_os = "wasi"))]
+            if args.watch {
+                let watched = [&args.definition, &args.input, &args.input_dir];
+                return watch(watched.into_iter().flatten(), check);
+            }
+            check()
+        }
+    }
+}
+
+/// Runs `check` now and again whenever one of the `paths` changes, until interrupted
+#[cfg(not(target_os = "wasi"))]
+fn watch<'a>(
+    paths: impl IntoIterator<Item = &'a PathBuf>,
+    check: impl Fn() -> Result<(), Failure>,
+) -> Result<(), Failure> {
+    use ::notify::{RecursiveMode, Watcher};
+    let (sender, receiver) = std::sync::mpsc::channel();
+    let mut watcher = ::notify::recommended_watcher(sender)
+        .map_err(|e| format!("error: Could not watch the files : {e}"))?;
+    let mut watched = Vec::new();
+    for path in paths {
+        let path = path
+            .canonicalize()
+            .map_err(|e| format!("error: Could not watch {path:?} : {e}"))?;
+        // Editors often save by replacing the file, the folder holding it is watched instead
+        let (folder, mode) = match path.is_dir() {
+            true => (path.as_path(), RecursiveMode::Recursive),
+            false => (path.parent().unwrap_or(&path), RecursiveMode::NonRecursive),
+        };
+        watcher
+            .watch(folder, mode)
+            .map_err(|e| format!("error: Could not watch {path:?} : {e}"))?;
+        watched.push(path);
+    }
+    loop {
+        println!("[{}]", chrono::Local::now().format("%H:%M:%S"));
+        match check() {
+            Err(failure) if !failure.message.is_empty() => eprintln!("{}", failure.message),
+            _ => {}
+        }
+        println!("Watching for changes, Ctrl+C to stop");
+        loop {
+            let event: ::notify::Event = match receiver.recv() {
+                Ok(Ok(event)) => event,
+                Ok(Err(e)) => return Err(format!("error: Could not watch the files : {e}").into()),
+                Err(_) => return Ok(()),
+            };
+            if changes(&event, &watched) {
+                break;
+            }
+        }
+        // A save is often several events, the file is read once they have settled
+        std::thread::sleep(std::time::Duration::from_millis(100));
+        drain(&receiver);
+    }
+}
+
+/// Whether `event` changes one of the `watched` files or folders, given canonicalized. Their
+/// folders are watched, so that events of the other files in them are received as well
+#[cfg(not(target_os = "wasi"))]
+fn changes(event: &::notify::Event, watched: &[PathBuf]) -> bool {
+    !event.kind.is_access()
+        && event
+            .paths
+            .iter()
+            .any(|changed| watched.iter().any(|path| changed.starts_with(path)))
+}
+
+/// Discards the events already received, returning how many there were
+#[cfg(not(target_os = "wasi"))]
+fn drain<T>(receiver: &std::sync::mpsc::Receiver<T>) -> usize {
+    receiver.try_iter().count()
+}
+
+/// Sample with its seed, a random one being printed so that the sample can be picked again
+fn sampling(sample: Option<Sample>, seed: Option<u64>) -> Option<(Sample, u64)> {
+    let sample = sample?;
+    let seed = seed.unwrap_or_else(|| {
+        let seed = random_seed();
+        eprintln!("Sampling with --seed {seed}");
+        seed
+    });
+    Some((sample, seed))
+}
+
+fn definition_options(lenient_schema: bool, allow_remote: bool) -> DefinitionOptions {
+    DefinitionOptions {
+        lenient: lenient_schema,
+        fetcher: match allow_remote {
+            true => Some(Arc::new(HttpFetcher)),
+            false => None,
+        },
+        ..Default::default()
+    }
+}
+
+fn read_definition(path: &Path, format: Option<Format>) -> Result<serde_yaml::Value, Failure> {
+    let source = std::fs::read_to_string(path)
+        .map_err(|e| format!("error: Could not read {path:?} : {e}"))?;
+    diagnostic::parse("definition file", path, &source, format).map_err(Failure::definition)
+}
+
+/// `path` is the file the definition was read from, which `+include` paths are relative to
+fn build_validator(
+    definition: &serde_yaml::Value,
+    path: &Path,
+    definition_options: &DefinitionOptions,
+    verbosity: Verbosity,
+) -> Result<AS3Validator, Failure> {
+    if verbosity > Verbosity::Quiet {
+        for deprecated in deprecated_keywords(definition) {
+            eprintln!("⚠️ {deprecated}");
+        }
+    }
+    let start = Instant::now();
+    let options = definition_options.located(path);
+    let validator = match AS3Validator::from_with_options(definition, &options) {
+        Ok(validator) => validator.optimize(),
+        Err(e) => {
+            return Err(Failure::definition(format!(
+                "error: Invalid definition : {e}"
+            )))
+        }
+    };
+    if verbosity >= Verbosity::Verbose {
+        let fields = match root_fields(&validator) {
+            Some(count) => format!(" with {count} fields"),
+            None => String::new(),
+        };
+        let defs = definition
+            .get("+defs")
+            .and_then(serde_yaml::Value::as_mapping)
+            .map_or(0, serde_yaml::Mapping::len);
+        eprintln!(
+            "Definition {}: {}{fields} at the root, {defs} `+defs`, built in {:.1?}",
+            path.display(),
+            validator.type_name(),
+            start.elapsed()
+        );
+    }
+    Ok(validator)
+}
+
+/// Number of fields of an Object at the root of `validator`
+fn root_fields(validator: &AS3Validator) -> Option<usize> {
+    match validator {
+        AS3Validator::Annotated { inner, .. } => root_fields(inner),
+        AS3Validator::Conditional { base, .. } => root_fields(base),
+        AS3Validator::Object { fields, .. } => Some(fields.len()),
+        _ => None,
+    }
+}
+
+/// Paths of the values validated, printed with `-vv`
+#[derive(Debug, Default)]
+struct VisitedPaths(Mutex<Vec<String>>);
+
+impl Tracer for VisitedPaths {
+    fn visit(&self, path: &JsonPath) {
+        self.0.lock().unwrap().push(path.to_string());
+    }
+}
+
+/// Outcome of `data`, with every error or only the first one. The paths validated and the
+/// time taken are printed as `verbosity` asks
+fn check_data(
+    validator: &AS3Validator,
+    data: &AS3Data,
+    options: &ValidationOptions,
+    all_errors: bool,
+    verbosity: Verbosity,
+) -> ValidationOutcome {
+    let visited = Arc::new(VisitedPaths::default());
+    let mut options = options.clone();
+    if verbosity == Verbosity::Trace {
+        options.tracer = Some(visited.clone());
+    }
+    let start = Instant::now();
+    let outcome = match all_errors {
+        true => validator.validate_with_warnings(data, &options),
+        false => ValidationOutcome {
+            errors: validator
+                .validate_with_options(data, &options)
+                .err()
+                .into_iter()
+                .collect(),
+            warnings: validator.warnings_with_options(data, &options),
+            truncated: false,
+        },
+    };
+    let elapsed = start.elapsed();
+    // Each path once, in the order of its first visit
+    let mut printed = HashSet::new();
+    for path in visited.0.lock().unwrap().iter() {
+        if printed.insert(path) {
+            eprintln!("  {path}");
+        }
+    }
+    if verbosity >= Verbosity::Verbose {
+        eprintln!("Validated in {elapsed:.1?}");
+    }
+    outcome
+}
+
+#[allow(clippy::too_many_arguments)]
+fn validate(
+    definition_path: Option<&Path>,
+    input: &Path,
+    definition_format: Option<Format>,
+    input_format: Option<Format>,
+    definition_options: &DefinitionOptions,
+    report_template: Option<&Path>,
+    error_format: ErrorFormat,
+    pretty: bool,
+    output_format: OutputFormat,
+    output: Option<&Path>,
+    options: &ValidationOptions,
+    all_errors: bool,
+    verbosity: Verbosity,
+) -> Result<(), Failure> {
+    let source = std::fs::read_to_string(input)
+        .map_err(|e| format!("error: Could not read {input:?} : {e}"))?;
+    let definition = match definition_path {
+        Some(definition_path) => read_definition(definition_path, definition_format)?,
+        // Self-validating config file, the definition lives in its comments
+        None => definition_from_comments(&source).ok_or_else(|| {
+            Failure::definition(format!(
+                "error: No `--definition` given and no `# as3:` comments found in {:?}",
+                input
+            ))
+        })?,
+    };
+    let input_format = input_format.unwrap_or_else(|| Format::detect(input, &source));
+    let start = Instant::now();
+    let data: serde_json::Value =
+        diagnostic::parse("Data file", input, &source, Some(input_format))?;
+    if verbosity >= Verbosity::Verbose {
+        eprintln!(
+            "Data {}: parsed in {:.1?}",
+            input.display(),
+            start.elapsed()
+        );
+    }
+
+    let validator = build_validator(
+        &definition,
+        definition_path.unwrap_or(input),
+        definition_options,
+        verbosity,
+    )?;
+    let data = AS3Data::from(&data);
+    let outcome = check_data(&validator, &data, options, all_errors, verbosity);
+    if output_format != OutputFormat::Human {
+        let document = match output_format {
+            OutputFormat::Yaml => serde_yaml::to_string(&outcome).unwrap(),
+            _ => serde_json::to_string_pretty(&outcome).unwrap() + "\n",
+        };
+        match output {
+            Some(output) => std::fs::write(output, document)
+                .map_err(|e| format!("error: Could not write {output:?} : {e}"))?,
+            None if verbosity == Verbosity::Quiet => {}
+            None => print!("{document}"),
+        }
+        if !outcome.is_valid() {
+            return Err(Failure::invalid(String::new()));
+        }
+        return Ok(());
+    }
+    let spans = SourceMap::new(&source, input_format);
+    if error_format == ErrorFormat::Sarif {
+        let uri = input.display().to_string();
+        let log = to_sarif(&uri, &outcome, &spans);
+        println!("{}", serde_json::to_string_pretty(&log).unwrap());
+        if !outcome.is_valid() {
+            return Err(Failure::invalid(String::new()));
+        }
+        return Ok(());
+    }
+    let ValidationOutcome {
+        mut errors,
+        warnings,
+        truncated,
+    } = outcome;
+    for warning in warnings.iter().filter(|_| verbosity > Verbosity::Quiet) {
+        eprintln!("⚠️ {warning}");
+    }
+    let formatter: Option<&dyn ErrorFormatter> = match error_format {
+        ErrorFormat::Text | ErrorFormat::Sarif => None,
+        ErrorFormat::Json => Some(&JsonFormatter),
+        ErrorFormat::Yaml => Some(&YamlFormatter),
+        ErrorFormat::Compact => Some(&CompactFormatter),
+    };
+    if report_template.is_some() || formatter.is_some() {
+        let report = ValidationReport::new(
+            input.display().to_string(),
+            definition_path.map(|path| path.display().to_string()),
+            // Reports describe the first error
+            &match errors.is_empty() {
+                true => Ok(()),
+                false => Err(errors.remove(0)),
+            },
+        );
+        let output = match (report_template, formatter) {
+            (Some(report_template), _) => {
+                let template = std::fs::read_to_string(report_template)
+                    .map_err(|e| format!("error: Could not read {report_template:?} : {e}"))?;
+                report.render(&template)?
+            }
+            (None, formatter) => formatter.map_or(String::new(), |f| f.format(&report)),
+        };
+        println!("{}", output.trim_end_matches('\n'));
+        if !report.valid {
+            return Err(Failure::invalid(String::new()));
+        }
+        return Ok(());
+    }
+    if errors.is_empty() {
+        if verbosity > Verbosity::Quiet {
+            println!("✅✅ The provided schema matches the data");
+        }
+        return Ok(());
+    }
+    if pretty {
+        let mut failures: Vec<String> = errors
+            .iter()
+            .map(|e| diagnostic::validation_failure(input, &source, &spans, e))
+            .collect();
+        if truncated {
+            failures.push(format!("… stopped after {} errors", errors.len()));
+        }
+        return Err(Failure::invalid(failures.join("\n\n")));
+    }
+    let mut lines = error_lines(input, &spans, &errors, "❌❌ ");
+    if truncated {
+        lines.push(format!("… stopped after {} errors", errors.len()));
+    }
+    Err(Failure::invalid(lines.join("\n")))
+}
+
+/// Errors of `input`, each on a line starting with `prefix` and, when known, the line and
+/// column of the value
+fn error_lines(
+    input: &Path,
+    spans: &SourceMap,
+    errors: &[As3JsonPath<AS3ValidationError>],
+    prefix: &str,
+) -> Vec<String> {
+    errors
+        .iter()
+        .map(|e| match spans.locate_error(e) {
+            Some(position) => format!("{prefix}{}:{position}: {e}", input.display()),
+            None => format!("{prefix}{e}"),
+        })
+        .collect()
+}
+
+/// Validates every file of `folder` with one of the `extensions`, printing the outcome of
+/// each file and the number of valid and invalid files
+#[allow(clippy::too_many_arguments)]
+fn validate_dir(
+    definition_path: &Path,
+    folder: &Path,
+    extensions: &[String],
+    definition_format: Option<Format>,
+    input_format: Option<Format>,
+    definition_options: &DefinitionOptions,
+    options: &ValidationOptions,
+    all_errors: bool,
+    verbosity: Verbosity,
+) -> Result<(), Failure> {
+    let validator = build_validator(
+        &read_definition(definition_path, definition_format)?,
+        definition_path,
+        definition_options,
+        verbosity,
+    )?;
+    let print = |line: String| {
+        if verbosity > Verbosity::Quiet {
+            println!("{line}");
+        }
+    };
+    let extensions: Vec<&str> = extensions
+        .iter()
+        .map(|e| e.trim_start_matches('.'))
+        .collect();
+    let (mut valid, mut invalid) = (0, 0);
+    for file in files_in(folder, &extensions)? {
+        let source = std::fs::read_to_string(&file)
+            .map_err(|e| format!("error: Could not read {file:?} : {e}"))?;
+        let format = input_format.unwrap_or_else(|| Format::detect(&file, &source));
+        let start = Instant::now();
+        let data: serde_json::Value =
+            match diagnostic::parse("Data file", &file, &source, Some(format)) {
+                Ok(data) => data,
+                Err(e) => {
+                    invalid += 1;
+                    print(format!("❌ {}\n{e}", file.display()));
+                    continue;
+                }
+            };
+        if verbosity >= Verbosity::Verbose {
+            eprintln!("Data {}: parsed in {:.1?}", file.display(), start.elapsed());
+        }
+        let data = AS3Data::from(&data);
+        let errors = check_data(&validator, &data, options, all_errors, verbosity).errors;
+        if errors.is_empty() {
+            valid += 1;
+            print(format!("✅ {}", file.display()));
+            continue;
+        }
+        invalid += 1;
+        let count = match errors.len() {
+            1 => "1 error".to_string(),
+            count => format!("{count} errors"),
+        };
+        print(format!("❌ {} ({count})", file.display()));
+        let spans = SourceMap::new(&source, format);
+        for line in error_lines(&file, &spans, &errors, "    ") {
+            print(line);
+        }
+    }
+    let summary = format!("{valid} valid and {invalid} invalid files");
+    match invalid {
+        0 => {
+            print(format!("✅✅ {summary}"));
+            Ok(())
+        }
+        _ => Err(Failure::invalid(format!("❌❌ {summary}"))),
+    }
+}
+
+fn fmt(definition: &Path, fix: bool) -> Result<(), Failure> {
+    let deprecated = deprecated_keywords(&read_definition(definition, None)?);
+    if fix {
+        let source = std::fs::read_to_string(definition)
+            .map_err(|e| format!("error: Could not read {definition:?} : {e}"))?;
+        let (fixed, count) = fix_deprecations(&source);
+        std::fs::write(definition, fixed)
+            .map_err(|e| format!("error: Could not write {definition:?} : {e}"))?;
+        println!(
+            "Replaced {count} deprecated keywords in {}",
+            definition.display()
+        );
+        return Ok(());
+    }
+    if deprecated.is_empty() {
+        println!("✅✅ No deprecated keywords in {}", definition.display());
+        return Ok(());
+    }
+    let mut lines: Vec<String> = deprecated.iter().map(|d| format!("⚠️ {d}")).collect();
+    lines.push("Replace them with `as3 fmt --fix-deprecations`".to_string());
+    Err(Failure::invalid(lines.join("\n")))
+}
+
+/// Errors and warnings of a definition, each with the line and column of its type or keyword
+fn lint_definition(
+    definition: &Path,
+    definition_format: Option<Format>,
+    definition_options: &DefinitionOptions,
+) -> Result<(), Failure> {
+    let source = std::fs::read_to_string(definition)
+        .map_err(|e| format!("error: Could not read {definition:?} : {e}"))?;
+    let format = definition_format.unwrap_or_else(|| Format::detect(definition, &source));
+    let yaml: serde_yaml::Value =
+        diagnostic::parse("definition file", definition, &source, Some(format))
+            .map_err(Failure::definition)?;
+    let lints = lint(&yaml, &definition_options.located(definition));
+    if lints.is_empty() {
+        println!("✅✅ No problems found in {}", definition.display());
+        return Ok(());
+    }
+    let spans = SourceMap::new(&source, format);
+    let mut lines: Vec<String> = lints
+        .iter()
+        .map(|lint| {
+            let icon = match lint.severity {
+                Severity::Error => "❌",
+                Severity::Warning => "⚠️",
+            };
+            // The definition is the document read, from its `ROOT`
+            let mut path = JsonPath::root("ROOT");
+            path.0.extend(lint.path.0.iter().cloned());
+            match spans.locate(&path) {
+                Some(position) => format!("{icon} {}:{position}: {lint}", definition.display()),
+                None => format!("{icon} {lint}"),
+            }
+        })
+        .collect();
+    let errors = lints
+        .iter()
+        .filter(|lint| lint.severity == Severity::Error)
+        .count();
+    let count = |count: usize, noun: &str| match count {
+        1 => format!("1 {noun}"),
+        count => format!("{count} {noun}s"),
+    };
+    lines.push(format!(
+        "❌❌ {} and {}",
+        count(errors, "error"),
+        count(lints.len() - errors, "warning")
+    ));
+    match errors {
+        0 => Err(Failure::invalid(lines.join("\n"))),
+        _ => Err(Failure::definition(lines.join("\n"))),
+    }
+}
+
+fn editor_schema(
+    definition: &Path,
+    output: Option<&Path>,
+    definition_options: &DefinitionOptions,
+) -> Result<(), Failure> {
+    let validator = build_validator(
+        &read_definition(definition, None)?,
+        definition,
+        definition_options,
+        Verbosity::Normal,
+    )?;
+    let schema = serde_json::to_string_pretty(&to_editor_schema(&validator)).unwrap();
+    match output {
+        Some(output) => std::fs::write(output, schema + "\n")
+            .map_err(|e| format!("error: Could not write {output:?} : {e}").into()),
+        None => {
+            println!("{schema}");
+            Ok(())
+        }
+    }
+}
+
+fn generate_form(
+    definition: &Path,
+    style: FormStyle,
+    output: Option<&Path>,
+    definition_options: &DefinitionOptions,
+) -> Result<(), Failure> {
+    let validator = build_validator(
+        &read_definition(definition, None)?,
+        definition,
+        definition_options,
+        Verbosity::Normal,
+    )?;
+    let form = serde_json::to_string_pretty(&to_form(&validator, style)).unwrap();
+    match output {
+        Some(output) => std::fs::write(output, form + "\n")
+            .map_err(|e| format!("error: Could not write {output:?} : {e}").into()),
+        None => {
+            println!("{form}");
+            Ok(())
+        }
+    }
+}
+
+fn pact_rules(
+    definition: &Path,
+    root: &str,
+    output: Option<&Path>,
+    definition_options: &DefinitionOptions,
+) -> Result<(), Failure> {
+    let options = definition_options.located(definition);
+    let validator =
+        AS3Validator::from_root_with_options(&read_definition(definition, None)?, root, &options)
+            .map_err(|e| Failure::definition(format!("error: Invalid definition : {e}")))?
+            .optimize();
+    let rules = serde_json::to_string_pretty(&to_pact_rules(&validator)).unwrap();
+    match output {
+        Some(output) => std::fs::write(output, rules + "\n")
+            .map_err(|e| format!("error: Could not write {output:?} : {e}").into()),
+        None => {
+            println!("{rules}");
+            Ok(())
+        }
+    }
+}
+
+fn impact_analysis(
+    old: &Path,
+    new: &Path,
+    corpus: &Path,
+    definition_options: &DefinitionOptions,
+    notify_webhook: Option<&str>,
+    sample_values: usize,
+) -> Result<(), Failure> {
+    let old = build_validator(
+        &read_definition(old, None)?,
+        old,
+        definition_options,
+        Verbosity::Normal,
+    )?;
+    let new = build_validator(
+        &read_definition(new, None)?,
+        new,
+        definition_options,
+        Verbosity::Normal,
+    )?;
+
+    let mut documents = Vec::new();
+    for file in documents_in(corpus)? {
+        let source = std::fs::read_to_string(&file)
+            .map_err(|e| format!("error: Could not read {file:?} : {e}"))?;
+        let data: serde_json::Value = diagnostic::parse("Data file", &file, &source, None)?;
+        documents.push((file.display().to_string(), AS3Data::from(&data)));
+    }
+
+    let report = impact(
+        &old,
+        &new,
+        documents
+            .iter()
+            .map(|(name, document)| (name.clone(), document)),
+    );
+    println!("{report}");
+
+    if let Some(url) = notify_webhook {
+        let failures: Vec<_> = documents
+            .iter()
+            .filter_map(|(name, document)| {
+                Some((name.as_str(), document, new.validate(document).err()?))
+            })
+            .collect();
+        if !failures.is_empty() {
+            let mut summary = FailureSummary::new(
+                documents.len(),
+                failures.iter().map(|(name, _, error)| (*name, error)),
+            );
+            summary.sample_values(
+                &new,
+                failures
+                    .iter()
+                    .map(|(name, document, error)| (*name, *document, error)),
+                sample_values,
+                random_seed(),
+            );
+            notify::send(url, &summary)?;
+        }
+    }
+    Ok(())
+}
+
+#[cfg(not(target_os = "wasi"))]
+fn daemon(config: &Path, once: bool) -> Result<(), Failure> {
+    let config = DaemonConfig::read(config)?;
+    if config.jobs.is_empty() {
+        return Err("error: The daemon configuration has no `[[job]]`"
+            .to_string()
+            .into());
+    }
+    let mut history = match &config.history {
+        Some(path) => Some(History::open(path)?),
+        None => None,
+    };
+    let start = std::time::Instant::now();
+    let mut next_runs = vec![start; config.jobs.len()];
+
+    loop {
+        let (index, next_run) = next_runs
+            .iter()
+            .copied()
+            .enumerate()
+            .min_by_key(|(_, next_run)| *next_run)
+            .unwrap();
+        if once && next_run > start {
+            return Ok(());
+        }
+        std::thread::sleep(next_run.saturating_duration_since(std::time::Instant::now()));
+
+        let job = &config.jobs[index];
+        next_runs[index] = next_run + job.every;
+        // A failing job is reported and retried at its next run
+        let result = match job.run() {
+            Ok(result) => result,
+            Err(e) => {
+                eprintln!("[{}] {e}", job.name);
+                continue;
+            }
+        };
+        let status = if result.summary.failing == 0 {
+            "✅"
+        } else {
+            "❌"
+        };
+        println!(
+            "{status} [{}] {} of {} documents failed",
+            result.job, result.summary.failing, result.summary.total
+        );
+        if let Some(results) = &config.results {
+            use std::io::Write;
+            std::fs::OpenOptions::new()
+                .create(true)
+                .append(true)
+                .open(results)
+                .and_then(|mut file| writeln!(file, "{}", serde_json::to_string(&result).unwrap()))
+                .map_err(|e| format!("error: Could not write {results:?} : {e}"))?;
+        }
+        if let Some(history) = &mut history {
+            history.record(&result)?;
+        }
+    }
+}
+
+fn stats(
+    definition: &Path,
+    corpus: &Path,
+    format: StatsFormat,
+    output: Option<&Path>,
+    sampling: Option<(Sample, u64)>,
+    definition_options: &DefinitionOptions,
+) -> Result<(), Failure> {
+    let validator = build_validator(
+        &read_definition(definition, None)?,
+        definition,
+        definition_options,
+        Verbosity::Normal,
+    )?;
+    let mut files = documents_in(corpus)?;
+    if let Some((sample, seed)) = sampling {
+        files = as3::sampling::sample(files, sample, seed).collect();
+    }
+    let mut stats = CorpusStats::default();
+    for file in files {
+        let source = std::fs::read_to_string(&file)
+            .map_err(|e| format!("error: Could not read {file:?} : {e}"))?;
+        let data: serde_json::Value = diagnostic::parse("Data file", &file, &source, None)?;
+        stats.add(&validator, &AS3Data::from(&data));
+    }
+
+    let metrics = match format {
+        StatsFormat::Prometheus => stats.to_prometheus(),
+        StatsFormat::Json => {
+            stats
+                .to_json(&chrono::Local::now().to_rfc3339())
+                .to_string()
+                + "\n"
+        }
+    };
+    match output {
+        Some(output) => std::fs::write(output, metrics)
+            .map_err(|e| format!("error: Could not write {output:?} : {e}").into()),
+        None => {
+            print!("{metrics}");
+            Ok(())
+        }
+    }
+}
+
+fn anonymize_document(
+    definition: &Path,
+    input: &Path,
+    output: Option<&Path>,
+    salt: &str,
+    definition_options: &DefinitionOptions,
+) -> Result<(), Failure> {
+    let validator = build_validator(
+        &read_definition(definition, None)?,
+        definition,
+        definition_options,
+        Verbosity::Normal,
+    )?;
+    let source = std::fs::read_to_string(input)
+        .map_err(|e| format!("error: Could not read {input:?} : {e}"))?;
+    let mut data: serde_json::Value = diagnostic::parse("Data file", input, &source, None)?;
+    // Replacements are only guaranteed to fit values that were valid
+    validator
+        .validate(&AS3Data::from(&data))
+        .map_err(|e| Failure::invalid(format!("❌❌ {e}")))?;
+    anonymize(&validator, &mut data, salt)?;
+    validator.validate(&AS3Data::from(&data)).map_err(|e| {
+        format!("error: The anonymized document doesn't match the definition anymore : {e}")
+    })?;
+
+    let document = serde_json::to_string_pretty(&data).unwrap();
+    match output {
+        Some(output) => std::fs::write(output, document + "\n")
+            .map_err(|e| format!("error: Could not write {output:?} : {e}").into()),
+        None => {
+            println!("{document}");
+            Ok(())
+        }
+    }
+}
 
-    let Ok(definition) =   serde_yaml::from_str::<serde_yaml::Value>(&std::fs::read_to_string(&args.definition).unwrap()) else {
-        return Err(format!("error: The definition file {:?} is not propper json or yaml", &args.definition))
+/// Valid records are written to `output`, invalid ones to `quarantine` along with their error.
+/// Fails on an invalid record only if there is no quarantine to receive it
+fn validate_records(
+    definition: &Path,
+    input: &Path,
+    output: Option<&Path>,
+    quarantine: Option<&Path>,
+    coerce: bool,
+    sampling: Option<(Sample, u64)>,
+    definition_options: &DefinitionOptions,
+) -> Result<(), Failure> {
+    use std::io::{BufRead, Write};
+    let validator = build_validator(
+        &read_definition(definition, None)?,
+        definition,
+        definition_options,
+        Verbosity::Normal,
+    )?;
+    let create = |path: &Path| {
+        std::fs::File::create(path)
+            .map(|file| Box::new(std::io::BufWriter::new(file)) as Box<dyn Write>)
+            .map_err(|e| format!("error: Could not write {path:?} : {e}"))
+    };
+    let mut valid = match output {
+        Some(output) => create(output)?,
+        None => Box::new(std::io::stdout().lock()),
     };
+    let mut invalid = quarantine.map(create).transpose()?;
+    let reader = std::io::BufReader::new(
+        std::fs::File::open(input).map_err(|e| format!("error: Could not read {input:?} : {e}"))?,
+    );
 
-    // let data = match &args.input {
-    //     Some(path) => {
-    //         let Ok(json_data) = serde_json::from_str::<serde_json::Value>(&std::fs::read_to_string(path).unwrap())else {
-    //             return Err(format!("error: The Data file {:?} is not propper json or yaml", &args.definition))
-    //         } ;
-    //         json_data
-    //     }
-    //     None => {
-    //         // let x = String::from_iter(std::io::stdin().lines().into_iter();
-    //         // std::io::stdin().lines();
-
-    //         // let input = std::io::stdin()
-    //         //     .lines()
-    //         //     .fold("".to_string(), |acc, line| acc + &line.unwrap() + "\n");
-
-    //         match serde_json::from_str::<serde_json::Value>(&input) {
-    //             Ok(json_data) => json_data,
-    //             Err(e) => return Err(format!("Could not serialise the piped data : {e}")),
-    //         }
-    //         // let stdin = std::io::stdin();
-    //         // for line in stdin.lines() {
-    //         //     let line = line.expect("Could not read line from standard in");
-    //         //     if line.is_empty() {
-    //         //         return Err("Data has not been passed. Use `--input <data_path>` or pipe it `cat data.json | as3 --definition <definition_path>`".to_owned());
-    //         //     }
-    //         //     println!("{}", line);
-    //         // }
-    //         unimplemented!()
-    //     }
-    // };
-
-    let Ok(data) =  serde_json::from_str::<serde_json::Value>(&std::fs::read_to_string(&args.input).unwrap()) else {
-        return Err(format!("error: The Data file {:?} is not propper json or yaml", &args.input))
+    // Read as the validation goes, stopping at the first line that can't be read. Lines that
+    // aren't JSON are set aside with their number, and quarantined as well
+    let mut failure = None;
+    let mut unparsed = Vec::new();
+    let mut lines = reader.lines().enumerate();
+    let records = std::iter::from_fn(|| loop {
+        let (index, line) = lines.next()?;
+        let line = match line {
+            Ok(line) => line,
+            Err(e) => {
+                failure = Some(format!(
+                    "error: Could not read line {} of {input:?} : {e}",
+                    index + 1
+                ));
+                return None;
+            }
+        };
+        if line.trim().is_empty() {
+            continue;
+        }
+        match serde_json::from_str(&line) {
+            Ok(record) => return Some(AS3Data::from(&record)),
+            Err(e) => unparsed.push((index + 1, line, e.to_string())),
+        }
+    });
+    let records = match sampling {
+        Some((sample, seed)) => as3::sampling::sample(records, sample, seed),
+        None => Box::new(records),
     };
+    let options = ValidationOptions {
+        coerce,
+        ..Default::default()
+    };
+    let (mut passed, mut failed) = (0, 0);
+    let mut written = Ok(());
+    validator.validate_stream(records, &options, |record| {
+        let line = match record.result {
+            Ok(()) => {
+                passed += 1;
+                writeln!(valid, "{}", serde_json::Value::from(&record.data))
+            }
+            Err(error) => {
+                failed += 1;
+                eprintln!("❌ {error}");
+                match &mut invalid {
+                    Some(invalid) => writeln!(
+                        invalid,
+                        "{}",
+                        serde_json::json!({
+                            "error": error,
+                            "record": serde_json::Value::from(&record.data),
+                        })
+                    ),
+                    None => Ok(()),
+                }
+            }
+        };
+        if written.is_ok() {
+            written = line;
+        }
+    });
+    for (number, line, error) in &unparsed {
+        eprintln!("❌ Line {number} of {input:?} is not JSON : {error}");
+        if let (Some(invalid), Ok(())) = (&mut invalid, &written) {
+            written = writeln!(
+                invalid,
+                "{}",
+                serde_json::json!({
+                    "error": format!("not JSON : {error}"),
+                    "line": number,
+                    "text": line,
+                })
+            );
+        }
+    }
+    written
+        .and(valid.flush())
+        .and(invalid.as_mut().map_or(Ok(()), |invalid| invalid.flush()))
+        .map_err(|e| format!("error: Could not write the records : {e}"))?;
+    if let Some(failure) = failure {
+        return Err(failure.into());
+    }
+    match unparsed.len() {
+        0 => eprintln!("{passed} valid and {failed} invalid records"),
+        unparsed => eprintln!("{passed} valid and {failed} invalid records, {unparsed} not JSON"),
+    }
+    match (failed, unparsed.len(), quarantine) {
+        (0, 0, _) | (_, _, Some(_)) => Ok(()),
+        (failed, 0, None) => Err(Failure::invalid(format!(
+            "❌❌ {failed} records don't match the definition"
+        ))),
+        (_, unparsed, None) => {
+            Err(format!("error: {unparsed} lines of {input:?} are not JSON").into())
+        }
+    }
+}
 
-    let validator = AS3Validator::from(&definition).unwrap();
+fn validate_transcript(
+    definition: &Path,
+    input: &Path,
+    definition_options: &DefinitionOptions,
+) -> Result<(), Failure> {
+    let validator = TranscriptValidator::from_with_options(
+        &read_definition(definition, None)?,
+        &definition_options.located(definition),
+    )
+    .map_err(|e| Failure::definition(format!("error: Invalid definition : {e}")))?;
+    let source = std::fs::read_to_string(input)
+        .map_err(|e| format!("error: Could not read {input:?} : {e}"))?;
+    let data: serde_json::Value = diagnostic::parse("Data file", input, &source, None)?;
     match validator.validate(&AS3Data::from(&data)) {
-        Ok(_) => println!("✅✅ The provided schema matches the data"),
-        Err(e) => return Err(format!("❌❌ {}", e.to_string())),
+        Ok(_) => println!("✅✅ The exchanges match the definition"),
+        Err(e) => return Err(Failure::invalid(format!("❌❌ {e}"))),
+    }
+    Ok(())
+}
+
+fn check_contract(
+    definition: &Path,
+    har: &Path,
+    definition_options: &DefinitionOptions,
+) -> Result<(), Failure> {
+    let validator = ContractValidator::from_with_options(
+        &read_definition(definition, None)?,
+        &definition_options.located(definition),
+    )
+    .map_err(|e| Failure::definition(format!("error: Invalid definition : {e}")))?;
+    let source =
+        std::fs::read_to_string(har).map_err(|e| format!("error: Could not read {har:?} : {e}"))?;
+    let capture: serde_json::Value =
+        diagnostic::parse("HAR file", har, &source, Some(Format::Json))?;
+
+    let report = validator.validate_har(&capture)?;
+    for failure in report.failures() {
+        if let Err(e) = &failure.result {
+            eprintln!("❌ {} : {e}", failure.endpoint);
+        }
+    }
+    let failed = report.failures().count();
+    let summary = format!(
+        "{} exchanges checked, {failed} failed, {} matching no endpoint",
+        report.exchanges.len(),
+        report.unmatched
+    );
+    match failed {
+        0 => {
+            println!("✅✅ {summary}");
+            Ok(())
+        }
+        _ => Err(Failure::invalid(format!("❌❌ {summary}"))),
+    }
+}
+
+#[cfg(not(target_os = "wasi"))]
+fn report_digest(
+    db: &Path,
+    since: std::time::Duration,
+    format: DigestFormat,
+    output: Option<&Path>,
+) -> Result<(), Failure> {
+    let since_time = chrono::Local::now().fixed_offset()
+        - chrono::Duration::from_std(since).map_err(|e| format!("error: Invalid --since : {e}"))?;
+    let digests = History::open(db)?.digest(since_time)?;
+    let period = humantime::format_duration(since).to_string();
+    let digest = match format {
+        DigestFormat::Markdown => digest::to_markdown(&digests, &period),
+        DigestFormat::Html => digest::to_html(&digests, &period),
+    };
+    match output {
+        Some(output) => std::fs::write(output, digest)
+            .map_err(|e| format!("error: Could not write {output:?} : {e}").into()),
+        None => {
+            print!("{digest}");
+            Ok(())
+        }
+    }
+}
+
+#[cfg(not(target_os = "wasi"))]
+fn history(db: &Path, job: Option<&str>, limit: usize) -> Result<(), Failure> {
+    for run in History::open(db)?.runs(job, limit)? {
+        println!("{run}");
     }
     Ok(())
 }
@@ -81,3 +1791,156 @@ fn verify_cli() {
     use clap::CommandFactory;
     Args::command().debug_assert()
 }
+
+#[cfg(not(target_os = "wasi"))]
+#[test]
+fn watched_changes() {
+    use ::notify::{event::AccessKind, event::ModifyKind, Event, EventKind};
+
+    let folder = PathBuf::from("/data");
+    let watched = [folder.join("definition.yaml"), folder.join("records")];
+    let modified =
+        |path: &str| Event::new(EventKind::Modify(ModifyKind::Any)).add_path(path.into());
+
+    assert!(changes(&modified("/data/definition.yaml"), &watched));
+    assert!(changes(&modified("/data/records/2024/a.json"), &watched));
+    // Other files of the folder of a watched file
+    assert!(!changes(&modified("/data/notes.txt"), &watched));
+    assert!(!changes(&modified("/data/definition.yaml.swp"), &watched));
+    // Reading a file changes nothing
+    let read =
+        Event::new(EventKind::Access(AccessKind::Any)).add_path("/data/definition.yaml".into());
+    assert!(!changes(&read, &watched));
+
+    let (sender, receiver) = std::sync::mpsc::channel();
+    for _ in 0..3 {
+        sender.send(modified("/data/definition.yaml")).unwrap();
+    }
+    assert_eq!(drain(&receiver), 3);
+    assert_eq!(drain(&receiver), 0);
+}
+
+#[test]
+fn records() {
+    let folder = std::env::temp_dir().join(format!("as3-records-{}", std::process::id()));
+    std::fs::create_dir_all(&folder).unwrap();
+    std::fs::write(
+        folder.join("record.yaml"),
+        "Root: { +type: Object, id: Integer }",
+    )
+    .unwrap();
+    std::fs::write(
+        folder.join("mixed.jsonl"),
+        "{\"id\": 1}\n{\"id\": \"two\"}\n{ id: 3\n\n{\"id\": 4}\n",
+    )
+    .unwrap();
+    std::fs::write(
+        folder.join("invalid.jsonl"),
+        "{\"id\": 1}\n{\"id\": \"two\"}\n",
+    )
+    .unwrap();
+    let run_records = |input: &str, quarantine: bool| {
+        let definition = folder.join("record.yaml");
+        let input = folder.join(input);
+        let output = folder.join("valid.jsonl");
+        let quarantined = folder.join("quarantine.jsonl");
+        let _ = std::fs::remove_file(&quarantined);
+        let mut args = vec![
+            "as3".into(),
+            "records".into(),
+            "--definition".into(),
+            definition.into_os_string(),
+            "--input".into(),
+            input.into_os_string(),
+            "--output".into(),
+            output.clone().into_os_string(),
+        ];
+        if quarantine {
+            args.extend(["--quarantine".into(), quarantined.clone().into_os_string()]);
+        }
+        let exit = run(Args::try_parse_from(args).unwrap())
+            .err()
+            .map(|failure| failure.exit as i32);
+        let lines = |path: &Path| -> Vec<serde_json::Value> {
+            std::fs::read_to_string(path)
+                .unwrap_or_default()
+                .lines()
+                .map(|line| serde_json::from_str(line).unwrap())
+                .collect()
+        };
+        (exit, lines(&output), lines(&quarantined))
+    };
+
+    // Records that aren't JSON are quarantined, and the reading goes on
+    let (exit, valid, quarantined) = run_records("mixed.jsonl", true);
+    assert_eq!(exit, None);
+    assert_eq!(
+        valid,
+        [
+            serde_json::json!({ "id": 1 }),
+            serde_json::json!({ "id": 4 })
+        ]
+    );
+    assert_eq!(quarantined.len(), 2);
+    assert_eq!(quarantined[0]["record"], serde_json::json!({ "id": "two" }));
+    assert_eq!(quarantined[0]["error"]["path"], "ROOT -> [1] -> id");
+    assert_eq!(quarantined[1]["line"], 3);
+    assert_eq!(quarantined[1]["text"], "{ id: 3");
+
+    // Without a quarantine, the records after a line that isn't JSON are still written
+    let (exit, valid, _) = run_records("mixed.jsonl", false);
+    assert_eq!(exit, Some(Exit::Input as i32));
+    assert_eq!(valid.len(), 2);
+
+    let (exit, valid, _) = run_records("invalid.jsonl", false);
+    assert_eq!(exit, Some(Exit::Invalid as i32));
+    assert_eq!(valid, [serde_json::json!({ "id": 1 })]);
+    let (exit, _, quarantined) = run_records("invalid.jsonl", true);
+    assert_eq!(exit, None);
+    assert_eq!(quarantined.len(), 1);
+    std::fs::remove_dir_all(&folder).unwrap();
+}
+
+#[test]
+fn exit_codes() {
+    let folder = std::env::temp_dir().join(format!("as3-exit-{}", std::process::id()));
+    std::fs::create_dir_all(&folder).unwrap();
+    std::fs::write(folder.join("definition.yaml"), "Root: Integer(max=10)").unwrap();
+    std::fs::write(folder.join("broken.yaml"), "Root: { +type: Nope }").unwrap();
+    std::fs::write(folder.join("valid.json"), "3").unwrap();
+    std::fs::write(folder.join("invalid.json"), "11").unwrap();
+    std::fs::write(folder.join("unreadable.json"), "{ 3").unwrap();
+    let exit = |definition: &str, input: &str| {
+        let definition = folder.join(definition);
+        let input = folder.join(input);
+        let args = Args::try_parse_from([
+            "as3".as_ref(),
+            "-q".as_ref(),
+            "--definition".as_ref(),
+            definition.as_os_str(),
+            "--input".as_ref(),
+            input.as_os_str(),
+        ])
+        .unwrap();
+        run(args).err().map(|failure| failure.exit as i32)
+    };
+
+    assert_eq!(exit("definition.yaml", "valid.json"), None);
+    assert_eq!(exit("definition.yaml", "invalid.json"), Some(1));
+    assert_eq!(exit("broken.yaml", "valid.json"), Some(2));
+    assert_eq!(exit("definition.yaml", "unreadable.json"), Some(3));
+    assert_eq!(
+        usage_exit(&Args::try_parse_from(["as3", "--unknown"]).unwrap_err()),
+        4
+    );
+    assert_eq!(
+        usage_exit(&Args::try_parse_from(["as3", "--help"]).unwrap_err()),
+        0
+    );
+    // A limit of no errors would stop before the first one
+    assert_eq!(
+        usage_exit(&Args::try_parse_from(["as3", "--max-errors", "0"]).unwrap_err()),
+        4
+    );
+    std::fs::remove_dir_all(&folder).unwrap();
+}
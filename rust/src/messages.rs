@@ -0,0 +1,188 @@
+//! Error messages rendered from templates looked up by error code, so that embedders can show
+//! validation errors to their users in their language:
+//!
+//! ```yaml
+//! E_MISSING_KEY: La clé {key} est manquante
+//! E_MAX_INT: "`{got}` dépasse le maximum de `{expected}`"
+//! ```
+//!
+//! Templates refer to the `{expected}` constraint and the value `{got}` of
+//! [`AS3ValidationError::expected_and_got`], and to the parameters listed by [`parameters`].
+//! Codes missing from a catalog are rendered in English, and `+message` texts as written.
+//!
+//! [`render_error`] writes an error in one of the [`Format`]s whose output is guaranteed, unlike
+//! the `Display` of the errors, which can change with any release.
+
+use crate::error::{AS3ValidationError, As3JsonPath};
+use serde_json::Value;
+use std::collections::HashMap;
+
+/// Source of the message templates, by error code
+pub trait Catalog: std::fmt::Debug + Send + Sync {
+    /// Template of the errors with `code`, `None` to fall back on English
+    fn template(&self, code: &str) -> Option<&str>;
+}
+
+/// Default catalog
+#[derive(Debug, Default, Clone, Copy)]
+pub struct English;
+
+impl Catalog for English {
+    fn template(&self, code: &str) -> Option<&str> {
+        Some(match code {
+            "E_TYPE_MISMATCH" => "Expected {expected}, got `{got}`",
+            "E_MISSING_KEY" => "Key {key} is missing",
+            "E_UNEXPECTED_KEY" => "Key {key} is not expected",
+            "E_DEPRECATED" => "Deprecated value",
+            "E_UNKNOWN_TAG" => "Unknown {discriminator} `{got}`, expected one of {expected}",
+            "E_RECURSION_LIMIT" => "More than {expected} nested references, the data is too deep",
+            "E_MISSING_DEPENDENCY" => "Key {key} is required when {required_by} is present",
+            "E_ASSERTION" => "Assertion `{expected}` failed",
+            "E_REGEX" => "`{got}` doesn't match the `{expected}` regex",
+            "E_ANY_REGEX" => "`{got}` doesn't match any of the {expected} regexes",
+            "E_DENY_REGEX" => "`{got}` matches a denied regex",
+            "E_CHARACTER_CLASS" => "`{got}` at position {position} is not allowed by {expected}",
+            "E_FORMAT" => "`{word}` is not a valid {expected}: {reason}",
+            "E_MIN_DECIMAL" | "E_MIN_INT" => "`{got}` is under the minimum of `{expected}`",
+            "E_MAX_DECIMAL" | "E_MAX_INT" => "`{got}` is above the maximum of `{expected}`",
+            "E_INT_WIDTH" => "`{got}` doesn't fit in a `{expected}`",
+            "E_GENERIC" => "Error during validation: {reason}",
+            "E_MAX_LENGTH" => "{got} {unit} long, above the maximum length of {expected}",
+            "E_MIN_LENGTH" => "{got} {unit} long, under the minimum length of {expected}",
+            "E_MIN_DATE" | "E_MIN_DATETIME" => "`{got}` is before the minimum of `{expected}`",
+            "E_MAX_DATE" | "E_MAX_DATETIME" => "`{got}` is after the maximum of `{expected}`",
+            "E_MIN_TIME" => "`{got}` is earlier than the minimum of `{expected}`",
+            "E_MAX_TIME" => "`{got}` is later than the maximum of `{expected}`",
+            "E_MIN_DURATION" => "`{got}` is shorter than the minimum of `{expected}`",
+            "E_MAX_DURATION" => "`{got}` is longer than the maximum of `{expected}`",
+            "E_MIN_ENTRIES" => "{got} entries, under the minimum of {expected}",
+            "E_MAX_ENTRIES" => "{got} entries, above the maximum of {expected}",
+            "E_MIN_BYTES" => "{got} bytes once decoded, under the minimum of {expected}",
+            "E_MAX_BYTES" => "{got} bytes once decoded, above the maximum of {expected}",
+            "E_NULL" => "The value can't be null",
+            _ => return None,
+        })
+    }
+}
+
+/// Templates by error code, such as a translation file
+#[derive(Debug, Default, Clone, PartialEq)]
+pub struct TemplateCatalog(pub HashMap<String, String>);
+
+impl TemplateCatalog {
+    /// Reads a YAML (or JSON) mapping of error codes to templates
+    pub fn from_yaml(source: &str) -> Result<TemplateCatalog, String> {
+        serde_yaml::from_str(source)
+            .map(TemplateCatalog)
+            .map_err(|e| format!("error: Invalid message catalog : {e}"))
+    }
+}
+
+impl Catalog for TemplateCatalog {
+    fn template(&self, code: &str) -> Option<&str> {
+        self.0.get(code).map(String::as_str)
+    }
+}
+
+/// Message of `error` from the template of its code in `catalog`, or in English
+pub fn render(error: &AS3ValidationError, catalog: &dyn Catalog) -> String {
+    if let AS3ValidationError::Custom { message, .. } = error {
+        return message.clone();
+    }
+    let template = catalog
+        .template(error.code())
+        .or_else(|| English.template(error.code()))
+        .unwrap_or_default();
+    let mut message = template.to_string();
+    for (name, value) in parameters(error) {
+        message = message.replace(&format!("{{{name}}}"), &value);
+    }
+    message
+}
+
+/// Values available to the template of `error`: `expected` and `got` when the error has them,
+/// and `key`, `suggestion`, `required_by`, `discriminator`, `unit`, `word`, `position` or
+/// `reason` for the errors they describe
+pub fn parameters(error: &AS3ValidationError) -> Vec<(&'static str, String)> {
+    let (expected, got) = error.expected_and_got();
+    let mut parameters: Vec<(&'static str, String)> = [("expected", expected), ("got", got)]
+        .into_iter()
+        .filter_map(|(name, value)| Some((name, text(value?))))
+        .collect();
+    match error.underlying() {
+        AS3ValidationError::MissingKey { key, suggestion }
+        | AS3ValidationError::UnexpectedKey { key, suggestion } => {
+            parameters.push(("key", key.clone()));
+            parameters.extend(
+                suggestion
+                    .clone()
+                    .map(|suggestion| ("suggestion", suggestion)),
+            );
+        }
+        AS3ValidationError::MissingDependency { key, required_by } => {
+            parameters.push(("key", key.clone()));
+            parameters.push(("required_by", required_by.clone()));
+        }
+        AS3ValidationError::UnknownTag { discriminator, .. } => {
+            parameters.push(("discriminator", discriminator.clone()))
+        }
+        AS3ValidationError::MaximumString { unit, .. }
+        | AS3ValidationError::MinimumString { unit, .. } => {
+            parameters.push(("unit", unit.to_string()))
+        }
+        AS3ValidationError::CharacterClass { word, position, .. } => {
+            parameters.push(("word", word.clone()));
+            parameters.push(("position", position.to_string()));
+        }
+        AS3ValidationError::FormatError { word, reason, .. } => {
+            parameters.push(("word", word.clone()));
+            parameters.push(("reason", reason.clone()));
+        }
+        AS3ValidationError::Generic(reason) => parameters.push(("reason", reason.clone())),
+        _ => {}
+    }
+    parameters
+}
+
+/// Version of the output of [`render_error`]. The text of an error stays the same within a
+/// version, and the version is bumped whenever the text of any error changes
+pub const RENDERING_VERSION: u32 = 1;
+
+/// Shape of an error written by [`render_error`]
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Format {
+    /// `ROOT -> age: Expected Integer, got `7``, on one line
+    Short,
+    /// Code and message, then the path, the `expected` constraint and the value `got` on their
+    /// own indented lines
+    Long,
+    /// The serialized error, on one line
+    Json,
+}
+
+/// `error` written in `format`, with the English messages
+pub fn render_error(error: &As3JsonPath<AS3ValidationError>, format: Format) -> String {
+    match format {
+        Format::Short => format!("{}: {}", error.0, render(&error.1, &English)),
+        Format::Long => {
+            let mut lines = vec![
+                format!("error[{}]: {}", error.1.code(), render(&error.1, &English)),
+                format!("  path: {}", error.0),
+            ];
+            let (expected, got) = error.1.expected_and_got();
+            lines.extend(expected.map(|expected| format!("  expected: {}", text(expected))));
+            lines.extend(got.map(|got| format!("  got: {got}")));
+            lines.join("\n")
+        }
+        Format::Json => serde_json::to_string(error).unwrap(),
+    }
+}
+
+/// Strings as is, Lists joined with commas and other values as JSON
+fn text(value: Value) -> String {
+    match value {
+        Value::String(string) => string,
+        Value::Array(items) => items.into_iter().map(text).collect::<Vec<_>>().join(", "),
+        value => value.to_string(),
+    }
+}
@@ -0,0 +1,65 @@
+//! Counters of the work done by the validation, so that performance work on the engine can
+//! observe more than end-to-end timings. They are only incremented with the `metrics` feature,
+//! and are shared by all the validations of the process: [`Metrics::reset`] them before the
+//! validation to measure.
+
+use serde::Serialize;
+use std::sync::atomic::{AtomicU64, Ordering};
+
+/// Work done since the counters were last reset
+#[derive(Debug, Default, Clone, Copy, PartialEq, Eq, Serialize)]
+pub struct Metrics {
+    /// Values checked against a validator
+    pub nodes_visited: u64,
+    /// Regexes, and sets of regexes, run on a String or a key
+    pub regex_evaluations: u64,
+    /// Fields of Objects validated as rayon tasks
+    pub parallel_tasks: u64,
+}
+
+static NODES_VISITED: AtomicU64 = AtomicU64::new(0);
+static REGEX_EVALUATIONS: AtomicU64 = AtomicU64::new(0);
+static PARALLEL_TASKS: AtomicU64 = AtomicU64::new(0);
+
+impl Metrics {
+    /// Current value of the counters, all 0 without the `metrics` feature
+    pub fn snapshot() -> Metrics {
+        Metrics {
+            nodes_visited: NODES_VISITED.load(Ordering::Relaxed),
+            regex_evaluations: REGEX_EVALUATIONS.load(Ordering::Relaxed),
+            parallel_tasks: PARALLEL_TASKS.load(Ordering::Relaxed),
+        }
+    }
+
+    /// Sets the counters back to 0
+    pub fn reset() {
+        for counter in [&NODES_VISITED, &REGEX_EVALUATIONS, &PARALLEL_TASKS] {
+            counter.store(0, Ordering::Relaxed);
+        }
+    }
+}
+
+#[derive(Debug, Clone, Copy)]
+pub(crate) enum Counter {
+    NodesVisited,
+    RegexEvaluations,
+    #[cfg_attr(
+        not(all(feature = "parallel", not(target_os = "wasi"))),
+        allow(dead_code)
+    )]
+    ParallelTasks,
+}
+
+/// Adds `count` to `counter`, nothing without the `metrics` feature
+#[inline(always)]
+pub(crate) fn add(counter: Counter, count: u64) {
+    #[cfg(feature = "metrics")]
+    match counter {
+        Counter::NodesVisited => &NODES_VISITED,
+        Counter::RegexEvaluations => &REGEX_EVALUATIONS,
+        Counter::ParallelTasks => &PARALLEL_TASKS,
+    }
+    .fetch_add(count, Ordering::Relaxed);
+    #[cfg(not(feature = "metrics"))]
+    let _ = (counter, count);
+}
@@ -0,0 +1,149 @@
+//! Summaries of failing documents posted to a webhook, so AS3 can run as a data-quality
+//! monitor. The payload has a `text` field for Slack-compatible webhooks and the detailed
+//! counts for generic ones.
+
+use crate::{
+    anonymize::redact,
+    error::{AS3ValidationError, As3JsonPath, JsonPath, PathSegment},
+    sampling::{sample, Sample},
+    validator::AS3Validator,
+    AS3Data,
+};
+use serde::Serialize;
+use std::collections::HashMap;
+
+/// Number of error kinds and of sample paths kept in a summary
+const TOP: usize = 5;
+
+#[derive(Debug, PartialEq, Serialize)]
+pub struct FailureSummary {
+    pub total: usize,
+    pub failing: usize,
+    /// Most frequent error kinds with their count
+    pub top_errors: Vec<(String, usize)>,
+    /// `document: path` of the first failures
+    pub samples: Vec<String>,
+    /// Offending values picked at random, empty unless [`FailureSummary::sample_values`] is used
+    #[serde(skip_serializing_if = "Vec::is_empty")]
+    pub values: Vec<ValueSample>,
+}
+
+/// Value found at the path of a failure
+#[derive(Debug, PartialEq, Serialize)]
+pub struct ValueSample {
+    pub document: String,
+    pub path: String,
+    /// Truncated rendering, with the fields annotated with `+anonymize` redacted
+    pub value: String,
+}
+
+impl FailureSummary {
+    pub fn new<'a>(
+        total: usize,
+        failures: impl IntoIterator<Item = (&'a str, &'a As3JsonPath<AS3ValidationError>)>,
+    ) -> Self {
+        let mut failing = 0;
+        let mut counts: HashMap<&str, usize> = HashMap::new();
+        let mut samples = Vec::new();
+        for (document, As3JsonPath(path, error)) in failures {
+            failing += 1;
+            *counts.entry(error.kind()).or_default() += 1;
+            if samples.len() < TOP {
+                samples.push(format!("{document}: {path}"));
+            }
+        }
+        let mut top_errors: Vec<(String, usize)> = counts
+            .into_iter()
+            .map(|(kind, count)| (kind.to_string(), count))
+            .collect();
+        top_errors.sort_by(|a, b| b.1.cmp(&a.1).then_with(|| a.0.cmp(&b.0)));
+        top_errors.truncate(TOP);
+
+        FailureSummary {
+            total,
+            failing,
+            top_errors,
+            samples,
+            values: Vec::new(),
+        }
+    }
+
+    /// Keeps at most `limit` offending values, chosen uniformly at random among the failures so
+    /// that a report on a large batch neither grows with it nor always shows the same documents.
+    /// Fields annotated with `+anonymize` are redacted. `seed` makes the choice reproducible
+    pub fn sample_values<'a>(
+        &mut self,
+        validator: &AS3Validator,
+        failures: impl IntoIterator<Item = (&'a str, &'a AS3Data, &'a As3JsonPath<AS3ValidationError>)>,
+        limit: usize,
+        seed: u64,
+    ) {
+        self.values = sample(failures, Sample::Count(limit), seed)
+            .map(|(document, data, As3JsonPath(path, _))| ValueSample {
+                document: document.to_string(),
+                path: path.to_string(),
+                value: value_at(&redact(validator, data), path).to_string(),
+            })
+            .collect();
+    }
+
+    /// Message for chat webhooks
+    pub fn text(&self) -> String {
+        let mut text = format!(
+            "❌ AS3: {} of {} documents failed validation",
+            self.failing, self.total
+        );
+        for (kind, count) in &self.top_errors {
+            text.push_str(&format!("\n• {kind}: {count}"));
+        }
+        for sample in &self.samples {
+            text.push_str(&format!("\n◦ {sample}"));
+        }
+        for sample in &self.values {
+            text.push_str(&format!(
+                "\n◦ {}: {} = {}",
+                sample.document, sample.path, sample.value
+            ));
+        }
+        text
+    }
+
+    pub fn payload(&self) -> serde_json::Value {
+        let mut payload = serde_json::to_value(self).unwrap();
+        payload["text"] = self.text().into();
+        payload
+    }
+}
+
+/// Value at a validation path such as `ROOT -> a -> [2] -> b`
+fn value_at<'a>(data: &'a AS3Data, path: &JsonPath) -> &'a AS3Data {
+    let mut value = data;
+    for segment in path.segments().iter().skip(1) {
+        match (value, segment) {
+            (AS3Data::Object(values), PathSegment::Key(key)) if values.contains_key(key) => {
+                value = &values[key]
+            }
+            (AS3Data::List(items), PathSegment::Index(index)) if *index < items.len() => {
+                value = &items[*index]
+            }
+            _ => break,
+        }
+    }
+    value
+}
+
+/// POSTs the summary as JSON to `url`
+#[cfg(not(target_os = "wasi"))]
+pub fn send(url: &str, summary: &FailureSummary) -> Result<(), String> {
+    ureq::post(url)
+        .send_json(summary.payload())
+        .map(|_| ())
+        .map_err(|e| format!("error: Could not notify {url} : {e}"))
+}
+
+#[cfg(target_os = "wasi")]
+pub fn send(url: &str, _summary: &FailureSummary) -> Result<(), String> {
+    Err(format!(
+        "error: Could not notify {url} : the network is not available on WASI"
+    ))
+}
@@ -0,0 +1,147 @@
+//! Pact matching rules generated from a definition, so that the matchers of consumer tests
+//! follow the AS3 schema of a response instead of being written by hand.
+//!
+//! Rules use the Pact specification v3 layout, keyed by the JSON path of each value:
+//!
+//! ```json
+//! { "body": { "$.id": { "matchers": [{ "match": "integer" }], "combine": "AND" } } }
+//! ```
+//!
+//! Bounds such as `+max` or `+maxLength` have no Pact matcher and are left out. `Any` values
+//! get no rule, and are compared by equality with the example of the interaction.
+
+use crate::validator::{AS3Validator, StringFormat};
+use serde_json::{json, Value};
+use std::collections::BTreeMap;
+
+/// Matching rules of the values accepted by `validator`, to be used as the `matchingRules` of a
+/// Pact request or response
+pub fn to_pact_rules(validator: &AS3Validator) -> Value {
+    let mut rules = Rules::default();
+    rules.walk(validator, "$");
+    json!({ "body": rules.rules })
+}
+
+#[derive(Default)]
+struct Rules {
+    rules: BTreeMap<String, Value>,
+    /// `+defs` being expanded, as definitions can refer to themselves
+    expanding: Vec<String>,
+}
+
+impl Rules {
+    fn walk(&mut self, validator: &AS3Validator, path: &str) {
+        let matchers = match validator {
+            AS3Validator::Annotated { inner, .. } => return self.walk(inner, path),
+            AS3Validator::Conditional { base, .. } => return self.walk(base, path),
+            AS3Validator::Ref(reference) => match reference.target() {
+                Some(target) if !self.expanding.iter().any(|name| name == reference.name()) => {
+                    self.expanding.push(reference.name().to_string());
+                    self.walk(&target, path);
+                    self.expanding.pop();
+                    return;
+                }
+                _ => vec![json!({ "match": "type" })],
+            },
+            AS3Validator::Nullable(inner) => {
+                self.walk(inner, path);
+                if let Some(rule) = self.rules.get_mut(path) {
+                    rule["matchers"]
+                        .as_array_mut()
+                        .unwrap()
+                        .insert(0, json!({ "match": "null" }));
+                    rule["combine"] = json!("OR");
+                }
+                return;
+            }
+            AS3Validator::Object { fields, .. } => {
+                for (key, field) in fields {
+                    self.walk(field, &child(path, key));
+                }
+                vec![json!({ "match": "type" })]
+            }
+            AS3Validator::TaggedUnion {
+                discriminator,
+                variants,
+            } => {
+                let tags: Vec<String> = variants.keys().map(|tag| regex::escape(tag)).collect();
+                self.insert(
+                    &child(path, discriminator),
+                    vec![json!({ "match": "regex", "regex": format!("^(?:{})$", tags.join("|")) })],
+                );
+                vec![json!({ "match": "type" })]
+            }
+            AS3Validator::List(items_type) => {
+                self.walk(items_type, &format!("{path}[*]"));
+                vec![json!({ "match": "type" })]
+            }
+            AS3Validator::Map { value_type, .. } => {
+                self.walk(value_type, &format!("{path}.*"));
+                vec![json!({ "match": "values" })]
+            }
+            AS3Validator::String { regex, format, .. } => {
+                let mut matchers: Vec<Value> = regex
+                    .iter()
+                    .map(|regex| json!({ "match": "regex", "regex": regex }))
+                    .collect();
+                if let Some(regex) = format.as_ref().and_then(format_regex) {
+                    matchers.push(json!({ "match": "regex", "regex": regex }));
+                }
+                if matchers.is_empty() {
+                    matchers.push(json!({ "match": "type" }));
+                }
+                matchers
+            }
+            AS3Validator::Literal { regex, .. } => {
+                vec![json!({ "match": "regex", "regex": regex })]
+            }
+            AS3Validator::Integer { .. } => vec![json!({ "match": "integer" })],
+            AS3Validator::Decimal { .. } => vec![json!({ "match": "number" })],
+            AS3Validator::Null => vec![json!({ "match": "null" })],
+            AS3Validator::Date { .. } => vec![json!({ "match": "date", "format": "yyyy-MM-dd" })],
+            AS3Validator::DateTime { .. } => vec![json!({
+                "match": "timestamp",
+                "format": "yyyy-MM-dd'T'HH:mm:ss[.SSS]XXX",
+            })],
+            AS3Validator::Time { .. } => vec![json!({ "match": "time", "format": "HH:mm[:ss]" })],
+            AS3Validator::Boolean | AS3Validator::Duration { .. } | AS3Validator::Bytes { .. } => {
+                vec![json!({ "match": "type" })]
+            }
+            AS3Validator::Any => return,
+        };
+        self.insert(path, matchers);
+    }
+
+    fn insert(&mut self, path: &str, matchers: Vec<Value>) {
+        self.rules.insert(
+            path.to_string(),
+            json!({ "matchers": matchers, "combine": "AND" }),
+        );
+    }
+}
+
+/// Path of the `key` field of the value at `path`, bracketed when it isn't an identifier
+fn child(path: &str, key: &str) -> String {
+    let identifier = key
+        .chars()
+        .next()
+        .is_some_and(|first| first.is_ascii_alphabetic() || first == '_')
+        && key.chars().all(|c| c.is_ascii_alphanumeric() || c == '_');
+    if identifier {
+        format!("{path}.{key}")
+    } else {
+        format!("{path}['{}']", key.replace('\'', "\\'"))
+    }
+}
+
+/// Regex of the formats Pact can check, the others only being matched by type
+fn format_regex(format: &StringFormat) -> Option<&'static str> {
+    match format {
+        StringFormat::Ipv4 { cidr: false } => Some(r"^(?:\d{1,3}\.){3}\d{1,3}$"),
+        StringFormat::Phone { .. } => Some(r"^\+[1-9]\d{1,14}$"),
+        StringFormat::Semver { .. } => {
+            Some(r"^\d+\.\d+\.\d+(?:-[0-9A-Za-z.-]+)?(?:\+[0-9A-Za-z.-]+)?$")
+        }
+        _ => None,
+    }
+}
@@ -0,0 +1,184 @@
+//! Outcome of a validation, and its rendering through user supplied templates so that it can
+//! be turned into tickets, chat messages or wiki tables without post-processing.
+//!
+//! Templates use the Handlebars/Tera-compatible subset below:
+//!
+//! ```text
+//! {{#if valid}}✅ {{input}} is valid{{else}}❌ {{input}}: {{message}} at {{path}}{{/if}}
+//! ```
+//!
+//! Available fields are `input`, `definition`, `valid`, `status` (`passed` or `failed`),
+//! `path`, `code` and `message`. `{{#if field}}` is true for `valid` and for non-empty fields.
+//!
+//! Reports are also written by an [`ErrorFormatter`], such as the built-in [`TextFormatter`],
+//! [`JsonFormatter`], [`YamlFormatter`] and [`CompactFormatter`].
+
+use crate::{
+    error::{AS3ValidationError, As3JsonPath},
+    messages,
+    metrics::Metrics,
+};
+use serde::Serialize;
+
+#[derive(Debug, PartialEq, Clone, Serialize)]
+pub struct ValidationReport {
+    pub input: String,
+    pub definition: Option<String>,
+    pub valid: bool,
+    /// Path of the first error
+    pub path: Option<String>,
+    /// Stable code of the first error, such as `E_MAX_INT`
+    pub code: Option<String>,
+    pub message: Option<String>,
+    /// Counters of the validation engine when the report is made, with the `metrics` feature
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub metrics: Option<Metrics>,
+}
+
+impl ValidationReport {
+    pub fn new(
+        input: String,
+        definition: Option<String>,
+        result: &Result<(), As3JsonPath<AS3ValidationError>>,
+    ) -> ValidationReport {
+        let (path, code, message) = match result {
+            Ok(()) => (None, None, None),
+            Err(As3JsonPath(path, error)) => (
+                Some(path.to_string()),
+                Some(error.code().to_string()),
+                Some(messages::render(error, &messages::English)),
+            ),
+        };
+        ValidationReport {
+            input,
+            definition,
+            valid: result.is_ok(),
+            path,
+            code,
+            message,
+            metrics: cfg!(feature = "metrics").then(Metrics::snapshot),
+        }
+    }
+
+    fn field(&self, name: &str) -> Result<String, String> {
+        Ok(match name {
+            "input" => self.input.clone(),
+            "definition" => self.definition.clone().unwrap_or_default(),
+            "valid" => self.valid.to_string(),
+            "status" => if self.valid { "passed" } else { "failed" }.to_string(),
+            "path" => self.path.clone().unwrap_or_default(),
+            "code" => self.code.clone().unwrap_or_default(),
+            "message" => self.message.clone().unwrap_or_default(),
+            _ => return Err(format!("Unknown field `{name}` in the report template")),
+        })
+    }
+
+    fn is_true(&self, name: &str) -> Result<bool, String> {
+        match name {
+            "valid" | "status" => Ok(self.valid),
+            _ => Ok(!self.field(name)?.is_empty()),
+        }
+    }
+
+    /// Renders the report with `template`
+    pub fn render(&self, template: &str) -> Result<String, String> {
+        let mut output = String::new();
+        // Whether each open `{{#if}}` section is currently written, and if it got an `{{else}}`
+        let mut sections: Vec<(bool, bool)> = Vec::new();
+        let mut rest = template;
+
+        while let Some(start) = rest.find("{{") {
+            let writing = sections.iter().all(|(condition, _)| *condition);
+            if writing {
+                output.push_str(&rest[..start]);
+            }
+            let Some(end) = rest[start..].find("}}") else {
+                return Err("Unclosed `{{` in the report template".to_string());
+            };
+            let tag = rest[start + 2..start + end].trim();
+            rest = &rest[start + end + 2..];
+
+            if let Some(condition) = tag.strip_prefix("#if ") {
+                sections.push((self.is_true(condition.trim())?, false));
+            } else if tag == "else" {
+                match sections.last_mut() {
+                    Some((condition, seen_else @ false)) => {
+                        *condition = !*condition;
+                        *seen_else = true;
+                    }
+                    _ => return Err("`{{else}}` outside of an `{{#if}}`".to_string()),
+                }
+            } else if tag == "/if" {
+                if sections.pop().is_none() {
+                    return Err("`{{/if}}` without an `{{#if}}`".to_string());
+                }
+            } else {
+                let value = self.field(tag)?;
+                if writing {
+                    output.push_str(&value);
+                }
+            }
+        }
+
+        if !sections.is_empty() {
+            return Err("`{{#if}}` without an `{{/if}}`".to_string());
+        }
+        output.push_str(rest);
+        Ok(output)
+    }
+}
+
+/// Writes a report, so that every surface prints reports the same way and embedders can supply
+/// their own rendering
+pub trait ErrorFormatter: std::fmt::Debug + Send + Sync {
+    fn format(&self, report: &ValidationReport) -> String;
+}
+
+/// `✅ data.json is valid`, or `❌ data.json: <message> at <path>`
+#[derive(Debug, Default, Clone, Copy)]
+pub struct TextFormatter;
+
+impl ErrorFormatter for TextFormatter {
+    fn format(&self, report: &ValidationReport) -> String {
+        match (&report.message, &report.path) {
+            (Some(message), Some(path)) => format!("❌ {}: {message} at {path}", report.input),
+            _ => format!("✅ {} is valid", report.input),
+        }
+    }
+}
+
+/// The serialized report, on one line
+#[derive(Debug, Default, Clone, Copy)]
+pub struct JsonFormatter;
+
+impl ErrorFormatter for JsonFormatter {
+    fn format(&self, report: &ValidationReport) -> String {
+        serde_json::to_string(report).unwrap()
+    }
+}
+
+/// The serialized report, as a YAML mapping
+#[derive(Debug, Default, Clone, Copy)]
+pub struct YamlFormatter;
+
+impl ErrorFormatter for YamlFormatter {
+    fn format(&self, report: &ValidationReport) -> String {
+        serde_yaml::to_string(report)
+            .unwrap()
+            .trim_end()
+            .to_string()
+    }
+}
+
+/// `passed data.json`, or `failed data.json E_MAX_INT ROOT -> count`, for grep and awk
+#[derive(Debug, Default, Clone, Copy)]
+pub struct CompactFormatter;
+
+impl ErrorFormatter for CompactFormatter {
+    fn format(&self, report: &ValidationReport) -> String {
+        match (&report.code, &report.path) {
+            (Some(code), Some(path)) => format!("failed {} {code} {path}", report.input),
+            _ => format!("passed {}", report.input),
+        }
+    }
+}
@@ -0,0 +1,91 @@
+//! Random subsets of large corpora and streams, for quick data-quality checks. A sample is
+//! a share of the records, `1%`, or a number of them, `1000`, and the same seed picks the same
+//! records again.
+
+/// Share or number of the records kept
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum Sample {
+    /// Each record is kept with this probability, between 0 and 1
+    Fraction(f64),
+    /// Records kept at most, chosen uniformly among all of them
+    Count(usize),
+}
+
+impl std::str::FromStr for Sample {
+    type Err = String;
+
+    fn from_str(sample: &str) -> Result<Sample, String> {
+        let invalid = || format!("`{sample}` is not a percentage such as `1%` nor a count");
+        match sample.trim().strip_suffix('%') {
+            Some(percent) => {
+                let percent: f64 = percent.trim().parse().map_err(|_| invalid())?;
+                match (0.0..=100.0).contains(&percent) {
+                    true => Ok(Sample::Fraction(percent / 100.0)),
+                    false => Err(invalid()),
+                }
+            }
+            None => sample
+                .trim()
+                .parse()
+                .map(Sample::Count)
+                .map_err(|_| invalid()),
+        }
+    }
+}
+
+/// Records of `items` picked by `sample`, in their original order. A fraction is picked as
+/// the records are read, while a count keeps its records until the last one is read
+pub fn sample<'a, T: 'a>(
+    items: impl IntoIterator<Item = T> + 'a,
+    sample: Sample,
+    seed: u64,
+) -> Box<dyn Iterator<Item = T> + 'a> {
+    let mut random = SplitMix64(seed);
+    match sample {
+        Sample::Fraction(fraction) => {
+            // Compared on 53 bits, the precision of an f64
+            let threshold = (fraction * (1u64 << 53) as f64) as u64;
+            Box::new(
+                items
+                    .into_iter()
+                    .filter(move |_| random.next() >> 11 < threshold),
+            )
+        }
+        Sample::Count(limit) => {
+            // Reservoir sampling, the items being read once
+            let mut reservoir = Vec::with_capacity(limit);
+            for (index, item) in items.into_iter().enumerate() {
+                if reservoir.len() < limit {
+                    reservoir.push((index, item));
+                } else {
+                    let picked = (random.next() % (index as u64 + 1)) as usize;
+                    if picked < limit {
+                        reservoir[picked] = (index, item);
+                    }
+                }
+            }
+            reservoir.sort_by_key(|(index, _)| *index);
+            Box::new(reservoir.into_iter().map(|(_, item)| item))
+        }
+    }
+}
+
+/// Seed changing at every run
+pub fn random_seed() -> u64 {
+    std::time::SystemTime::now()
+        .duration_since(std::time::UNIX_EPOCH)
+        .map_or(0, |elapsed| elapsed.as_nanos() as u64)
+}
+
+/// Small generator for the sampling, which doesn't need to be cryptographic
+struct SplitMix64(u64);
+
+impl SplitMix64 {
+    fn next(&mut self) -> u64 {
+        self.0 = self.0.wrapping_add(0x9e3779b97f4a7c15);
+        let mut z = self.0;
+        z = (z ^ (z >> 30)).wrapping_mul(0xbf58476d1ce4e5b9);
+        z = (z ^ (z >> 27)).wrapping_mul(0x94d049bb133111eb);
+        z ^ (z >> 31)
+    }
+}
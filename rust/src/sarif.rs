@@ -0,0 +1,68 @@
+//! Validation errors as a SARIF 2.1.0 log, the format read by the code scanning UIs of GitHub
+//! and Azure DevOps. Each error is a result whose rule is its stable code, located in the input
+//! file by line and column when the [`SourceMap`] has its value
+
+use crate::{
+    error::{AS3ValidationError, As3JsonPath},
+    messages::{self, English},
+    spans::SourceMap,
+    validator::ValidationOutcome,
+};
+use serde_json::{json, Value};
+
+/// SARIF log of the errors and warnings of `outcome`, found in the file at `uri`
+pub fn to_sarif(uri: &str, outcome: &ValidationOutcome, spans: &SourceMap) -> Value {
+    let mut rules: Vec<&str> = Vec::new();
+    let mut results = Vec::new();
+    let found = outcome
+        .errors
+        .iter()
+        .map(|error| ("error", error))
+        .chain(outcome.warnings.iter().map(|warning| ("warning", warning)));
+    for (level, error) in found {
+        let code = error.1.code();
+        let index = match rules.iter().position(|rule| *rule == code) {
+            Some(index) => index,
+            None => {
+                rules.push(code);
+                rules.len() - 1
+            }
+        };
+        results.push(json!({
+            "ruleId": code,
+            "ruleIndex": index,
+            "level": level,
+            "message": { "text": messages::render(&error.1, &English) },
+            "locations": [location(uri, error, spans)],
+        }));
+    }
+    json!({
+        "$schema": "https://json.schemastore.org/sarif-2.1.0.json",
+        "version": "2.1.0",
+        "runs": [{
+            "tool": {
+                "driver": {
+                    "name": "as3",
+                    "version": env!("CARGO_PKG_VERSION"),
+                    "rules": rules.iter().map(|rule| json!({ "id": rule })).collect::<Vec<_>>(),
+                },
+            },
+            "results": results,
+        }],
+    })
+}
+
+/// File and, when known, region of `error`, along with its path in the data
+fn location(uri: &str, error: &As3JsonPath<AS3ValidationError>, spans: &SourceMap) -> Value {
+    let mut physical = json!({ "artifactLocation": { "uri": uri } });
+    if let Some(position) = spans.locate_error(error) {
+        physical["region"] = json!({
+            "startLine": position.line,
+            "startColumn": position.column,
+        });
+    }
+    json!({
+        "physicalLocation": physical,
+        "logicalLocations": [{ "fullyQualifiedName": error.0.to_string() }],
+    })
+}
@@ -0,0 +1,165 @@
+//! Lines and columns of the values of a document, so that the CLI can point at the value of an
+//! error in its file: `data.json:42:7: Expected Integer, …`. The positions are read by parsing
+//! the document a second time, [`AS3Data`](crate::AS3Data) being left without them
+
+use crate::{
+    diagnostic::Format,
+    error::{AS3ValidationError, As3JsonPath, JsonPath, PathSegment},
+};
+use saphyr_parser::{Event, Parser};
+use std::collections::HashMap;
+
+/// Line and column of a value, both starting at 1
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Position {
+    pub line: usize,
+    pub column: usize,
+}
+
+impl std::fmt::Display for Position {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "{}:{}", self.line, self.column)
+    }
+}
+
+/// Positions of the values of a document, by their path from `ROOT`
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct SourceMap(pub HashMap<JsonPath, Position>);
+
+impl SourceMap {
+    /// Positions of the values of `source`. JSON and YAML are read up to their first syntax
+    /// error and TOML as a whole, while the other formats have no positions
+    pub fn new(source: &str, format: Format) -> SourceMap {
+        let mut map = SourceMap::default();
+        let root = JsonPath::root("ROOT");
+        match format {
+            Format::Json | Format::Yaml => {
+                let mut events =
+                    Parser::new_from_str(source)
+                        .map_while(Result::ok)
+                        .map(|(event, span)| {
+                            let position = Position {
+                                line: span.start.line(),
+                                column: span.start.col() + 1,
+                            };
+                            (event, position)
+                        });
+                // The first document only, as read by the validation
+                if let Some((event, position)) = events.find(|(event, _)| {
+                    !matches!(
+                        event,
+                        Event::StreamStart | Event::DocumentStart(_) | Event::Nothing
+                    )
+                }) {
+                    map.yaml(&mut events, event, position, &mut root.clone());
+                }
+            }
+            Format::Toml => {
+                if let Ok(table) = toml::de::DeTable::parse(source) {
+                    let value = toml::de::DeValue::Table(table.into_inner());
+                    map.toml(source, &value, 0, &mut root.clone());
+                }
+            }
+            Format::Hcl | Format::Ini | Format::Properties => {}
+        }
+        map
+    }
+
+    /// Position of the value at `path` or, when it has none such as a missing key, of the
+    /// closest value containing it
+    pub fn locate(&self, path: &JsonPath) -> Option<Position> {
+        (1..=path.0.len())
+            .rev()
+            .find_map(|length| self.0.get(&JsonPath(path.0[..length].to_vec())))
+            .copied()
+    }
+
+    /// Position of the value of `error`, that of the key itself for an unexpected key
+    pub fn locate_error(&self, error: &As3JsonPath<AS3ValidationError>) -> Option<Position> {
+        match error.1.underlying() {
+            AS3ValidationError::UnexpectedKey { key, .. } => {
+                let mut path = error.0.clone();
+                path.0.push(PathSegment::Key(key.clone()));
+                self.locate(&path)
+            }
+            _ => self.locate(&error.0),
+        }
+    }
+
+    /// Records the node starting with `event` and what it contains. `None` when the events
+    /// stop before the end of the node
+    fn yaml<'i>(
+        &mut self,
+        events: &mut impl Iterator<Item = (Event<'i>, Position)>,
+        event: Event<'i>,
+        position: Position,
+        path: &mut JsonPath,
+    ) -> Option<()> {
+        self.0.insert(path.clone(), position);
+        match event {
+            Event::SequenceStart(..) => {
+                for index in 0.. {
+                    let (item, position) = events.next()?;
+                    if item == Event::SequenceEnd {
+                        break;
+                    }
+                    path.0.push(PathSegment::Index(index));
+                    self.yaml(events, item, position, path)?;
+                    path.0.pop();
+                }
+            }
+            Event::MappingStart(..) => loop {
+                let (key, key_position) = events.next()?;
+                let key = match key {
+                    Event::MappingEnd => break,
+                    Event::Scalar(key, ..) => PathSegment::Key(key.into_owned()),
+                    // Complex keys aren't read by the validation, only skipped
+                    key => {
+                        self.yaml(events, key, key_position, &mut JsonPath::default())?;
+                        PathSegment::Key(String::new())
+                    }
+                };
+                let (value, position) = events.next()?;
+                path.0.push(key);
+                self.yaml(events, value, position, path)?;
+                path.0.pop();
+            },
+            _ => {}
+        }
+        Some(())
+    }
+
+    /// Records `value`, starting at byte `start` of `source`, and what it contains
+    fn toml(&mut self, source: &str, value: &toml::de::DeValue, start: usize, path: &mut JsonPath) {
+        self.0.insert(path.clone(), position(source, start));
+        match value {
+            toml::de::DeValue::Array(items) => {
+                for (index, item) in items.iter().enumerate() {
+                    path.0.push(PathSegment::Index(index));
+                    self.toml(source, item.get_ref(), item.span().start, path);
+                    path.0.pop();
+                }
+            }
+            toml::de::DeValue::Table(table) => {
+                for (key, value) in table {
+                    path.0.push(PathSegment::Key(key.get_ref().to_string()));
+                    self.toml(source, value.get_ref(), value.span().start, path);
+                    path.0.pop();
+                }
+            }
+            _ => {}
+        }
+    }
+}
+
+/// Line and column of byte `offset` of `source`
+fn position(source: &str, offset: usize) -> Position {
+    let before = &source[..offset.min(source.len())];
+    Position {
+        line: before.matches('\n').count() + 1,
+        column: before[before.rfind('\n').map_or(0, |index| index + 1)..]
+            .chars()
+            .count()
+            + 1,
+    }
+}
@@ -0,0 +1,199 @@
+//! Per-field data-quality metrics over a corpus of documents, exported as a Prometheus
+//! textfile or as JSON for dashboards.
+//!
+//! Fields are named by their validation path. Items of a `List` are grouped under
+//! `-> []` and values of a `Map` under `-> *`.
+
+use crate::{
+    error::{AS3ValidationError, As3JsonPath, JsonPath},
+    validator::AS3Validator,
+    AS3Data,
+};
+use serde::Serialize;
+use std::collections::BTreeMap;
+
+/// Value of a field metric, `None` when it doesn't apply
+type Metric = fn(&FieldStats) -> Option<f64>;
+
+#[derive(Debug, Default, PartialEq, Serialize)]
+pub struct FieldStats {
+    /// Values found, `null` included
+    pub values: usize,
+    pub nulls: usize,
+    /// Documents where the field was required and absent
+    pub missing: usize,
+    /// Values rejected by the validator of the field itself
+    pub violations: usize,
+    /// Smallest and largest number seen
+    pub minimum: Option<f64>,
+    pub maximum: Option<f64>,
+}
+
+impl FieldStats {
+    pub fn null_rate(&self) -> f64 {
+        match self.values {
+            0 => 0.0,
+            values => self.nulls as f64 / values as f64,
+        }
+    }
+
+    fn observe(&mut self, number: f64) {
+        self.minimum = Some(self.minimum.map_or(number, |minimum| minimum.min(number)));
+        self.maximum = Some(self.maximum.map_or(number, |maximum| maximum.max(number)));
+    }
+}
+
+#[derive(Debug, Default, PartialEq, Serialize)]
+pub struct CorpusStats {
+    pub documents: usize,
+    pub fields: BTreeMap<String, FieldStats>,
+}
+
+impl CorpusStats {
+    pub fn add(&mut self, validator: &AS3Validator, document: &AS3Data) {
+        self.documents += 1;
+        self.walk(
+            validator,
+            document,
+            "ROOT".to_string(),
+            &mut JsonPath::root("ROOT"),
+        );
+    }
+
+    /// Walk of `data` at the validation `path`, counted in the field `name`
+    fn walk(
+        &mut self,
+        validator: &AS3Validator,
+        data: &AS3Data,
+        name: String,
+        path: &mut JsonPath,
+    ) {
+        match validator {
+            AS3Validator::Ref(reference) => {
+                if let Some(target) = reference.target() {
+                    return self.walk(&target, data, name, path);
+                }
+            }
+            AS3Validator::TaggedUnion { .. } => {
+                if let Some(variant) = validator.variant_of(data) {
+                    return self.walk(variant, data, name, path);
+                }
+            }
+            _ => {}
+        }
+        let stats = self.fields.entry(name.clone()).or_default();
+        stats.values += 1;
+        match data {
+            AS3Data::Null => stats.nulls += 1,
+            AS3Data::Integer(number) => stats.observe(*number as f64),
+            AS3Data::UInteger(number) => stats.observe(*number as f64),
+            AS3Data::Decimal(number) => stats.observe(*number),
+            _ => {}
+        }
+        match validator.check(data, path) {
+            // Reported on the missing field instead
+            Err(As3JsonPath(_, error))
+                if matches!(error.underlying(), AS3ValidationError::MissingKey { .. }) => {}
+            Err(As3JsonPath(error_path, _)) if error_path == *path => stats.violations += 1,
+            _ => {}
+        }
+
+        let validator = match validator {
+            AS3Validator::Nullable(inner) | AS3Validator::Annotated { inner, .. } => inner,
+            validator => validator,
+        };
+        let validator = match validator {
+            AS3Validator::Nullable(inner) | AS3Validator::Annotated { inner, .. } => inner,
+            validator => validator,
+        };
+        // Fields of the conditional branches are only counted when they are also in the base
+        let validator = match validator {
+            AS3Validator::Conditional { base, .. } => base,
+            validator => validator,
+        };
+        match (validator, data) {
+            (AS3Validator::Object { fields, .. }, AS3Data::Object(values)) => {
+                for (key, field) in fields {
+                    let field_name = format!("{name} -> {key}");
+                    match values.get(key) {
+                        Some(value) => {
+                            path.push_key(key);
+                            self.walk(field, value, field_name, path);
+                            path.pop();
+                        }
+                        None => self.fields.entry(field_name).or_default().missing += 1,
+                    }
+                }
+            }
+            (AS3Validator::List(items_type), AS3Data::List(items)) => {
+                for (index, item) in items.iter().enumerate() {
+                    path.push_index(index);
+                    self.walk(items_type, item, format!("{name} -> []"), path);
+                    path.pop();
+                }
+            }
+            (AS3Validator::Map { value_type, .. }, AS3Data::Object(values)) => {
+                for (key, value) in values {
+                    path.push_key(key);
+                    self.walk(value_type, value, format!("{name} -> *"), path);
+                    path.pop();
+                }
+            }
+            _ => {}
+        }
+    }
+
+    /// Prometheus text exposition format, for the node exporter textfile collector
+    pub fn to_prometheus(&self) -> String {
+        let mut output = format!(
+            "# HELP as3_documents Documents in the corpus\n# TYPE as3_documents gauge\nas3_documents {}\n",
+            self.documents
+        );
+        let metrics: [(&str, &str, Metric); 7] = [
+            ("values", "Values found", |s| Some(s.values as f64)),
+            ("nulls", "Null values", |s| Some(s.nulls as f64)),
+            ("null_rate", "Share of null values", |s| Some(s.null_rate())),
+            ("missing", "Documents without the field", |s| {
+                Some(s.missing as f64)
+            }),
+            (
+                "violations",
+                "Values rejected by the field validator",
+                |s| Some(s.violations as f64),
+            ),
+            ("minimum", "Smallest number seen", |s| s.minimum),
+            ("maximum", "Largest number seen", |s| s.maximum),
+        ];
+        for (name, help, value) in metrics {
+            output.push_str(&format!(
+                "# HELP as3_field_{name} {help}\n# TYPE as3_field_{name} gauge\n"
+            ));
+            for (field, stats) in &self.fields {
+                if let Some(value) = value(stats) {
+                    output.push_str(&format!(
+                        "as3_field_{name}{{field=\"{}\"}} {value}\n",
+                        escape_label(field)
+                    ));
+                }
+            }
+        }
+        output
+    }
+
+    /// JSON sample, timestamped to be appended to a time-series
+    pub fn to_json(&self, time: &str) -> serde_json::Value {
+        let mut json = serde_json::to_value(self).unwrap();
+        json["time"] = time.into();
+        for (field, stats) in &self.fields {
+            json["fields"][field]["null_rate"] = stats.null_rate().into();
+        }
+        json
+    }
+}
+
+fn escape_label(value: &str) -> String {
+    value
+        .replace('\\', r"\\")
+        .replace('"', "\\\"")
+        .replace('\n', r"\n")
+}
@@ -0,0 +1,115 @@
+//! Contract testing of recorded API interactions. A definition can declare several roots, such
+//! as `Request` and `Response`, next to each other:
+//!
+//! ```yaml
+//! Request:
+//!   +type: Object
+//!   method: String
+//! Response:
+//!   +type: Object
+//!   status: Integer(min=100, max=599)
+//! ```
+//!
+//! A transcript is a List of exchanges, or a single exchange, each holding a value for every
+//! root under its name in any case (`request` and `response` here). Other keys of an exchange,
+//! such as a timestamp, are not checked.
+
+use crate::{
+    error::{AS3DefinitionError, AS3ValidationError, As3JsonPath, JsonPath},
+    validator::{AS3Validator, DefinitionOptions},
+    AS3Data,
+};
+use std::collections::BTreeMap;
+
+/// Validators of the roots of a definition, by root name
+#[derive(Debug, Clone)]
+pub struct TranscriptValidator {
+    roots: BTreeMap<String, AS3Validator>,
+}
+
+impl TranscriptValidator {
+    /// Builds every top-level key of `yaml_config` but the `+` keywords, sharing its `+defs`
+    pub fn from_with_options(
+        yaml_config: &serde_yaml::Value,
+        options: &DefinitionOptions,
+    ) -> Result<TranscriptValidator, As3JsonPath<AS3DefinitionError>> {
+        let serde_yaml::Value::Mapping(inner) = yaml_config else {
+            return Err(As3JsonPath(
+                JsonPath::root("Root"),
+                AS3DefinitionError::NotAMapping,
+            ));
+        };
+        let mut roots = BTreeMap::new();
+        for key in inner.keys() {
+            let Some(root) = key.as_str().filter(|key| !key.starts_with('+')) else {
+                continue;
+            };
+            let validator = AS3Validator::from_root_with_options(yaml_config, root, options)?;
+            roots.insert(root.to_string(), validator.optimize());
+        }
+        if roots.is_empty() {
+            return Err(As3JsonPath(
+                JsonPath::root("Root"),
+                AS3DefinitionError::Generic("a transcript definition needs roots".to_string()),
+            ));
+        }
+        Ok(TranscriptValidator { roots })
+    }
+
+    /// Names of the roots, sorted
+    pub fn roots(&self) -> impl Iterator<Item = &str> {
+        self.roots.keys().map(String::as_str)
+    }
+
+    pub fn root(&self, name: &str) -> Option<&AS3Validator> {
+        self.roots.get(name)
+    }
+
+    /// Validates each exchange of `transcript`, stopping at the first error. Exchanges of a
+    /// List are named by their index in the error path, such as `ROOT -> [2] -> response`
+    #[allow(clippy::result_large_err)]
+    pub fn validate(&self, transcript: &AS3Data) -> Result<(), As3JsonPath<AS3ValidationError>> {
+        match transcript {
+            AS3Data::List(exchanges) => {
+                for (index, exchange) in exchanges.iter().enumerate() {
+                    self.validate_exchange(exchange, &JsonPath::root("ROOT").with_index(index))?;
+                }
+                Ok(())
+            }
+            exchange => self.validate_exchange(exchange, &JsonPath::root("ROOT")),
+        }
+    }
+
+    #[allow(clippy::result_large_err)]
+    fn validate_exchange(
+        &self,
+        exchange: &AS3Data,
+        path: &JsonPath,
+    ) -> Result<(), As3JsonPath<AS3ValidationError>> {
+        let AS3Data::Object(values) = exchange else {
+            return Err(As3JsonPath(
+                path.clone(),
+                AS3ValidationError::Generic(format!(
+                    "an exchange must be an Object with the {} keys",
+                    self.roots().collect::<Vec<_>>().join(", ")
+                )),
+            ));
+        };
+        for (root, validator) in &self.roots {
+            let Some((key, value)) = values
+                .iter()
+                .find(|(key, _)| key.eq_ignore_ascii_case(root))
+            else {
+                return Err(As3JsonPath(
+                    path.clone(),
+                    AS3ValidationError::MissingKey {
+                        key: root.to_lowercase(),
+                        suggestion: None,
+                    },
+                ));
+            };
+            validator.check(value, &mut path.with_key(key))?;
+        }
+        Ok(())
+    }
+}
@@ -1,27 +1,56 @@
 use crate::{
-    error::{AS3ValidationError, As3JsonPath},
+    error::{AS3DefinitionError, AS3ValidationError, As3JsonPath, Cause, JsonPath},
+    fetch::Fetcher,
+    metrics::{self, Counter},
     AS3Data,
 };
 
+#[cfg(all(feature = "parallel", not(target_os = "wasi")))]
 use rayon::prelude::*;
-use regex::Regex;
+use regex::{Regex, RegexSet};
 use serde::{Deserialize, Serialize};
-use std::collections::HashMap;
+#[cfg(feature = "std")]
+use std::path::{Path, PathBuf};
+use std::{
+    collections::{BTreeMap, HashMap, HashSet},
+    num::NonZeroUsize,
+    sync::{Arc, OnceLock, Weak},
+};
+use unicode_segmentation::UnicodeSegmentation;
 
 #[derive(Debug, PartialEq, Clone, Serialize, Deserialize)]
 pub enum AS3Validator {
     #[serde(rename(serialize = "Object"))]
-    Object(HashMap<String, AS3Validator>),
+    Object {
+        fields: HashMap<String, AS3Validator>,
+        extra_keys: ExtraKeys,
+        pattern_keys: Option<PatternKeys>,
+        assertions: Vec<Assertion>,
+        /// Keys that must be present whenever the key they are listed under is
+        requires: BTreeMap<String, Vec<String>>,
+        /// Keys of `fields` in the order of the definition
+        order: Vec<String>,
+    },
     #[serde(rename(serialize = "String"))]
     String {
-        regex: Option<String>,
+        regex: Option<RegexPattern>,
         max_length: Option<i64>,
         min_length: Option<i64>,
+        format: Option<StringFormat>,
+        any_regex: Option<RegexList>,
+        deny_regex: Option<RegexList>,
+        /// Classes every character must belong to
+        #[serde(default, skip_serializing_if = "Vec::is_empty")]
+        char_classes: Vec<CharClass>,
+        /// What `min_length` and `max_length` count
+        #[serde(default, skip_serializing_if = "LengthUnit::is_default")]
+        length_unit: LengthUnit,
     },
     #[serde(rename(serialize = "Integer"))]
     Integer {
         minimum: Option<i64>,
         maximum: Option<i64>,
+        width: Option<IntegerWidth>,
     },
     #[serde(rename(serialize = "Decimal"))]
     Decimal {
@@ -34,473 +63,3948 @@ pub enum AS3Validator {
     Map {
         key_type: Box<AS3Validator>,
         value_type: Box<AS3Validator>,
+        min_entries: Option<i64>,
+        max_entries: Option<i64>,
     },
     #[serde(rename(serialize = "Bool"))]
     Boolean,
+    /// Only accepts `null`, unlike [`AS3Validator::Nullable`] which also accepts its inner type
+    #[serde(rename(serialize = "Null"))]
+    Null,
+    /// Accepts any value, for fields intentionally left untyped
+    #[serde(rename(serialize = "Any"))]
+    Any,
     #[serde(rename(serialize = "Date"))]
-    Date,
+    Date {
+        minimum: Option<DateBound>,
+        maximum: Option<DateBound>,
+    },
     #[serde(rename(serialize = "Nullable"))]
     Nullable(Box<AS3Validator>),
+    /// Definition of `+defs` used with `+ref`
+    #[serde(rename(serialize = "Ref"))]
+    Ref(Reference),
+    /// `inner` with keywords that don't change the values it accepts
+    #[serde(rename(serialize = "Annotated"))]
+    Annotated {
+        inner: Box<AS3Validator>,
+        annotations: Annotations,
+    },
+    /// `base`, followed by `then` for the values accepted by `condition` and by `otherwise` for
+    /// the others. Declared on an Object with `+if`, `+then` and `+else`
+    #[serde(rename(serialize = "Conditional"))]
+    Conditional {
+        base: Box<AS3Validator>,
+        condition: Box<AS3Validator>,
+        then: Option<Box<AS3Validator>>,
+        otherwise: Option<Box<AS3Validator>>,
+    },
+    /// String that must be one of `values`. Produced by [`AS3Validator::optimize`] from an
+    /// anchored `+regex` of literals, such as `^active$` or `^(draft|published)$`, which is
+    /// kept to report the same error as the original
+    #[serde(rename(serialize = "Literal"))]
+    Literal {
+        #[serde(serialize_with = "sorted_values")]
+        values: HashSet<String>,
+        regex: String,
+    },
+    #[serde(rename(serialize = "DateTime"))]
+    DateTime {
+        minimum: Option<chrono::DateTime<chrono::FixedOffset>>,
+        maximum: Option<chrono::DateTime<chrono::FixedOffset>>,
+    },
+    #[serde(rename(serialize = "Time"))]
+    Time {
+        minimum: Option<chrono::NaiveTime>,
+        maximum: Option<chrono::NaiveTime>,
+    },
+    #[serde(rename(serialize = "Duration"))]
+    Duration {
+        syntax: DurationSyntax,
+        minimum: Option<std::time::Duration>,
+        maximum: Option<std::time::Duration>,
+    },
+    #[serde(rename(serialize = "Bytes"))]
+    Bytes {
+        encoding: BytesEncoding,
+        min_bytes: Option<i64>,
+        max_bytes: Option<i64>,
+    },
+    /// Object validated by the variant named by its `discriminator` String field
+    #[serde(rename(serialize = "TaggedUnion"))]
+    TaggedUnion {
+        discriminator: String,
+        variants: BTreeMap<String, AS3Validator>,
+    },
 }
 
-impl AS3Validator {
-    pub fn validate(&self, data: &AS3Data) -> Result<(), As3JsonPath<AS3ValidationError>> {
-        self.check(data, &mut "ROOT".to_string())
-    }
+/// Notations accepted by a `Duration`
+#[derive(Debug, PartialEq, Clone, Copy, Serialize, Deserialize)]
+pub enum DurationSyntax {
+    /// ISO 8601 durations such as `PT30M` or `P1DT12H`
+    Iso8601,
+    /// Human friendly durations such as `90s` or `2h 30min`
+    Humantime,
+    /// Either of them
+    Any,
+}
 
-    fn check(
-        &self,
-        data: &AS3Data,
-        path: &mut String,
-    ) -> Result<(), As3JsonPath<AS3ValidationError>> {
-        match (self, data) {
-            (AS3Validator::Nullable(..), AS3Data::Null) => return Ok(()),
-            (_, AS3Data::Null) => {
-                return Err(As3JsonPath(
-                    path.to_string(),
-                    AS3ValidationError::NotNullableNull,
-                ))
+impl DurationSyntax {
+    fn parse(&self, string: &str) -> Result<std::time::Duration, String> {
+        match self {
+            DurationSyntax::Iso8601 => parse_iso8601_duration(string),
+            DurationSyntax::Humantime => {
+                humantime::parse_duration(string).map_err(|e| e.to_string())
             }
-            _ => {}
-        };
-
-        match (self, data) {
-            (AS3Validator::Object(validator_inner), AS3Data::Object(data_inner)) => {
-                let res: Vec<Result<(), As3JsonPath<AS3ValidationError>>> = validator_inner
-                    .into_par_iter()
-                    .map(|(validator_key, validator_value)| {
-                        let mut temp_path = path.clone();
-                        temp_path.push_str(" -> ");
-                        temp_path.push_str(&validator_key.as_str());
-                        if let Some(value_from_key) = data_inner.get(validator_key) {
-                            return validator_value.check(value_from_key, &mut temp_path);
-                        }
-                        Err(As3JsonPath(
-                            path.to_string(),
-                            AS3ValidationError::MissingKey {
-                                key: validator_key.clone(),
-                            },
-                        ))
-                    })
-                    .collect();
+            DurationSyntax::Any if string.starts_with('P') => parse_iso8601_duration(string),
+            DurationSyntax::Any => humantime::parse_duration(string).map_err(|e| e.to_string()),
+        }
+    }
+}
 
-                match res
-                    .into_iter()
-                    .collect::<Result<Vec<()>, As3JsonPath<AS3ValidationError>>>()
-                {
-                    Ok(_) => Ok(()),
-                    Err(e) => Err(e),
-                }
-            }
-            (
-                AS3Validator::Map {
-                    key_type,
-                    value_type,
-                },
-                AS3Data::Object(data_inner),
-            ) => {
-                for (key_data, value_data) in data_inner {
-                    let mut temp_path = path.clone();
-                    temp_path.push_str(" -> ");
-                    temp_path.push_str(&key_data.as_str());
-                    match (
-                        value_type.check(value_data, &mut temp_path),
-                        AS3Validator::check_map_key_value(key_data, key_type, &mut temp_path),
-                    ) {
-                        (Ok(_), Ok(_)) => {}
-                        (Err(e), _) => return Err(e),
-                        (_, Err(e)) => {
-                            return Err(As3JsonPath(
-                                temp_path.to_string(),
-                                AS3ValidationError::Generic(e),
-                            ))
-                        }
-                    };
-                }
-                Ok(())
-            }
-            (AS3Validator::Integer { minimum, maximum }, AS3Data::Integer(number)) => {
-                if let Some(minimum) = minimum {
-                    if number < minimum {
-                        return Err(As3JsonPath(
-                            path.to_string(),
-                            AS3ValidationError::MinimumInteger {
-                                number: *number,
-                                minimum: *minimum,
-                            },
-                        ));
-                    }
-                }
+/// Parses the `PnW` and `PnDTnHnMnS` forms of ISO 8601 durations. Years and months are
+/// rejected as they don't have a fixed length, and each unit can only appear once, in that
+/// order
+fn parse_iso8601_duration(string: &str) -> Result<std::time::Duration, String> {
+    let Some(rest) = string.strip_prefix('P') else {
+        return Err("ISO 8601 durations start with `P`".to_string());
+    };
+    if rest.is_empty() || rest == "T" || rest.ends_with('T') {
+        return Err("duration has no components".to_string());
+    }
 
-                if let Some(maximum) = maximum {
-                    if number > maximum {
-                        return Err(As3JsonPath(
-                            path.to_string(),
-                            AS3ValidationError::MaximumInteger {
-                                number: *number,
-                                maximum: *maximum,
-                            },
+    let mut seconds = 0f64;
+    let mut time = false;
+    // Position of the last unit in `UNITS`
+    let mut last = None;
+    let mut number = String::new();
+    for c in rest.chars() {
+        match c {
+            'T' if !time && number.is_empty() => time = true,
+            '0'..='9' | '.' | ',' => number.push(if c == ',' { '.' } else { c }),
+            unit => {
+                let value: f64 = number
+                    .parse()
+                    .map_err(|_| format!("missing or invalid number before `{unit}`"))?;
+                number.clear();
+                const UNITS: [(char, bool); 5] = [
+                    ('W', false),
+                    ('D', false),
+                    ('H', true),
+                    ('M', true),
+                    ('S', true),
+                ];
+                if let Some(position) = UNITS.iter().position(|known| *known == (unit, time)) {
+                    if last.is_some_and(|last| position <= last) {
+                        return Err(format!(
+                            "`{unit}` is repeated or out of order, units go as W, D, T, H, M, S"
                         ));
                     }
+                    last = Some(position);
                 }
-                Ok(())
+                seconds +=
+                    value
+                        * match (unit, time) {
+                            ('W', false) => 604800.0,
+                            ('D', false) => 86400.0,
+                            ('H', true) => 3600.0,
+                            ('M', true) => 60.0,
+                            ('S', true) => 1.0,
+                            ('Y' | 'M', false) => return Err(
+                                "years and months are not supported as they have no fixed length"
+                                    .to_string(),
+                            ),
+                            _ => return Err(format!("unexpected `{unit}`")),
+                        };
             }
-            (AS3Validator::Decimal { minimum, maximum }, AS3Data::Decimal(number)) => {
-                if let Some(minimum) = minimum {
-                    if number < minimum {
-                        return Err(As3JsonPath(
-                            path.to_string(),
-                            AS3ValidationError::MinimumDouble {
-                                number: *number as f64,
-                                minimum: *minimum as f64,
-                            },
-                        ));
-                    }
-                }
+        }
+    }
+    if !number.is_empty() {
+        return Err(format!("`{number}` is missing its unit"));
+    }
+    std::time::Duration::try_from_secs_f64(seconds)
+        .map_err(|_| "duration is too long to be represented".to_string())
+}
 
-                if let Some(maximum) = maximum {
-                    if number > maximum {
-                        return Err(As3JsonPath(
-                            path.to_string(),
-                            AS3ValidationError::MinimumDouble {
-                                number: *number as f64,
-                                minimum: *maximum as f64,
-                            },
-                        ));
-                    }
-                }
-                Ok(())
-            }
-            (
-                AS3Validator::String {
-                    regex,
-                    max_length,
-                    min_length,
-                },
-                AS3Data::String(string),
-            ) => {
-                if let Some(regex) = regex {
-                    let re = Regex::new(regex).unwrap();
-                    if !re.is_match(string) {
-                        return Err(As3JsonPath(
-                            path.to_string(),
-                            AS3ValidationError::RegexError {
-                                word: string.to_owned(),
-                                regex: regex.to_owned(),
-                            },
-                        ));
-                    }
-                };
-                if let Some(min_length) = min_length {
-                    if string.len() < *min_length as usize {
-                        return Err(As3JsonPath(
-                            path.to_string(),
-                            AS3ValidationError::MinimumString {
-                                string: string.clone(),
-                                current_lenght: string.len() as i64,
-                                min_length: *min_length,
-                            },
-                        ));
-                    }
-                }
+/// Text encoding of the binary payload accepted by a `Bytes`
+#[derive(Debug, PartialEq, Clone, Copy, Serialize, Deserialize)]
+pub enum BytesEncoding {
+    Base64,
+    Hex,
+}
 
-                if let Some(max_length) = max_length {
-                    if string.len() > *max_length as usize {
-                        return Err(As3JsonPath(
-                            path.to_string(),
-                            AS3ValidationError::MaximumString {
-                                string: string.clone(),
-                                current_lenght: string.len() as i64,
-                                max_length: *max_length,
-                            },
-                        ));
-                    }
-                }
+impl std::fmt::Display for BytesEncoding {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            BytesEncoding::Base64 => write!(f, "base64"),
+            BytesEncoding::Hex => write!(f, "hex"),
+        }
+    }
+}
 
-                Ok(())
-            }
-            (AS3Validator::List(items_type), AS3Data::List(items)) => {
-                // Ok(items.iter().all(|item| items_type.check(item)))
+/// What an `Object` does with keys that aren't among its fields
+#[derive(Debug, Default, PartialEq, Clone, Copy, Serialize, Deserialize)]
+pub enum ExtraKeys {
+    #[default]
+    Allow,
+    /// Rejected with an `UnexpectedKey` error
+    Forbid,
+    /// Accepted, and reported by [`AS3Validator::unexpected_keys`]
+    Warn,
+}
 
-                let res = items
-                    .iter()
-                    .map(|item| items_type.check(item, path))
-                    .collect::<Vec<Result<(), As3JsonPath<AS3ValidationError>>>>();
+/// Comparison between two fields of an `Object`, declared with `+assert: end >= start`. It is
+/// skipped when either field is missing or `null`
+#[derive(Debug, PartialEq, Clone, Serialize, Deserialize)]
+pub struct Assertion {
+    pub left: String,
+    pub operator: Comparison,
+    pub right: String,
+}
 
-                match res
-                    .into_iter()
-                    .collect::<Result<Vec<()>, As3JsonPath<AS3ValidationError>>>()
-                {
-                    Ok(_) => Ok(()),
-                    Err(e) => Err(e),
-                }
-            }
-            (AS3Validator::Date, AS3Data::String(items)) => {
-                // Ok(items.iter().all(|item| items_type.check(item)))
-                let date_regex =
-                    Regex::new(r"^\d{4}-(0[1-9]|1[0-2])-(0[1-9]|[12][0-9]|3[01])$").unwrap();
+#[derive(Debug, PartialEq, Clone, Copy, Serialize, Deserialize)]
+pub enum Comparison {
+    Equal,
+    NotEqual,
+    Less,
+    LessOrEqual,
+    Greater,
+    GreaterOrEqual,
+}
 
-                if !date_regex.is_match(items) {
-                    return Err(As3JsonPath(
-                        path.to_string(),
-                        AS3ValidationError::Generic(format!(
-                            " `{}` can't be converted to a valid date. [Supported YYYY-MM-DD] ",
-                            items
-                        )),
-                    ));
-                };
-                Ok(())
-            }
-            (AS3Validator::Boolean, AS3Data::Boolean(..)) => Ok(()),
+impl Comparison {
+    /// Two characters operators first, so that `>=` isn't read as `>`
+    const OPERATORS: [(&'static str, Comparison); 6] = [
+        ("==", Comparison::Equal),
+        ("!=", Comparison::NotEqual),
+        ("<=", Comparison::LessOrEqual),
+        (">=", Comparison::GreaterOrEqual),
+        ("<", Comparison::Less),
+        (">", Comparison::Greater),
+    ];
 
-            _ => Err(As3JsonPath(
-                path.to_string(),
-                AS3ValidationError::TypeError {
-                    expected: self.clone(),
-                    got: data.clone(),
-                },
-            )),
+    fn holds(&self, ordering: std::cmp::Ordering) -> bool {
+        use std::cmp::Ordering::*;
+        match self {
+            Comparison::Equal => ordering == Equal,
+            Comparison::NotEqual => ordering != Equal,
+            Comparison::Less => ordering == Less,
+            Comparison::LessOrEqual => ordering != Greater,
+            Comparison::Greater => ordering == Greater,
+            Comparison::GreaterOrEqual => ordering != Less,
         }
     }
+}
 
-    fn check_map_key_value(
-        key: &String,
-        wanted_type: &AS3Validator,
-        path: &mut String,
-    ) -> Result<(), String> {
-        let _ = match wanted_type {
-            AS3Validator::String { .. } => wanted_type.check(&AS3Data::String(key.clone()), path),
-            AS3Validator::Integer { .. } => {
-                let Ok(n) = key.clone().parse::<i64>() else {
-                    return Err(format!("The Key `{}` can't be converted to an Integer", key));
-                };
+impl std::fmt::Display for Comparison {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        let (symbol, _) = Comparison::OPERATORS
+            .iter()
+            .find(|(_, comparison)| comparison == self)
+            .unwrap();
+        write!(f, "{symbol}")
+    }
+}
 
-                match wanted_type.check(&&AS3Data::Integer(n), path) {
-                    Ok(()) => Ok(()),
-                    Err(e) => return Err(e.to_string()),
-                }
-            }
-            AS3Validator::Boolean => match key.to_lowercase().as_str() {
-                "true" | "false" | "1" | "0" => Ok(()),
-                _ => return Err(format!("The Key `{}` can't be converted to a Boolean", key)),
-            },
-            AS3Validator::Date => match wanted_type.check(&AS3Data::String(key.clone()), path) {
-                Ok(())=> Ok(()),
-                _ => return Err(format!("The Key `{}` can't be converted to a Date", key)),
-            },
-            _ => return Err(
-                "Usupported Map's KeyValue conversion. [Supported types : String, Integer, Bool, Date(YYYY-MM-DD) ]"
-                    .to_string(),
-            ),
-        };
-        Ok(())
+impl std::fmt::Display for Assertion {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "{} {} {}", self.left, self.operator, self.right)
     }
-    pub fn to_yaml_string(self) -> String {
-        let serialized_json = serde_json::to_string(&self).unwrap();
-        let serialized_yaml: serde_yaml::Value =
-            serde_yaml::from_str::<serde_yaml::Value>(&serialized_json).unwrap();
-        serde_yaml::to_string(&serialized_yaml).unwrap()
+}
+
+impl Assertion {
+    fn parse(assertion: &str) -> Option<Assertion> {
+        let (symbol, operator) = Comparison::OPERATORS
+            .iter()
+            .find(|(symbol, _)| assertion.contains(symbol))?;
+        let (left, right) = assertion.split_once(symbol)?;
+        let (left, right) = (left.trim(), right.trim());
+        if left.is_empty() || right.is_empty() {
+            return None;
+        }
+        Some(Assertion {
+            left: left.to_string(),
+            operator: *operator,
+            right: right.to_string(),
+        })
     }
 
-    pub fn from(yaml_config: &serde_yaml::Value) -> Result<AS3Validator, String> {
-        let serde_yaml::Value::Mapping(inner) = yaml_config else {
-            println!("Definition must start with a Yaml Mapping");
-            return Err("Definition must start with a Yaml Mapping".to_string());
+    /// Checks the assertion on the values of an Object. Numbers are compared by value, Strings
+    /// that are both RFC 3339 date times as instants, other values with the ordering of
+    /// [`AS3Data`]
+    #[allow(clippy::result_large_err)]
+    fn check(
+        &self,
+        values: &HashMap<String, Box<AS3Data>>,
+        path: &JsonPath,
+    ) -> Result<(), As3JsonPath<AS3ValidationError>> {
+        let (Some(left), Some(right)) = (values.get(&self.left), values.get(&self.right)) else {
+            return Ok(());
         };
-        let mut root_word: String = "Root".to_string();
-        if !inner.contains_key(&root_word) {
-            return Err(format!("Missing root word `{root_word}` from definition"));
+        if **left == AS3Data::Null || **right == AS3Data::Null {
+            return Ok(());
+        }
+        let ordering = match (left.as_ref(), right.as_ref()) {
+            (AS3Data::String(a), AS3Data::String(b)) => match (
+                chrono::DateTime::parse_from_rfc3339(a),
+                chrono::DateTime::parse_from_rfc3339(b),
+            ) {
+                (Ok(a), Ok(b)) => a.cmp(&b),
+                _ => a.cmp(b),
+            },
+            (left, right) => left.cmp_numbers(right).unwrap_or_else(|| left.cmp(right)),
         };
+        if self.operator.holds(ordering) {
+            return Ok(());
+        }
+        Err(As3JsonPath(
+            path.clone(),
+            AS3ValidationError::AssertionFailed {
+                assertion: self.to_string(),
+                left: (self.left.clone(), *left.clone()),
+                right: (self.right.clone(), *right.clone()),
+            },
+        ))
+    }
+}
+
+/// Machine integer an `Integer` must fit in, such as `u16`
+#[derive(Debug, PartialEq, Clone, Copy, Serialize, Deserialize)]
+pub struct IntegerWidth {
+    pub bits: u8,
+    pub unsigned: bool,
+}
 
-        AS3Validator::build_from_yaml(&inner.get(&root_word).unwrap(), &mut root_word)
+impl std::fmt::Display for IntegerWidth {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        let sign = if self.unsigned { 'u' } else { 'i' };
+        write!(f, "{sign}{}", self.bits)
     }
+}
 
-    fn build_from_yaml(
-        // validator: &mut AS3Validator,
-        yaml_config: &&serde_yaml::Value,
-        path: &mut String,
-    ) -> Result<AS3Validator, String> {
-        // Used to get the validator_type from the canonical long form and also from the shortened syntax
-        let validator_type = match (yaml_config.get("+type"), yaml_config) {
-            (Some(serde_yaml::Value::String(validator_type)), _) => validator_type,
-            (_, serde_yaml::Value::String(validator_type)) => validator_type,
-            _ => return Err(format!("Type definition missing for {path} ")),
+impl IntegerWidth {
+    /// Returns the width named by the `Int8`..`Int64` and `UInt8`..`UInt64` types
+    fn from_type_name(type_name: &str) -> Option<IntegerWidth> {
+        let (unsigned, bits) = match type_name.strip_prefix("UInt") {
+            Some(bits) => (true, bits),
+            None => (false, type_name.strip_prefix("Int")?),
         };
+        match bits {
+            "8" | "16" | "32" | "64" => Some(IntegerWidth {
+                bits: bits.parse().unwrap(),
+                unsigned,
+            }),
+            _ => None,
+        }
+    }
 
-        let nullable = validator_type.contains("?");
+    /// Smallest and largest value of the width
+    pub fn range(&self) -> (i128, i128) {
+        match (self.unsigned, self.bits) {
+            (true, bits) => (0, (1 << bits) - 1),
+            (false, bits) => (-(1 << (bits - 1)), (1 << (bits - 1)) - 1),
+        }
+    }
 
-        let validator = match (validator_type.replace("?", "").as_str(), yaml_config) {
-            ("Object", serde_yaml::Value::Mapping(inner)) => {
-                let x: HashMap<String, AS3Validator> = inner
-                    .into_iter()
-                    .filter(|(key, _)| key != &&serde_yaml::Value::String("+type".to_string()))
-                    .map(|(key, value)| {
-                        let mut temp_path = path.clone();
-                        temp_path.push_str(" -> ");
-                        temp_path.push_str(&key.as_str().unwrap());
-                        (
-                            key.as_str().unwrap().to_string(),
-                            AS3Validator::build_from_yaml(&value, &mut temp_path).unwrap(),
-                        )
-                    })
-                    .collect();
+    #[allow(clippy::result_large_err)]
+    fn check(&self, number: i128, path: &JsonPath) -> Result<(), As3JsonPath<AS3ValidationError>> {
+        let (lowest, highest) = self.range();
+        if number < lowest || number > highest {
+            return Err(As3JsonPath(
+                path.clone(),
+                AS3ValidationError::IntegerWidth {
+                    number,
+                    width: self.to_string(),
+                },
+            ));
+        }
+        Ok(())
+    }
+}
 
-                AS3Validator::Object(x)
-            }
-            ("String", serde_yaml::Value::Mapping(inner)) => {
-                let regex = if let Some(serde_yaml::Value::String(regex)) = inner.get("+regex") {
-                    Some(regex.clone())
-                } else {
-                    None
-                };
+/// Bound of a `Date`, either a calendar date or `today` resolved at validation time
+#[derive(Debug, PartialEq, Clone, Copy, Serialize, Deserialize)]
+pub enum DateBound {
+    Fixed(chrono::NaiveDate),
+    Today,
+}
 
-                let max_length = {
-                    let x: Vec<&&str> = ["+MaxLength", "+maxLength", "+max_length"]
-                        .iter()
-                        .filter(|key_word| inner.get(key_word).is_some())
-                        .collect();
+impl std::fmt::Display for DateBound {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            DateBound::Fixed(date) => write!(f, "{date}"),
+            DateBound::Today => write!(f, "today ({})", self.resolve()),
+        }
+    }
+}
 
-                    if x.len() > 1 {
-                        return Err(
-                            format!("Multiple field indicating the maximum length of a String have been passed : ({})", x.iter().map(|k|k.to_string()).collect::<Vec<String>>().join(",") ),
-                        );
-                    }
+impl DateBound {
+    /// Returns the date of the bound, `today` being the local date
+    pub fn resolve(&self) -> chrono::NaiveDate {
+        match self {
+            DateBound::Fixed(date) => *date,
+            DateBound::Today => chrono::Local::now().date_naive(),
+        }
+    }
+}
 
-                    if let Some(key_word) = x.first() {
-                        if let Some(serde_yaml::Value::Number(max_length)) = inner.get(key_word) {
-                            if let Some(max_length) = max_length.as_i64() {
-                                Some(max_length)
-                            } else {
-                                None
-                            }
-                        } else {
-                            None
-                        }
-                    } else {
-                        None
-                    }
-                };
+impl BytesEncoding {
+    /// Returns the decoded length of `string`
+    fn decoded_len(&self, string: &str) -> Result<usize, String> {
+        match self {
+            BytesEncoding::Base64 => {
+                use base64::Engine;
+                base64::engine::general_purpose::STANDARD
+                    .decode(string)
+                    .map(|bytes| bytes.len())
+                    .map_err(|e| e.to_string())
+            }
+            BytesEncoding::Hex => hex::decode(string)
+                .map(|bytes| bytes.len())
+                .map_err(|e| e.to_string()),
+        }
+    }
+}
 
-                let min_length = {
-                    let x: Vec<&&str> = ["+MinLength", "+minLength", "+min_length"]
+/// Values of a [`AS3Validator::Literal`] in order, so that equal validators serialize alike
+fn sorted_values<S: serde::Serializer>(
+    values: &HashSet<String>,
+    serializer: S,
+) -> Result<S::Ok, S::Error> {
+    let mut values: Vec<&String> = values.iter().collect();
+    values.sort();
+    values.serialize(serializer)
+}
+
+/// Pattern of `+regex`, compiled when the definition is read or the validator deserialized
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(try_from = "String", into = "String")]
+pub struct RegexPattern {
+    pattern: String,
+    regex: Box<Regex>,
+}
+
+impl PartialEq for RegexPattern {
+    fn eq(&self, other: &Self) -> bool {
+        self.pattern == other.pattern
+    }
+}
+
+impl TryFrom<String> for RegexPattern {
+    type Error = regex::Error;
+
+    fn try_from(pattern: String) -> Result<RegexPattern, regex::Error> {
+        RegexPattern::new(pattern)
+    }
+}
+
+impl From<RegexPattern> for String {
+    fn from(pattern: RegexPattern) -> String {
+        pattern.pattern
+    }
+}
+
+impl RegexPattern {
+    pub fn new(pattern: String) -> Result<RegexPattern, regex::Error> {
+        let regex = Box::new(Regex::new(&pattern)?);
+        Ok(RegexPattern { pattern, regex })
+    }
+
+    pub fn as_str(&self) -> &str {
+        &self.pattern
+    }
+
+    fn is_match(&self, string: &str) -> bool {
+        metrics::add(Counter::RegexEvaluations, 1);
+        self.regex.is_match(string)
+    }
+}
+
+/// Several regex patterns compiled once into a single [`RegexSet`], so that a String is
+/// matched against all of them in one pass. Deserializing compiles the set as well
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(try_from = "Vec<String>", into = "Vec<String>")]
+pub struct RegexList {
+    patterns: Vec<String>,
+    set: RegexSet,
+}
+
+impl PartialEq for RegexList {
+    fn eq(&self, other: &Self) -> bool {
+        self.patterns == other.patterns
+    }
+}
+
+impl TryFrom<Vec<String>> for RegexList {
+    type Error = regex::Error;
+
+    fn try_from(patterns: Vec<String>) -> Result<RegexList, regex::Error> {
+        RegexList::new(patterns)
+    }
+}
+
+impl From<RegexList> for Vec<String> {
+    fn from(list: RegexList) -> Vec<String> {
+        list.patterns
+    }
+}
+
+impl RegexList {
+    pub fn new(patterns: Vec<String>) -> Result<RegexList, regex::Error> {
+        let set = RegexSet::new(&patterns)?;
+        Ok(RegexList { patterns, set })
+    }
+
+    pub fn patterns(&self) -> &[String] {
+        &self.patterns
+    }
+
+    /// Indexes of the patterns matching `string`
+    fn matches(&self, string: &str) -> regex::SetMatches {
+        metrics::add(Counter::RegexEvaluations, 1);
+        self.set.matches(string)
+    }
+
+    fn build_from_yaml(
+        keywords: &Keywords,
+        keyword: &str,
+    ) -> Result<Option<RegexList>, As3JsonPath<AS3DefinitionError>> {
+        let Some(patterns) = keywords.string_list(keyword)? else {
+            return Ok(None);
+        };
+        match RegexList::new(patterns) {
+            Ok(list) => Ok(Some(list)),
+            Err(e) => Err(keywords.error(AS3DefinitionError::InvalidRegex {
+                keyword: keyword.to_string(),
+                reason: Cause::new(e),
+            })),
+        }
+    }
+}
+
+/// How `as3 anonymize` replaces a value annotated with `+anonymize`
+#[derive(Debug, PartialEq, Clone, Copy, Serialize, Deserialize)]
+pub enum Anonymize {
+    /// Hex digest of the value, or a number derived from it
+    Hash,
+    /// Characters replaced by `*` but the last 4 of long Strings, numbers set to zero
+    Mask,
+    /// Plausible value of the same type and `+format`
+    Fake,
+}
+
+/// Keywords accepted by every type, which don't change the values a validator accepts
+#[derive(Debug, Default, PartialEq, Clone, Serialize, Deserialize)]
+pub struct Annotations {
+    /// How `as3 anonymize` replaces the value
+    pub anonymize: Option<Anonymize>,
+    /// Values present are reported by [`AS3Validator::warnings`]
+    pub deprecated: Option<Deprecation>,
+    /// Replaces the text of the errors raised by the validator
+    pub message: Option<String>,
+    pub severity: Severity,
+    /// Label of the value, for generated forms
+    pub title: Option<String>,
+    pub description: Option<String>,
+    /// Sample values, for documentation
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub examples: Vec<serde_json::Value>,
+}
+
+/// Whether the errors of a definition make the data invalid, set with `+severity`
+#[derive(Debug, Default, PartialEq, Eq, Clone, Copy, Serialize, Deserialize)]
+pub enum Severity {
+    #[default]
+    Error,
+    /// Errors are reported by [`AS3Validator::warnings`] instead, such as for soft limits
+    Warning,
+}
+
+/// Set with `+deprecated: true`, or with the message to show such as `use email instead`
+#[derive(Debug, PartialEq, Clone, Serialize, Deserialize)]
+pub struct Deprecation {
+    pub message: Option<String>,
+}
+
+impl Annotations {
+    const KEYWORDS: &'static [&'static str] = &[
+        "+anonymize",
+        "+deprecated",
+        "+description",
+        "+examples",
+        "+message",
+        "+severity",
+        "+title",
+    ];
+
+    fn is_empty(&self) -> bool {
+        *self == Annotations::default()
+    }
+
+    /// `error` behind the `+message`, unless the message of a closer definition is set
+    fn with_message(
+        &self,
+        As3JsonPath(path, error): As3JsonPath<AS3ValidationError>,
+    ) -> As3JsonPath<AS3ValidationError> {
+        match (&self.message, error) {
+            (Some(message), error) if !matches!(error, AS3ValidationError::Custom { .. }) => {
+                As3JsonPath(
+                    path,
+                    AS3ValidationError::Custom {
+                        message: message.clone(),
+                        error: Box::new(error),
+                    },
+                )
+            }
+            (_, error) => As3JsonPath(path, error),
+        }
+    }
+
+    fn build_from_yaml(
+        keywords: &Keywords,
+    ) -> Result<Annotations, As3JsonPath<AS3DefinitionError>> {
+        let anonymize = match keywords.string("+anonymize")?.map(String::as_str) {
+            None => None,
+            Some("hash") => Some(Anonymize::Hash),
+            Some("mask") => Some(Anonymize::Mask),
+            Some("fake") => Some(Anonymize::Fake),
+            Some(_) => return Err(keywords.invalid("+anonymize", "`hash`, `mask` or `fake`")),
+        };
+        let deprecated = match keywords.inner.get("+deprecated") {
+            None | Some(serde_yaml::Value::Bool(false)) => None,
+            Some(serde_yaml::Value::Bool(true)) => Some(Deprecation { message: None }),
+            Some(serde_yaml::Value::String(message)) => Some(Deprecation {
+                message: Some(message.clone()),
+            }),
+            Some(_) => return Err(keywords.invalid("+deprecated", "a Bool or a message")),
+        };
+        let severity = match keywords.string("+severity")?.map(String::as_str) {
+            None | Some("error") => Severity::Error,
+            Some("warning") => Severity::Warning,
+            Some(_) => return Err(keywords.invalid("+severity", "`error` or `warning`")),
+        };
+        let examples = match keywords.inner.get("+examples") {
+            None => Vec::new(),
+            Some(serde_yaml::Value::Sequence(examples)) => examples
+                .iter()
+                .map(serde_json::to_value)
+                .collect::<Result<_, _>>()
+                .map_err(|_| keywords.invalid("+examples", "a List of JSON values"))?,
+            Some(_) => return Err(keywords.invalid("+examples", "a List of values")),
+        };
+        Ok(Annotations {
+            anonymize,
+            deprecated,
+            message: keywords.string("+message")?.cloned(),
+            severity,
+            title: keywords.string("+title")?.cloned(),
+            description: keywords.string("+description")?.cloned(),
+            examples,
+        })
+    }
+}
+
+/// Named definition of `+defs`, shared by all its `+ref` nodes. Its validator is set once
+/// built, which lets definitions refer to themselves, such as a tree whose `children` are a
+/// List of the same node. It is compared, printed and serialized by name only, as the
+/// validator can contain the reference itself.
+///
+/// References only hold the definition weakly: the `+defs` are kept by the references of the
+/// rest of the definition, so that a definition referring to itself is not kept forever
+#[derive(Clone)]
+pub struct Reference {
+    name: String,
+    target: Weak<OnceLock<AS3Validator>>,
+    /// Every `+defs` of the definition, for references outside of them
+    defs: Option<Arc<Vec<Arc<OnceLock<AS3Validator>>>>>,
+}
+
+/// Validator of a [`Reference`], kept while it is used
+#[derive(Clone)]
+pub struct Target(Arc<OnceLock<AS3Validator>>);
+
+impl std::ops::Deref for Target {
+    type Target = AS3Validator;
+
+    fn deref(&self) -> &AS3Validator {
+        // Only created for a set validator
+        self.0.get().unwrap()
+    }
+}
+
+/// Deepest nesting of references followed during a validation
+const MAX_REFERENCE_DEPTH: usize = 128;
+
+impl Reference {
+    pub fn name(&self) -> &str {
+        &self.name
+    }
+
+    /// Validator of the definition, `None` for a reference that was deserialized, or that was
+    /// taken out of a definition that was dropped since
+    pub fn target(&self) -> Option<Target> {
+        let target = self.target.upgrade()?;
+        target.get()?;
+        Some(Target(target))
+    }
+
+    fn build_from_yaml(
+        yaml_config: &serde_yaml::Value,
+        reference: &serde_yaml::Value,
+        path: &JsonPath,
+        options: &DefinitionOptions,
+    ) -> Result<Reference, As3JsonPath<AS3DefinitionError>> {
+        let error = |error| Err(As3JsonPath(path.clone(), error));
+        if yaml_config
+            .as_mapping()
+            .is_some_and(|inner| inner.len() > 1)
+        {
+            return error(AS3DefinitionError::Conflict(
+                "`+ref` can't be used together with other properties".to_string(),
+            ));
+        }
+        let Some(name) = reference.as_str().and_then(|r| r.strip_prefix("#/defs/")) else {
+            return error(AS3DefinitionError::InvalidValue {
+                keyword: "+ref".to_string(),
+                expected: "a reference such as `#/defs/Address`".to_string(),
+            });
+        };
+        match options.defs.get(name) {
+            Some(reference) => Ok(reference.clone()),
+            None => error(AS3DefinitionError::UnknownDefinition(name.to_string())),
+        }
+    }
+
+    /// Fails if the definition can reach a reference of `stack` without consuming data
+    fn check_productive(
+        &self,
+        stack: &mut Vec<String>,
+    ) -> Result<(), As3JsonPath<AS3DefinitionError>> {
+        let Some(target) = self.target() else {
+            return Ok(());
+        };
+        for next in target.leading_references() {
+            if stack.contains(&next.name) {
+                stack.push(next.name.clone());
+                return Err(As3JsonPath(
+                    JsonPath::root("+defs").with_key(&stack[0]),
+                    AS3DefinitionError::CircularReference(stack.join(" -> ")),
+                ));
+            }
+            stack.push(next.name.clone());
+            next.check_productive(stack)?;
+            stack.pop();
+        }
+        Ok(())
+    }
+}
+
+impl std::fmt::Debug for Reference {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.debug_tuple("Reference").field(&self.name).finish()
+    }
+}
+
+impl PartialEq for Reference {
+    fn eq(&self, other: &Self) -> bool {
+        self.name == other.name
+    }
+}
+
+impl Serialize for Reference {
+    fn serialize<S: serde::Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        serializer.serialize_str(&format!("#/defs/{}", self.name))
+    }
+}
+
+impl<'de> Deserialize<'de> for Reference {
+    fn deserialize<D: serde::Deserializer<'de>>(deserializer: D) -> Result<Self, D::Error> {
+        let reference = String::deserialize(deserializer)?;
+        Ok(Reference {
+            name: reference
+                .strip_prefix("#/defs/")
+                .unwrap_or(&reference)
+                .to_string(),
+            target: Weak::new(),
+            defs: None,
+        })
+    }
+}
+
+/// Validators of the `Object` keys matching a regex, declared with `+patternKeys`. They only
+/// apply to keys that aren't fields of the Object, and every matching pattern applies.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct PatternKeys {
+    patterns: RegexList,
+    validators: Vec<AS3Validator>,
+}
+
+impl PatternKeys {
+    /// Pairs of pattern and validator, in declaration order
+    pub fn iter(&self) -> impl Iterator<Item = (&String, &AS3Validator)> {
+        self.patterns.patterns().iter().zip(&self.validators)
+    }
+
+    /// Validators of the patterns matching `key`
+    pub(crate) fn matching(&self, key: &str) -> impl Iterator<Item = &AS3Validator> {
+        self.patterns
+            .matches(key)
+            .into_iter()
+            .map(|index| &self.validators[index])
+    }
+
+    fn build_from_yaml(
+        yaml_config: &serde_yaml::Value,
+        path: &JsonPath,
+        options: &DefinitionOptions,
+    ) -> Result<PatternKeys, As3JsonPath<AS3DefinitionError>> {
+        let path = path.with_key("+patternKeys");
+        let serde_yaml::Value::Mapping(inner) = yaml_config else {
+            return Err(As3JsonPath(
+                path.clone(),
+                AS3DefinitionError::InvalidValue {
+                    keyword: "+patternKeys".to_string(),
+                    expected: "a Mapping of regex to definition".to_string(),
+                },
+            ));
+        };
+
+        let mut patterns = Vec::new();
+        let mut validators = Vec::new();
+        for (pattern, definition) in inner {
+            let Some(pattern) = pattern.as_str() else {
+                return Err(As3JsonPath(
+                    path.clone(),
+                    AS3DefinitionError::InvalidValue {
+                        keyword: "+patternKeys".to_string(),
+                        expected: "a Mapping with regex Strings as keys".to_string(),
+                    },
+                ));
+            };
+            validators.push(AS3Validator::build_from_yaml(
+                &definition,
+                &mut path.with_key(pattern),
+                options,
+            )?);
+            patterns.push(pattern.to_string());
+        }
+
+        match RegexList::new(patterns) {
+            Ok(patterns) => Ok(PatternKeys {
+                patterns,
+                validators,
+            }),
+            Err(e) => Err(As3JsonPath(
+                path.clone(),
+                AS3DefinitionError::InvalidRegex {
+                    keyword: "+patternKeys".to_string(),
+                    reason: Cause::new(e),
+                },
+            )),
+        }
+    }
+}
+
+/// Validator compared by meaning rather than by spelling, returned by
+/// [`AS3Validator::canonical`]. Two definitions written differently, such as `UInt8` and
+/// `Integer(bits=8, unsigned=true, min=0)`, give equal values with the same hash, so that they
+/// can be used as keys of registries and caches.
+#[derive(Debug, Clone)]
+pub struct CanonicalValidator {
+    validator: AS3Validator,
+    /// Serialization of `validator`, whose maps have their keys sorted
+    key: String,
+}
+
+impl CanonicalValidator {
+    /// Normalized validator, which validates the same documents as the original one
+    pub fn validator(&self) -> &AS3Validator {
+        &self.validator
+    }
+}
+
+impl PartialEq for CanonicalValidator {
+    fn eq(&self, other: &Self) -> bool {
+        self.key == other.key
+    }
+}
+
+impl Eq for CanonicalValidator {}
+
+impl std::hash::Hash for CanonicalValidator {
+    fn hash<H: std::hash::Hasher>(&self, state: &mut H) {
+        self.key.hash(state);
+    }
+}
+
+/// What the length bounds of a `String` count, set with `+lengthUnit`
+#[derive(Debug, PartialEq, Eq, Clone, Copy, Default, Serialize, Deserialize)]
+pub enum LengthUnit {
+    /// UTF-8 bytes, as stored in a database column sized in bytes
+    Bytes,
+    /// Unicode scalar values, so that "héllo" is 5 long
+    #[default]
+    Chars,
+    /// User-perceived characters, a flag emoji or a letter with combining accents being 1
+    Graphemes,
+}
+
+impl std::fmt::Display for LengthUnit {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            LengthUnit::Bytes => write!(f, "bytes"),
+            LengthUnit::Chars => write!(f, "characters"),
+            LengthUnit::Graphemes => write!(f, "graphemes"),
+        }
+    }
+}
+
+impl LengthUnit {
+    pub fn length(&self, string: &str) -> usize {
+        match self {
+            LengthUnit::Bytes => string.len(),
+            LengthUnit::Chars => string.chars().count(),
+            LengthUnit::Graphemes => string.graphemes(true).count(),
+        }
+    }
+
+    fn is_default(&self) -> bool {
+        *self == LengthUnit::default()
+    }
+}
+
+/// Characters allowed in a `String`, set with `+ascii`, `+alphanumeric` and `+noWhitespace`
+#[derive(Debug, PartialEq, Eq, PartialOrd, Ord, Clone, Copy, Serialize, Deserialize)]
+pub enum CharClass {
+    Ascii,
+    /// Letters and digits of any script, combined with `Ascii` to only allow `[a-zA-Z0-9]`
+    Alphanumeric,
+    NoWhitespace,
+}
+
+impl CharClass {
+    const ALL: [CharClass; 3] = [
+        CharClass::Ascii,
+        CharClass::Alphanumeric,
+        CharClass::NoWhitespace,
+    ];
+
+    pub fn keyword(&self) -> &'static str {
+        match self {
+            CharClass::Ascii => "+ascii",
+            CharClass::Alphanumeric => "+alphanumeric",
+            CharClass::NoWhitespace => "+noWhitespace",
+        }
+    }
+
+    pub fn contains(&self, character: char) -> bool {
+        match self {
+            CharClass::Ascii => character.is_ascii(),
+            CharClass::Alphanumeric => character.is_alphanumeric(),
+            CharClass::NoWhitespace => !character.is_whitespace(),
+        }
+    }
+}
+
+/// Additional syntactic checks applied to a `String` through the `+format` property
+#[derive(Debug, PartialEq, Clone, Serialize, Deserialize)]
+pub enum StringFormat {
+    /// Absolute URL, optionally restricted to a set of schemes with `+schemes`
+    Url { schemes: Option<Vec<String>> },
+    /// UUID in any of its textual forms, optionally restricted to a version with `+version`
+    Uuid { version: Option<usize> },
+    /// IPv4 address, in CIDR notation (`10.0.0.0/8`) when `+cidr: true`
+    Ipv4 { cidr: bool },
+    /// IPv6 address, in CIDR notation (`fd00::/8`) when `+cidr: true`
+    Ipv6 { cidr: bool },
+    /// Either an IPv4 or an IPv6 address
+    Ip { cidr: bool },
+    /// DNS name following the RFC 1123 label rules
+    Hostname,
+    /// E.164 phone number such as `+14155550100`, optionally restricted to the country calling
+    /// codes of `+countryCodes`
+    Phone { country_codes: Option<Vec<u16>> },
+    /// SemVer 2.0 version, optionally within `+minVersion` and `+maxVersion` (inclusive)
+    Semver {
+        min_version: Option<semver::Version>,
+        max_version: Option<semver::Version>,
+    },
+}
+
+impl std::fmt::Display for StringFormat {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            StringFormat::Url { .. } => write!(f, "url"),
+            StringFormat::Uuid { .. } => write!(f, "uuid"),
+            StringFormat::Ipv4 { .. } => write!(f, "ipv4"),
+            StringFormat::Ipv6 { .. } => write!(f, "ipv6"),
+            StringFormat::Ip { .. } => write!(f, "ip"),
+            StringFormat::Hostname => write!(f, "hostname"),
+            StringFormat::Phone { .. } => write!(f, "phone"),
+            StringFormat::Semver { .. } => write!(f, "semver"),
+        }
+    }
+}
+
+impl StringFormat {
+    /// Returns the reason why `string` doesn't respect the format
+    fn check(&self, string: &str) -> Result<(), String> {
+        match self {
+            StringFormat::Url { schemes } => {
+                let url = url::Url::parse(string).map_err(|e| e.to_string())?;
+                if let Some(schemes) = schemes {
+                    if !schemes.iter().any(|scheme| scheme == url.scheme()) {
+                        return Err(format!(
+                            "scheme `{}` is not one of the allowed schemes ({})",
+                            url.scheme(),
+                            schemes.join(",")
+                        ));
+                    }
+                }
+                Ok(())
+            }
+            StringFormat::Uuid { version } => {
+                let uuid = uuid::Uuid::parse_str(string).map_err(|e| e.to_string())?;
+                if let Some(version) = version {
+                    if uuid.get_version_num() != *version {
+                        return Err(format!(
+                            "UUID is version {} instead of version {}",
+                            uuid.get_version_num(),
+                            version
+                        ));
+                    }
+                }
+                Ok(())
+            }
+            StringFormat::Ipv4 { cidr } => match StringFormat::check_ip(string, *cidr)? {
+                std::net::IpAddr::V4(..) => Ok(()),
+                std::net::IpAddr::V6(..) => {
+                    Err("expected an IPv4 address, got an IPv6 one".to_string())
+                }
+            },
+            StringFormat::Ipv6 { cidr } => match StringFormat::check_ip(string, *cidr)? {
+                std::net::IpAddr::V6(..) => Ok(()),
+                std::net::IpAddr::V4(..) => {
+                    Err("expected an IPv6 address, got an IPv4 one".to_string())
+                }
+            },
+            StringFormat::Ip { cidr } => StringFormat::check_ip(string, *cidr).map(|_| ()),
+            StringFormat::Hostname => StringFormat::check_hostname(string),
+            StringFormat::Phone { country_codes } => {
+                StringFormat::check_phone(string, country_codes.as_deref())
+            }
+            StringFormat::Semver {
+                min_version,
+                max_version,
+            } => {
+                let version = semver::Version::parse(string).map_err(|e| e.to_string())?;
+                // Build metadata doesn't take part in the precedence
+                if let Some(min_version) = min_version {
+                    if version.cmp_precedence(min_version).is_lt() {
+                        return Err(format!("version is below the minimum of `{min_version}`"));
+                    }
+                }
+                if let Some(max_version) = max_version {
+                    if version.cmp_precedence(max_version).is_gt() {
+                        return Err(format!("version is above the maximum of `{max_version}`"));
+                    }
+                }
+                Ok(())
+            }
+        }
+    }
+
+    /// RFC 1123: at most 253 characters made of dot separated labels of 1 to 63
+    /// alphanumeric characters or dashes, never starting or ending with a dash
+    fn check_hostname(string: &str) -> Result<(), String> {
+        let hostname = string.strip_suffix('.').unwrap_or(string);
+        if hostname.is_empty() {
+            return Err("hostname is empty".to_string());
+        }
+        if hostname.len() > 253 {
+            return Err(format!(
+                "hostname is {} characters long, above the maximum of 253",
+                hostname.len()
+            ));
+        }
+        for label in hostname.split('.') {
+            if label.is_empty() {
+                return Err("hostname contains an empty label".to_string());
+            }
+            if label.len() > 63 {
+                return Err(format!(
+                    "label `{label}` is {} characters long, above the maximum of 63",
+                    label.len()
+                ));
+            }
+            if let Some(c) = label
+                .chars()
+                .find(|c| !c.is_ascii_alphanumeric() && *c != '-')
+            {
+                return Err(format!(
+                    "label `{label}` contains the invalid character `{c}`"
+                ));
+            }
+            if label.starts_with('-') || label.ends_with('-') {
+                return Err(format!("label `{label}` starts or ends with a dash"));
+            }
+        }
+        Ok(())
+    }
+
+    /// E.164: a `+` followed by at most 15 digits, the first one not being 0. Calling codes
+    /// being prefix free, the number starts with at most one of `country_codes`
+    fn check_phone(string: &str, country_codes: Option<&[u16]>) -> Result<(), String> {
+        let Some(digits) = string.strip_prefix('+') else {
+            return Err("expected a `+` followed by the country calling code".to_string());
+        };
+        if let Some(c) = digits.chars().find(|c| !c.is_ascii_digit()) {
+            return Err(format!("contains the invalid character `{c}`"));
+        }
+        if digits.len() < 7 || digits.len() > 15 {
+            return Err(format!(
+                "{} digits long, expected between 7 and 15",
+                digits.len()
+            ));
+        }
+        if digits.starts_with('0') {
+            return Err("country calling codes don't start with 0".to_string());
+        }
+        if let Some(country_codes) = country_codes {
+            if !country_codes
+                .iter()
+                .any(|code| digits.starts_with(&code.to_string()))
+            {
+                let codes: Vec<String> =
+                    country_codes.iter().map(|code| code.to_string()).collect();
+                return Err(format!(
+                    "country calling code is not one of the allowed codes ({})",
+                    codes.join(",")
+                ));
+            }
+        }
+        Ok(())
+    }
+
+    /// Parses an IP address, followed by a valid prefix length when `cidr` is set
+    fn check_ip(string: &str, cidr: bool) -> Result<std::net::IpAddr, String> {
+        if !cidr {
+            return string
+                .parse::<std::net::IpAddr>()
+                .map_err(|e| e.to_string());
+        }
+
+        let Some((address, prefix)) = string.split_once('/') else {
+            return Err("missing the `/<prefix>` of the CIDR notation".to_string());
+        };
+        let address = address
+            .parse::<std::net::IpAddr>()
+            .map_err(|e| e.to_string())?;
+        let max_prefix = if address.is_ipv4() { 32 } else { 128 };
+        match prefix.parse::<u8>() {
+            Ok(prefix) if prefix <= max_prefix => Ok(address),
+            _ => Err(format!(
+                "`{prefix}` is not a valid prefix length (0 to {max_prefix})"
+            )),
+        }
+    }
+
+    fn build_from_yaml(
+        format: &str,
+        keywords: &Keywords,
+    ) -> Result<StringFormat, As3JsonPath<AS3DefinitionError>> {
+        let format = match format.to_lowercase().as_str() {
+            "url" | "uri" => StringFormat::Url {
+                schemes: keywords
+                    .string_list("+schemes")?
+                    .map(|schemes| schemes.iter().map(|scheme| scheme.to_lowercase()).collect()),
+            },
+            "uuid" => StringFormat::Uuid {
+                version: match keywords.integer("+version")? {
+                    Some(version @ 1..=8) => Some(version as usize),
+                    Some(_) => {
+                        return Err(keywords.invalid("+version", "an Integer between 1 and 8"))
+                    }
+                    None => None,
+                },
+            },
+            "ipv4" => StringFormat::Ipv4 {
+                cidr: keywords.bool("+cidr")?.unwrap_or(false),
+            },
+            "ipv6" => StringFormat::Ipv6 {
+                cidr: keywords.bool("+cidr")?.unwrap_or(false),
+            },
+            "ip" => StringFormat::Ip {
+                cidr: keywords.bool("+cidr")?.unwrap_or(false),
+            },
+            "hostname" | "domain" => StringFormat::Hostname,
+            "phone" => StringFormat::Phone {
+                country_codes: match keywords.inner.get("+countryCodes") {
+                    None => None,
+                    Some(serde_yaml::Value::Sequence(codes)) => Some(
+                        codes
+                            .iter()
+                            .map(|code| match code.as_u64() {
+                                Some(code @ 1..=999) => Ok(code as u16),
+                                _ => Err(keywords.invalid(
+                                    "+countryCodes",
+                                    "a list of country calling codes between 1 and 999",
+                                )),
+                            })
+                            .collect::<Result<Vec<u16>, _>>()?,
+                    ),
+                    Some(_) => {
+                        return Err(keywords.invalid(
+                            "+countryCodes",
+                            "a list of country calling codes between 1 and 999",
+                        ))
+                    }
+                },
+            },
+            "semver" => {
+                let version = |keyword: &str| match keywords.string(keyword)? {
+                    Some(version) => match semver::Version::parse(version) {
+                        Ok(version) => Ok(Some(version)),
+                        Err(_) => Err(keywords.invalid(keyword, "a SemVer 2.0 version")),
+                    },
+                    None => Ok(None),
+                };
+                let (min_version, max_version) = (version("+minVersion")?, version("+maxVersion")?);
+                if let (Some(min_version), Some(max_version)) = (&min_version, &max_version) {
+                    if min_version.cmp_precedence(max_version).is_gt() {
+                        return Err(keywords.error(AS3DefinitionError::Conflict(format!(
+                            "`+minVersion` {min_version} is above `+maxVersion` {max_version}"
+                        ))));
+                    }
+                }
+                StringFormat::Semver {
+                    min_version,
+                    max_version,
+                }
+            }
+            _ => return Err(keywords.invalid(
+                "+format",
+                "one of the supported String formats (url, uuid, ipv4, ipv6, ip, hostname, phone, semver)",
+            )),
+        };
+
+        for (keyword, allowed) in [
+            ("+schemes", matches!(format, StringFormat::Url { .. })),
+            ("+version", matches!(format, StringFormat::Uuid { .. })),
+            (
+                "+countryCodes",
+                matches!(format, StringFormat::Phone { .. }),
+            ),
+            ("+minVersion", matches!(format, StringFormat::Semver { .. })),
+            ("+maxVersion", matches!(format, StringFormat::Semver { .. })),
+            (
+                "+cidr",
+                matches!(
+                    format,
+                    StringFormat::Ipv4 { .. } | StringFormat::Ipv6 { .. } | StringFormat::Ip { .. }
+                ),
+            ),
+        ] {
+            if keywords.contains(keyword) && !allowed {
+                return Err(keywords.error(AS3DefinitionError::Conflict(format!(
+                    "`{keyword}` can't be used together with `+format: {format}`"
+                ))));
+            }
+        }
+
+        Ok(format)
+    }
+}
+
+impl AS3Validator {
+    #[allow(clippy::result_large_err)]
+    pub fn validate(&self, data: &AS3Data) -> Result<(), As3JsonPath<AS3ValidationError>> {
+        self.check(data, &mut JsonPath::root("ROOT"))
+    }
+
+    /// Validates the parts of `data` selected by `options`
+    #[allow(clippy::result_large_err)]
+    pub fn validate_with_options(
+        &self,
+        data: &AS3Data,
+        options: &ValidationOptions,
+    ) -> Result<(), As3JsonPath<AS3ValidationError>> {
+        let mut path = JsonPath::root("ROOT");
+        if options.skips(&path) {
+            return Ok(());
+        }
+        #[cfg(feature = "coerce")]
+        if options.coerce {
+            return self.check_with(&self.coerced(data), &mut path, options, 0);
+        }
+        self.check_with(data, &mut path, options, 0)
+    }
+
+    /// Validates `data`, reporting every error instead of stopping at the first one
+    pub fn validate_all(&self, data: &AS3Data) -> Result<(), Vec<As3JsonPath<AS3ValidationError>>> {
+        self.validate_all_with_options(data, &ValidationOptions::default())
+    }
+
+    /// [`AS3Validator::validate_all`] of the parts of `data` selected by `options`. Errors are
+    /// ordered by path, the fields of an Object by key
+    pub fn validate_all_with_options(
+        &self,
+        data: &AS3Data,
+        options: &ValidationOptions,
+    ) -> Result<(), Vec<As3JsonPath<AS3ValidationError>>> {
+        let mut found = self.errors_with_options(data, options);
+        found.truncate(options.max_errors.map_or(usize::MAX, NonZeroUsize::get));
+        match found.is_empty() {
+            true => Ok(()),
+            false => Err(found),
+        }
+    }
+
+    /// Errors of `data`, one more than `max_errors` at most so that a truncation is noticed
+    fn errors_with_options(
+        &self,
+        data: &AS3Data,
+        options: &ValidationOptions,
+    ) -> Vec<As3JsonPath<AS3ValidationError>> {
+        let mut path = JsonPath::root("ROOT");
+        let mut found = Vec::new();
+        if options.skips(&path) {
+            return found;
+        }
+        #[cfg(feature = "coerce")]
+        if options.coerce {
+            self.collect_errors(&self.coerced(data), &mut path, options, 0, &mut found);
+            return found;
+        }
+        self.collect_errors(data, &mut path, options, 0, &mut found);
+        found
+    }
+
+    /// Every error and warning of the parts of `data` selected by `options`
+    pub fn validate_with_warnings(
+        &self,
+        data: &AS3Data,
+        options: &ValidationOptions,
+    ) -> ValidationOutcome {
+        let mut errors = self.errors_with_options(data, options);
+        let truncated = options.is_full(&errors);
+        errors.truncate(options.max_errors.map_or(usize::MAX, NonZeroUsize::get));
+        ValidationOutcome {
+            errors,
+            warnings: self.warnings_with_options(data, options),
+            truncated,
+        }
+    }
+
+    /// Validates each of the `records`, such as the lines of a JSON lines file, as a value of
+    /// the validator. Every record is handed to `on_record` with its result, so that valid
+    /// records can be passed on and invalid ones set aside in a single pass
+    pub fn validate_stream(
+        &self,
+        records: impl IntoIterator<Item = AS3Data>,
+        options: &ValidationOptions,
+        mut on_record: impl FnMut(StreamRecord),
+    ) {
+        for (index, data) in records.into_iter().enumerate() {
+            on_record(self.record(JsonPath::root("ROOT").with_index(index), data, options));
+        }
+    }
+
+    /// [`AS3Validator::validate_stream`] of the items of a List, or of the values of a Map, by
+    /// the validator of its items or values. Other validators get `data` as a single record
+    pub fn validate_each(
+        &self,
+        data: &AS3Data,
+        options: &ValidationOptions,
+        mut on_record: impl FnMut(StreamRecord),
+    ) {
+        self.each_record(self, data, options, &mut on_record);
+    }
+
+    /// [`AS3Validator::validate_each`] past the annotations and references of `root`
+    fn each_record(
+        &self,
+        root: &AS3Validator,
+        data: &AS3Data,
+        options: &ValidationOptions,
+        on_record: &mut impl FnMut(StreamRecord),
+    ) {
+        match (self, data) {
+            (AS3Validator::Annotated { inner, .. }, _) => {
+                inner.each_record(root, data, options, on_record)
+            }
+            (AS3Validator::Ref(reference), _) => match reference.target() {
+                Some(target) => target.each_record(root, data, options, on_record),
+                None => on_record(root.record(JsonPath::root("ROOT"), data.clone(), options)),
+            },
+            (AS3Validator::List(items_type), AS3Data::List(items)) => {
+                for (index, item) in items.iter().enumerate() {
+                    let path = JsonPath::root("ROOT").with_index(index);
+                    on_record(items_type.record(path, item.clone(), options));
+                }
+            }
+            (AS3Validator::Map { value_type, .. }, AS3Data::Object(values)) => {
+                let mut keys: Vec<&String> = values.keys().collect();
+                keys.sort();
+                for key in keys {
+                    let path = JsonPath::root("ROOT").with_key(key);
+                    on_record(value_type.record(path, (*values[key]).clone(), options));
+                }
+            }
+            _ => on_record(root.record(JsonPath::root("ROOT"), data.clone(), options)),
+        }
+    }
+
+    /// `data` validated as the value at `path`, coerced first with [`ValidationOptions::coerce`]
+    fn record(
+        &self,
+        mut path: JsonPath,
+        data: AS3Data,
+        options: &ValidationOptions,
+    ) -> StreamRecord {
+        #[cfg(feature = "coerce")]
+        let data = match options.coerce {
+            true => self.coerced(&data),
+            false => data,
+        };
+        let result = match options.skips(&path) {
+            true => Ok(()),
+            false => self.check_with(&data, &mut path, options, 0),
+        };
+        StreamRecord { path, data, result }
+    }
+
+    /// Validates `data` with the coercions of [`ValidationOptions::coerce`], returning the
+    /// data with its values coerced
+    #[cfg(feature = "coerce")]
+    #[allow(clippy::result_large_err)]
+    pub fn validate_coerced(
+        &self,
+        data: &AS3Data,
+    ) -> Result<AS3Data, As3JsonPath<AS3ValidationError>> {
+        let coerced = self.coerced(data);
+        self.validate(&coerced)?;
+        Ok(coerced)
+    }
+
+    /// Copy of `data` whose stringified numbers and booleans are converted to the type
+    /// expected by the validator. Values that can't be converted are left for the validation
+    /// to reject
+    #[cfg(feature = "coerce")]
+    fn coerced(&self, data: &AS3Data) -> AS3Data {
+        match (self, data) {
+            (AS3Validator::Nullable(inner) | AS3Validator::Annotated { inner, .. }, _) => {
+                inner.coerced(data)
+            }
+            (AS3Validator::Ref(reference), _) => match reference.target() {
+                Some(target) => target.coerced(data),
+                None => data.clone(),
+            },
+            (
+                AS3Validator::Conditional {
+                    base,
+                    condition,
+                    then,
+                    otherwise,
+                },
+                _,
+            ) => {
+                let data = base.coerced(data);
+                let branch = match condition.validate(&condition.coerced(&data)) {
+                    Ok(()) => then,
+                    Err(_) => otherwise,
+                };
+                match branch {
+                    Some(branch) => branch.coerced(&data),
+                    None => data,
+                }
+            }
+            (
+                AS3Validator::Object {
+                    fields,
+                    pattern_keys,
+                    ..
+                },
+                AS3Data::Object(values),
+            ) => AS3Data::Object(
+                values
+                    .iter()
+                    .map(|(key, value)| {
+                        let value = match (fields.get(key), pattern_keys) {
+                            (Some(field), _) => field.coerced(value),
+                            (None, Some(pattern_keys)) => pattern_keys
+                                .matching(key)
+                                .fold(value.as_ref().clone(), |value, validator| {
+                                    validator.coerced(&value)
+                                }),
+                            (None, None) => value.as_ref().clone(),
+                        };
+                        (key.clone(), Box::new(value))
+                    })
+                    .collect(),
+            ),
+            (AS3Validator::Map { value_type, .. }, AS3Data::Object(values)) => AS3Data::Object(
+                values
+                    .iter()
+                    .map(|(key, value)| (key.clone(), Box::new(value_type.coerced(value))))
+                    .collect(),
+            ),
+            (AS3Validator::List(items_type), AS3Data::List(items)) => {
+                AS3Data::List(items.iter().map(|item| items_type.coerced(item)).collect())
+            }
+            (AS3Validator::TaggedUnion { .. }, _) => match self.variant_of(data) {
+                Some(variant) => variant.coerced(data),
+                None => data.clone(),
+            },
+            (AS3Validator::Integer { .. }, AS3Data::String(string)) => {
+                match (string.parse::<i64>(), string.parse::<u64>()) {
+                    (Ok(number), _) => AS3Data::Integer(number),
+                    (_, Ok(number)) => AS3Data::UInteger(number),
+                    _ => data.clone(),
+                }
+            }
+            (AS3Validator::Decimal { .. }, AS3Data::Integer(number)) => {
+                AS3Data::Decimal(*number as f64)
+            }
+            (AS3Validator::Decimal { .. }, AS3Data::UInteger(number)) => {
+                AS3Data::Decimal(*number as f64)
+            }
+            (AS3Validator::Decimal { .. }, AS3Data::String(string)) => {
+                match string.parse::<f64>() {
+                    Ok(number) if number.is_finite() => AS3Data::Decimal(number),
+                    _ => data.clone(),
+                }
+            }
+            (AS3Validator::Boolean, AS3Data::Integer(1)) => AS3Data::Boolean(true),
+            (AS3Validator::Boolean, AS3Data::Integer(0)) => AS3Data::Boolean(false),
+            (AS3Validator::Boolean, AS3Data::String(string)) => match string.as_str() {
+                "true" => AS3Data::Boolean(true),
+                "false" => AS3Data::Boolean(false),
+                _ => data.clone(),
+            },
+            _ => data.clone(),
+        }
+    }
+
+    /// Variant of a TaggedUnion named by the discriminator of `data`, if any
+    pub fn variant_of(&self, data: &AS3Data) -> Option<&AS3Validator> {
+        match (self, data) {
+            (
+                AS3Validator::TaggedUnion {
+                    discriminator,
+                    variants,
+                },
+                AS3Data::Object(values),
+            ) => match values.get(discriminator)?.as_ref() {
+                AS3Data::String(tag) => variants.get(tag),
+                _ => None,
+            },
+            _ => None,
+        }
+    }
+
+    /// Keywords such as `+description` set on the definition, if any
+    pub fn annotations(&self) -> Option<&Annotations> {
+        match self {
+            AS3Validator::Annotated { annotations, .. } => Some(annotations),
+            AS3Validator::Nullable(inner) => inner.annotations(),
+            _ => None,
+        }
+    }
+
+    /// Values accepted by a Literal, or by a String whose regex lists them, such as
+    /// `^(draft|published)$`
+    pub fn enumeration(&self) -> Option<Vec<String>> {
+        match self {
+            AS3Validator::Literal { values, .. } => {
+                let mut values: Vec<String> = values.iter().cloned().collect();
+                values.sort();
+                Some(values)
+            }
+            AS3Validator::String {
+                regex: Some(regex),
+                format: None,
+                any_regex: None,
+                deny_regex: None,
+                ..
+            } => {
+                let alternatives = regex.as_str().strip_prefix('^')?.strip_suffix('$')?;
+                let alternatives = alternatives
+                    .strip_prefix("(?:")
+                    .or_else(|| alternatives.strip_prefix('('))
+                    .and_then(|alternatives| alternatives.strip_suffix(')'))
+                    .unwrap_or(alternatives);
+                let values: Vec<String> = alternatives.split('|').map(str::to_string).collect();
+                values
+                    .iter()
+                    .all(|value| {
+                        !value.is_empty() && !value.contains(|c| r"\.^$|?*+()[]{}".contains(c))
+                    })
+                    .then_some(values)
+            }
+            _ => None,
+        }
+    }
+
+    /// Short name of the type checked, such as `List`, or `Integer?` when nullable
+    pub fn type_name(&self) -> String {
+        crate::error::type_name(self)
+    }
+
+    pub fn title(&self) -> Option<&str> {
+        self.annotations()?.title.as_deref()
+    }
+
+    pub fn description(&self) -> Option<&str> {
+        self.annotations()?.description.as_deref()
+    }
+
+    pub fn examples(&self) -> &[serde_json::Value] {
+        self.annotations()
+            .map_or(&[], |annotations| annotations.examples.as_slice())
+    }
+
+    /// Keys accepted by the Objects with `+extraKeys: warn` without being among their fields
+    pub fn unexpected_keys(&self, data: &AS3Data) -> Vec<As3JsonPath<AS3ValidationError>> {
+        let mut found = self.warnings(data);
+        found.retain(|As3JsonPath(_, warning)| {
+            matches!(warning, AS3ValidationError::UnexpectedKey { .. })
+        });
+        found
+    }
+
+    /// Findings that don't make `data` invalid: keys accepted by `+extraKeys: warn`, values of
+    /// `+deprecated` definitions and errors of `+severity: warning` ones
+    pub fn warnings(&self, data: &AS3Data) -> Vec<As3JsonPath<AS3ValidationError>> {
+        self.warnings_with_options(data, &ValidationOptions::default())
+    }
+
+    /// [`AS3Validator::warnings`] of the parts of `data` selected by `options`, coerced first
+    /// with [`ValidationOptions::coerce`] as they are for the validation
+    pub fn warnings_with_options(
+        &self,
+        data: &AS3Data,
+        options: &ValidationOptions,
+    ) -> Vec<As3JsonPath<AS3ValidationError>> {
+        let mut found = Vec::new();
+        #[cfg(feature = "coerce")]
+        if options.coerce {
+            self.collect_warnings(
+                &self.coerced(data),
+                &mut JsonPath::root("ROOT"),
+                options,
+                &mut found,
+            );
+            return found;
+        }
+        self.collect_warnings(data, &mut JsonPath::root("ROOT"), options, &mut found);
+        found
+    }
+
+    fn collect_warnings(
+        &self,
+        data: &AS3Data,
+        path: &mut JsonPath,
+        options: &ValidationOptions,
+        found: &mut Vec<As3JsonPath<AS3ValidationError>>,
+    ) {
+        if options.skips(path) {
+            return;
+        }
+        match (self, data) {
+            (AS3Validator::Annotated { inner, annotations }, _) => {
+                if let (Some(deprecation), false, true) = (
+                    &annotations.deprecated,
+                    matches!(data, AS3Data::Null),
+                    options.selects(path),
+                ) {
+                    found.push(As3JsonPath(
+                        path.clone(),
+                        AS3ValidationError::Deprecated {
+                            message: deprecation.message.clone(),
+                        },
+                    ));
+                }
+                if annotations.severity == Severity::Warning {
+                    let mut errors = Vec::new();
+                    // Every error is a warning, and the values aren't visited again
+                    let options = ValidationOptions {
+                        max_errors: None,
+                        tracer: None,
+                        ..options.clone()
+                    };
+                    inner.collect_errors(data, path, &options, 0, &mut errors);
+                    found.extend(
+                        errors
+                            .into_iter()
+                            .map(|error| annotations.with_message(error)),
+                    );
+                }
+                inner.collect_warnings(data, path, options, found)
+            }
+            (AS3Validator::Nullable(inner), _) => {
+                inner.collect_warnings(data, path, options, found)
+            }
+            (AS3Validator::Ref(reference), _) => {
+                if let Some(target) = reference.target() {
+                    target.collect_warnings(data, path, options, found);
+                }
+            }
+            (
+                AS3Validator::Conditional {
+                    base,
+                    condition,
+                    then,
+                    otherwise,
+                },
+                _,
+            ) => {
+                base.collect_warnings(data, path, options, found);
+                let branch = match condition.check(data, path) {
+                    Ok(()) => then,
+                    Err(_) => otherwise,
+                };
+                if let Some(branch) = branch {
+                    branch.collect_warnings(data, path, options, found);
+                }
+            }
+            (
+                AS3Validator::Object {
+                    fields,
+                    extra_keys,
+                    pattern_keys,
+                    ..
+                },
+                AS3Data::Object(values),
+            ) => {
+                if *extra_keys == ExtraKeys::Warn && options.selects(path) {
+                    for key in AS3Validator::extra_keys_of(fields, pattern_keys, values) {
+                        found.push(As3JsonPath(
+                            path.clone(),
+                            AS3Validator::unexpected_key(key, fields, values),
+                        ));
+                    }
+                }
+                let mut keys: Vec<&String> = fields.keys().collect();
+                keys.sort();
+                for key in keys {
+                    if let Some(value) = values.get(key) {
+                        path.push_key(key);
+                        fields[key].collect_warnings(value, path, options, found);
+                        path.pop();
+                    }
+                }
+            }
+            (AS3Validator::List(items_type), AS3Data::List(items)) => {
+                for (index, item) in items.iter().enumerate() {
+                    path.push_index(index);
+                    items_type.collect_warnings(item, path, options, found);
+                    path.pop();
+                }
+            }
+            (AS3Validator::Map { value_type, .. }, AS3Data::Object(values)) => {
+                let mut keys: Vec<&String> = values.keys().collect();
+                keys.sort();
+                for key in keys {
+                    path.push_key(key);
+                    value_type.collect_warnings(&values[key], path, options, found);
+                    path.pop();
+                }
+            }
+            (AS3Validator::TaggedUnion { .. }, _) => {
+                if let Some(variant) = self.variant_of(data) {
+                    variant.collect_warnings(data, path, options, found);
+                }
+            }
+            _ => {}
+        }
+    }
+
+    /// Errors of `data` pushed to `found`. Containers are walked so that an error doesn't hide
+    /// those of the other values, the checks of [`AS3Validator::check_with`] being used for the
+    /// rest
+    fn collect_errors(
+        &self,
+        data: &AS3Data,
+        path: &mut JsonPath,
+        options: &ValidationOptions,
+        depth: usize,
+        found: &mut Vec<As3JsonPath<AS3ValidationError>>,
+    ) {
+        if options.is_full(found) {
+            return;
+        }
+        if let Some(tracer) = &options.tracer {
+            tracer.visit(path);
+        }
+        let error = |path: &JsonPath, error| As3JsonPath(path.clone(), error);
+        match (self, data) {
+            (AS3Validator::Ref(reference), _) if depth < MAX_REFERENCE_DEPTH => {
+                match reference.target() {
+                    Some(target) => target.collect_errors(data, path, options, depth + 1, found),
+                    None => found.extend(self.check_with(data, path, options, depth).err()),
+                }
+            }
+            (AS3Validator::Annotated { inner, annotations }, _) => {
+                if annotations.severity == Severity::Warning {
+                    return;
+                }
+                // Collected in `found` so that `max_errors` counts them
+                let start = found.len();
+                inner.collect_errors(data, path, options, depth, found);
+                let inner_errors = found.split_off(start);
+                found.extend(
+                    inner_errors
+                        .into_iter()
+                        .map(|error| annotations.with_message(error)),
+                );
+            }
+            (AS3Validator::Nullable(inner), _) if !matches!(data, AS3Data::Null) => {
+                inner.collect_errors(data, path, options, depth, found)
+            }
+            (
+                AS3Validator::Conditional {
+                    base,
+                    condition,
+                    then,
+                    otherwise,
+                },
+                _,
+            ) if !matches!(data, AS3Data::Null) => {
+                base.collect_errors(data, path, options, depth, found);
+                let branch = match condition.check(data, path) {
+                    Ok(()) => then,
+                    Err(_) => otherwise,
+                };
+                if let Some(branch) = branch {
+                    branch.collect_errors(data, path, options, depth, found);
+                }
+            }
+            (AS3Validator::TaggedUnion { .. }, _) => match self.variant_of(data) {
+                Some(variant) => variant.collect_errors(data, path, options, depth, found),
+                None => found.extend(self.check_with(data, path, options, depth).err()),
+            },
+            (
+                AS3Validator::Object {
+                    fields,
+                    extra_keys,
+                    pattern_keys,
+                    assertions,
+                    requires,
+                    ..
+                },
+                AS3Data::Object(values),
+            ) => {
+                let selected = options.selects(path);
+                for (key, required) in requires.iter().filter(|_| selected) {
+                    if !values.contains_key(key) {
+                        continue;
+                    }
+                    for missing in required.iter().filter(|other| !values.contains_key(*other)) {
+                        found.push(error(
+                            path,
+                            AS3ValidationError::MissingDependency {
+                                key: missing.clone(),
+                                required_by: key.clone(),
+                            },
+                        ));
+                    }
+                }
+                if *extra_keys == ExtraKeys::Forbid && selected {
+                    for key in AS3Validator::extra_keys_of(fields, pattern_keys, values) {
+                        if !options.skips(&path.with_key(&key)) {
+                            found.push(error(
+                                path,
+                                AS3Validator::unexpected_key(key, fields, values),
+                            ));
+                        }
+                    }
+                }
+                let mut keys: Vec<&String> = fields.keys().chain(values.keys()).collect();
+                keys.sort();
+                keys.dedup();
+                for key in keys {
+                    path.push_key(key);
+                    let skipped = options.skips(path);
+                    match (fields.get(key), values.get(key), pattern_keys) {
+                        _ if skipped => {}
+                        (Some(field), Some(value), _) => {
+                            field.collect_errors(value, path, options, depth, found)
+                        }
+                        (None, Some(value), Some(pattern_keys)) => {
+                            for validator in pattern_keys.matching(key) {
+                                validator.collect_errors(value, path, options, depth, found);
+                            }
+                        }
+                        _ => {}
+                    }
+                    path.pop();
+                    if !skipped && fields.contains_key(key) && !values.contains_key(key) {
+                        let missing = AS3Validator::missing_key(key.clone(), fields, values);
+                        found.push(error(path, missing));
+                    }
+                }
+                for assertion in assertions.iter().filter(|_| selected) {
+                    found.extend(assertion.check(values, path).err());
+                }
+            }
+            (
+                AS3Validator::Map {
+                    key_type,
+                    value_type,
+                    min_entries,
+                    max_entries,
+                },
+                AS3Data::Object(values),
+            ) => {
+                let count = values.len() as i64;
+                match (min_entries, max_entries) {
+                    (Some(min_entries), _) if count < *min_entries => found.push(error(
+                        path,
+                        AS3ValidationError::MinimumEntries {
+                            count,
+                            min_entries: *min_entries,
+                        },
+                    )),
+                    (_, Some(max_entries)) if count > *max_entries => found.push(error(
+                        path,
+                        AS3ValidationError::MaximumEntries {
+                            count,
+                            max_entries: *max_entries,
+                        },
+                    )),
+                    _ => {}
+                }
+                let mut keys: Vec<&String> = values.keys().collect();
+                keys.sort();
+                for key in keys {
+                    path.push_key(key);
+                    if !options.skips(path) {
+                        value_type.collect_errors(&values[key], path, options, depth, found);
+                        if let Err(e) = AS3Validator::check_map_key_value(key, key_type, path) {
+                            found.push(As3JsonPath(path.clone(), AS3ValidationError::Generic(e)));
+                        }
+                    }
+                    path.pop();
+                }
+            }
+            (AS3Validator::List(items_type), AS3Data::List(items)) => {
+                for (index, item) in items.iter().enumerate() {
+                    path.push_index(index);
+                    items_type.collect_errors(item, path, options, depth, found);
+                    path.pop();
+                }
+            }
+            _ => found.extend(self.check_with(data, path, options, depth).err()),
+        }
+    }
+
+    /// Error of the `key` field missing from `values`, suggesting a key of `values` that
+    /// isn't a field and looks like a typo
+    fn missing_key(
+        key: String,
+        fields: &HashMap<String, AS3Validator>,
+        values: &HashMap<String, Box<AS3Data>>,
+    ) -> AS3ValidationError {
+        let unknown: Vec<&str> = (values.keys())
+            .filter(|value_key| !fields.contains_key(*value_key))
+            .map(String::as_str)
+            .collect();
+        AS3ValidationError::MissingKey {
+            suggestion: did_you_mean(&key, &unknown),
+            key,
+        }
+    }
+
+    /// Error of the `key` of `values` that isn't among `fields`, suggesting a missing field that
+    /// it looks like a typo of
+    fn unexpected_key(
+        key: String,
+        fields: &HashMap<String, AS3Validator>,
+        values: &HashMap<String, Box<AS3Data>>,
+    ) -> AS3ValidationError {
+        let missing: Vec<&str> = (fields.keys())
+            .filter(|field| !values.contains_key(*field))
+            .map(String::as_str)
+            .collect();
+        AS3ValidationError::UnexpectedKey {
+            suggestion: did_you_mean(&key, &missing),
+            key,
+        }
+    }
+
+    /// Keys of `values` that aren't in `fields` nor match `pattern_keys`, sorted
+    fn extra_keys_of(
+        fields: &HashMap<String, AS3Validator>,
+        pattern_keys: &Option<PatternKeys>,
+        values: &HashMap<String, Box<AS3Data>>,
+    ) -> Vec<String> {
+        let mut extra: Vec<String> = values
+            .keys()
+            .filter(|key| !fields.contains_key(*key))
+            .filter(|key| {
+                pattern_keys
+                    .as_ref()
+                    .is_none_or(|pattern_keys| !pattern_keys.patterns.matches(key).matched_any())
+            })
+            .cloned()
+            .collect();
+        extra.sort();
+        extra
+    }
+
+    #[allow(clippy::result_large_err)]
+    pub(crate) fn check(
+        &self,
+        data: &AS3Data,
+        path: &mut JsonPath,
+    ) -> Result<(), As3JsonPath<AS3ValidationError>> {
+        self.check_with(data, path, &ValidationOptions::default(), 0)
+    }
+
+    #[allow(clippy::result_large_err)]
+    fn check_with(
+        &self,
+        data: &AS3Data,
+        path: &mut JsonPath,
+        options: &ValidationOptions,
+        depth: usize,
+    ) -> Result<(), As3JsonPath<AS3ValidationError>> {
+        metrics::add(Counter::NodesVisited, 1);
+        if let Some(tracer) = &options.tracer {
+            tracer.visit(path);
+        }
+        if let AS3Validator::Ref(reference) = self {
+            if depth >= MAX_REFERENCE_DEPTH {
+                return Err(As3JsonPath(
+                    path.clone(),
+                    AS3ValidationError::RecursionLimit {
+                        depth: MAX_REFERENCE_DEPTH,
+                    },
+                ));
+            }
+            let Some(target) = reference.target() else {
+                return Err(As3JsonPath(
+                    path.clone(),
+                    AS3ValidationError::Generic(format!(
+                        "`#/defs/{}` is not resolved",
+                        reference.name()
+                    )),
+                ));
+            };
+            return target.check_with(data, path, options, depth + 1);
+        }
+        if let AS3Validator::Annotated { inner, annotations } = self {
+            if annotations.severity == Severity::Warning {
+                return Ok(());
+            }
+            return inner
+                .check_with(data, path, options, depth)
+                .map_err(|error| annotations.with_message(error));
+        }
+
+        match (self, data) {
+            (
+                AS3Validator::Nullable(..) | AS3Validator::Null | AS3Validator::Any,
+                AS3Data::Null,
+            ) => return Ok(()),
+            (_, AS3Data::Null) => {
+                return Err(As3JsonPath(
+                    path.clone(),
+                    AS3ValidationError::NotNullableNull,
+                ))
+            }
+            _ => {}
+        };
+
+        match (self, data) {
+            (AS3Validator::Any, _) => Ok(()),
+            (AS3Validator::Nullable(inner), _) => inner.check_with(data, path, options, depth),
+            (
+                AS3Validator::Conditional {
+                    base,
+                    condition,
+                    then,
+                    otherwise,
+                },
+                _,
+            ) => {
+                base.check_with(data, path, options, depth)?;
+                let branch = match condition.check(data, &mut path.clone()) {
+                    Ok(()) => then,
+                    Err(_) => otherwise,
+                };
+                match branch {
+                    Some(branch) => branch.check_with(data, path, options, depth),
+                    None => Ok(()),
+                }
+            }
+            (
+                AS3Validator::Object {
+                    fields: validator_inner,
+                    extra_keys,
+                    pattern_keys,
+                    assertions,
+                    requires,
+                    ..
+                },
+                AS3Data::Object(data_inner),
+            ) => {
+                // Only leads to the selected paths, its own constraints don't apply
+                let selected = options.selects(path);
+
+                for (key, required) in requires.iter().filter(|_| selected) {
+                    if !data_inner.contains_key(key) {
+                        continue;
+                    }
+                    if let Some(missing) = required
+                        .iter()
+                        .find(|other| !data_inner.contains_key(*other))
+                    {
+                        return Err(As3JsonPath(
+                            path.clone(),
+                            AS3ValidationError::MissingDependency {
+                                key: missing.clone(),
+                                required_by: key.clone(),
+                            },
+                        ));
+                    }
+                }
+
+                if *extra_keys == ExtraKeys::Forbid && selected {
+                    if let Some(key) =
+                        AS3Validator::extra_keys_of(validator_inner, pattern_keys, data_inner)
+                            .into_iter()
+                            .find(|key| !options.skips(&path.with_key(key)))
+                    {
+                        return Err(As3JsonPath(
+                            path.clone(),
+                            AS3Validator::unexpected_key(key, validator_inner, data_inner),
+                        ));
+                    }
+                }
+
+                if let Some(pattern_keys) = pattern_keys {
+                    let mut keys: Vec<&String> = data_inner
+                        .keys()
+                        .filter(|key| !validator_inner.contains_key(*key))
+                        .collect();
+                    keys.sort();
+                    for key in keys {
+                        path.push_key(key);
+                        let result = match options.skips(path) {
+                            true => Ok(()),
+                            false => pattern_keys.matching(key).try_for_each(|validator| {
+                                validator.check_with(&data_inner[key], path, options, depth)
+                            }),
+                        };
+                        path.pop();
+                        result?;
+                    }
+                }
+
+                #[cfg(all(feature = "parallel", not(target_os = "wasi")))]
+                metrics::add(Counter::ParallelTasks, validator_inner.len() as u64);
+                #[cfg(all(feature = "parallel", not(target_os = "wasi")))]
+                let fields = validator_inner.par_iter();
+                #[cfg(not(all(feature = "parallel", not(target_os = "wasi"))))]
+                let fields = validator_inner.iter();
+                let res: Vec<Result<(), As3JsonPath<AS3ValidationError>>> = fields
+                    .map(|(validator_key, validator_value)| {
+                        // Each thread has its own path
+                        let mut temp_path = path.with_key(validator_key);
+                        if options.skips(&temp_path) {
+                            return Ok(());
+                        }
+                        if let Some(value_from_key) = data_inner.get(validator_key) {
+                            return validator_value.check_with(
+                                value_from_key,
+                                &mut temp_path,
+                                options,
+                                depth,
+                            );
+                        }
+                        Err(As3JsonPath(
+                            path.clone(),
+                            AS3Validator::missing_key(
+                                validator_key.clone(),
+                                validator_inner,
+                                data_inner,
+                            ),
+                        ))
+                    })
+                    .collect();
+
+                res.into_iter()
+                    .collect::<Result<Vec<()>, As3JsonPath<AS3ValidationError>>>()?;
+
+                for assertion in assertions.iter().filter(|_| selected) {
+                    assertion.check(data_inner, path)?;
+                }
+                Ok(())
+            }
+            (
+                AS3Validator::Map {
+                    key_type,
+                    value_type,
+                    min_entries,
+                    max_entries,
+                },
+                AS3Data::Object(data_inner),
+            ) => {
+                let count = data_inner.len() as i64;
+                if let Some(min_entries) = min_entries {
+                    if count < *min_entries {
+                        return Err(As3JsonPath(
+                            path.clone(),
+                            AS3ValidationError::MinimumEntries {
+                                count,
+                                min_entries: *min_entries,
+                            },
+                        ));
+                    }
+                }
+                if let Some(max_entries) = max_entries {
+                    if count > *max_entries {
+                        return Err(As3JsonPath(
+                            path.clone(),
+                            AS3ValidationError::MaximumEntries {
+                                count,
+                                max_entries: *max_entries,
+                            },
+                        ));
+                    }
+                }
+
+                for (key_data, value_data) in data_inner {
+                    path.push_key(key_data);
+                    let result = match options.skips(path) {
+                        true => Ok(()),
+                        false => match (
+                            value_type.check_with(value_data, path, options, depth),
+                            AS3Validator::check_map_key_value(key_data, key_type, path),
+                        ) {
+                            (Ok(_), Ok(_)) => Ok(()),
+                            (Err(e), _) => Err(e),
+                            (_, Err(e)) => {
+                                Err(As3JsonPath(path.clone(), AS3ValidationError::Generic(e)))
+                            }
+                        },
+                    };
+                    path.pop();
+                    result?;
+                }
+                Ok(())
+            }
+            (
+                AS3Validator::Integer {
+                    minimum,
+                    maximum,
+                    width,
+                },
+                AS3Data::Integer(number),
+            ) => {
+                if let Some(width) = width {
+                    width.check(i128::from(*number), path)?;
+                }
+
+                if let Some(minimum) = minimum {
+                    if number < minimum {
+                        return Err(As3JsonPath(
+                            path.clone(),
+                            AS3ValidationError::MinimumInteger {
+                                number: i128::from(*number),
+                                minimum: *minimum,
+                            },
+                        ));
+                    }
+                }
+
+                if let Some(maximum) = maximum {
+                    if number > maximum {
+                        return Err(As3JsonPath(
+                            path.clone(),
+                            AS3ValidationError::MaximumInteger {
+                                number: i128::from(*number),
+                                maximum: *maximum,
+                            },
+                        ));
+                    }
+                }
+                Ok(())
+            }
+            // Above `i64::MAX`, so above any `+max` and never below `+min`
+            (AS3Validator::Integer { maximum, width, .. }, AS3Data::UInteger(number)) => {
+                if let Some(width) = width {
+                    width.check(i128::from(*number), path)?;
+                }
+
+                if let Some(maximum) = maximum {
+                    return Err(As3JsonPath(
+                        path.clone(),
+                        AS3ValidationError::MaximumInteger {
+                            number: i128::from(*number),
+                            maximum: *maximum,
+                        },
+                    ));
+                }
+                Ok(())
+            }
+            (AS3Validator::Decimal { minimum, maximum }, AS3Data::Decimal(number)) => {
+                if let Some(minimum) = minimum {
+                    if number < minimum {
+                        return Err(As3JsonPath(
+                            path.clone(),
+                            AS3ValidationError::MinimumDecimal {
+                                number: *number,
+                                minimum: *minimum,
+                            },
+                        ));
+                    }
+                }
+
+                if let Some(maximum) = maximum {
+                    if number > maximum {
+                        return Err(As3JsonPath(
+                            path.clone(),
+                            AS3ValidationError::MaximumDecimal {
+                                number: *number,
+                                maximum: *maximum,
+                            },
+                        ));
+                    }
+                }
+                Ok(())
+            }
+            (
+                AS3Validator::String {
+                    regex,
+                    max_length,
+                    min_length,
+                    format,
+                    any_regex,
+                    deny_regex,
+                    char_classes,
+                    length_unit,
+                },
+                AS3Data::String(string),
+            ) => {
+                for class in char_classes {
+                    if let Some((position, character)) = string
+                        .chars()
+                        .enumerate()
+                        .find(|(_, character)| !class.contains(*character))
+                    {
+                        return Err(As3JsonPath(
+                            path.clone(),
+                            AS3ValidationError::CharacterClass {
+                                word: string.to_owned(),
+                                class: class.keyword().to_string(),
+                                character,
+                                position: position + 1,
+                            },
+                        ));
+                    }
+                }
+                if let Some(regex) = regex {
+                    if !regex.is_match(string) {
+                        return Err(As3JsonPath(
+                            path.clone(),
+                            AS3ValidationError::RegexError {
+                                word: string.to_owned(),
+                                regex: regex.as_str().to_owned(),
+                            },
+                        ));
+                    }
+                };
+                if let Some(any_regex) = any_regex {
+                    if !any_regex.matches(string).matched_any() {
+                        return Err(As3JsonPath(
+                            path.clone(),
+                            AS3ValidationError::AnyRegexError {
+                                word: string.to_owned(),
+                                regexes: any_regex.patterns().to_vec(),
+                            },
+                        ));
+                    }
+                }
+                if let Some(deny_regex) = deny_regex {
+                    if let Some(index) = deny_regex.matches(string).iter().next() {
+                        return Err(As3JsonPath(
+                            path.clone(),
+                            AS3ValidationError::DenyRegexError {
+                                word: string.to_owned(),
+                                regex: deny_regex.patterns()[index].clone(),
+                            },
+                        ));
+                    }
+                }
+                let length = length_unit.length(string) as i64;
+                if let Some(min_length) = min_length {
+                    if length < *min_length {
+                        return Err(As3JsonPath(
+                            path.clone(),
+                            AS3ValidationError::MinimumString {
+                                string: string.clone(),
+                                current_lenght: length,
+                                min_length: *min_length,
+                                unit: *length_unit,
+                            },
+                        ));
+                    }
+                }
+
+                if let Some(max_length) = max_length {
+                    if length > *max_length {
+                        return Err(As3JsonPath(
+                            path.clone(),
+                            AS3ValidationError::MaximumString {
+                                string: string.clone(),
+                                current_lenght: length,
+                                max_length: *max_length,
+                                unit: *length_unit,
+                            },
+                        ));
+                    }
+                }
+
+                if let Some(format) = format {
+                    if let Err(reason) = format.check(string) {
+                        return Err(As3JsonPath(
+                            path.clone(),
+                            AS3ValidationError::FormatError {
+                                word: string.to_owned(),
+                                format: format.to_string(),
+                                reason,
+                            },
+                        ));
+                    }
+                }
+
+                Ok(())
+            }
+            (AS3Validator::List(items_type), AS3Data::List(items)) => {
+                // Ok(items.iter().all(|item| items_type.check(item)))
+
+                for (index, item) in items.iter().enumerate() {
+                    path.push_index(index);
+                    let result = items_type.check_with(item, path, options, depth);
+                    path.pop();
+                    result?;
+                }
+                Ok(())
+            }
+            (AS3Validator::Date { minimum, maximum }, AS3Data::String(items)) => {
+                let Some(date) = parse_date(items) else {
+                    return Err(As3JsonPath(
+                        path.clone(),
+                        AS3ValidationError::Generic(format!(
+                            " `{}` can't be converted to a valid date. [Supported YYYY-MM-DD] ",
+                            items
+                        )),
+                    ));
+                };
+
+                if let Some(minimum) = minimum {
+                    if date < minimum.resolve() {
+                        return Err(As3JsonPath(
+                            path.clone(),
+                            AS3ValidationError::MinimumDate {
+                                date: items.to_owned(),
+                                minimum: minimum.to_string(),
+                            },
+                        ));
+                    }
+                }
+
+                if let Some(maximum) = maximum {
+                    if date > maximum.resolve() {
+                        return Err(As3JsonPath(
+                            path.clone(),
+                            AS3ValidationError::MaximumDate {
+                                date: items.to_owned(),
+                                maximum: maximum.to_string(),
+                            },
+                        ));
+                    }
+                }
+                Ok(())
+            }
+            (AS3Validator::Literal { values, regex }, AS3Data::String(string)) => {
+                if !values.contains(string) {
+                    return Err(As3JsonPath(
+                        path.clone(),
+                        AS3ValidationError::RegexError {
+                            word: string.to_owned(),
+                            regex: regex.to_owned(),
+                        },
+                    ));
+                }
+                Ok(())
+            }
+            (AS3Validator::DateTime { minimum, maximum }, AS3Data::String(string)) => {
+                let datetime = match chrono::DateTime::parse_from_rfc3339(string) {
+                    Ok(datetime) => datetime,
+                    Err(e) => {
+                        return Err(As3JsonPath(
+                            path.clone(),
+                            AS3ValidationError::FormatError {
+                                word: string.to_owned(),
+                                format: "RFC 3339 date-time".to_string(),
+                                reason: e.to_string(),
+                            },
+                        ))
+                    }
+                };
+
+                if let Some(minimum) = minimum {
+                    if datetime < *minimum {
+                        return Err(As3JsonPath(
+                            path.clone(),
+                            AS3ValidationError::MinimumDateTime {
+                                datetime: string.to_owned(),
+                                minimum: minimum.to_rfc3339(),
+                            },
+                        ));
+                    }
+                }
+
+                if let Some(maximum) = maximum {
+                    if datetime > *maximum {
+                        return Err(As3JsonPath(
+                            path.clone(),
+                            AS3ValidationError::MaximumDateTime {
+                                datetime: string.to_owned(),
+                                maximum: maximum.to_rfc3339(),
+                            },
+                        ));
+                    }
+                }
+                Ok(())
+            }
+            (AS3Validator::Time { minimum, maximum }, AS3Data::String(string)) => {
+                let Some(time) = parse_time(string) else {
+                    return Err(As3JsonPath(
+                        path.clone(),
+                        AS3ValidationError::FormatError {
+                            word: string.to_owned(),
+                            format: "time".to_string(),
+                            reason: "expected HH:MM or HH:MM:SS".to_string(),
+                        },
+                    ));
+                };
+
+                if let Some(minimum) = minimum {
+                    if time < *minimum {
+                        return Err(As3JsonPath(
+                            path.clone(),
+                            AS3ValidationError::MinimumTime {
+                                time: string.to_owned(),
+                                minimum: minimum.to_string(),
+                            },
+                        ));
+                    }
+                }
+
+                if let Some(maximum) = maximum {
+                    if time > *maximum {
+                        return Err(As3JsonPath(
+                            path.clone(),
+                            AS3ValidationError::MaximumTime {
+                                time: string.to_owned(),
+                                maximum: maximum.to_string(),
+                            },
+                        ));
+                    }
+                }
+                Ok(())
+            }
+            (
+                AS3Validator::Duration {
+                    syntax,
+                    minimum,
+                    maximum,
+                },
+                AS3Data::String(string),
+            ) => {
+                let duration = match syntax.parse(string) {
+                    Ok(duration) => duration,
+                    Err(reason) => {
+                        return Err(As3JsonPath(
+                            path.clone(),
+                            AS3ValidationError::FormatError {
+                                word: string.to_owned(),
+                                format: "duration".to_string(),
+                                reason,
+                            },
+                        ))
+                    }
+                };
+
+                if let Some(minimum) = minimum {
+                    if duration < *minimum {
+                        return Err(As3JsonPath(
+                            path.clone(),
+                            AS3ValidationError::MinimumDuration {
+                                duration: string.to_owned(),
+                                minimum: humantime::format_duration(*minimum).to_string(),
+                            },
+                        ));
+                    }
+                }
+
+                if let Some(maximum) = maximum {
+                    if duration > *maximum {
+                        return Err(As3JsonPath(
+                            path.clone(),
+                            AS3ValidationError::MaximumDuration {
+                                duration: string.to_owned(),
+                                maximum: humantime::format_duration(*maximum).to_string(),
+                            },
+                        ));
+                    }
+                }
+                Ok(())
+            }
+            (
+                AS3Validator::Bytes {
+                    encoding,
+                    min_bytes,
+                    max_bytes,
+                },
+                AS3Data::String(string),
+            ) => {
+                let length = match encoding.decoded_len(string) {
+                    Ok(length) => length as i64,
+                    Err(reason) => {
+                        return Err(As3JsonPath(
+                            path.clone(),
+                            AS3ValidationError::FormatError {
+                                word: string.to_owned(),
+                                format: encoding.to_string(),
+                                reason,
+                            },
+                        ))
+                    }
+                };
+
+                if let Some(min_bytes) = min_bytes {
+                    if length < *min_bytes {
+                        return Err(As3JsonPath(
+                            path.clone(),
+                            AS3ValidationError::MinimumBytes {
+                                length,
+                                min_bytes: *min_bytes,
+                            },
+                        ));
+                    }
+                }
+
+                if let Some(max_bytes) = max_bytes {
+                    if length > *max_bytes {
+                        return Err(As3JsonPath(
+                            path.clone(),
+                            AS3ValidationError::MaximumBytes {
+                                length,
+                                max_bytes: *max_bytes,
+                            },
+                        ));
+                    }
+                }
+                Ok(())
+            }
+            (AS3Validator::Boolean, AS3Data::Boolean(..)) => Ok(()),
+            (
+                AS3Validator::TaggedUnion {
+                    discriminator,
+                    variants,
+                },
+                AS3Data::Object(values),
+            ) => {
+                let Some(tag) = values.get(discriminator) else {
+                    let keys: Vec<&str> = values.keys().map(String::as_str).collect();
+                    return Err(As3JsonPath(
+                        path.clone(),
+                        AS3ValidationError::MissingKey {
+                            key: discriminator.clone(),
+                            suggestion: did_you_mean(discriminator, &keys),
+                        },
+                    ));
+                };
+                let variant = match tag.as_ref() {
+                    AS3Data::String(tag) => variants.get(tag),
+                    _ => None,
+                };
+                match variant {
+                    Some(variant) => variant.check_with(data, path, options, depth),
+                    None => Err(As3JsonPath(
+                        path.with_key(discriminator),
+                        AS3ValidationError::UnknownTag {
+                            discriminator: discriminator.clone(),
+                            tag: match tag.as_ref() {
+                                AS3Data::String(tag) => tag.clone(),
+                                tag => tag.to_string(),
+                            },
+                            expected: variants.keys().cloned().collect(),
+                        },
+                    )),
+                }
+            }
+
+            _ => Err(As3JsonPath(
+                path.clone(),
+                AS3ValidationError::TypeError {
+                    expected: self.clone(),
+                    got: data.clone(),
+                },
+            )),
+        }
+    }
+
+    fn check_map_key_value(
+        key: &String,
+        wanted_type: &AS3Validator,
+        path: &mut JsonPath,
+    ) -> Result<(), String> {
+        let _ = match wanted_type {
+            AS3Validator::String { .. } | AS3Validator::Literal { .. } => {
+                wanted_type.check(&AS3Data::String(key.clone()), path)
+            }
+            AS3Validator::Integer { .. } => {
+                let number = match (key.parse::<i64>(), key.parse::<u64>()) {
+                    (Ok(n), _) => AS3Data::Integer(n),
+                    (_, Ok(n)) => AS3Data::UInteger(n),
+                    _ => {
+                        return Err(format!("The Key `{}` can't be converted to an Integer", key))
+                    }
+                };
+
+                match wanted_type.check(&number, path) {
+                    Ok(()) => Ok(()),
+                    Err(e) => return Err(e.to_string()),
+                }
+            }
+            AS3Validator::Any => Ok(()),
+            AS3Validator::Boolean => match key.to_lowercase().as_str() {
+                "true" | "false" | "1" | "0" => Ok(()),
+                _ => return Err(format!("The Key `{}` can't be converted to a Boolean", key)),
+            },
+            AS3Validator::Date { .. } => match wanted_type.check(&AS3Data::String(key.clone()), path) {
+                Ok(())=> Ok(()),
+                _ => return Err(format!("The Key `{}` can't be converted to a Date", key)),
+            },
+            _ => return Err(
+                "Usupported Map's KeyValue conversion. [Supported types : String, Integer, Bool, Date(YYYY-MM-DD) ]"
+                    .to_string(),
+            ),
+        };
+        Ok(())
+    }
+    /// Simplifies the validator ahead of validation without changing what it accepts:
+    /// nested `Nullable` are collapsed and Strings constrained only by an anchored regex of
+    /// literals (e.g. `^active$` or `^(draft|published)$`) become a lookup in a set of values
+    pub fn optimize(self) -> AS3Validator {
+        match self {
+            AS3Validator::Nullable(inner) => match inner.optimize() {
+                AS3Validator::Nullable(inner) => AS3Validator::Nullable(inner),
+                AS3Validator::Null => AS3Validator::Null,
+                AS3Validator::Any => AS3Validator::Any,
+                inner => AS3Validator::Nullable(Box::new(inner)),
+            },
+            AS3Validator::Object {
+                fields,
+                extra_keys,
+                pattern_keys,
+                assertions,
+                requires,
+                order,
+            } => AS3Validator::Object {
+                fields: fields
+                    .into_iter()
+                    .map(|(key, value)| (key, value.optimize()))
+                    .collect(),
+                extra_keys,
+                assertions,
+                requires,
+                order,
+                pattern_keys: pattern_keys.map(|pattern_keys| PatternKeys {
+                    validators: pattern_keys
+                        .validators
+                        .into_iter()
+                        .map(AS3Validator::optimize)
+                        .collect(),
+                    ..pattern_keys
+                }),
+            },
+            AS3Validator::Conditional {
+                base,
+                condition,
+                then,
+                otherwise,
+            } => AS3Validator::Conditional {
+                base: Box::new(base.optimize()),
+                condition: Box::new(condition.optimize()),
+                then: then.map(|then| Box::new(then.optimize())),
+                otherwise: otherwise.map(|otherwise| Box::new(otherwise.optimize())),
+            },
+            AS3Validator::Annotated { inner, annotations } => AS3Validator::Annotated {
+                inner: Box::new(inner.optimize()),
+                annotations,
+            },
+            AS3Validator::List(items_type) => AS3Validator::List(Box::new(items_type.optimize())),
+            AS3Validator::TaggedUnion {
+                discriminator,
+                variants,
+            } => AS3Validator::TaggedUnion {
+                discriminator,
+                variants: variants
+                    .into_iter()
+                    .map(|(tag, variant)| (tag, variant.optimize()))
+                    .collect(),
+            },
+            AS3Validator::Map {
+                key_type,
+                value_type,
+                min_entries,
+                max_entries,
+            } => AS3Validator::Map {
+                key_type: Box::new(key_type.optimize()),
+                value_type: Box::new(value_type.optimize()),
+                min_entries,
+                max_entries,
+            },
+            AS3Validator::String {
+                regex: Some(regex),
+                max_length: None,
+                min_length: None,
+                format: None,
+                any_regex: None,
+                deny_regex: None,
+                char_classes,
+                length_unit,
+            } if char_classes.is_empty() => match AS3Validator::anchored_literals(regex.as_str()) {
+                Some(values) => AS3Validator::Literal {
+                    values,
+                    regex: regex.as_str().to_string(),
+                },
+                None => AS3Validator::String {
+                    regex: Some(regex),
+                    max_length: None,
+                    min_length: None,
+                    format: None,
+                    any_regex: None,
+                    deny_regex: None,
+                    char_classes,
+                    length_unit,
+                },
+            },
+            validator => validator,
+        }
+    }
+
+    /// Returns the validator in a canonical form, for comparisons that ignore the ordering of
+    /// fields, regex lists, pattern keys and schemes, as well as bounds that don't restrict
+    /// anything
+    pub fn canonical(&self) -> CanonicalValidator {
+        let validator = self.clone().optimize().normalized();
+        let key = serde_json::to_value(&validator).unwrap().to_string();
+        CanonicalValidator { validator, key }
+    }
+
+    fn normalized(self) -> AS3Validator {
+        // Patterns that compiled once compile again, the list being kept as is otherwise
+        fn sorted(list: Option<RegexList>) -> Option<RegexList> {
+            let list = list?;
+            let mut patterns = list.patterns.clone();
+            patterns.sort();
+            patterns.dedup();
+            Some(RegexList::new(patterns).unwrap_or(list))
+        }
+        let positive = |bound: Option<i64>| bound.filter(|bound| *bound > 0);
+
+        match self {
+            AS3Validator::Object {
+                fields,
+                extra_keys,
+                pattern_keys,
+                assertions,
+                requires,
+                mut order,
+            } => AS3Validator::Object {
+                fields: fields
+                    .into_iter()
+                    .map(|(key, value)| (key, value.normalized()))
+                    .collect(),
+                order: {
+                    order.sort();
+                    order
+                },
+                extra_keys,
+                assertions: {
+                    let mut assertions = assertions;
+                    assertions.sort_by_key(Assertion::to_string);
+                    assertions.dedup();
+                    assertions
+                },
+                requires: requires
+                    .into_iter()
+                    .map(|(key, mut required)| {
+                        required.sort();
+                        required.dedup();
+                        (key, required)
+                    })
+                    .collect(),
+                pattern_keys: pattern_keys.map(|pattern_keys| {
+                    let mut pairs: Vec<(String, AS3Validator)> = pattern_keys
                         .iter()
-                        .filter(|key_word| inner.get(key_word).is_some())
+                        .map(|(pattern, validator)| {
+                            (pattern.clone(), validator.clone().normalized())
+                        })
                         .collect();
+                    pairs.sort_by(|(a, _), (b, _)| a.cmp(b));
+                    let (patterns, validators): (Vec<String>, _) = pairs.into_iter().unzip();
+                    match RegexList::new(patterns) {
+                        Ok(patterns) => PatternKeys {
+                            patterns,
+                            validators,
+                        },
+                        Err(_) => PatternKeys {
+                            validators: (pattern_keys.validators.into_iter())
+                                .map(AS3Validator::normalized)
+                                .collect(),
+                            ..pattern_keys
+                        },
+                    }
+                }),
+            },
+            AS3Validator::String {
+                regex,
+                max_length,
+                min_length,
+                format,
+                any_regex,
+                deny_regex,
+                mut char_classes,
+                length_unit,
+            } => AS3Validator::String {
+                regex,
+                max_length,
+                min_length: positive(min_length),
+                format: match format {
+                    Some(StringFormat::Url {
+                        schemes: Some(mut schemes),
+                    }) => {
+                        schemes.sort();
+                        schemes.dedup();
+                        Some(StringFormat::Url {
+                            schemes: Some(schemes),
+                        })
+                    }
+                    format => format,
+                },
+                any_regex: sorted(any_regex),
+                deny_regex: sorted(deny_regex),
+                char_classes: {
+                    char_classes.sort();
+                    char_classes
+                },
+                length_unit,
+            },
+            AS3Validator::Integer {
+                minimum,
+                maximum,
+                width: Some(width),
+            } => {
+                let (lowest, highest) = width.range();
+                AS3Validator::Integer {
+                    minimum: minimum.filter(|minimum| i128::from(*minimum) > lowest),
+                    maximum: maximum.filter(|maximum| i128::from(*maximum) < highest),
+                    width: Some(width),
+                }
+            }
+            AS3Validator::Conditional {
+                base,
+                condition,
+                then,
+                otherwise,
+            } => AS3Validator::Conditional {
+                base: Box::new(base.normalized()),
+                condition: Box::new(condition.normalized()),
+                then: then.map(|then| Box::new(then.normalized())),
+                otherwise: otherwise.map(|otherwise| Box::new(otherwise.normalized())),
+            },
+            AS3Validator::Annotated { annotations, .. }
+                if annotations.severity == Severity::Warning =>
+            {
+                AS3Validator::Any
+            }
+            // Other annotations don't change the values accepted
+            AS3Validator::Annotated { inner, .. } => inner.normalized(),
+            AS3Validator::List(items_type) => AS3Validator::List(Box::new(items_type.normalized())),
+            AS3Validator::TaggedUnion {
+                discriminator,
+                variants,
+            } => AS3Validator::TaggedUnion {
+                discriminator,
+                variants: variants
+                    .into_iter()
+                    .map(|(tag, variant)| (tag, variant.normalized()))
+                    .collect(),
+            },
+            AS3Validator::Map {
+                key_type,
+                value_type,
+                min_entries,
+                max_entries,
+            } => AS3Validator::Map {
+                key_type: Box::new(key_type.normalized()),
+                value_type: Box::new(value_type.normalized()),
+                min_entries: positive(min_entries),
+                max_entries,
+            },
+            AS3Validator::Nullable(inner) => AS3Validator::Nullable(Box::new(inner.normalized())),
+            AS3Validator::Bytes {
+                encoding,
+                min_bytes,
+                max_bytes,
+            } => AS3Validator::Bytes {
+                encoding,
+                min_bytes: positive(min_bytes),
+                max_bytes,
+            },
+            validator => validator,
+        }
+    }
 
-                    if x.len() > 1 {
-                        return Err(
-                                format!("Multiple field indicating the minimum length of a String have been passed : ({})", x.iter().map(|k|k.to_string()).collect::<Vec<String>>().join(",") ),
-                            );
+    /// References reached without consuming any data, through `Nullable` and conditionals
+    fn leading_references(&self) -> Vec<&Reference> {
+        match self {
+            AS3Validator::Ref(reference) => vec![reference],
+            AS3Validator::Nullable(inner) | AS3Validator::Annotated { inner, .. } => {
+                inner.leading_references()
+            }
+            AS3Validator::Conditional {
+                base,
+                condition,
+                then,
+                otherwise,
+            } => [
+                Some(base),
+                Some(condition),
+                then.as_ref(),
+                otherwise.as_ref(),
+            ]
+            .into_iter()
+            .flatten()
+            .flat_map(|validator| validator.leading_references())
+            .collect(),
+            _ => Vec::new(),
+        }
+    }
+
+    /// Returns the matched texts if `regex` is `^<literal>$` or `^(<literal>|<literal>)$` with
+    /// no special characters
+    fn anchored_literals(regex: &str) -> Option<HashSet<String>> {
+        let literals = regex.strip_prefix('^')?.strip_suffix('$')?;
+        let literals = match literals
+            .strip_prefix("(?:")
+            .or_else(|| literals.strip_prefix('('))
+        {
+            Some(group) => group.strip_suffix(')')?,
+            None if literals.contains('|') => return None,
+            None => literals,
+        };
+        literals
+            .split('|')
+            .map(|literal| {
+                let special = |c: char| "\\.+*?()|[]{}^$".contains(c);
+                (!literal.is_empty() && !literal.contains(special)).then(|| literal.to_string())
+            })
+            .collect()
+    }
+
+    pub fn to_yaml_string(self) -> String {
+        let serialized_json = serde_json::to_string(&self).unwrap();
+        let serialized_yaml: serde_yaml::Value =
+            serde_yaml::from_str::<serde_yaml::Value>(&serialized_json).unwrap();
+        serde_yaml::to_string(&serialized_yaml).unwrap()
+    }
+
+    pub fn from(
+        yaml_config: &serde_yaml::Value,
+    ) -> Result<AS3Validator, As3JsonPath<AS3DefinitionError>> {
+        AS3Validator::from_with_options(yaml_config, &DefinitionOptions::default())
+    }
+
+    /// Reads the definition file at `path`, whose `+include` are relative to its folder
+    #[cfg(feature = "std")]
+    pub fn from_file(
+        path: &Path,
+        options: &DefinitionOptions,
+    ) -> Result<AS3Validator, As3JsonPath<AS3DefinitionError>> {
+        let error = |reason: Cause| {
+            As3JsonPath(
+                JsonPath::root("Root"),
+                AS3DefinitionError::Include {
+                    file: path.display().to_string(),
+                    reason,
+                },
+            )
+        };
+        let source = std::fs::read_to_string(path).map_err(|e| error(Cause::new(e)))?;
+        let definition: serde_yaml::Value =
+            serde_yaml::from_str(&source).map_err(|e| error(Cause::new(e)))?;
+        AS3Validator::from_with_options(&definition, &options.located(path))
+    }
+
+    pub fn from_with_options(
+        yaml_config: &serde_yaml::Value,
+        options: &DefinitionOptions,
+    ) -> Result<AS3Validator, As3JsonPath<AS3DefinitionError>> {
+        AS3Validator::from_root_with_options(yaml_config, "Root", options)
+    }
+
+    /// Builds the `root` key of the definition instead of `Root`, for definitions declaring
+    /// several roots
+    pub fn from_root_with_options(
+        yaml_config: &serde_yaml::Value,
+        root: &str,
+        options: &DefinitionOptions,
+    ) -> Result<AS3Validator, As3JsonPath<AS3DefinitionError>> {
+        let mut root_path = JsonPath::root(root);
+        let serde_yaml::Value::Mapping(inner) = yaml_config else {
+            return Err(As3JsonPath(root_path, AS3DefinitionError::NotAMapping));
+        };
+        let Some(definition) = inner.get(root) else {
+            return Err(As3JsonPath(
+                root_path,
+                AS3DefinitionError::MissingRoot(root.to_string()),
+            ));
+        };
+
+        let options = match inner.get("+defs") {
+            None => options.clone(),
+            Some(serde_yaml::Value::Mapping(defs)) => AS3Validator::build_defs(defs, options)?,
+            Some(_) => {
+                return Err(As3JsonPath(
+                    JsonPath::root("+defs"),
+                    AS3DefinitionError::InvalidValue {
+                        keyword: "+defs".to_string(),
+                        expected: "a Mapping of names to definitions".to_string(),
+                    },
+                ))
+            }
+        };
+
+        AS3Validator::build_from_yaml(&definition, &mut root_path, &options)
+    }
+
+    /// Builds the `Root` of another definition file, with its own `+defs`
+    fn build_include(
+        yaml_config: &serde_yaml::Value,
+        include: &serde_yaml::Value,
+        path: &JsonPath,
+        options: &DefinitionOptions,
+    ) -> Result<AS3Validator, As3JsonPath<AS3DefinitionError>> {
+        let error = |error| Err(As3JsonPath(path.clone(), error));
+        if yaml_config
+            .as_mapping()
+            .is_some_and(|inner| inner.len() > 1)
+        {
+            return error(AS3DefinitionError::Conflict(
+                "`+include` can't be used together with other properties".to_string(),
+            ));
+        }
+        let Some(include) = include.as_str() else {
+            return error(AS3DefinitionError::InvalidValue {
+                keyword: "+include".to_string(),
+                expected: "the path of a definition file".to_string(),
+            });
+        };
+        let include_error = |reason: Cause| {
+            error(AS3DefinitionError::Include {
+                file: include.to_string(),
+                reason,
+            })
+        };
+        let remote = match (url::Url::parse(include), &options.base_url) {
+            (Ok(url), _) if matches!(url.scheme(), "http" | "https") => Some(url),
+            // Relative to the remote definition including it
+            (Err(url::ParseError::RelativeUrlWithoutBase), Some(base_url)) => {
+                match base_url.join(include) {
+                    Ok(url) => Some(url),
+                    Err(e) => return include_error(Cause::new(e)),
+                }
+            }
+            _ => None,
+        };
+        let location = match &remote {
+            Some(url) => url.to_string(),
+            None => match options.include_path(include) {
+                Ok(file) => file,
+                Err(reason) => return include_error(reason),
+            },
+        };
+        if let Some(start) = options
+            .including
+            .iter()
+            .position(|other| *other == location)
+        {
+            let mut chain = options.including[start..].to_vec();
+            chain.push(location);
+            return error(AS3DefinitionError::CircularInclude(chain.join(" -> ")));
+        }
+
+        let mut included_options = DefinitionOptions {
+            lenient: options.lenient,
+            fetcher: options.fetcher.clone(),
+            including: options.including.clone(),
+            ..Default::default()
+        };
+        let included = match remote {
+            #[cfg(feature = "std")]
+            None => AS3Validator::from_file(Path::new(&location), &included_options),
+            // Refused by `include_path`
+            #[cfg(not(feature = "std"))]
+            None => unreachable!(),
+            Some(url) => {
+                let Some(fetcher) = &options.fetcher else {
+                    return include_error("remote definitions are not allowed".to_string().into());
+                };
+                let definition = match fetcher.fetch(&url) {
+                    Ok(source) => match serde_yaml::from_str(&source) {
+                        Ok(definition) => definition,
+                        Err(e) => return include_error(Cause::new(e)),
+                    },
+                    Err(reason) => return include_error(reason.into()),
+                };
+                included_options.including.push(location);
+                included_options.base_url = Some(url);
+                AS3Validator::from_with_options(&definition, &included_options)
+            }
+        };
+        included.map_err(|As3JsonPath(inner_path, e)| {
+            let mut located = path.with_key(include);
+            located.0.extend(inner_path.0);
+            As3JsonPath(located, e)
+        })
+    }
+
+    /// Object definition merged with the definitions of its `+extends` chain. The keys of
+    /// `yaml_config`, fields and `+` keywords alike, replace those of the definition it extends,
+    /// without merging their content. `chain` holds the definitions already extended
+    fn extended(
+        yaml_config: &serde_yaml::Mapping,
+        path: &JsonPath,
+        options: &DefinitionOptions,
+        chain: &mut Vec<String>,
+    ) -> Result<serde_yaml::Mapping, As3JsonPath<AS3DefinitionError>> {
+        let error = |error| Err(As3JsonPath(path.clone(), error));
+        let not_an_object = || {
+            error(AS3DefinitionError::Conflict(
+                "`+extends` only applies to Object definitions".to_string(),
+            ))
+        };
+        let Some(extends) = yaml_config.get("+extends") else {
+            return Ok(yaml_config.clone());
+        };
+        if yaml_config
+            .get("+type")
+            .is_some_and(|validator_type| validator_type.as_str() != Some("Object"))
+        {
+            return not_an_object();
+        }
+        let Some(name) = extends.as_str().and_then(|r| r.strip_prefix("#/defs/")) else {
+            return error(AS3DefinitionError::InvalidValue {
+                keyword: "+extends".to_string(),
+                expected: "a reference such as `#/defs/Record`".to_string(),
+            });
+        };
+        if chain.iter().any(|extended| extended == name) {
+            chain.push(name.to_string());
+            return error(AS3DefinitionError::CircularReference(chain.join(" -> ")));
+        }
+        let Some(base) = options.def_sources.get(name) else {
+            return error(AS3DefinitionError::UnknownDefinition(name.to_string()));
+        };
+        let Some(base) = base.as_mapping().filter(|base| {
+            base.contains_key("+extends")
+                || base.get("+type").and_then(|t| t.as_str()) == Some("Object")
+        }) else {
+            return not_an_object();
+        };
+
+        chain.push(name.to_string());
+        let mut merged = AS3Validator::extended(base, path, options, chain)?;
+        for (key, value) in yaml_config {
+            if key.as_str() != Some("+extends") {
+                merged.insert(key.clone(), value.clone());
+            }
+        }
+        Ok(merged)
+    }
+
+    /// Builds the `+defs` into shared [`Reference`]s, returned in the options used to build
+    /// the rest of the definition. References are created before the definitions are built,
+    /// so that definitions can refer to themselves
+    fn build_defs(
+        defs: &serde_yaml::Mapping,
+        options: &DefinitionOptions,
+    ) -> Result<DefinitionOptions, As3JsonPath<AS3DefinitionError>> {
+        let mut options = options.clone();
+        let mut targets = Vec::new();
+        for name in defs.keys() {
+            let Some(name) = name.as_str() else {
+                return Err(As3JsonPath(
+                    JsonPath::root("+defs"),
+                    AS3DefinitionError::InvalidValue {
+                        keyword: "+defs".to_string(),
+                        expected: "a Mapping with names as keys".to_string(),
+                    },
+                ));
+            };
+            let target = Arc::new(OnceLock::new());
+            options.defs.insert(
+                name.to_string(),
+                Reference {
+                    name: name.to_string(),
+                    target: Arc::downgrade(&target),
+                    defs: None,
+                },
+            );
+            targets.push(target);
+        }
+        options
+            .def_sources
+            .extend(defs.iter().filter_map(|(name, definition)| {
+                Some((name.as_str()?.to_string(), definition.clone()))
+            }));
+
+        for (name, definition) in defs {
+            let name = name.as_str().unwrap();
+            let validator = AS3Validator::build_from_yaml(
+                &definition,
+                &mut JsonPath::root("+defs").with_key(name),
+                &options,
+            )?;
+            if let Some(target) = options.defs[name].target.upgrade() {
+                let _ = target.set(validator);
+            }
+        }
+
+        // A definition reaching itself without going through a field, an item or a value
+        // would never stop
+        for name in defs.keys().filter_map(|name| name.as_str()) {
+            let mut stack = vec![name.to_string()];
+            options.defs[name].check_productive(&mut stack)?;
+        }
+
+        // The references of the rest of the definition keep the `+defs`
+        let targets = Arc::new(targets);
+        for name in defs.keys().filter_map(|name| name.as_str()) {
+            if let Some(reference) = options.defs.get_mut(name) {
+                reference.defs = Some(targets.clone());
+            }
+        }
+        Ok(options)
+    }
+
+    fn build_from_yaml(
+        yaml_config: &&serde_yaml::Value,
+        path: &mut JsonPath,
+        options: &DefinitionOptions,
+    ) -> Result<AS3Validator, As3JsonPath<AS3DefinitionError>> {
+        if let Some(reference) = yaml_config.get("+ref") {
+            return Reference::build_from_yaml(yaml_config, reference, path, options)
+                .map(AS3Validator::Ref);
+        }
+        if let Some(include) = yaml_config.get("+include") {
+            return AS3Validator::build_include(yaml_config, include, path, options);
+        }
+        if let (Some(_), serde_yaml::Value::Mapping(inner)) =
+            (yaml_config.get("+extends"), yaml_config)
+        {
+            let extended = AS3Validator::extended(inner, path, options, &mut Vec::new())?;
+            return AS3Validator::build_from_yaml(
+                &&serde_yaml::Value::Mapping(extended),
+                path,
+                options,
+            );
+        }
+
+        if let serde_yaml::Value::String(shorthand) = yaml_config {
+            match shorthand
+                .find(['<', '('])
+                .map(|index| &shorthand[index..index + 1])
+            {
+                Some("<") => return AS3Validator::build_from_shorthand(shorthand, path, options),
+                Some("(") => return AS3Validator::build_from_inline(shorthand, path, options),
+                _ => {}
+            }
+        }
+
+        // Used to get the validator_type from the canonical long form and also from the shortened syntax
+        // YAML reads an unquoted `Null` as null
+        let validator_type: &str = match (yaml_config.get("+type"), yaml_config) {
+            (Some(serde_yaml::Value::String(validator_type)), _) => validator_type,
+            (Some(serde_yaml::Value::Null), _) => "Null",
+            (Some(_), _) => {
+                return Err(As3JsonPath(
+                    path.clone(),
+                    AS3DefinitionError::InvalidValue {
+                        keyword: "+type".to_string(),
+                        expected: "a String".to_string(),
+                    },
+                ))
+            }
+            (_, serde_yaml::Value::String(validator_type)) => validator_type,
+            (_, serde_yaml::Value::Null) => "Null",
+            _ => return Err(As3JsonPath(path.clone(), AS3DefinitionError::MissingType)),
+        };
+
+        let nullable = validator_type.contains('?');
+
+        if let (serde_yaml::Value::Mapping(inner), false) = (yaml_config, options.lenient) {
+            let allowed = AS3Validator::keywords_of(&validator_type.replace('?', ""));
+            let unknown = inner.keys().filter_map(|key| key.as_str()).find(|key| {
+                key.starts_with('+')
+                    && *key != "+type"
+                    && !allowed.contains(key)
+                    && !Annotations::KEYWORDS.contains(key)
+            });
+            if let Some(keyword) = unknown {
+                return Err(As3JsonPath(
+                    path.clone(),
+                    AS3DefinitionError::UnknownKeyword {
+                        keyword: keyword.to_string(),
+                        suggestion: did_you_mean(
+                            keyword,
+                            allowed
+                                .iter()
+                                .chain(Annotations::KEYWORDS)
+                                .chain(&["+type"]),
+                        ),
+                    },
+                ));
+            }
+        }
+
+        let validator = match (validator_type.replace('?', "").as_str(), yaml_config) {
+            ("Object", serde_yaml::Value::Mapping(inner)) => {
+                let mut fields = HashMap::new();
+                let mut order = Vec::new();
+                for (key, value) in inner {
+                    let Some(key) = key.as_str() else {
+                        return Err(As3JsonPath(
+                            path.clone(),
+                            AS3DefinitionError::Generic(format!(
+                                "Object keys must be Strings, found {key:?}"
+                            )),
+                        ));
+                    };
+                    if key.starts_with('+') {
+                        continue;
+                    }
+                    path.push_key(key);
+                    let field = AS3Validator::build_from_yaml(&value, path, options);
+                    path.pop();
+                    fields.insert(key.to_string(), field?);
+                    order.push(key.to_string());
+                }
+
+                let keywords = Keywords { inner, path };
+                let extra_keys = match keywords.string("+extraKeys")?.map(String::as_str) {
+                    None | Some("allow") => ExtraKeys::Allow,
+                    Some("forbid") => ExtraKeys::Forbid,
+                    Some("warn") => ExtraKeys::Warn,
+                    Some(_) => {
+                        return Err(keywords.invalid("+extraKeys", "`forbid`, `allow` or `warn`"))
                     }
-                    if let Some(key_word) = x.first() {
-                        if let Some(serde_yaml::Value::Number(max_length)) = inner.get(key_word) {
-                            if let Some(max_length) = max_length.as_i64() {
-                                Some(max_length)
-                            } else {
-                                None
+                };
+
+                let pattern_keys = match inner.get("+patternKeys") {
+                    Some(pattern_keys) => {
+                        Some(PatternKeys::build_from_yaml(pattern_keys, path, options)?)
+                    }
+                    None => None,
+                };
+
+                let assertions = match inner.get("+assert") {
+                    None => Vec::new(),
+                    Some(serde_yaml::Value::String(assertion)) => vec![assertion.clone()],
+                    Some(_) => keywords.string_list("+assert")?.unwrap_or_default(),
+                };
+                let assertions = assertions
+                    .iter()
+                    .map(|assertion| {
+                        let parsed = Assertion::parse(assertion).ok_or_else(|| {
+                            keywords.invalid(
+                                "+assert",
+                                "comparisons such as `end >= start` (==, !=, <, <=, >, >=)",
+                            )
+                        })?;
+                        let unknown = [&parsed.left, &parsed.right]
+                            .into_iter()
+                            .find(|field| !fields.contains_key(*field))
+                            .cloned();
+                        match unknown {
+                            Some(field) => Err(keywords.error(AS3DefinitionError::Generic(format!(
+                                "`+assert: {assertion}` refers to `{field}` which is not a field of the Object"
+                            )))),
+                            None => Ok(parsed),
+                        }
+                    })
+                    .collect::<Result<Vec<Assertion>, _>>()?;
+
+                let mut requires = BTreeMap::new();
+                match inner.get("+requires") {
+                    None => {}
+                    Some(serde_yaml::Value::Mapping(dependencies)) => {
+                        for (key, required) in dependencies {
+                            let required = match required {
+                                serde_yaml::Value::String(required) => Some(vec![required.clone()]),
+                                serde_yaml::Value::Sequence(list) => list
+                                    .iter()
+                                    .map(|item| item.as_str().map(str::to_string))
+                                    .collect(),
+                                _ => None,
+                            };
+                            match (key.as_str(), required) {
+                                (Some(key), Some(required)) => {
+                                    requires.insert(key.to_string(), required);
+                                }
+                                _ => {
+                                    return Err(keywords.invalid(
+                                        "+requires",
+                                        "a Mapping of keys to the list of keys they require",
+                                    ))
+                                }
                             }
-                        } else {
-                            None
                         }
-                    } else {
-                        None
                     }
+                    Some(_) => {
+                        return Err(keywords.invalid(
+                            "+requires",
+                            "a Mapping of keys to the list of keys they require",
+                        ))
+                    }
+                }
+
+                let object = AS3Validator::Object {
+                    fields,
+                    extra_keys,
+                    pattern_keys,
+                    assertions,
+                    requires,
+                    order,
+                };
+
+                let branch = |keyword: &str| -> Result<_, As3JsonPath<AS3DefinitionError>> {
+                    match inner.get(keyword) {
+                        Some(definition) => Ok(Some(Box::new(AS3Validator::build_from_yaml(
+                            &definition,
+                            &mut path.with_key(keyword),
+                            options,
+                        )?))),
+                        None => Ok(None),
+                    }
+                };
+                match (branch("+if")?, branch("+then")?, branch("+else")?) {
+                    (None, None, None) => object,
+                    (None, _, _) => {
+                        return Err(
+                            keywords.error(AS3DefinitionError::MissingKeyword("+if".to_string()))
+                        )
+                    }
+                    (Some(condition), then, otherwise) => AS3Validator::Conditional {
+                        base: Box::new(object),
+                        condition,
+                        then,
+                        otherwise,
+                    },
+                }
+            }
+            ("String", serde_yaml::Value::Mapping(inner)) => {
+                let keywords = Keywords { inner, path };
+
+                let mut regex = keywords.string("+regex")?.cloned();
+                for keyword in ["+regexFlags", "+fullMatch"] {
+                    if regex.is_none() && keywords.contains(keyword) {
+                        return Err(keywords.error(AS3DefinitionError::Conflict(format!(
+                            "`{keyword}` can only be used together with `+regex`"
+                        ))));
+                    }
+                }
+                // Folded into the pattern, which is then reported as it is matched
+                if keywords.bool("+fullMatch")?.unwrap_or(false) {
+                    regex = regex.map(|regex| format!("^(?:{regex})$"));
+                }
+                if let Some(mut flags) = keywords.string_list("+regexFlags")? {
+                    if flags.is_empty()
+                        || flags
+                            .iter()
+                            .any(|flag| !matches!(flag.as_str(), "i" | "m" | "s"))
+                    {
+                        return Err(keywords.invalid("+regexFlags", "a list of i, m and s flags"));
+                    }
+                    flags.sort();
+                    flags.dedup();
+                    regex = regex.map(|regex| format!("(?{}){regex}", flags.concat()));
+                }
+                let regex = match regex.map(RegexPattern::new).transpose() {
+                    Ok(regex) => regex,
+                    Err(e) => {
+                        return Err(keywords.error(AS3DefinitionError::InvalidRegex {
+                            keyword: "+regex".to_string(),
+                            reason: Cause::new(e),
+                        }))
+                    }
+                };
+
+                let max_length =
+                    match keywords.alias(&["+MaxLength", "+maxLength", "+max_length"])? {
+                        Some(keyword) => Some(keywords.length(keyword)?),
+                        None => None,
+                    };
+                let min_length =
+                    match keywords.alias(&["+MinLength", "+minLength", "+min_length"])? {
+                        Some(keyword) => Some(keywords.length(keyword)?),
+                        None => None,
+                    };
+                if let (Some(min_length), Some(max_length)) = (min_length, max_length) {
+                    if min_length > max_length {
+                        return Err(keywords.error(AS3DefinitionError::Conflict(format!(
+                            "the minimum length ({min_length}) is above the maximum length ({max_length})"
+                        ))));
+                    }
+                }
+
+                let format = match keywords.string("+format")? {
+                    Some(format) => Some(StringFormat::build_from_yaml(format, &keywords)?),
+                    None => None,
+                };
+                for keyword in [
+                    "+schemes",
+                    "+version",
+                    "+cidr",
+                    "+minVersion",
+                    "+maxVersion",
+                    "+countryCodes",
+                ] {
+                    if format.is_none() && keywords.contains(keyword) {
+                        return Err(keywords.error(AS3DefinitionError::Conflict(format!(
+                            "`{keyword}` can only be used together with `+format`"
+                        ))));
+                    }
+                }
+
+                let any_regex = RegexList::build_from_yaml(&keywords, "+anyRegex")?;
+                let deny_regex = RegexList::build_from_yaml(&keywords, "+denyRegex")?;
+
+                let length_unit = match keywords.string("+lengthUnit")? {
+                    None => LengthUnit::default(),
+                    Some(unit) => match unit.as_str() {
+                        "bytes" => LengthUnit::Bytes,
+                        "chars" => LengthUnit::Chars,
+                        "graphemes" => LengthUnit::Graphemes,
+                        _ => {
+                            return Err(
+                                keywords.invalid("+lengthUnit", "one of bytes, chars or graphemes")
+                            )
+                        }
+                    },
+                };
+
+                let mut char_classes = Vec::new();
+                for class in CharClass::ALL {
+                    if keywords.bool(class.keyword())?.unwrap_or(false) {
+                        char_classes.push(class);
+                    }
+                }
+
+                AS3Validator::String {
+                    regex,
+                    max_length,
+                    min_length,
+                    format,
+                    any_regex,
+                    deny_regex,
+                    char_classes,
+                    length_unit,
+                }
+            }
+            ("Date", serde_yaml::Value::Mapping(inner)) => {
+                let keywords = Keywords { inner, path };
+                let maximum = keywords.date("+max")?;
+                let minimum = keywords.date("+min")?;
+                if let (Some(DateBound::Fixed(minimum)), Some(DateBound::Fixed(maximum))) =
+                    (minimum, maximum)
+                {
+                    if minimum > maximum {
+                        return Err(keywords.error(AS3DefinitionError::Conflict(format!(
+                            "`+min` ({minimum}) is after `+max` ({maximum})"
+                        ))));
+                    }
+                }
+
+                AS3Validator::Date { minimum, maximum }
+            }
+            ("DateTime", serde_yaml::Value::Mapping(inner)) => {
+                let keywords = Keywords { inner, path };
+                let maximum = keywords.datetime("+max")?;
+                let minimum = keywords.datetime("+min")?;
+                if let (Some(minimum), Some(maximum)) = (minimum, maximum) {
+                    if minimum > maximum {
+                        return Err(keywords.error(AS3DefinitionError::Conflict(format!(
+                            "`+min` ({minimum}) is after `+max` ({maximum})"
+                        ))));
+                    }
+                }
+
+                AS3Validator::DateTime { minimum, maximum }
+            }
+            ("Time", serde_yaml::Value::Mapping(inner)) => {
+                let keywords = Keywords { inner, path };
+                let maximum = keywords.time("+max")?;
+                let minimum = keywords.time("+min")?;
+                if let (Some(minimum), Some(maximum)) = (minimum, maximum) {
+                    if minimum > maximum {
+                        return Err(keywords.error(AS3DefinitionError::Conflict(format!(
+                            "`+min` ({minimum}) is after `+max` ({maximum})"
+                        ))));
+                    }
+                }
+
+                AS3Validator::Time { minimum, maximum }
+            }
+            ("Duration", serde_yaml::Value::Mapping(inner)) => {
+                let keywords = Keywords { inner, path };
+                let syntax = match keywords.string("+format")?.map(|s| s.to_lowercase()) {
+                    None => DurationSyntax::Any,
+                    Some(syntax) if syntax == "iso8601" => DurationSyntax::Iso8601,
+                    Some(syntax) if syntax == "humantime" => DurationSyntax::Humantime,
+                    Some(_) => return Err(keywords.invalid("+format", "`iso8601` or `humantime`")),
+                };
+                let maximum = keywords.duration("+max")?;
+                let minimum = keywords.duration("+min")?;
+                if let (Some(minimum), Some(maximum)) = (minimum, maximum) {
+                    if minimum > maximum {
+                        return Err(keywords.error(AS3DefinitionError::Conflict(format!(
+                            "`+min` ({}) is longer than `+max` ({})",
+                            humantime::format_duration(minimum),
+                            humantime::format_duration(maximum)
+                        ))));
+                    }
+                }
+
+                AS3Validator::Duration {
+                    syntax,
+                    minimum,
+                    maximum,
+                }
+            }
+            ("Bytes", serde_yaml::Value::Mapping(inner)) => {
+                let keywords = Keywords { inner, path };
+                let encoding = match keywords.string("+encoding")?.map(|e| e.to_lowercase()) {
+                    None => BytesEncoding::Base64,
+                    Some(encoding) if encoding == "base64" => BytesEncoding::Base64,
+                    Some(encoding) if encoding == "hex" => BytesEncoding::Hex,
+                    Some(_) => return Err(keywords.invalid("+encoding", "`base64` or `hex`")),
+                };
+                let max_bytes = match keywords.contains("+maxBytes") {
+                    true => Some(keywords.length("+maxBytes")?),
+                    false => None,
                 };
+                let min_bytes = match keywords.contains("+minBytes") {
+                    true => Some(keywords.length("+minBytes")?),
+                    false => None,
+                };
+                if let (Some(min_bytes), Some(max_bytes)) = (min_bytes, max_bytes) {
+                    if min_bytes > max_bytes {
+                        return Err(keywords.error(AS3DefinitionError::Conflict(format!(
+                            "`+minBytes` ({min_bytes}) is above `+maxBytes` ({max_bytes})"
+                        ))));
+                    }
+                }
 
-                AS3Validator::String {
-                    regex,
-                    max_length,
-                    min_length,
+                AS3Validator::Bytes {
+                    encoding,
+                    min_bytes,
+                    max_bytes,
                 }
             }
-            ("Date", serde_yaml::Value::Mapping(..)) => AS3Validator::Date,
 
-            ("Integer", serde_yaml::Value::Mapping(inner)) => {
-                let maximum = if let Some(serde_yaml::Value::Number(max_length)) = inner.get("+max")
-                {
-                    if let Some(max_length) = max_length.as_i64() {
-                        Some(max_length)
-                    } else {
-                        None
+            (type_name, serde_yaml::Value::Mapping(inner))
+                if type_name == "Integer" || IntegerWidth::from_type_name(type_name).is_some() =>
+            {
+                let keywords = Keywords { inner, path };
+                let maximum = keywords.integer("+max")?;
+                let minimum = keywords.integer("+min")?;
+                if let (Some(minimum), Some(maximum)) = (minimum, maximum) {
+                    if minimum > maximum {
+                        return Err(keywords.error(AS3DefinitionError::Conflict(format!(
+                            "`+min` ({minimum}) is above `+max` ({maximum})"
+                        ))));
                     }
-                } else {
-                    None
-                };
+                }
 
-                let minimum = if let Some(serde_yaml::Value::Number(max_length)) = inner.get("+min")
-                {
-                    if let Some(max_length) = max_length.as_i64() {
-                        Some(max_length)
-                    } else {
-                        None
+                let bits = keywords.integer("+bits")?;
+                let unsigned = keywords.bool("+unsigned")?;
+                let width = match (IntegerWidth::from_type_name(type_name), bits, unsigned) {
+                    (Some(_), Some(_), _) | (Some(_), _, Some(_)) => {
+                        return Err(keywords.error(AS3DefinitionError::Conflict(format!(
+                            "`{type_name}` already sets `+bits` and `+unsigned`"
+                        ))))
+                    }
+                    (Some(width), None, None) => Some(width),
+                    (None, Some(bits @ (8 | 16 | 32 | 64)), unsigned) => Some(IntegerWidth {
+                        bits: bits as u8,
+                        unsigned: unsigned.unwrap_or(false),
+                    }),
+                    (None, Some(_), _) => return Err(keywords.invalid("+bits", "8, 16, 32 or 64")),
+                    (None, None, Some(_)) => {
+                        return Err(
+                            keywords.error(AS3DefinitionError::MissingKeyword("+bits".to_string()))
+                        )
                     }
-                } else {
-                    None
+                    (None, None, None) => None,
                 };
 
-                AS3Validator::Integer { minimum, maximum }
+                AS3Validator::Integer {
+                    minimum,
+                    maximum,
+                    width,
+                }
             }
             ("Decimal" | "Float", serde_yaml::Value::Mapping(inner)) => {
-                let maximum = if let Some(serde_yaml::Value::Number(max_length)) = inner.get("+max")
-                {
-                    if let Some(max_length) = max_length.as_f64() {
-                        Some(max_length)
-                    } else {
-                        None
-                    }
-                } else {
-                    None
-                };
-
-                let minimum = if let Some(serde_yaml::Value::Number(max_length)) = inner.get("+min")
-                {
-                    if let Some(max_length) = max_length.as_f64() {
-                        Some(max_length)
-                    } else {
-                        None
+                let keywords = Keywords { inner, path };
+                let maximum = keywords.number("+max")?;
+                let minimum = keywords.number("+min")?;
+                if let (Some(minimum), Some(maximum)) = (minimum, maximum) {
+                    if minimum > maximum {
+                        return Err(keywords.error(AS3DefinitionError::Conflict(format!(
+                            "`+min` ({minimum}) is above `+max` ({maximum})"
+                        ))));
                     }
-                } else {
-                    None
-                };
+                }
 
                 AS3Validator::Decimal { minimum, maximum }
             }
             ("List", serde_yaml::Value::Mapping(..)) => {
                 let Some(value_type) = yaml_config.get("+ValueType") else {
-                    return Err("List defined without the required `+ValueType` property".to_string());
+                    return Err(As3JsonPath(
+                        path.clone(),
+                        AS3DefinitionError::MissingKeyword("+ValueType".to_string()),
+                    ));
                 };
-                let list_value_type = AS3Validator::build_from_yaml(&value_type, path).unwrap();
+                let list_value_type = AS3Validator::build_from_yaml(&value_type, path, options)?;
 
                 AS3Validator::List(Box::new(list_value_type))
             }
-            ("Map", serde_yaml::Value::Mapping(..)) => {
-                let (Some(key_type), Some(value_type)) = (yaml_config.get("+KeyType"), yaml_config.get("+ValueType")) else {
-                    return Err(format!("Map MUST have the `+KeyType` and `+ValueType` fields [ {} ] ", path));
+            ("TaggedUnion", serde_yaml::Value::Mapping(inner)) => {
+                let keywords = Keywords { inner, path };
+                let Some(discriminator) = keywords.string("+discriminator")?.cloned() else {
+                    return Err(keywords.error(AS3DefinitionError::MissingKeyword(
+                        "+discriminator".to_string(),
+                    )));
                 };
+                let variants = match inner.get("+variants") {
+                    Some(serde_yaml::Value::Mapping(variants)) if !variants.is_empty() => variants,
+                    Some(_) => {
+                        return Err(keywords
+                            .invalid("+variants", "a Mapping of tag values to Object definitions"))
+                    }
+                    None => {
+                        return Err(keywords
+                            .error(AS3DefinitionError::MissingKeyword("+variants".to_string())))
+                    }
+                };
+
+                let mut built = BTreeMap::new();
+                for (tag, variant) in variants {
+                    let tag = match tag {
+                        serde_yaml::Value::String(tag) => tag.clone(),
+                        _ => {
+                            return Err(keywords.invalid(
+                                "+variants",
+                                "a Mapping of tag values to Object definitions",
+                            ))
+                        }
+                    };
+                    let mut validator = AS3Validator::build_from_yaml(
+                        &variant,
+                        &mut path.with_key("+variants").with_key(&tag),
+                        options,
+                    )?;
+                    // The discriminator is part of each variant, declared or not
+                    if let AS3Validator::Object { fields, .. } = &mut validator {
+                        fields.entry(discriminator.clone()).or_insert_with(|| {
+                            AS3Validator::Literal {
+                                values: HashSet::from([tag.clone()]),
+                                regex: format!("^{}$", regex::escape(&tag)),
+                            }
+                        });
+                    }
+                    built.insert(tag, validator);
+                }
+                AS3Validator::TaggedUnion {
+                    discriminator,
+                    variants: built,
+                }
+            }
+            ("Map", serde_yaml::Value::Mapping(inner)) => {
+                let (Some(key_type), Some(value_type)) =
+                    (yaml_config.get("+KeyType"), yaml_config.get("+ValueType"))
+                else {
+                    let missing = if yaml_config.get("+KeyType").is_none() {
+                        "+KeyType"
+                    } else {
+                        "+ValueType"
+                    };
+                    return Err(As3JsonPath(
+                        path.clone(),
+                        AS3DefinitionError::MissingKeyword(missing.to_string()),
+                    ));
+                };
+
+                let keywords = Keywords { inner, path };
+                let max_entries = match keywords.contains("+MaxEntries") {
+                    true => Some(keywords.length("+MaxEntries")?),
+                    false => None,
+                };
+                let min_entries = match keywords.contains("+MinEntries") {
+                    true => Some(keywords.length("+MinEntries")?),
+                    false => None,
+                };
+                if let (Some(min_entries), Some(max_entries)) = (min_entries, max_entries) {
+                    if min_entries > max_entries {
+                        return Err(keywords.error(AS3DefinitionError::Conflict(format!(
+                            "`+MinEntries` ({min_entries}) is above `+MaxEntries` ({max_entries})"
+                        ))));
+                    }
+                }
 
                 AS3Validator::Map {
-                    key_type: Box::new(
-                        match AS3Validator::build_from_yaml(
-                            &key_type,
-                            &mut format!("{} -> +KeyType", path),
-                        ) {
-                            Ok(d) => d,
-                            Err(e) => return Err(e),
-                        },
-                    ),
+                    key_type: Box::new(AS3Validator::build_from_yaml(
+                        &key_type,
+                        &mut path.with_key("+KeyType"),
+                        options,
+                    )?),
 
-                    value_type: Box::new(
-                        match AS3Validator::build_from_yaml(
-                            &value_type,
-                            &mut format!("{} -> +KeyType", path),
-                        ) {
-                            Ok(d) => d,
-                            Err(e) => return Err(e),
-                        },
-                    ),
+                    value_type: Box::new(AS3Validator::build_from_yaml(
+                        &value_type,
+                        &mut path.with_key("+ValueType"),
+                        options,
+                    )?),
+                    min_entries,
+                    max_entries,
                 }
             }
             ("Bool" | "Boolean", serde_yaml::Value::Mapping(..)) => AS3Validator::Boolean,
+            ("Null", serde_yaml::Value::Mapping(..) | serde_yaml::Value::Null) => {
+                AS3Validator::Null
+            }
+            ("Any", serde_yaml::Value::Mapping(..)) => AS3Validator::Any,
 
             // Responsable for the abbreviated syntax
             (type_def, serde_yaml::Value::String(..)) => match type_def {
@@ -508,30 +4012,792 @@ impl AS3Validator {
                     regex: None,
                     max_length: None,
                     min_length: None,
+                    format: None,
+                    any_regex: None,
+                    deny_regex: None,
+                    char_classes: Vec::new(),
+                    length_unit: LengthUnit::default(),
                 },
                 "Integer" => AS3Validator::Integer {
                     minimum: None,
                     maximum: None,
+                    width: None,
                 },
                 "Decimal" => AS3Validator::Decimal {
                     minimum: None,
                     maximum: None,
                 },
-                "Date" => AS3Validator::Date,
+                "Date" => AS3Validator::Date {
+                    minimum: None,
+                    maximum: None,
+                },
                 "Bool" => AS3Validator::Boolean,
+                "Null" => AS3Validator::Null,
+                "Any" => AS3Validator::Any,
+                "DateTime" => AS3Validator::DateTime {
+                    minimum: None,
+                    maximum: None,
+                },
+                "Time" => AS3Validator::Time {
+                    minimum: None,
+                    maximum: None,
+                },
+                "Duration" => AS3Validator::Duration {
+                    syntax: DurationSyntax::Any,
+                    minimum: None,
+                    maximum: None,
+                },
+                "Bytes" => AS3Validator::Bytes {
+                    encoding: BytesEncoding::Base64,
+                    min_bytes: None,
+                    max_bytes: None,
+                },
+                type_name if IntegerWidth::from_type_name(type_name).is_some() => {
+                    AS3Validator::Integer {
+                        minimum: None,
+                        maximum: None,
+                        width: IntegerWidth::from_type_name(type_name),
+                    }
+                }
                 _ => {
-                    return Err(format!(
-                        " {validator_type} can't be used without the `+type` property"
+                    return Err(As3JsonPath(
+                        path.clone(),
+                        AS3DefinitionError::Generic(format!(
+                            "{validator_type} can't be used without the `+type` property"
+                        )),
                     ))
                 }
             },
-            _ => return Err(format!(" {validator_type} is an unsupported type")),
+            _ => {
+                return Err(As3JsonPath(
+                    path.clone(),
+                    AS3DefinitionError::UnsupportedType(validator_type.to_string()),
+                ))
+            }
         };
 
-        if nullable {
-            Ok(AS3Validator::Nullable(Box::new(validator)))
+        let validator = if nullable {
+            AS3Validator::Nullable(Box::new(validator))
         } else {
+            validator
+        };
+
+        let annotations = match yaml_config {
+            serde_yaml::Value::Mapping(inner) => {
+                Annotations::build_from_yaml(&Keywords { inner, path })?
+            }
+            _ => Annotations::default(),
+        };
+        if annotations.is_empty() {
             Ok(validator)
+        } else {
+            Ok(AS3Validator::Annotated {
+                inner: Box::new(validator),
+                annotations,
+            })
+        }
+    }
+
+    /// Collection types written on one line, e.g. `List<String>` or `Map<String, List<Integer>>`
+    fn build_from_shorthand(
+        shorthand: &str,
+        path: &JsonPath,
+        options: &DefinitionOptions,
+    ) -> Result<AS3Validator, As3JsonPath<AS3DefinitionError>> {
+        let shorthand = shorthand.trim();
+        let invalid = || {
+            As3JsonPath(
+                path.clone(),
+                AS3DefinitionError::Generic(format!(
+                    "`{shorthand}` is not a valid shorthand [Supported : List<Type>, Map<KeyType, ValueType>]"
+                )),
+            )
+        };
+
+        if let Some(inner) = shorthand.strip_suffix('?') {
+            let validator = AS3Validator::build_from_shorthand(inner, path, options)?;
+            return Ok(AS3Validator::Nullable(Box::new(validator)));
+        }
+
+        if let Some(value_type) = shorthand
+            .strip_prefix("List<")
+            .and_then(|rest| rest.strip_suffix('>'))
+        {
+            let value_type = AS3Validator::build_from_shorthand(value_type, path, options)?;
+            return Ok(AS3Validator::List(Box::new(value_type)));
+        }
+
+        if let Some(arguments) = shorthand
+            .strip_prefix("Map<")
+            .and_then(|rest| rest.strip_suffix('>'))
+        {
+            // Split on the first comma that isn't nested inside another type
+            let Some(comma) = top_level_comma(arguments) else {
+                return Err(invalid());
+            };
+            return Ok(AS3Validator::Map {
+                key_type: Box::new(AS3Validator::build_from_shorthand(
+                    &arguments[..comma],
+                    &path.with_key("+KeyType"),
+                    options,
+                )?),
+                value_type: Box::new(AS3Validator::build_from_shorthand(
+                    &arguments[comma + 1..],
+                    &path.with_key("+ValueType"),
+                    options,
+                )?),
+                min_entries: None,
+                max_entries: None,
+            });
+        }
+
+        if !shorthand.contains('(') && shorthand.contains(['<', '>', ',']) {
+            return Err(invalid());
+        }
+        AS3Validator::build_from_yaml(
+            &&serde_yaml::Value::String(shorthand.to_string()),
+            &mut path.clone(),
+            options,
+        )
+    }
+
+    /// Constraints written on one line, e.g. `Integer(min=0, max=150)` or
+    /// `String(regex="^[A-Z]")?`, read as the equivalent `+` keywords block
+    fn build_from_inline(
+        inline: &str,
+        path: &JsonPath,
+        options: &DefinitionOptions,
+    ) -> Result<AS3Validator, As3JsonPath<AS3DefinitionError>> {
+        let inline = inline.trim();
+        let invalid = |reason: String| {
+            As3JsonPath(
+                path.clone(),
+                AS3DefinitionError::Generic(format!(
+                    "`{inline}` is not a valid inline definition : {reason}"
+                )),
+            )
+        };
+
+        let (inline, nullable) = match inline.strip_suffix('?') {
+            Some(inline) => (inline, true),
+            None => (inline, false),
+        };
+        let Some((validator_type, arguments)) = inline.split_once('(') else {
+            return Err(invalid("missing `(`".to_string()));
+        };
+        let Some(arguments) = arguments.strip_suffix(')') else {
+            return Err(invalid("missing the closing `)`".to_string()));
+        };
+
+        let mut definition = serde_yaml::Mapping::new();
+        let validator_type = format!(
+            "{}{}",
+            validator_type.trim(),
+            if nullable { "?" } else { "" }
+        );
+        definition.insert("+type".into(), validator_type.into());
+
+        let mut rest = arguments.trim();
+        while !rest.is_empty() {
+            let Some((name, value)) = rest.split_once('=') else {
+                return Err(invalid(format!("expected `name=value` in `{rest}`")));
+            };
+            let name = name.trim();
+            if name.is_empty() || !name.chars().all(|c| c.is_ascii_alphanumeric() || c == '_') {
+                return Err(invalid(format!("`{name}` is not a valid constraint name")));
+            }
+
+            let value = value.trim_start();
+            let (value, remaining) = if let Some(quoted) = value.strip_prefix('"') {
+                // Quoted Strings support `\"` and `\\` escapes
+                let mut string = String::new();
+                let mut chars = quoted.char_indices();
+                let mut end = None;
+                while let Some((index, c)) = chars.next() {
+                    match c {
+                        '\\' => match chars.next() {
+                            Some((_, escaped)) => string.push(escaped),
+                            None => break,
+                        },
+                        '"' => {
+                            end = Some(index);
+                            break;
+                        }
+                        c => string.push(c),
+                    }
+                }
+                let Some(end) = end else {
+                    return Err(invalid(format!("unterminated String for `{name}`")));
+                };
+                (serde_yaml::Value::String(string), &quoted[end + 1..])
+            } else {
+                let end = value.find(',').unwrap_or(value.len());
+                let raw = value[..end].trim();
+                match serde_yaml::from_str::<serde_yaml::Value>(raw) {
+                    Ok(parsed) if !raw.is_empty() => (parsed, &value[end..]),
+                    _ => return Err(invalid(format!("`{name}` has no valid value"))),
+                }
+            };
+
+            definition.insert(format!("+{name}").into(), value);
+            rest = remaining.trim_start();
+            rest = match rest.strip_prefix(',') {
+                Some(rest) => rest.trim_start(),
+                None if rest.is_empty() => rest,
+                None => return Err(invalid(format!("expected `,` before `{rest}`"))),
+            };
+        }
+
+        AS3Validator::build_from_yaml(
+            &&serde_yaml::Value::Mapping(definition),
+            &mut path.clone(),
+            options,
+        )
+    }
+
+    /// `+` prefixed keywords accepted, on top of `+type`, by the definition of each type
+    fn keywords_of(validator_type: &str) -> &'static [&'static str] {
+        match validator_type {
+            "String" => &[
+                "+regex",
+                "+regexFlags",
+                "+fullMatch",
+                "+MaxLength",
+                "+maxLength",
+                "+max_length",
+                "+MinLength",
+                "+minLength",
+                "+min_length",
+                "+lengthUnit",
+                "+format",
+                "+schemes",
+                "+version",
+                "+cidr",
+                "+countryCodes",
+                "+minVersion",
+                "+maxVersion",
+                "+anyRegex",
+                "+denyRegex",
+                "+ascii",
+                "+alphanumeric",
+                "+noWhitespace",
+            ],
+            "Decimal" | "Float" | "Date" | "DateTime" | "Time" => &["+max", "+min"],
+            type_name
+                if type_name == "Integer" || IntegerWidth::from_type_name(type_name).is_some() =>
+            {
+                &["+max", "+min", "+bits", "+unsigned"]
+            }
+            "List" => &["+ValueType"],
+            "Object" => &[
+                "+assert",
+                "+else",
+                "+extraKeys",
+                "+if",
+                "+patternKeys",
+                "+requires",
+                "+then",
+            ],
+            "Map" => &["+KeyType", "+ValueType", "+MaxEntries", "+MinEntries"],
+            "TaggedUnion" => &["+discriminator", "+variants"],
+            "Duration" => &["+format", "+max", "+min"],
+            "Bytes" => &["+encoding", "+maxBytes", "+minBytes"],
+            _ => &[],
+        }
+    }
+}
+
+/// Settings changing how a definition is read
+#[derive(Debug, Default, Clone)]
+pub struct DefinitionOptions {
+    /// Ignore unknown `+` keywords instead of rejecting them, for definitions written
+    /// for a newer version
+    pub lenient: bool,
+    /// Definitions usable with `+ref`, completed with the `+defs` of the definition read
+    pub defs: HashMap<String, Reference>,
+    /// The same definitions as written, which `+extends` merges into the Objects extending them
+    pub def_sources: HashMap<String, serde_yaml::Value>,
+    /// Folder `+include` paths are relative to, the current folder when missing
+    #[cfg(feature = "std")]
+    pub base: Option<PathBuf>,
+    /// URL of the remote definition being read, which its `+include` paths are relative to
+    pub base_url: Option<url::Url>,
+    /// Files and URLs being read, outermost first, to detect circular `+include`
+    pub including: Vec<String>,
+    /// Source of the definitions included from an URL, which are refused when missing
+    pub fetcher: Option<Arc<dyn Fetcher>>,
+}
+
+impl DefinitionOptions {
+    /// Options to read the definition file at `path`
+    #[cfg(feature = "std")]
+    pub fn located(&self, path: &Path) -> DefinitionOptions {
+        let path = path.canonicalize().unwrap_or_else(|_| path.to_path_buf());
+        let mut options = self.clone();
+        options.base = path.parent().map(Path::to_path_buf);
+        options.base_url = None;
+        options.including.push(path.display().to_string());
+        options
+    }
+
+    /// Absolute path of the file included with `include`
+    #[cfg(feature = "std")]
+    fn include_path(&self, include: &str) -> Result<String, Cause> {
+        let base = self.base.as_deref().unwrap_or(Path::new(""));
+        match base.join(include).canonicalize() {
+            Ok(file) => Ok(file.display().to_string()),
+            Err(e) => Err(Cause::new(e)),
+        }
+    }
+
+    /// Files can't be included without the filesystem
+    #[cfg(not(feature = "std"))]
+    fn include_path(&self, _include: &str) -> Result<String, Cause> {
+        Err("files can't be read without the `std` feature"
+            .to_string()
+            .into())
+    }
+}
+
+/// Settings restricting which parts of a document are validated
+#[derive(Debug, Default, Clone)]
+pub struct ValidationOptions {
+    /// When not empty, only the values at these paths are validated. The Objects leading to
+    /// them are traversed without applying their own `+extraKeys`, `+requires` and `+assert`
+    pub include: Vec<PathGlob>,
+    /// Values skipped, whatever `include` says
+    pub exclude: Vec<PathGlob>,
+    /// Accept `"42"` as an Integer, `1`, `0`, `"true"` and `"false"` as a Bool, and Integers
+    /// or numeric Strings as a Decimal
+    #[cfg(feature = "coerce")]
+    pub coerce: bool,
+    /// Errors after which [`AS3Validator::validate_all`] stops
+    pub max_errors: Option<NonZeroUsize>,
+    /// Told of every value checked, to follow the validation while debugging a definition
+    pub tracer: Option<Arc<dyn Tracer>>,
+}
+
+/// Observer of a validation, called from the threads validating the fields of Objects
+pub trait Tracer: std::fmt::Debug + Send + Sync {
+    /// Called before checking the value at `path`, such as `ROOT -> list -> [2]`. A value can
+    /// be visited more than once, by wrappers such as `+ref` or `Nullable` checking it again
+    fn visit(&self, path: &JsonPath);
+}
+
+impl ValidationOptions {
+    /// Whether enough errors were found to know that there are more than `max_errors`
+    fn is_full(&self, found: &[As3JsonPath<AS3ValidationError>]) -> bool {
+        self.max_errors.is_some_and(|max| found.len() > max.get())
+    }
+
+    /// Whether the value at the validation `path` is left out, with everything it contains
+    fn skips(&self, path: &JsonPath) -> bool {
+        let keys: Vec<&str> = path.keys().collect();
+        self.exclude.iter().any(|glob| glob.matches(&keys, false))
+            || (!self.include.is_empty()
+                && !self.include.iter().any(|glob| glob.matches(&keys, true)))
+    }
+
+    /// Whether the value at the validation `path` is within an included path
+    fn selects(&self, path: &JsonPath) -> bool {
+        let keys: Vec<&str> = path.keys().collect();
+        self.include.is_empty() || self.include.iter().any(|glob| glob.matches(&keys, false))
+    }
+}
+
+/// Result of [`AS3Validator::validate_with_warnings`]
+#[derive(Debug, Default, PartialEq)]
+pub struct ValidationOutcome {
+    pub errors: Vec<As3JsonPath<AS3ValidationError>>,
+    pub warnings: Vec<As3JsonPath<AS3ValidationError>>,
+    /// Whether errors were left out past [`ValidationOptions::max_errors`]
+    pub truncated: bool,
+}
+
+impl ValidationOutcome {
+    /// Whether the data is valid, warnings or not
+    pub fn is_valid(&self) -> bool {
+        self.errors.is_empty()
+    }
+}
+
+/// Serialized as `{"valid": true}`, with the `errors`, the `warnings` and `truncated` when there
+/// are any
+impl Serialize for ValidationOutcome {
+    fn serialize<S: serde::Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        use serde::ser::SerializeMap;
+        let mut map = serializer.serialize_map(None)?;
+        map.serialize_entry("valid", &self.is_valid())?;
+        if !self.errors.is_empty() {
+            map.serialize_entry("errors", &self.errors)?;
+        }
+        if !self.warnings.is_empty() {
+            map.serialize_entry("warnings", &self.warnings)?;
+        }
+        if self.truncated {
+            map.serialize_entry("truncated", &true)?;
+        }
+        map.end()
+    }
+}
+
+/// Record handed to the callback of [`AS3Validator::validate_stream`]
+#[derive(Debug, PartialEq)]
+pub struct StreamRecord {
+    /// `ROOT -> [2]` for the third record, or `ROOT -> key` for a value of a Map
+    pub path: JsonPath,
+    /// Record as validated, coerced with [`ValidationOptions::coerce`]
+    pub data: AS3Data,
+    pub result: Result<(), As3JsonPath<AS3ValidationError>>,
+}
+
+impl StreamRecord {
+    pub fn is_valid(&self) -> bool {
+        self.result.is_ok()
+    }
+}
+
+/// Selector of document paths written with dots, such as `vehicles.list[*].year`. `*` matches
+/// any key and `**` any number of keys. The items of a List are selected together, so `[*]` is
+/// accepted for readability only and a single index such as `[0]` is refused. A `\` makes the
+/// next character part of the key, such as `\.` in `hosts.example\.com`. A glob also selects
+/// everything below the paths it matches
+#[derive(Debug, Clone, PartialEq)]
+pub struct PathGlob {
+    segments: Vec<GlobSegment>,
+}
+
+#[derive(Debug, Clone, PartialEq)]
+enum GlobSegment {
+    Key(String),
+    /// `*`
+    AnyKey,
+    /// `**`
+    AnyKeys,
+}
+
+impl std::str::FromStr for PathGlob {
+    type Err = String;
+
+    fn from_str(glob: &str) -> Result<PathGlob, String> {
+        let mut segments = Vec::new();
+        let mut key = String::new();
+        // Whether `key` has escaped characters, `\*` being a key rather than a wildcard
+        let mut escaped = false;
+        // Whether `key` is followed by `[*]`
+        let mut items = false;
+        let mut chars = glob.chars();
+        loop {
+            let c = chars.next();
+            match c {
+                None | Some('.') => {
+                    match (key.as_str(), escaped) {
+                        ("", false) if items => {}
+                        ("", false) => return Err(format!("`{glob}` has an empty segment")),
+                        ("*", false) => segments.push(GlobSegment::AnyKey),
+                        ("**", false) => segments.push(GlobSegment::AnyKeys),
+                        _ => segments.push(GlobSegment::Key(key.clone())),
+                    }
+                    key.clear();
+                    escaped = false;
+                    items = false;
+                    if c.is_none() {
+                        break;
+                    }
+                }
+                Some('[') => {
+                    let mut index = String::new();
+                    loop {
+                        match chars.next() {
+                            Some(']') => break,
+                            Some(c) => index.push(c),
+                            None => return Err(format!("`{glob}` has an unclosed `[`")),
+                        }
+                    }
+                    if index != "*" {
+                        return Err(format!(
+                            "`{glob}` selects the List index `[{index}]`, only `[*]` is supported"
+                        ));
+                    }
+                    items = true;
+                }
+                Some(c) if items => {
+                    return Err(format!("`{glob}` has `{c}` after `[*]` instead of a `.`"))
+                }
+                Some('\\') => match chars.next() {
+                    Some(c) => {
+                        key.push(c);
+                        escaped = true;
+                    }
+                    None => return Err(format!("`{glob}` ends with a `\\` escaping nothing")),
+                },
+                Some(c) => key.push(c),
+            }
+        }
+        segments.push(GlobSegment::AnyKeys);
+        Ok(PathGlob { segments })
+    }
+}
+
+impl PathGlob {
+    /// Whether `keys` is matched or, with `prefix`, could be extended into a match
+    fn matches(&self, keys: &[&str], prefix: bool) -> bool {
+        fn matches(segments: &[GlobSegment], keys: &[&str], prefix: bool) -> bool {
+            match (segments.first(), keys.first()) {
+                (None, _) => keys.is_empty(),
+                (Some(GlobSegment::AnyKeys), _) => {
+                    matches(&segments[1..], keys, prefix)
+                        || (!keys.is_empty() && matches(segments, &keys[1..], prefix))
+                }
+                (Some(_), None) => prefix,
+                (Some(segment), Some(key)) => {
+                    let matched = match segment {
+                        GlobSegment::Key(segment) => segment == key,
+                        _ => true,
+                    };
+                    matched && matches(&segments[1..], &keys[1..], prefix)
+                }
+            }
+        }
+        matches(&self.segments, keys, prefix)
+    }
+}
+
+/// Parses a `YYYY-MM-DD` calendar date, rejecting days that don't exist such as `2023-02-30`
+fn parse_date(string: &str) -> Option<chrono::NaiveDate> {
+    let shape = string.len() == 10
+        && string.char_indices().all(|(index, c)| match index {
+            4 | 7 => c == '-',
+            _ => c.is_ascii_digit(),
+        });
+    if !shape {
+        return None;
+    }
+    chrono::NaiveDate::parse_from_str(string, "%Y-%m-%d").ok()
+}
+
+/// Parses a time of day written `HH:MM` or `HH:MM:SS`
+fn parse_time(string: &str) -> Option<chrono::NaiveTime> {
+    if string.len() != 5 && string.len() != 8 {
+        return None;
+    }
+    chrono::NaiveTime::parse_from_str(string, "%H:%M:%S")
+        .or_else(|_| chrono::NaiveTime::parse_from_str(string, "%H:%M"))
+        .ok()
+}
+
+/// Index of the first comma of `arguments` not nested inside `<>`, `()` or a quoted String
+fn top_level_comma(arguments: &str) -> Option<usize> {
+    let mut depth = 0;
+    let mut quoted = false;
+    let mut escaped = false;
+    for (index, c) in arguments.char_indices() {
+        match c {
+            _ if escaped => escaped = false,
+            '\\' if quoted => escaped = true,
+            '"' => quoted = !quoted,
+            _ if quoted => {}
+            '<' | '(' => depth += 1,
+            '>' | ')' => depth -= 1,
+            ',' if depth == 0 => return Some(index),
+            _ => {}
+        }
+    }
+    None
+}
+
+/// Closest of `candidates` to `word`, if it's close enough to be a probable typo
+pub(crate) fn did_you_mean<'a>(
+    word: &str,
+    candidates: impl IntoIterator<Item = &'a &'a str>,
+) -> Option<String> {
+    candidates
+        .into_iter()
+        .map(|candidate| {
+            let distance = if candidate.eq_ignore_ascii_case(word) {
+                0
+            } else {
+                levenshtein(&word.to_lowercase(), &candidate.to_lowercase())
+            };
+            (distance, candidate)
+        })
+        .filter(|(distance, _)| *distance <= 2)
+        .min_by_key(|(distance, _)| *distance)
+        .map(|(_, candidate)| candidate.to_string())
+}
+
+fn levenshtein(a: &str, b: &str) -> usize {
+    let b: Vec<char> = b.chars().collect();
+    let mut previous: Vec<usize> = (0..=b.len()).collect();
+    for (i, a_char) in a.chars().enumerate() {
+        let mut current = vec![i + 1];
+        for (j, b_char) in b.iter().enumerate() {
+            let substitution = previous[j] + usize::from(a_char != *b_char);
+            current.push(substitution.min(previous[j + 1] + 1).min(current[j] + 1));
+        }
+        previous = current;
+    }
+    previous[b.len()]
+}
+
+/// `+` prefixed properties of a type definition, read strictly: a keyword set with the wrong
+/// kind of value is an error instead of being ignored
+struct Keywords<'a> {
+    inner: &'a serde_yaml::Mapping,
+    path: &'a JsonPath,
+}
+
+impl<'a> Keywords<'a> {
+    fn error(&self, error: AS3DefinitionError) -> As3JsonPath<AS3DefinitionError> {
+        As3JsonPath(self.path.clone(), error)
+    }
+
+    fn invalid(&self, keyword: &str, expected: &str) -> As3JsonPath<AS3DefinitionError> {
+        self.error(AS3DefinitionError::InvalidValue {
+            keyword: keyword.to_string(),
+            expected: expected.to_string(),
+        })
+    }
+
+    fn contains(&self, keyword: &str) -> bool {
+        self.inner.contains_key(keyword)
+    }
+
+    fn integer(&self, keyword: &str) -> Result<Option<i64>, As3JsonPath<AS3DefinitionError>> {
+        match self.inner.get(keyword) {
+            None => Ok(None),
+            Some(serde_yaml::Value::Number(number)) if number.as_i64().is_some() => {
+                Ok(number.as_i64())
+            }
+            Some(_) => Err(self.invalid(keyword, "an Integer")),
+        }
+    }
+
+    /// Integer that can't be negative
+    fn length(&self, keyword: &str) -> Result<i64, As3JsonPath<AS3DefinitionError>> {
+        match self.integer(keyword)? {
+            Some(length) if length >= 0 => Ok(length),
+            _ => Err(self.invalid(keyword, "a positive Integer")),
+        }
+    }
+
+    fn number(&self, keyword: &str) -> Result<Option<f64>, As3JsonPath<AS3DefinitionError>> {
+        match self.inner.get(keyword) {
+            None => Ok(None),
+            Some(serde_yaml::Value::Number(number)) => Ok(number.as_f64()),
+            Some(_) => Err(self.invalid(keyword, "a Number")),
+        }
+    }
+
+    fn string(&self, keyword: &str) -> Result<Option<&'a String>, As3JsonPath<AS3DefinitionError>> {
+        match self.inner.get(keyword) {
+            None => Ok(None),
+            Some(serde_yaml::Value::String(string)) => Ok(Some(string)),
+            Some(_) => Err(self.invalid(keyword, "a String")),
+        }
+    }
+
+    /// Calendar date written `YYYY-MM-DD`, or `today`
+    fn date(&self, keyword: &str) -> Result<Option<DateBound>, As3JsonPath<AS3DefinitionError>> {
+        match self.string(keyword) {
+            Ok(None) => Ok(None),
+            Ok(Some(date)) if date == "today" => Ok(Some(DateBound::Today)),
+            Ok(Some(date)) => match parse_date(date) {
+                Some(date) => Ok(Some(DateBound::Fixed(date))),
+                None => Err(self.invalid(keyword, "a date (YYYY-MM-DD) or `today`")),
+            },
+            Err(_) => Err(self.invalid(keyword, "a date (YYYY-MM-DD) or `today`")),
+        }
+    }
+
+    /// RFC 3339 timestamp with its offset
+    fn datetime(
+        &self,
+        keyword: &str,
+    ) -> Result<Option<chrono::DateTime<chrono::FixedOffset>>, As3JsonPath<AS3DefinitionError>>
+    {
+        match self.string(keyword) {
+            Ok(None) => Ok(None),
+            Ok(Some(datetime)) => match chrono::DateTime::parse_from_rfc3339(datetime) {
+                Ok(datetime) => Ok(Some(datetime)),
+                Err(_) => Err(self.invalid(keyword, "an RFC 3339 date-time")),
+            },
+            Err(_) => Err(self.invalid(keyword, "an RFC 3339 date-time")),
+        }
+    }
+
+    /// Time of day written `HH:MM` or `HH:MM:SS`
+    fn time(
+        &self,
+        keyword: &str,
+    ) -> Result<Option<chrono::NaiveTime>, As3JsonPath<AS3DefinitionError>> {
+        match self.string(keyword) {
+            Ok(None) => Ok(None),
+            Ok(Some(time)) => match parse_time(time) {
+                Some(time) => Ok(Some(time)),
+                None => Err(self.invalid(keyword, "a time of day (HH:MM or HH:MM:SS)")),
+            },
+            Err(_) => Err(self.invalid(keyword, "a time of day (HH:MM or HH:MM:SS)")),
+        }
+    }
+
+    /// Duration in either the ISO 8601 or the humantime notation
+    fn duration(
+        &self,
+        keyword: &str,
+    ) -> Result<Option<std::time::Duration>, As3JsonPath<AS3DefinitionError>> {
+        match self.string(keyword) {
+            Ok(None) => Ok(None),
+            Ok(Some(duration)) => match DurationSyntax::Any.parse(duration) {
+                Ok(duration) => Ok(Some(duration)),
+                Err(_) => Err(self.invalid(keyword, "a duration (`PT30M`, `90s`, ...)")),
+            },
+            Err(_) => Err(self.invalid(keyword, "a duration (`PT30M`, `90s`, ...)")),
+        }
+    }
+
+    fn bool(&self, keyword: &str) -> Result<Option<bool>, As3JsonPath<AS3DefinitionError>> {
+        match self.inner.get(keyword) {
+            None => Ok(None),
+            Some(serde_yaml::Value::Bool(boolean)) => Ok(Some(*boolean)),
+            Some(_) => Err(self.invalid(keyword, "a Bool")),
+        }
+    }
+
+    fn string_list(
+        &self,
+        keyword: &str,
+    ) -> Result<Option<Vec<String>>, As3JsonPath<AS3DefinitionError>> {
+        match self.inner.get(keyword) {
+            None => Ok(None),
+            Some(serde_yaml::Value::Sequence(list)) => list
+                .iter()
+                .map(|item| match item {
+                    serde_yaml::Value::String(item) => Ok(item.clone()),
+                    _ => Err(self.invalid(keyword, "a list of Strings")),
+                })
+                .collect::<Result<Vec<String>, _>>()
+                .map(Some),
+            Some(_) => Err(self.invalid(keyword, "a list of Strings")),
+        }
+    }
+
+    /// Returns which of the equivalent `aliases` is set, failing if more than one is
+    fn alias(
+        &self,
+        aliases: &[&'static str],
+    ) -> Result<Option<&'static str>, As3JsonPath<AS3DefinitionError>> {
+        let set: Vec<&'static str> = aliases
+            .iter()
+            .copied()
+            .filter(|keyword| self.contains(keyword))
+            .collect();
+        if set.len() > 1 {
+            return Err(self.error(AS3DefinitionError::Conflict(format!(
+                "multiple equivalent keywords have been passed : ({})",
+                set.join(",")
+            ))));
         }
+        Ok(set.first().copied())
     }
 }